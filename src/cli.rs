@@ -1,14 +1,32 @@
 use crate::{
     consts::{
-        DEFAULT_IMAGE_WITH_PKG_TAG, FUNCTIONS_DEFAULT_NAMESPACE, FUNCTIONS_NAMESPACE_ENV_VAR,
-        GATEWAY_DEFAULT_URL, GATEWAY_URL_ENV_VAR, OPF_FO_C_UPDATE_STRATEGY_ENV_VAR, PKG_VERSION,
+        DEFAULT_IMAGE_WITH_PKG_TAG, FIELD_MANAGER_DEFAULT, FIELD_MANAGER_ENV_VAR,
+        FUNCTIONS_DEFAULT_NAMESPACE, FUNCTIONS_NAMESPACE_ENV_VAR, GATEWAY_DEFAULT_URL,
+        GATEWAY_URL_ENV_VAR, JSON_LOGS_INCLUDE_SPAN_LIST_ENV_VAR, LEADER_ELECTION_DEFAULT_ID,
+        LOG_FORMAT_ENV_VAR, METRICS_DEFAULT_ADDR, OPF_FO_CL_ACCEPT_INVALID_CERTS_ENV_VAR,
+        OPF_FO_CL_CA_CERT_PATH_ENV_VAR, OPF_FO_CL_RETRIES_ENV_VAR,
+        OPF_FO_CL_RETRY_BACKOFF_MILLIS_ENV_VAR, OPF_FO_CL_TIMEOUT_SECONDS_ENV_VAR,
+        OPF_FO_C_ADOPT_ORPHANS_ENV_VAR, OPF_FO_C_ALL_NAMESPACES_ENV_VAR,
+        OPF_FO_C_DEFAULT_POD_LABELS_ENV_VAR, OPF_FO_C_ENABLE_LEADER_ELECTION_ENV_VAR,
+        OPF_FO_C_ERROR_REQUEUE_SECONDS_ENV_VAR, OPF_FO_C_FUNCTIONS_NAMESPACES_ENV_VAR,
+        OPF_FO_C_IMAGE_PULL_SECRETS_SERVICE_ACCOUNT_ENV_VAR, OPF_FO_C_LEADER_ELECTION_ID_ENV_VAR,
+        OPF_FO_C_MAX_ENV_BYTES_ENV_VAR, OPF_FO_C_MAX_ERROR_REQUEUE_SECONDS_ENV_VAR,
+        OPF_FO_C_MAX_RECONCILE_DURATION_ENV_VAR, OPF_FO_C_METRICS_ADDR_ENV_VAR,
+        OPF_FO_C_NO_IMAGE_NORMALIZATION_ENV_VAR, OPF_FO_C_PRESERVE_ANNOTATIONS_ENV_VAR,
+        OPF_FO_C_PRESERVE_FIELDS_ENV_VAR, OPF_FO_C_RECONCILE_CONCURRENCY_ENV_VAR,
+        OPF_FO_C_RECONCILE_DEBOUNCE_MILLIS_ENV_VAR, OPF_FO_C_RECONCILE_JITTER_STARTUP_ENV_VAR,
+        OPF_FO_C_REQUIRE_NAMESPACE_ENV_VAR, OPF_FO_C_REQUIRE_RBAC_ENV_VAR,
+        OPF_FO_C_SECRETS_NAMESPACE_ENV_VAR, OPF_FO_C_UPDATE_MODE_ENV_VAR,
+        OPF_FO_C_UPDATE_STRATEGY_ENV_VAR, OPF_FO_C_VERIFY_RUNTIME_CLASS_ENV_VAR,
+        OPF_FO_C_WAIT_FOR_READY_ON_RENAME_ENV_VAR, OPF_FO_C_WATCH_BUFFER_SIZE_ENV_VAR,
+        OTLP_ENDPOINT_ENV_VAR, PKG_VERSION, QUIET_ENV_VAR, TRACE_SAMPLE_RATE_ENV_VAR,
     },
-    crds::defs::VERSION as CRD_VERSION,
-    operator::controller::UpdateStrategy,
+    crds::defs::{DEFAULT_MAX_ENV_BYTES, VERSION as CRD_VERSION},
+    operator::controller::{preserve::PreservedField, UpdateMode, UpdateStrategy},
 };
 use clap::{Parser, Subcommand};
 use const_format::formatcp;
-use std::path::PathBuf;
+use std::{net::SocketAddr, path::PathBuf};
 use url::Url;
 
 const VERSION: &str = formatcp!("{0}, crd {1}", PKG_VERSION, CRD_VERSION);
@@ -21,24 +39,107 @@ const NO_BINARY_NAME: bool = false;
 #[derive(Parser, Debug)]
 #[command(author, version=VERSION, about, long_about = None, no_binary_name(NO_BINARY_NAME))]
 pub struct Cli {
+    /// The field manager name used for all write operations against the cluster
+    #[clap(long, global = true, env = FIELD_MANAGER_ENV_VAR, default_value = FIELD_MANAGER_DEFAULT)]
+    pub field_manager: String,
+    /// Only log warnings and errors and skip the banner
+    ///
+    /// Takes precedence over the default log level, but not over RUST_LOG
+    #[clap(short, long, global = true, env = QUIET_ENV_VAR, default_value = "false")]
+    pub quiet: bool,
+    /// The format of the logs
+    #[clap(long, global = true, env = LOG_FORMAT_ENV_VAR, value_enum, default_value_t = LogFormat::default())]
+    pub log_format: LogFormat,
+    /// Include the list of active spans in every JSON log line, e.g. Operator > ReconcileResource > CheckDeployment
+    ///
+    /// Only applies if the log format is json
+    #[clap(long, global = true, env = JSON_LOGS_INCLUDE_SPAN_LIST_ENV_VAR, default_value = "false")]
+    pub json_logs_include_span_list: bool,
+    /// The OTLP gRPC endpoint spans are exported to, e.g. http://otel-collector:4317
+    ///
+    /// If not set, spans are not exported
+    #[clap(long, global = true, env = OTLP_ENDPOINT_ENV_VAR)]
+    pub otlp_endpoint: Option<Url>,
+    /// The fraction of traces exported to the OTLP endpoint, between 0.0 and 1.0
+    ///
+    /// Child spans follow the sampling decision of their parent
+    #[clap(long, global = true, env = TRACE_SAMPLE_RATE_ENV_VAR, default_value = "1.0", value_parser = parse_sample_rate)]
+    pub trace_sample_rate: f64,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+fn parse_sample_rate(value: &str) -> Result<f64, String> {
+    let rate: f64 = value
+        .parse()
+        .map_err(|_| format!("{value} is not a number"))?;
+
+    if !(0.0..=1.0).contains(&rate) {
+        return Err(format!("{rate} is not between 0.0 and 1.0"));
+    }
+
+    Ok(rate)
+}
+
+fn parse_label(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!("{value} is not a key=value pair")),
+    }
+}
+
+/// The format of the logs
+#[derive(Debug, Clone, clap::ValueEnum, Default, PartialEq)]
+pub enum LogFormat {
+    /// Human readable text
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
 impl Cli {
     pub fn operator_controller_run_args(
         namesapce: String,
         update_strategy: UpdateStrategy,
+        all_namespaces: bool,
+        functions_namespaces: Vec<String>,
+        verify_runtime_class: bool,
+        secrets_namespace: Option<String>,
     ) -> Vec<String> {
-        vec![
+        let mut args = vec![
             String::from("operator"),
             String::from("controller"),
             String::from("--functions-namespace"),
             namesapce,
             String::from("--update-strategy"),
             update_strategy.to_string(),
-            String::from("run"),
-        ]
+        ];
+
+        if all_namespaces {
+            args.push(String::from("--all-namespaces"));
+        }
+
+        if !functions_namespaces.is_empty() {
+            args.push(String::from("--functions-namespaces"));
+            args.push(functions_namespaces.join(","));
+        }
+
+        if verify_runtime_class {
+            args.push(String::from("--verify-runtime-class"));
+        }
+
+        if let Some(secrets_namespace) = secrets_namespace {
+            args.push(String::from("--secrets-namespace"));
+            args.push(secrets_namespace);
+        }
+
+        args.push(String::from("run"));
+
+        args
     }
 }
 
@@ -103,12 +204,147 @@ pub enum OperatorCommands {
         /// The namespace for OpenFaaS functions
         #[clap(short = 'n', long, env = FUNCTIONS_NAMESPACE_ENV_VAR, default_value = FUNCTIONS_DEFAULT_NAMESPACE)]
         functions_namespace: String,
+        /// Reconcile functions in all namespaces, each in its own namespace
+        ///
+        /// The functions namespace is then only used by reconcile-once, migrate and deploy.
+        /// Deploy generates a cluster role and cluster role binding instead of a role and role binding
+        #[clap(long, env = OPF_FO_C_ALL_NAMESPACES_ENV_VAR, conflicts_with = "functions_namespaces")]
+        all_namespaces: bool,
+        /// Reconcile functions in these namespaces, each in its own namespace, e.g. team-a,team-b
+        ///
+        /// Like --all-namespaces, functions are watched cluster wide and the operator needs cluster wide permissions.
+        /// Functions in other namespaces are ignored
+        #[clap(long, env = OPF_FO_C_FUNCTIONS_NAMESPACES_ENV_VAR, value_delimiter = ',')]
+        functions_namespaces: Vec<String>,
+        /// Elect a leader among the replicas of the operator, only the leader reconciles functions
+        ///
+        /// The leader holds a lease in the functions namespace. A replica that loses the lease exits
+        #[clap(long, env = OPF_FO_C_ENABLE_LEADER_ELECTION_ENV_VAR)]
+        enable_leader_election: bool,
+        /// Name of the lease used for leader election
+        #[clap(long, env = OPF_FO_C_LEADER_ELECTION_ID_ENV_VAR, default_value = LEADER_ELECTION_DEFAULT_ID)]
+        leader_election_id: String,
         /// Update strategy for the operator
         #[clap(short, long, env = OPF_FO_C_UPDATE_STRATEGY_ENV_VAR, value_enum, default_value_t = UpdateStrategy::default())]
         update_strategy: UpdateStrategy,
+        /// How outdated deployments are updated under the one-way update strategy
+        ///
+        /// auto patches only the metadata if nothing else changed and replaces the deployment otherwise.
+        /// patch never replaces, so fields removed from a function are not removed from its deployment
+        #[clap(long, env = OPF_FO_C_UPDATE_MODE_ENV_VAR, value_enum, default_value_t = UpdateMode::default())]
+        update_mode: UpdateMode,
+        /// Fail on startup if the functions namespace does not exist
+        ///
+        /// By default, a missing functions namespace only emits a warning
+        #[clap(long, env = OPF_FO_C_REQUIRE_NAMESPACE_ENV_VAR, default_value = "false")]
+        require_namespace: bool,
+        /// Fail on startup if the operator is missing permissions it needs to reconcile functions
+        ///
+        /// By default, missing permissions only emit warnings
+        #[clap(long, env = OPF_FO_C_REQUIRE_RBAC_ENV_VAR, default_value = "false")]
+        require_rbac: bool,
+        /// Seconds a function's reconciliation may keep failing before the operator backs off
+        ///
+        /// Once exceeded, the function gets a ReconcileBackoff status and is requeued less frequently.
+        /// If not set, failing functions are requeued indefinitely
+        #[clap(long, env = OPF_FO_C_MAX_RECONCILE_DURATION_ENV_VAR)]
+        max_reconcile_duration: Option<u64>,
+        /// Maximum number of functions reconciled at the same time, 0 for no limit
+        ///
+        /// Further changes are queued until a reconciliation finishes.
+        /// The reconciles at concurrency limit counter is incremented whenever a reconciliation reaches the limit
+        #[clap(long, env = OPF_FO_C_RECONCILE_CONCURRENCY_ENV_VAR, default_value = "0")]
+        reconcile_concurrency: u16,
+        /// Milliseconds to wait for further changes of a function before reconciling it
+        ///
+        /// Bursts of changes, e.g. a rollout updating the deployment's status, result in a single reconciliation
+        #[clap(long, env = OPF_FO_C_RECONCILE_DEBOUNCE_MILLIS_ENV_VAR, default_value = "0")]
+        reconcile_debounce_millis: u64,
+        /// Seconds over which the initial reconciliations of existing functions are spread after the operator starts, 0 to disable
+        ///
+        /// Avoids a spike of API server requests when the operator restarts in a cluster with many functions.
+        /// Functions created after the operator started are reconciled immediately
+        #[clap(long, env = OPF_FO_C_RECONCILE_JITTER_STARTUP_ENV_VAR, default_value = "0")]
+        reconcile_jitter_startup: u64,
+        /// Number of changes per watched resource type that are read ahead while the controller is busy, 0 to disable
+        ///
+        /// Keeps the watches flowing during bursts of changes on large clusters.
+        /// Functions waiting for their reconciliation are reported by the reconcile_queue_depth metric
+        #[clap(long, env = OPF_FO_C_WATCH_BUFFER_SIZE_ENV_VAR, default_value = "0")]
+        watch_buffer_size: usize,
+        /// Maximum summed size in bytes of a function's env vars
+        ///
+        /// Functions exceeding it get an EnvTooLarge status instead of pods failing to start
+        #[clap(long, env = OPF_FO_C_MAX_ENV_BYTES_ENV_VAR, default_value_t = DEFAULT_MAX_ENV_BYTES)]
+        max_env_bytes: usize,
+        /// Take over deployments and services that clearly belong to a function but lack its owner reference
+        ///
+        /// A resource is adopted if it is named after the function, carries the function's label and has no controller,
+        /// e.g. if it was created by 'crd convert apply'. Otherwise such resources result in a DeploymentAlreadyExists
+        /// or ServiceAlreadyExists status
+        #[clap(long, env = OPF_FO_C_ADOPT_ORPHANS_ENV_VAR)]
+        adopt_orphans: bool,
+        /// Fields of a function's deployment that are managed by other controllers and kept when the deployment is replaced
+        ///
+        /// Prevents the operator from fighting with admission webhooks and autoscalers under the one-way update strategy.
+        /// Preserving replicas requires permission to list horizontal pod autoscalers
+        #[clap(long, env = OPF_FO_C_PRESERVE_FIELDS_ENV_VAR, value_enum, value_delimiter = ',')]
+        preserve_fields: Vec<PreservedField>,
+        /// Prefixes of annotations on a function's deployment and pod template that are kept when the deployment is replaced
+        ///
+        /// e.g. sidecar.istio.io/,linkerd.io/
+        #[clap(long, env = OPF_FO_C_PRESERVE_ANNOTATIONS_ENV_VAR, value_delimiter = ',')]
+        preserve_annotations: Vec<String>,
+        /// Keep the old deployment of a renamed function until the new one has a ready replica
+        ///
+        /// Otherwise the old deployment is deleted as soon as the new one is created,
+        /// leaving the function unavailable until the new pods are ready
+        #[clap(long, env = OPF_FO_C_WAIT_FOR_READY_ON_RENAME_ENV_VAR)]
+        wait_for_ready_on_rename: bool,
+        /// Seconds to wait before reconciling a function again after its reconciliation failed
+        ///
+        /// The wait doubles with every consecutive failure of the same function, up to --max-error-requeue-seconds
+        #[clap(long, env = OPF_FO_C_ERROR_REQUEUE_SECONDS_ENV_VAR, default_value = "10", value_parser = clap::value_parser!(u64).range(1..))]
+        error_requeue_seconds: u64,
+        /// Maximum seconds to wait before reconciling a repeatedly failing function again
+        #[clap(long, env = OPF_FO_C_MAX_ERROR_REQUEUE_SECONDS_ENV_VAR, default_value = "300", value_parser = clap::value_parser!(u64).range(1..))]
+        max_error_requeue_seconds: u64,
+        /// A namespace holding shared secrets, that is checked for secrets missing in the functions namespace
+        ///
+        /// Kubernetes can not mount secrets across namespaces, so functions referencing secrets that only exist here
+        /// get a SecretsNotMountable status. The operator needs permission to list secrets in this namespace,
+        /// which the deployed resources grant with a role there
+        #[clap(long, env = OPF_FO_C_SECRETS_NAMESPACE_ENV_VAR)]
+        secrets_namespace: Option<String>,
+        /// Address of the HTTP server serving the operator's Prometheus metrics at /metrics, its health at /healthz and /readyz,
+        /// its view of all functions at /functions and a Server-Sent Events stream of reconcile outcomes at /events
+        #[clap(long, env = OPF_FO_C_METRICS_ADDR_ENV_VAR, default_value = METRICS_DEFAULT_ADDR)]
+        metrics_addr: SocketAddr,
+        /// Deploy function images exactly as written instead of normalizing them
+        ///
+        /// By default, image references are normalized the way Docker resolves them, e.g. nginx => docker.io/library/nginx:latest
+        #[clap(long, env = OPF_FO_C_NO_IMAGE_NORMALIZATION_ENV_VAR, default_value = "false")]
+        no_image_normalization: bool,
+        /// Labels added to the pods of every function, e.g. data-classification=internal,cost-center=platform
+        ///
+        /// Labels set by a function take precedence. Changing the default labels rolls out all functions
+        #[clap(long, env = OPF_FO_C_DEFAULT_POD_LABELS_ENV_VAR, value_delimiter = ',', value_parser = parse_label)]
+        default_pod_labels: Vec<(String, String)>,
+        /// A service account in the functions namespace whose image pull secrets are added to every function's pods
+        ///
+        /// Secrets are read when a function's deployment is created or replaced.
+        /// The operator needs permission to get service accounts
+        #[clap(long, env = OPF_FO_C_IMAGE_PULL_SECRETS_SERVICE_ACCOUNT_ENV_VAR)]
+        image_pull_secrets_service_account: Option<String>,
+        /// Check that the runtime class of a function exists before creating its deployment
+        ///
+        /// Functions with a missing runtime class get a RuntimeClassNotFound status.
+        /// The operator needs permission to get runtime classes, which are cluster scoped
+        #[clap(long, env = OPF_FO_C_VERIFY_RUNTIME_CLASS_ENV_VAR, default_value = "false")]
+        verify_runtime_class: bool,
 
         #[command(subcommand)]
-        command: OperatorSubCommands,
+        command: Box<OperatorSubCommands>,
     },
     /// Runs the OpenFaaS functions operator in client mode
     #[clap(visible_alias = "cl")]
@@ -132,17 +368,160 @@ pub enum OperatorCommands {
         /// If this is set, the password argument is ignored
         #[clap(long)]
         password_file: Option<PathBuf>,
+        /// Retries of requests the gateway rejects with 429 Too Many Requests
+        ///
+        /// Idempotent requests, e.g. reads, updates, deletions and scaling, are also retried if they time out or fail with a 5xx status code.
+        /// Deployments and invocations are not, as they may have taken effect.
+        /// The gateway's Retry-After header is honored, up to 60 seconds
+        #[clap(long, env = OPF_FO_CL_RETRIES_ENV_VAR, default_value = "0")]
+        retries: u32,
+        /// Seconds a request to the gateway may take before it times out
+        #[clap(long, env = OPF_FO_CL_TIMEOUT_SECONDS_ENV_VAR, default_value = "30", value_parser = clap::value_parser!(u64).range(1..))]
+        timeout_seconds: u64,
+        /// Milliseconds waited before the first retry, doubled on each further retry
+        #[clap(long, env = OPF_FO_CL_RETRY_BACKOFF_MILLIS_ENV_VAR, default_value = "1000")]
+        retry_backoff_millis: u64,
+        /// The path to a PEM file containing a CA certificate to trust, e.g. of a gateway with a self-signed certificate
+        #[clap(long, env = OPF_FO_CL_CA_CERT_PATH_ENV_VAR)]
+        ca_cert_path: Option<PathBuf>,
+        /// Accept any certificate the gateway presents, including invalid and expired ones
+        ///
+        /// This disables the verification of the gateway's identity and is meant for development only
+        #[clap(long, env = OPF_FO_CL_ACCEPT_INVALID_CERTS_ENV_VAR)]
+        accept_invalid_certs: bool,
 
         #[command(subcommand)]
-        command: OperatorSubCommands,
+        command: Box<ClientSubCommands>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ClientSubCommands {
+    /// Runs the OpenFaaS functions operator, reconciling functions through the gateway
+    #[clap(visible_alias = "r")]
+    Run {
+        /// The namespace to watch for OpenFaaS functions
+        ///
+        /// Functions without a namespace in their spec are deployed to this namespace on the gateway
+        #[clap(short = 'n', long, env = FUNCTIONS_NAMESPACE_ENV_VAR, default_value = FUNCTIONS_DEFAULT_NAMESPACE)]
+        functions_namespace: String,
+    },
+    /// Prints the health of all functions deployed on the gateway across all namespaces
+    #[clap(visible_alias = "sa")]
+    StatusAll {
+        /// The output format
+        #[clap(short, long, value_enum, default_value_t = OutputFormat::default())]
+        output: OutputFormat,
+    },
+    /// Deploys the functions defined in a file to the gateway
+    #[clap(visible_alias = "d")]
+    Deploy {
+        /// The path to the file to read the CRDs from
+        ///
+        /// Use `-` to read from stdin. Multiple documents separated by `---` are supported
+        #[clap(short = 'f', long)]
+        crd_file: PathBuf,
+        /// Create the function's namespace on the gateway if it does not exist
+        #[clap(long, default_value = "false")]
+        create_namespace: bool,
+        /// The CPU architecture the functions' images are built for, e.g. amd64 or arm64
+        ///
+        /// If set, the cluster's nodes are checked before deploying and a warning is emitted if none of them runs this architecture.
+        /// Requires access to the Kubernetes API, the check is skipped otherwise
+        #[clap(long)]
+        expected_arch: Option<String>,
+    },
+    /// Invokes a function through the gateway and prints its response
+    #[clap(visible_alias = "i")]
+    Invoke {
+        /// The name of the function
+        name: String,
+        /// The namespace of the function
+        ///
+        /// If not set, the gateway's default functions namespace is used
+        #[clap(short, long)]
+        namespace: Option<String>,
+        /// The request body
+        ///
+        /// If not set, the body is read from stdin
+        #[clap(short, long)]
+        data: Option<String>,
+        /// Invoke the function asynchronously
+        ///
+        /// The gateway queues the request and returns immediately
+        #[clap(long = "async", default_value = "false")]
+        is_async: bool,
+        /// The URL the function's response is posted to once an asynchronous invocation completes
+        ///
+        /// Sent as the X-Callback-Url header, requires --async
+        #[clap(long, requires = "is_async")]
+        callback_url: Option<Url>,
+    },
+    /// Replaces the value of a function's secret and restarts the function
+    ///
+    /// By default the function is restarted by the operator, which requires access to the Kubernetes API.
+    /// If --crd-file is set, the function is redeployed through the gateway instead
+    #[clap(visible_alias = "rs")]
+    RotateSecret {
+        /// The name of the function
+        #[clap(long)]
+        function: String,
+        /// The namespace of the function and the secret
+        ///
+        /// If not set, the gateway's default functions namespace is used, or --functions-namespace in operator mode
+        #[clap(short, long)]
+        namespace: Option<String>,
+        /// The namespace of the function's CRD in operator mode, if --namespace is not set
+        #[clap(long, env = FUNCTIONS_NAMESPACE_ENV_VAR, default_value = FUNCTIONS_DEFAULT_NAMESPACE)]
+        functions_namespace: String,
+        /// The name of the secret
+        #[clap(long)]
+        secret: String,
+        /// The path to the file to read the new value of the secret from
+        ///
+        /// Use `-` to read from stdin
+        #[clap(long)]
+        from_file: PathBuf,
+        /// The path to the file to read the function's CRD from, to redeploy it through the gateway
+        #[clap(short = 'f', long)]
+        crd_file: Option<PathBuf>,
     },
 }
 
+/// The output format of commands printing to stdout
+#[derive(Debug, Clone, clap::ValueEnum, Default, PartialEq)]
+pub enum OutputFormat {
+    /// Human readable table
+    #[default]
+    Table,
+    /// Machine readable JSON
+    Json,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum OperatorSubCommands {
     /// Runs the OpenFaaS functions operator
     #[clap(visible_alias = "r")]
     Run {},
+    /// Reconciles a single function once and exits, without watching
+    ///
+    /// Prints the resulting action and the function's status. Useful for debugging
+    #[clap(hide = true)]
+    ReconcileOnce {
+        /// The name of the function to reconcile
+        #[clap(short, long)]
+        name: String,
+    },
+    /// Writes the spec of every function into its deployment's last applied annotation and exits
+    ///
+    /// Deployments created by an older operator lack the annotation and would be recreated on the first reconciliation.
+    /// Run this before upgrading the operator to avoid restarting every function
+    #[clap(visible_alias = "m")]
+    Migrate {
+        /// Only print which deployments would be migrated
+        #[clap(long)]
+        dry_run: bool,
+    },
     /// Generates the Kubernetes resources for the OpenFaaS functions operator
     #[clap(visible_alias = "d")]
     Deploy {
@@ -165,12 +544,24 @@ pub enum OperatorSubCommands {
 
 #[derive(Subcommand, Debug)]
 pub enum OperatorDeployCommands {
-    /// Writes the Kubernetes resources to a file
+    /// Writes the Kubernetes resources to a file, or to one file per resource with --split
     #[clap(visible_alias = "w")]
     Write {
         /// The path to the file to write the Kubernetes resources to
-        #[clap(short, long)]
-        file: PathBuf,
+        #[clap(
+            short,
+            long,
+            required_unless_present = "split",
+            conflicts_with = "split"
+        )]
+        file: Option<PathBuf>,
+        /// Write each resource to its own file in --dir,
+        /// e.g. serviceaccount.yaml, role.yaml, rolebinding.yaml and deployment.yaml
+        #[clap(long, requires = "dir")]
+        split: bool,
+        /// The directory to write the split resources to, created if missing
+        #[clap(long, requires = "split")]
+        dir: Option<PathBuf>,
     },
     /// Prints the Kubernetes resources to stdout
     #[clap(visible_alias = "p")]
@@ -197,7 +588,22 @@ pub enum CrdCommands {
     },
     /// Prints the CRDs to stdout
     #[clap(visible_alias = "p")]
-    Print {},
+    Print {
+        /// Add the annotations and labels Helm uses to manage the CRDs
+        ///
+        /// The CRDs are kept on uninstall (helm.sh/resource-policy: keep)
+        #[clap(long, default_value = "false")]
+        helm: bool,
+        /// The Helm release owning the CRDs, allows Helm to adopt already installed CRDs
+        #[clap(long, requires = "helm", requires = "helm_release_namespace")]
+        helm_release_name: Option<String>,
+        /// The namespace of the Helm release owning the CRDs
+        #[clap(long, requires = "helm_release_name")]
+        helm_release_namespace: Option<String>,
+    },
+    /// Prints an example function with every field set and described
+    #[clap(visible_alias = "e")]
+    Example {},
     /// Installs the CRDs to the cluster
     #[clap(visible_alias = "in")]
     Install {},
@@ -207,10 +613,41 @@ pub enum CrdCommands {
     /// Updates the CRDs in the cluster
     #[clap(visible_alias = "up")]
     Update {},
+    /// Validates the functions defined in a file and prints every problem found
+    ///
+    /// Runs the same checks as the operator before generating any resources, without access to the cluster
+    #[clap(visible_alias = "v")]
+    Validate {
+        /// The path to the file to read the CRDs from
+        ///
+        /// Use `-` to read from stdin. Multiple documents separated by `---` are supported
+        #[clap(short = 'f', long)]
+        crd_file: PathBuf,
+    },
+    /// Prints what the operator would do for the functions defined in a file, without access to the cluster
+    ///
+    /// Shows which checks pass or fail, which resources would be generated and the resulting status.
+    /// Checks that need the cluster, e.g. whether secrets exist, are skipped.
+    /// If a live deployment is given, drift detection is run against it
+    #[clap(visible_alias = "plan")]
+    PrintReconcilePlan {
+        /// The path to the file to read the CRDs from
+        ///
+        /// Use `-` to read from stdin. Multiple documents separated by `---` are supported
+        #[clap(short = 'f', long)]
+        crd_file: PathBuf,
+        /// The path to a file with the live deployments of the functions, e.g. from kubectl get deployment -o yaml
+        ///
+        /// Multiple documents separated by `---` are supported
+        #[clap(short = 'd', long)]
+        deployment_file: Option<PathBuf>,
+    },
     /// Converts the CRDs to Kubernetes resources
     #[clap(visible_alias = "c")]
     Convert {
         /// The path to the file to read the CRDs from
+        ///
+        /// Use `-` to read from stdin. Multiple documents separated by `---` are supported
         #[clap(short = 'f', long)]
         crd_file: PathBuf,
 
@@ -251,19 +688,33 @@ mod test {
         let namespace_arg = String::from("functions");
         let update_strategy_arg = UpdateStrategy::OneWay;
 
-        let args =
-            Cli::operator_controller_run_args(namespace_arg.clone(), update_strategy_arg.clone());
+        let args = Cli::operator_controller_run_args(
+            namespace_arg.clone(),
+            update_strategy_arg.clone(),
+            true,
+            vec![],
+            true,
+            Some(String::from("shared-secrets")),
+        );
 
         let cli = Cli::parse_from(args);
 
         if let Commands::Operator { command } = cli.command {
             if let OperatorCommands::Controller {
                 functions_namespace,
+                all_namespaces,
                 update_strategy,
-                command: OperatorSubCommands::Run {},
+                verify_runtime_class,
+                secrets_namespace,
+                command,
+                ..
             } = *command
             {
+                assert!(matches!(*command, OperatorSubCommands::Run {}));
                 assert_eq!(functions_namespace, namespace_arg);
+                assert!(all_namespaces);
+                assert!(verify_runtime_class);
+                assert_eq!(secrets_namespace.as_deref(), Some("shared-secrets"));
                 assert_eq!(update_strategy, update_strategy_arg);
                 return;
             }
@@ -271,4 +722,59 @@ mod test {
 
         panic!("Operator controller run args are invalid");
     }
+
+    #[test]
+    fn functions_namespaces_are_passed_to_the_controller() {
+        let args = Cli::operator_controller_run_args(
+            String::from("functions"),
+            UpdateStrategy::default(),
+            false,
+            vec![String::from("team-a"), String::from("team-b")],
+            false,
+            None,
+        );
+
+        let cli = Cli::parse_from(args);
+
+        let Commands::Operator { command } = cli.command else {
+            panic!("Operator controller run args are invalid");
+        };
+
+        let OperatorCommands::Controller {
+            functions_namespaces,
+            ..
+        } = *command
+        else {
+            panic!("Operator controller run args are invalid");
+        };
+
+        assert_eq!(functions_namespaces, ["team-a", "team-b"]);
+
+        assert!(Cli::try_parse_from([
+            "operator",
+            "controller",
+            "--all-namespaces",
+            "--functions-namespaces",
+            "team-a",
+            "run",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn trace_sample_rate_must_be_a_fraction() {
+        assert_eq!(parse_sample_rate("0.25"), Ok(0.25));
+        assert!(parse_sample_rate("1.5").is_err());
+        assert!(parse_sample_rate("half").is_err());
+    }
+
+    #[test]
+    fn default_pod_labels_are_key_value_pairs() {
+        assert_eq!(
+            parse_label("cost-center=platform"),
+            Ok((String::from("cost-center"), String::from("platform")))
+        );
+        assert!(parse_label("cost-center").is_err());
+        assert!(parse_label("=platform").is_err());
+    }
 }
@@ -6,8 +6,77 @@ pub const FUNCTIONS_DEFAULT_NAMESPACE: &str = "openfaas-fn";
 pub const GATEWAY_URL_ENV_VAR: &str = "OPENFAAS_GATEWAY_URL";
 pub const GATEWAY_DEFAULT_URL: &str = "http://gateway.openfaas:8080";
 
+pub const FIELD_MANAGER_ENV_VAR: &str = "OPF_FO_FIELD_MANAGER";
+pub const FIELD_MANAGER_DEFAULT: &str = "openfaasfunctions-operator";
+
+pub const LOG_FORMAT_ENV_VAR: &str = "OPF_FO_LOG_FORMAT";
+pub const QUIET_ENV_VAR: &str = "OPF_FO_QUIET";
+pub const JSON_LOGS_INCLUDE_SPAN_LIST_ENV_VAR: &str = "OPF_FO_JSON_LOGS_INCLUDE_SPAN_LIST";
+pub const OTLP_ENDPOINT_ENV_VAR: &str = "OPF_FO_OTLP_ENDPOINT";
+pub const TRACE_SAMPLE_RATE_ENV_VAR: &str = "OPF_FO_TRACE_SAMPLE_RATE";
+
 pub const OPF_FO_C_UPDATE_STRATEGY_ENV_VAR: &str = "OPF_FO_C_UPDATE_STRATEGY";
 
+pub const OPF_FO_C_UPDATE_MODE_ENV_VAR: &str = "OPF_FO_C_UPDATE_MODE";
+pub const OPF_FO_C_REQUIRE_NAMESPACE_ENV_VAR: &str = "OPF_FO_C_REQUIRE_NAMESPACE";
+pub const OPF_FO_C_REQUIRE_RBAC_ENV_VAR: &str = "OPF_FO_C_REQUIRE_RBAC";
+pub const OPF_FO_C_MAX_RECONCILE_DURATION_ENV_VAR: &str = "OPF_FO_C_MAX_RECONCILE_DURATION";
+pub const OPF_FO_C_SECRETS_NAMESPACE_ENV_VAR: &str = "OPF_FO_C_SECRETS_NAMESPACE";
+pub const OPF_FO_C_NO_IMAGE_NORMALIZATION_ENV_VAR: &str = "OPF_FO_C_NO_IMAGE_NORMALIZATION";
+pub const OPF_FO_C_DEFAULT_POD_LABELS_ENV_VAR: &str = "OPF_FO_C_DEFAULT_POD_LABELS";
+pub const OPF_FO_C_IMAGE_PULL_SECRETS_SERVICE_ACCOUNT_ENV_VAR: &str =
+    "OPF_FO_C_IMAGE_PULL_SECRETS_SERVICE_ACCOUNT";
+
+pub const OPF_FO_C_VERIFY_RUNTIME_CLASS_ENV_VAR: &str = "OPF_FO_C_VERIFY_RUNTIME_CLASS";
+
+pub const OPF_FO_C_RECONCILE_CONCURRENCY_ENV_VAR: &str = "OPF_FO_C_RECONCILE_CONCURRENCY";
+
+pub const OPF_FO_C_RECONCILE_DEBOUNCE_MILLIS_ENV_VAR: &str = "OPF_FO_C_RECONCILE_DEBOUNCE_MILLIS";
+
+pub const OPF_FO_C_RECONCILE_JITTER_STARTUP_ENV_VAR: &str = "OPF_FO_C_RECONCILE_JITTER_STARTUP";
+pub const OPF_FO_C_WATCH_BUFFER_SIZE_ENV_VAR: &str = "OPF_FO_C_WATCH_BUFFER_SIZE";
+
+pub const OPF_FO_C_MAX_ENV_BYTES_ENV_VAR: &str = "OPF_FO_C_MAX_ENV_BYTES";
+
+pub const OPF_FO_C_ADOPT_ORPHANS_ENV_VAR: &str = "OPF_FO_C_ADOPT_ORPHANS";
+
+pub const OPF_FO_C_PRESERVE_FIELDS_ENV_VAR: &str = "OPF_FO_C_PRESERVE_FIELDS";
+
+pub const OPF_FO_C_PRESERVE_ANNOTATIONS_ENV_VAR: &str = "OPF_FO_C_PRESERVE_ANNOTATIONS";
+
+pub const OPF_FO_C_WAIT_FOR_READY_ON_RENAME_ENV_VAR: &str = "OPF_FO_C_WAIT_FOR_READY_ON_RENAME";
+
+pub const OPF_FO_C_ERROR_REQUEUE_SECONDS_ENV_VAR: &str = "OPF_FO_C_ERROR_REQUEUE_SECONDS";
+
+pub const OPF_FO_C_MAX_ERROR_REQUEUE_SECONDS_ENV_VAR: &str = "OPF_FO_C_MAX_ERROR_REQUEUE_SECONDS";
+
+pub const OPF_FO_C_ALL_NAMESPACES_ENV_VAR: &str = "OPF_FO_C_ALL_NAMESPACES";
+
+pub const OPF_FO_C_FUNCTIONS_NAMESPACES_ENV_VAR: &str = "OPF_FO_C_FUNCTIONS_NAMESPACES";
+
+pub const OPF_FO_C_ENABLE_LEADER_ELECTION_ENV_VAR: &str = "OPF_FO_C_ENABLE_LEADER_ELECTION";
+
+pub const OPF_FO_C_LEADER_ELECTION_ID_ENV_VAR: &str = "OPF_FO_C_LEADER_ELECTION_ID";
+
+pub const LEADER_ELECTION_DEFAULT_ID: &str = "openfaas-functions-operator";
+
+pub const OPF_FO_C_METRICS_ADDR_ENV_VAR: &str = "OPF_FO_C_METRICS_ADDR";
+
+pub const METRICS_DEFAULT_ADDR: &str = "0.0.0.0:8080";
+
+/// Port of [`METRICS_DEFAULT_ADDR`], probed by the operator's deployment
+pub const METRICS_DEFAULT_PORT: i32 = 8080;
+
+pub const OPF_FO_CL_RETRIES_ENV_VAR: &str = "OPF_FO_CL_RETRIES";
+
+pub const OPF_FO_CL_TIMEOUT_SECONDS_ENV_VAR: &str = "OPF_FO_CL_TIMEOUT_SECONDS";
+
+pub const OPF_FO_CL_RETRY_BACKOFF_MILLIS_ENV_VAR: &str = "OPF_FO_CL_RETRY_BACKOFF_MILLIS";
+
+pub const OPF_FO_CL_CA_CERT_PATH_ENV_VAR: &str = "OPF_FO_CL_CA_CERT_PATH";
+
+pub const OPF_FO_CL_ACCEPT_INVALID_CERTS_ENV_VAR: &str = "OPF_FO_CL_ACCEPT_INVALID_CERTS";
+
 pub const PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
 pub const PKG_NAME: &str = env!("CARGO_PKG_NAME");
 
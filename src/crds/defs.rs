@@ -1,20 +1,84 @@
-use k8s_openapi::apimachinery::pkg::{api::resource::Quantity, apis::meta::v1::Time};
+use crate::utils::InvalidImageReferenceError;
+use k8s_openapi::api::core::v1::{Affinity, Toleration};
+use k8s_openapi::api::networking::v1::{NetworkPolicyEgressRule, NetworkPolicyPeer};
+use k8s_openapi::apimachinery::pkg::{
+    api::resource::Quantity, apis::meta::v1::Time, util::intstr::IntOrString,
+};
 use kube::CustomResource;
 use kube_quantity::ParseQuantityError;
-use schemars::JsonSchema;
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Schema, SchemaObject},
+    JsonSchema,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Error as SerdeJsonError;
 use serde_yaml::Error as SerdeYamlError;
 use std::collections::HashMap;
 use thiserror::Error as ThisError;
 
+// The `#[kube(...)]` attributes of `OpenFaasFunctionSpec` only accept literals,
+// so these must be kept in sync with them. Prefer the `kube::Resource` and `CustomResourceExt`
+// methods of `OpenFaaSFunction` over these consts when generating resources.
 pub const GROUP: &str = "operato.rs";
 pub const VERSION: &str = "v1alpha1";
 pub const KIND: &str = "OpenFaaSFunction";
 pub const PLURAL: &str = "openfaasfunctions";
 pub const NAME: &str = "openfaasfunctions.operato.rs";
 pub const FINALIZER_NAME: &str = "openfaasfunctions.operato.rs/finalizer";
+pub const FUNCTION_LABEL: &str = "faas_function";
+/// Gateway-style minimum number of replicas, superseded by `replicas` if both are set
+pub const SCALE_MIN_LABEL: &str = "com.openfaas.scale.min";
 pub const LAST_APPLIED_ANNOTATION: &str = "openfaasfunctions.operato.rs/last-applied-spec";
+pub const SOURCE_GENERATION_ANNOTATION: &str = "openfaasfunctions.operato.rs/source-generation";
+pub const RESTARTED_AT_ANNOTATION: &str = "openfaasfunctions.operato.rs/restarted-at";
+/// Set on functions sharing one service, e.g. for canary rollouts. The value is the name of the group's primary function
+pub const SERVICE_GROUP_ANNOTATION: &str = "openfaasfunctions.operato.rs/service-group";
+/// Added to the pods of every function in a service group, selected by the primary function's service
+pub const SERVICE_GROUP_LABEL: &str = "openfaasfunctions.operato.rs/service-group";
+pub const DEFAULT_POD_LABELS_ANNOTATION: &str = "openfaasfunctions.operato.rs/default-pod-labels";
+/// Annotations with this prefix are managed by the operator and can not be set in a function's spec
+pub const RESERVED_ANNOTATION_PREFIX: &str = "openfaasfunctions.operato.rs/";
+
+/// Linux rejects single environment strings (NAME=value) larger than this when starting a process
+pub const MAX_ENV_VAR_BYTES: usize = 128 * 1024;
+/// Default limit for the summed size of a function's environment
+pub const DEFAULT_MAX_ENV_BYTES: usize = 1024 * 1024;
+
+/// The port of the OpenFaaS watchdog, the service always exposes the function on it
+pub const DEFAULT_CONTAINER_PORT: i32 = 8080;
+pub const DEFAULT_HTTP_PROBE_PATH: &str = "/_/health";
+
+/// Values accepted in `terminationMessagePolicy`
+pub const TERMINATION_MESSAGE_POLICIES: [&str; 2] = ["File", "FallbackToLogsOnError"];
+
+/// Values accepted in `deploymentStrategyType`
+pub const DEPLOYMENT_STRATEGY_TYPES: [&str; 2] = ["RollingUpdate", "Recreate"];
+
+/// Values accepted in `imagePullPolicy`
+pub const IMAGE_PULL_POLICIES: [&str; 3] = ["Always", "IfNotPresent", "Never"];
+
+/// Values accepted in `serviceType`, ExternalName services can not route to the function's pods
+pub const SERVICE_TYPES: [&str; 3] = ["ClusterIP", "NodePort", "LoadBalancer"];
+
+/// Unprefixed application protocols accepted in `appProtocol`, custom protocols must be prefixed with a domain, e.g. example.com/my-protocol
+pub const APP_PROTOCOLS: [&str; 8] = [
+    "http",
+    "https",
+    "http2",
+    "grpc",
+    "tcp",
+    "kubernetes.io/h2c",
+    "kubernetes.io/ws",
+    "kubernetes.io/wss",
+];
+
+/// Environment variables that can be requested in `downwardEnv` and the pod fields they are populated from
+pub const DOWNWARD_ENV_FIELDS: [(&str, &str); 3] = [
+    ("POD_NAME", "metadata.name"),
+    ("POD_NAMESPACE", "metadata.namespace"),
+    ("NODE_NAME", "spec.nodeName"),
+];
 
 #[derive(CustomResource, Serialize, Deserialize, Debug, PartialEq, Clone, JsonSchema)]
 #[kube(
@@ -44,12 +108,33 @@ pub struct OpenFaasFunctionSpec {
     /// envVars can be provided to set environment variables for the function runtime
     pub env_vars: Option<HashMap<String, String>>,
 
+    /// downwardEnv lists environment variables populated from the pod's own fields
+    /// supported values are POD_NAME, POD_NAMESPACE and NODE_NAME
+    pub downward_env: Option<Vec<String>>,
+
     /// constraints are specific to the faas-provider.
+    /// key==value constraints produce the node selector, key!=value constraints a required node affinity
     pub constraints: Option<Vec<String>>,
 
+    /// tolerations allow the function's pods to be scheduled onto nodes with matching taints, e.g. GPU nodes
+    pub tolerations: Option<Vec<Toleration>>,
+
+    /// affinity are the node affinity, pod affinity and pod anti-affinity rules of the function's pods
+    /// placed on the pods as is, independent from constraints, which still produce the node selector
+    /// if both are set, a node must satisfy both the node selector and the node affinity
+    pub affinity: Option<Affinity>,
+
     /// list of names of secrets in the same namespace that will be mounted to secretsMountPath
     pub secrets: Option<Vec<String>>,
 
+    /// imagePullSecrets is a list of names of secrets in the same namespace used to pull the function's image from a private registry
+    /// added to the image pull secrets of the operator's service account, if configured
+    pub image_pull_secrets: Option<Vec<String>>,
+
+    /// imagePullPolicy is the pull policy of the function's image, Always, IfNotPresent or Never
+    /// defaults to the Kubernetes default, Always for :latest and untagged images, IfNotPresent otherwise
+    pub image_pull_policy: Option<String>,
+
     /// labels are metadata for functions which may be used by the
     /// faas-provider or the gateway
     pub labels: Option<HashMap<String, String>>,
@@ -68,13 +153,185 @@ pub struct OpenFaasFunctionSpec {
     /// mount-point.
     pub read_only_root_filesystem: Option<bool>,
 
+    /// tmpVolume overrides whether a writable emptyDir volume is mounted to tmpMountPath
+    /// defaults to the value of readOnlyRootFilesystem
+    pub tmp_volume: Option<bool>,
+
+    /// tmpMountPath is the path where the tmp volume will be mounted
+    /// defaults to /tmp
+    pub tmp_mount_path: Option<String>,
+
+    /// terminationMessagePolicy controls how the function container's termination message is populated, File or FallbackToLogsOnError
+    /// FallbackToLogsOnError uses the last lines of the container's log if the message file is empty and the container failed
+    pub termination_message_policy: Option<String>,
+
+    /// terminationMessagePath is the file the function writes its termination message to
+    /// defaults to the Kubernetes default (/dev/termination-log)
+    pub termination_message_path: Option<String>,
+
+    /// enableServiceLinks controls whether environment variables for every service in the namespace are injected into the function's pods
+    /// setting it to false prevents overflowing the environment in busy namespaces, defaults to the Kubernetes default (true)
+    pub enable_service_links: Option<bool>,
+
+    /// httpProbePath is the path the liveness and readiness probes request
+    /// defaults to /_/health
+    pub http_probe_path: Option<String>,
+
+    /// containerPort is the port the function's watchdog listens on, the service keeps exposing the function on 8080
+    /// defaults to 8080
+    pub container_port: Option<i32>,
+
+    /// livenessProbe tunes the timings of the liveness probe, unset fields keep the Kubernetes defaults
+    /// e.g. a longer initialDelaySeconds keeps slow-starting functions from being restarted before they warmed up
+    pub liveness_probe: Option<ProbeConfig>,
+
+    /// readinessProbe tunes the timings of the readiness probe, unset fields keep the Kubernetes defaults
+    /// takes precedence over the timings derived from readinessGateSeconds
+    pub readiness_probe: Option<ProbeConfig>,
+
+    /// readinessGateSeconds is the total time a starting function may take to become ready, e.g. behind slow init containers
+    /// the readiness probe's initialDelaySeconds, periodSeconds and failureThreshold are derived from it,
+    /// and a startup probe covering the window holds back the liveness probe until the function started
+    pub readiness_gate_seconds: Option<u32>,
+
+    /// trafficWeight is the number of replicas of the function, at least 1
+    /// functions sharing a service group receive the group's traffic in proportion to their weights, e.g. 9 and 1 for a 10% canary
+    /// defaults to 1
+    pub traffic_weight: Option<u32>,
+
+    /// deploymentStrategyType is how the function's pods are replaced, RollingUpdate or Recreate
+    /// Recreate stops all old pods before starting new ones, e.g. for functions holding exclusive locks. Defaults to RollingUpdate
+    pub deployment_strategy_type: Option<String>,
+
+    /// maxSurge is the number or percentage of pods created above the desired replicas during a rolling update, e.g. 1 or 25%
+    /// defaults to 1
+    pub max_surge: Option<IntOrString>,
+
+    /// maxUnavailable is the number or percentage of pods that can be unavailable during a rolling update, e.g. 0 or 25%
+    /// can not be 0 if maxSurge is 0, defaults to 0
+    /// maxSurge and maxUnavailable are only allowed for the RollingUpdate strategy
+    pub max_unavailable: Option<IntOrString>,
+
+    /// deploymentPaused pauses the function's deployment, changes to the function are applied to the deployment
+    /// but not rolled out until it is resumed. The operator keeps reconciling the function and reporting its status
+    pub deployment_paused: Option<bool>,
+
+    /// runtimeClassName is the name of the RuntimeClass the function's pods run with, e.g. gvisor or kata
+    /// defaults to the cluster's default container runtime
+    pub runtime_class_name: Option<String>,
+
+    /// replicas is the number of pods of the function, 0 scales the function to zero
+    /// takes precedence over the com.openfaas.scale.min label, can not be combined with trafficWeight
+    /// defaults to 1
+    pub replicas: Option<i32>,
+
     /// secretsMountPath is the path where secrets will be mounted
     /// defaults to /var/openfaas/secrets
     pub secrets_mount_path: Option<String>,
+
+    /// caBundleConfigMap is the name of a config map in the same namespace containing a CA bundle
+    /// the bundle is mounted read-only to caBundleMountPath and SSL_CERT_FILE is set accordingly
+    pub ca_bundle_config_map: Option<String>,
+
+    /// caBundleKey is the key of the CA bundle in caBundleConfigMap
+    /// defaults to ca.crt
+    pub ca_bundle_key: Option<String>,
+
+    /// caBundleMountPath is the file path where the CA bundle will be mounted
+    /// defaults to /etc/ssl/certs/custom-ca.pem
+    pub ca_bundle_mount_path: Option<String>,
+
+    /// podDisruptionBudget creates a PodDisruptionBudget for the function's pods
+    /// if not set, no PodDisruptionBudget is created and an existing one is deleted
+    pub pod_disruption_budget: Option<PdbSpec>,
+
+    /// serviceAnnotations are added to the function's service only,
+    /// e.g. provider specific load balancer configuration
+    pub service_annotations: Option<HashMap<String, String>>,
+
+    /// serviceType is the type of the function's service, ClusterIP, NodePort or LoadBalancer
+    /// defaults to ClusterIP
+    pub service_type: Option<String>,
+
+    /// nodePort is the port the function is exposed on on every node
+    /// only allowed if the service is of type NodePort or LoadBalancer, allocated by Kubernetes if not set
+    pub node_port: Option<i32>,
+
+    /// loadBalancerClass is the class of the load balancer implementation the service belongs to
+    /// only allowed if the service is of type LoadBalancer
+    pub load_balancer_class: Option<String>,
+
+    /// appProtocol is the application protocol of the function's service port, e.g. kubernetes.io/h2c for HTTP/2 or grpc
+    /// used by service meshes to classify the function's traffic
+    pub app_protocol: Option<String>,
+
+    /// networkPolicy creates a NetworkPolicy for the function's pods, allowing ingress only from the given peers
+    /// if not set, no NetworkPolicy is created and an existing one is deleted
+    pub network_policy: Option<FunctionNetworkPolicy>,
+
+    /// podSpecOverlay is merged onto the generated pod spec as a strategic merge patch, for pod fields the operator does not model
+    /// containers, volumes and other named lists are merged by name, e.g. the function's container is named after the service
+    #[serde(default)]
+    #[schemars(schema_with = "pod_spec_overlay_schema")]
+    pub pod_spec_overlay: Option<serde_json::Value>,
+}
+
+/// An arbitrary object, validated by the operator instead of the API server
+fn pod_spec_overlay_schema(_: &mut SchemaGenerator) -> Schema {
+    let mut schema = SchemaObject {
+        instance_type: Some(InstanceType::Object.into()),
+        ..Default::default()
+    };
+
+    schema
+        .extensions
+        .insert(String::from("nullable"), serde_json::Value::Bool(true));
+    schema.extensions.insert(
+        String::from("x-kubernetes-preserve-unknown-fields"),
+        serde_json::Value::Bool(true),
+    );
+
+    Schema::Object(schema)
+}
+
+/// FunctionNetworkPolicy restricts the traffic of the function's pods
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionNetworkPolicy {
+    /// ingressFrom are the peers allowed to reach the function's port
+    /// defaults to the OpenFaaS gateway, pods labeled app=gateway in the openfaas namespace
+    pub ingress_from: Option<Vec<NetworkPolicyPeer>>,
+    /// egress are the rules for traffic leaving the function's pods
+    /// if not set, egress is not restricted, an empty list denies all egress
+    pub egress: Option<Vec<NetworkPolicyEgressRule>>,
+}
+
+/// ProbeConfig the timings of a probe of the function's container
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeConfig {
+    /// initialDelaySeconds is the number of seconds after the container has started before the first probe, at least 0
+    pub initial_delay_seconds: Option<i32>,
+    /// periodSeconds is the number of seconds between two probes, at least 1
+    pub period_seconds: Option<i32>,
+    /// timeoutSeconds is the number of seconds after which a probe times out, at least 1
+    pub timeout_seconds: Option<i32>,
+    /// failureThreshold is the number of consecutive failed probes after which the probe fails, at least 1
+    pub failure_threshold: Option<i32>,
+}
+
+/// PdbSpec either minAvailable or maxUnavailable must be set, but not both
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PdbSpec {
+    /// minAvailable is the number or percentage of pods that must remain available
+    pub min_available: Option<IntOrString>,
+    /// maxUnavailable is the number or percentage of pods that can be unavailable
+    pub max_unavailable: Option<IntOrString>,
 }
 
 /// FunctionResources Memory and CPU
-/// Must match ^([+-]?[0-9.]+)([eEinumkKMGTP][-+]?[0-9])$
+/// A decimal number with an optional suffix, e.g. 0.5, 500m, 1, 128Mi or 1G
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, JsonSchema)]
 pub struct FunctionResources {
     /// memory is the memory limit for the function
@@ -102,6 +359,8 @@ pub struct OpenFaasFunctionStatusCondition {
     pub message: OpenFaasFunctionStatusConditionMessage,
     pub reason: OpenFaasFunctionPossibleStatus,
     pub last_update_time: Option<Time>,
+    /// lastTransitionTime is the time the condition last changed its reason
+    pub last_transition_time: Option<Time>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone, JsonSchema)]
@@ -130,6 +389,106 @@ pub enum OpenFaasFunctionPossibleStatus {
     DeploymentNotReady,
     ServiceAlreadyExists,
     SecretsNotFound,
+    InvalidPodDisruptionBudget,
+    PodDisruptionBudgetAlreadyExists,
+    NetworkPolicyAlreadyExists,
+    InvalidDownwardEnv,
+    ConfigMapsNotFound,
+    InvalidMetadataKey,
+    ReconcileBackoff,
+    InvalidLoadBalancerClass,
+    ServiceNotReady,
+    ReservedAnnotationKey,
+    InvalidAppProtocol,
+    InvalidImageReference,
+    InvalidTerminationMessagePolicy,
+    InvalidImagePullPolicy,
+    InvalidDeploymentStrategy,
+    InvalidServiceType,
+    InvalidNodePort,
+    SecretsNotMountable,
+    RuntimeClassNotFound,
+    OwnedByAnotherFunction,
+    InvalidSpec,
+    EnvTooLarge,
+    ImagePullSecretsNotFound,
+}
+
+/// Who owns a resource named after a function
+#[derive(Debug, PartialEq, Clone)]
+pub enum ResourceOwnership {
+    /// Owned by the function itself
+    Owned,
+    /// Owned by another OpenFaaSFunction with the given name, e.g. one with the same service name
+    OwnedByAnotherFunction(String),
+    /// Not owned by any OpenFaaSFunction
+    ThirdParty,
+}
+
+/// How a resource that is generated only if the function's spec asks for it,
+/// e.g. a pod disruption budget or a network policy, is brought to its desired state
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum OptionalResourcePlan {
+    /// Requested and missing
+    Create,
+    /// Requested, owned and outdated
+    Replace,
+    /// Requested, owned and up to date
+    UpToDate,
+    /// Owned, but no longer requested
+    Delete,
+    /// Requested, but the name is taken by a resource the function does not own
+    Conflict,
+    /// Not requested, a resource with the same name that the function does not own is left alone
+    NotRequested,
+}
+
+/// A part of a function's deployment that drifted from what the function generates
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DeploymentDiff {
+    Labels,
+    Annotations,
+    /// The deployment has no spec
+    Spec,
+    PodLabels,
+    PodAnnotations,
+    Paused,
+    /// The node selector generated from the constraints
+    Constraints,
+    Affinity,
+    Tolerations,
+    RuntimeClassName,
+    Secrets,
+    /// The function's container is missing
+    Container,
+    Image,
+    EnvProcess,
+    EnvVars,
+    ReadOnlyRootFilesystem,
+    Limits,
+    Requests,
+}
+
+/// A part of a function's service that differs from what the function generates
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ServiceDiff {
+    /// The service's type or load balancer class
+    Type,
+    Ports,
+    Selector,
+    /// The provider annotations from the spec's serviceAnnotations
+    Annotations,
+}
+
+/// The combined readiness of a function's deployment and service
+#[derive(Debug, PartialEq, Clone)]
+pub enum FunctionReadiness {
+    Ready,
+    DeploymentMissing,
+    DeploymentNotReady,
+    ServiceMissing,
+    ServiceNotOwned,
+    ServiceSelectorMismatch,
 }
 
 #[derive(ThisError, Debug)]
@@ -138,6 +497,10 @@ pub enum FunctionSpecIntoYamlError {
     Deployment(FunctionSpecIntoDeploymentError),
     #[error("Failed to generate service: {0}")]
     Service(FunctionSpecIntoServiceError),
+    #[error("Failed to generate pod disruption budget: {0}")]
+    PodDisruptionBudget(FunctionSpecIntoPodDisruptionBudgetError),
+    #[error("Failed to generate network policy: {0}")]
+    NetworkPolicy(SerdeJsonError),
     #[error("Failed to serialize: {0}")]
     Serialize(
         #[source]
@@ -166,12 +529,112 @@ pub enum FunctionSpecIntoDeploymentError {
         #[from]
         SerdeJsonError,
     ),
-    #[error("Failed to parse quantity: {0} | Quantity must match ^([+-]?[0-9.]+)([eEinumkKMGTP][-+]?[0-9])$")]
+    #[error("Failed to parse quantity: {0} | Quantity must be a decimal number with an optional m, k, M, G, T, P, Ki, Mi, Gi, Ti or Pi suffix, e.g. 0.5, 500m, 1, 128Mi or 1G")]
     Quantity(
         #[source]
         #[from]
         IntoQuantityError,
     ),
+    #[error("Unsupported downward env var: {0}")]
+    DownwardEnv(String),
+    #[error("Unsupported terminationMessagePolicy: {0}")]
+    TerminationMessagePolicy(String),
+    #[error("Unsupported imagePullPolicy: {0}")]
+    ImagePullPolicy(String),
+    #[error("Unsupported deploymentStrategyType: {0}")]
+    DeploymentStrategyType(String),
+    #[error("Invalid image: {0}")]
+    ImageReference(
+        #[source]
+        #[from]
+        InvalidImageReferenceError,
+    ),
+    #[error("Invalid metadata: {0}")]
+    Metadata(
+        #[source]
+        #[from]
+        InvalidMetadataError,
+    ),
+    #[error("Invalid podSpecOverlay: {0}")]
+    PodSpecOverlay(String),
+}
+
+/// A single problem found by validating a function's spec without generating any resources
+#[derive(ThisError, Debug)]
+pub enum SpecValidationError {
+    #[error(
+        "invalid service name {0:?}, must be a lowercase RFC 1123 label of at most 63 characters"
+    )]
+    Name(String),
+    #[error("Invalid image: {0}")]
+    ImageReference(#[source] InvalidImageReferenceError),
+    #[error("{0}")]
+    Quantity(#[source] IntoQuantityError),
+    #[error("env var {0:?} is set more than once")]
+    EnvCollision(String),
+    #[error("invalid secret name {0:?}")]
+    SecretName(String),
+    #[error("invalid constraint {0:?}, must be of the form key==value or key!=value")]
+    Constraint(String),
+    #[error("Unsupported downward env var: {0}")]
+    DownwardEnv(String),
+    #[error("Unsupported terminationMessagePolicy: {0}")]
+    TerminationMessagePolicy(String),
+    #[error("Unsupported imagePullPolicy: {0}")]
+    ImagePullPolicy(String),
+    #[error("Invalid metadata: {0}")]
+    Metadata(#[source] InvalidMetadataError),
+    #[error("{0}")]
+    Service(#[source] FunctionSpecIntoServiceError),
+    #[error("{0}")]
+    PodDisruptionBudget(#[source] FunctionSpecIntoPodDisruptionBudgetError),
+    #[error("trafficWeight must be at least 1")]
+    TrafficWeight,
+    #[error("replicas must not be negative, got {0}")]
+    Replicas(i32),
+    #[error("replicas and trafficWeight can not both be set")]
+    ConflictingReplicas,
+    #[error("{0} must be a non-negative number or a percentage, e.g. 25%")]
+    RollingUpdateValue(&'static str),
+    #[error("maxSurge and maxUnavailable can not both be 0")]
+    RollingUpdateZero,
+    #[error("Unsupported deploymentStrategyType: {0}")]
+    DeploymentStrategyType(String),
+    #[error("maxSurge and maxUnavailable are only allowed for the RollingUpdate strategy")]
+    RollingUpdateWithRecreate,
+    #[error("Invalid podSpecOverlay: {0}")]
+    PodSpecOverlay(String),
+    #[error("containerPort must be between 1 and 65535, got {0}")]
+    ContainerPort(i32),
+    #[error("httpProbePath must be an absolute path, got {0:?}")]
+    HttpProbePath(String),
+    #[error("{probe}.{field} must be at least {min}, got {value}")]
+    ProbeConfig {
+        probe: &'static str,
+        field: &'static str,
+        min: i32,
+        value: i32,
+    },
+    #[error("env var {name:?} is {size} bytes, at most {max} bytes are allowed, use a ConfigMap or a Secret instead")]
+    EnvVarTooLarge {
+        name: String,
+        size: usize,
+        max: usize,
+    },
+    #[error("environment is {size} bytes, at most {max} bytes are allowed, use a ConfigMap or a Secret instead")]
+    EnvTooLarge { size: usize, max: usize },
+}
+
+#[derive(ThisError, Debug)]
+pub enum InvalidMetadataError {
+    #[error("invalid label key {0:?}")]
+    LabelKey(String),
+    #[error("invalid value {value:?} for label {key:?}")]
+    LabelValue { key: String, value: String },
+    #[error("invalid annotation key {0:?}")]
+    AnnotationKey(String),
+    #[error("annotation key {0:?} is reserved for the operator")]
+    ReservedAnnotationKey(String),
 }
 
 #[derive(ThisError, Debug)]
@@ -194,6 +657,52 @@ pub enum FunctionSpecIntoServiceError {
         #[from]
         SerdeJsonError,
     ),
+    #[error("loadBalancerClass is only allowed for services of type LoadBalancer")]
+    LoadBalancerClass,
+    #[error("Unsupported serviceType: {0}")]
+    ServiceType(String),
+    #[error("nodePort is only allowed for services of type NodePort or LoadBalancer")]
+    NodePort,
+    #[error("nodePort must be between 1 and 65535, got {0}")]
+    NodePortRange(i32),
+    #[error("Unsupported appProtocol: {0}")]
+    AppProtocol(String),
+}
+
+#[derive(ThisError, Debug)]
+pub enum FunctionIntoPodDisruptionBudgetError {
+    #[error("Failed to get owner reference")]
+    OwnerReference,
+    #[error("Failed to generate pod disruption budget from spec: {0}")]
+    FunctionSpec(
+        #[source]
+        #[from]
+        FunctionSpecIntoPodDisruptionBudgetError,
+    ),
+}
+
+#[derive(ThisError, Debug)]
+pub enum FunctionSpecIntoPodDisruptionBudgetError {
+    #[error("Faild to serialize: {0}")]
+    Serialize(
+        #[source]
+        #[from]
+        SerdeJsonError,
+    ),
+    #[error("Either minAvailable or maxUnavailable must be set, but not both")]
+    MinAvailableOrMaxUnavailable,
+}
+
+#[derive(ThisError, Debug)]
+pub enum FunctionIntoNetworkPolicyError {
+    #[error("Failed to get owner reference")]
+    OwnerReference,
+    #[error("Faild to serialize: {0}")]
+    Serialize(
+        #[source]
+        #[from]
+        SerdeJsonError,
+    ),
 }
 
 #[derive(ThisError, Debug)]
@@ -203,3 +712,25 @@ pub enum IntoQuantityError {
     #[error("Failed to parse memory quantity: {0}")]
     Memory(#[source] ParseQuantityError),
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use kube::{CustomResourceExt, Resource};
+
+    #[test]
+    fn consts_match_crd() {
+        let crd = OpenFaaSFunction::crd();
+
+        assert_eq!(GROUP, crd.spec.group);
+        assert_eq!(KIND, crd.spec.names.kind);
+        assert_eq!(PLURAL, crd.spec.names.plural);
+        assert_eq!(NAME, OpenFaaSFunction::crd_name());
+        assert_eq!(VERSION, OpenFaaSFunction::version(&()));
+        assert!(FINALIZER_NAME.starts_with(&format!("{NAME}/")));
+        assert!(LAST_APPLIED_ANNOTATION.starts_with(&format!("{NAME}/")));
+        assert!(SOURCE_GENERATION_ANNOTATION.starts_with(&format!("{NAME}/")));
+        assert!(RESTARTED_AT_ANNOTATION.starts_with(&format!("{NAME}/")));
+        assert_eq!(RESERVED_ANNOTATION_PREFIX, format!("{NAME}/"));
+    }
+}
@@ -0,0 +1,165 @@
+use super::defs::{
+    FunctionNetworkPolicy, FunctionResources, OpenFaaSFunction, OpenFaasFunctionSpec, PdbSpec,
+    ProbeConfig,
+};
+use k8s_openapi::{api::core::v1::Toleration, apimachinery::pkg::util::intstr::IntOrString};
+use kube::CustomResourceExt;
+use serde_yaml::Error as SerdeYamlError;
+use std::collections::HashMap;
+
+impl OpenFaaSFunction {
+    /// A function with every field set to a representative value
+    ///
+    /// loadBalancerClass is left out, since the function's service is not of type LoadBalancer
+    pub fn example() -> Self {
+        let spec = OpenFaasFunctionSpec {
+            service: String::from("nodeinfo"),
+            image: String::from("ghcr.io/openfaas/nodeinfo:latest"),
+            namespace: Some(String::from("openfaas-fn")),
+            env_process: Some(String::from("node index.js")),
+            env_vars: Some(HashMap::from([(
+                String::from("write_debug"),
+                String::from("true"),
+            )])),
+            downward_env: Some(vec![String::from("POD_NAME"), String::from("NODE_NAME")]),
+            constraints: Some(vec![String::from("kubernetes.io/arch==amd64")]),
+            tolerations: Some(vec![Toleration {
+                key: Some(String::from("nvidia.com/gpu")),
+                operator: Some(String::from("Exists")),
+                effect: Some(String::from("NoSchedule")),
+                ..Default::default()
+            }]),
+            affinity: None,
+            secrets: Some(vec![String::from("api-key")]),
+            image_pull_secrets: Some(vec![String::from("registry-credentials")]),
+            image_pull_policy: Some(String::from("Always")),
+            labels: Some(HashMap::from([(
+                String::from("com.openfaas.scale.min"),
+                String::from("1"),
+            )])),
+            annotations: Some(HashMap::from([(
+                String::from("topic"),
+                String::from("nodeinfo"),
+            )])),
+            limits: Some(FunctionResources {
+                memory: Some(String::from("128Mi")),
+                cpu: Some(String::from("200m")),
+            }),
+            requests: Some(FunctionResources {
+                memory: Some(String::from("64Mi")),
+                cpu: Some(String::from("100m")),
+            }),
+            read_only_root_filesystem: Some(true),
+            tmp_volume: Some(true),
+            tmp_mount_path: Some(String::from("/tmp")),
+            termination_message_policy: Some(String::from("FallbackToLogsOnError")),
+            termination_message_path: Some(String::from("/dev/termination-log")),
+            enable_service_links: Some(false),
+            http_probe_path: None,
+            container_port: None,
+            liveness_probe: Some(ProbeConfig {
+                initial_delay_seconds: Some(30),
+                ..Default::default()
+            }),
+            readiness_probe: None,
+            readiness_gate_seconds: Some(60),
+            traffic_weight: None,
+            deployment_strategy_type: Some(String::from("RollingUpdate")),
+            max_surge: Some(IntOrString::Int(1)),
+            max_unavailable: Some(IntOrString::String(String::from("25%"))),
+            deployment_paused: None,
+            runtime_class_name: None,
+            replicas: None,
+            secrets_mount_path: Some(String::from("/var/openfaas/secrets")),
+            ca_bundle_config_map: Some(String::from("ca-bundle")),
+            ca_bundle_key: Some(String::from("ca.crt")),
+            ca_bundle_mount_path: Some(String::from("/etc/ssl/certs/custom-ca.pem")),
+            pod_disruption_budget: Some(PdbSpec {
+                min_available: Some(IntOrString::Int(1)),
+                max_unavailable: None,
+            }),
+            service_annotations: Some(HashMap::from([(
+                String::from("prometheus.io/scrape"),
+                String::from("false"),
+            )])),
+            service_type: Some(String::from("NodePort")),
+            node_port: Some(31112),
+            load_balancer_class: None,
+            app_protocol: Some(String::from("http")),
+            network_policy: Some(FunctionNetworkPolicy {
+                ingress_from: None,
+                egress: None,
+            }),
+            pod_spec_overlay: None,
+        };
+
+        let mut function = OpenFaaSFunction::new("nodeinfo", spec);
+        function.metadata.namespace = Some(String::from("openfaas-fn"));
+
+        function
+    }
+
+    /// The example function as YAML, with the spec fields annotated by their descriptions from the CRD schema
+    pub fn example_yaml() -> Result<String, SerdeYamlError> {
+        let yaml = serde_yaml::to_string(&Self::example())?;
+        let descriptions = Self::spec_field_descriptions();
+
+        let mut in_spec = false;
+        let mut annotated = String::new();
+
+        for line in yaml.lines() {
+            if !line.starts_with(' ') {
+                in_spec = line == "spec:";
+            } else if in_spec && !line.starts_with("   ") {
+                let field = line.trim_start().split(':').next().unwrap_or_default();
+
+                if let Some(description) = descriptions.get(field) {
+                    for description_line in description.lines() {
+                        annotated.push_str(&format!("  # {description_line}\n"));
+                    }
+                }
+            }
+
+            annotated.push_str(line);
+            annotated.push('\n');
+        }
+
+        Ok(annotated)
+    }
+
+    fn spec_field_descriptions() -> HashMap<String, String> {
+        OpenFaaSFunction::crd()
+            .spec
+            .versions
+            .into_iter()
+            .next()
+            .and_then(|version| version.schema)
+            .and_then(|schema| schema.open_api_v3_schema)
+            .and_then(|schema| schema.properties)
+            .and_then(|mut properties| properties.remove("spec"))
+            .and_then(|spec| spec.properties)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(field, schema)| schema.description.map(|d| (field, d)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn example_is_valid_and_annotated() {
+        let example = OpenFaaSFunction::example();
+        assert!(example.spec.to_yaml_string().is_ok());
+
+        let yaml = OpenFaaSFunction::example_yaml().expect("Failed to serialize example");
+        assert!(yaml
+            .contains("  # service is the name of the function deployment\n  service: nodeinfo"));
+
+        let parsed: OpenFaaSFunction =
+            serde_yaml::from_str(&yaml).expect("Failed to parse example");
+        assert_eq!(parsed, example);
+    }
+}
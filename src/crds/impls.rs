@@ -1,30 +1,49 @@
 use super::defs::{
-    FunctionIntoDeploymentError, FunctionIntoServiceError, FunctionResources,
-    FunctionResourcesQuantity, FunctionSpecIntoDeploymentError, FunctionSpecIntoServiceError,
-    FunctionSpecIntoYamlError, IntoQuantityError, OpenFaaSFunction, OpenFaasFunctionPossibleStatus,
-    OpenFaasFunctionSpec, OpenFaasFunctionStatus, OpenFaasFunctionStatusCondition,
-    OpenFaasFunctionStatusConditionMessage, OpenFaasFunctionStatusConditionStatus,
-    OpenFaasFunctionStatusConditionType, LAST_APPLIED_ANNOTATION,
+    DeploymentDiff, FunctionIntoDeploymentError, FunctionIntoNetworkPolicyError,
+    FunctionIntoPodDisruptionBudgetError, FunctionIntoServiceError, FunctionReadiness,
+    FunctionResources, FunctionResourcesQuantity, FunctionSpecIntoDeploymentError,
+    FunctionSpecIntoPodDisruptionBudgetError, FunctionSpecIntoServiceError,
+    FunctionSpecIntoYamlError, IntoQuantityError, InvalidMetadataError, OpenFaaSFunction,
+    OpenFaasFunctionPossibleStatus, OpenFaasFunctionSpec, OpenFaasFunctionStatus,
+    OpenFaasFunctionStatusCondition, OpenFaasFunctionStatusConditionMessage,
+    OpenFaasFunctionStatusConditionStatus, OpenFaasFunctionStatusConditionType,
+    OptionalResourcePlan, ProbeConfig, ResourceOwnership, ServiceDiff, SpecValidationError,
+    APP_PROTOCOLS, DEFAULT_CONTAINER_PORT, DEFAULT_HTTP_PROBE_PATH, DEFAULT_POD_LABELS_ANNOTATION,
+    DEPLOYMENT_STRATEGY_TYPES, DOWNWARD_ENV_FIELDS, FUNCTION_LABEL, IMAGE_PULL_POLICIES,
+    LAST_APPLIED_ANNOTATION, MAX_ENV_VAR_BYTES, RESERVED_ANNOTATION_PREFIX,
+    RESTARTED_AT_ANNOTATION, SCALE_MIN_LABEL, SERVICE_GROUP_ANNOTATION, SERVICE_GROUP_LABEL,
+    SERVICE_TYPES, SOURCE_GENERATION_ANNOTATION, TERMINATION_MESSAGE_POLICIES,
 };
-use crate::utils;
+use crate::utils::{self, ImageReference};
 use itertools::Itertools;
 use k8s_openapi::{
     api::{
         apps::v1::{Deployment, DeploymentSpec, DeploymentStrategy, RollingUpdateDeployment},
         core::v1::{
-            Container, ContainerPort, EnvVar, HTTPGetAction, KeyToPath, PodSpec, PodTemplateSpec,
-            Probe, ProjectedVolumeSource, ResourceRequirements, SecretProjection, SecurityContext,
-            Service, ServicePort, ServiceSpec, Volume, VolumeMount, VolumeProjection,
+            Affinity, ConfigMapVolumeSource, Container, ContainerPort, EnvVar, EnvVarSource,
+            HTTPGetAction, KeyToPath, LocalObjectReference, NodeAffinity, NodeSelector,
+            NodeSelectorRequirement, NodeSelectorTerm, ObjectFieldSelector, PodSpec,
+            PodTemplateSpec, Probe, ProjectedVolumeSource, ResourceRequirements, SecretProjection,
+            SecurityContext, Service, ServicePort, ServiceSpec, Volume, VolumeMount,
+            VolumeProjection,
         },
+        networking::v1::{
+            NetworkPolicy, NetworkPolicyIngressRule, NetworkPolicyPeer, NetworkPolicyPort,
+            NetworkPolicySpec,
+        },
+        policy::v1::{PodDisruptionBudget, PodDisruptionBudgetSpec},
     },
     apimachinery::pkg::{
         api::resource::Quantity,
-        apis::meta::v1::{LabelSelector, Time},
+        apis::meta::v1::{LabelSelector, OwnerReference, Time},
         util::intstr::IntOrString,
     },
     chrono,
 };
-use kube::core::{ObjectMeta, Resource};
+use kube::{
+    core::{ObjectMeta, Resource},
+    ResourceExt,
+};
 use kube_quantity::ParsedQuantity;
 use serde_json::Error as SerdeJsonError;
 use std::collections::BTreeMap;
@@ -80,1163 +99,4966 @@ impl TryFrom<&FunctionResources> for FunctionResourcesQuantity {
 }
 
 impl OpenFaasFunctionSpec {
-    pub fn deployment_needs_recreation(&self, deployment: &Deployment) -> bool {
-        let prev_spec = match serde_json::from_str::<OpenFaasFunctionSpec>(
-            deployment
-                .metadata
-                .annotations
+    fn previous_spec(deployment: &Deployment) -> Option<OpenFaasFunctionSpec> {
+        Self::previous_spec_of(&deployment.metadata)
+    }
+
+    /// The spec recorded in the last applied annotation of a resource generated from the function
+    fn previous_spec_of(meta: &ObjectMeta) -> Option<OpenFaasFunctionSpec> {
+        serde_json::from_str::<OpenFaasFunctionSpec>(
+            meta.annotations
                 .as_ref()
                 .unwrap_or(&BTreeMap::new())
                 .get(LAST_APPLIED_ANNOTATION)
                 .unwrap_or(&String::from("")),
-        ) {
-            Ok(prev_spec) => prev_spec,
-            Err(_) => {
-                tracing::error!("Previous spec missing or corrupted => recreate!");
-                return true;
-            }
-        };
-
-        self != &prev_spec
+        )
+        .ok()
     }
 
-    fn should_create_tmp_volume(&self) -> bool {
-        self.read_only_root_filesystem.unwrap_or(false)
-    }
+    /// Fields of the last applied spec that this version of the operator does not know.
+    ///
+    /// Unknown fields are ignored when deserializing, so a spec applied by a newer operator
+    /// still parses, but changes to these fields are not reconciled by this operator
+    pub fn unknown_fields(last_applied: &str) -> Vec<String> {
+        let Ok(serde_json::Value::Object(applied)) = serde_json::from_str(last_applied) else {
+            return vec![];
+        };
 
-    fn should_create_secrets_volume(&self) -> bool {
-        !self.secrets.as_ref().unwrap_or(&vec![]).is_empty()
-    }
+        let Ok(serde_json::Value::Object(known)) =
+            serde_json::from_str::<OpenFaasFunctionSpec>(last_applied)
+                .and_then(serde_json::to_value)
+        else {
+            return vec![];
+        };
 
-    pub fn get_secrets_unique_vec(&self) -> Vec<String> {
-        self.secrets
-            .clone()
-            .unwrap_or(vec![])
+        applied
             .into_iter()
-            .unique()
+            .map(|(key, _)| key)
+            .filter(|key| !known.contains_key(key))
             .collect()
     }
 
-    pub fn get_constraints_vec(&self) -> Vec<String> {
-        self.constraints.clone().unwrap_or(vec![])
-    }
+    pub fn deployment_needs_recreation(&self, deployment: &Deployment) -> bool {
+        let Some(last_applied) = deployment
+            .metadata
+            .annotations
+            .as_ref()
+            .and_then(|annotations| annotations.get(LAST_APPLIED_ANNOTATION))
+        else {
+            tracing::error!("Previous spec missing => recreate!");
+            return true;
+        };
 
-    fn to_env_process_name(&self) -> String {
-        String::from("fprocess")
-    }
+        let prev_spec = match serde_json::from_str::<OpenFaasFunctionSpec>(last_applied) {
+            Ok(prev_spec) => prev_spec,
+            Err(error) => {
+                tracing::error!(%error, "Previous spec corrupted or written by an incompatible operator version => recreate!");
+                return true;
+            }
+        };
 
-    pub fn to_name(&self) -> String {
-        self.service.clone()
-    }
+        let unknown_fields = Self::unknown_fields(last_applied);
+        if !unknown_fields.is_empty() {
+            tracing::warn!(
+                fields = %unknown_fields.join(", "),
+                "Previous spec contains fields unknown to this operator version. It was probably applied by a newer operator, these fields are ignored."
+            );
+        }
 
-    fn to_namespace(&self) -> Option<String> {
-        self.namespace.clone()
+        self != &prev_spec || self.deployment_secrets_changed(deployment)
     }
 
-    fn to_image(&self) -> String {
-        self.image.clone()
-    }
+    /// Fields of the spec that differ from the previously applied one, by their camelCase names.
+    ///
+    /// `None` if the deployment has no readable previously applied spec
+    pub fn changed_fields(&self, deployment: &Deployment) -> Option<Vec<String>> {
+        let prev_spec = Self::previous_spec(deployment)?;
 
-    fn to_meta_labels(&self) -> BTreeMap<String, String> {
-        [(String::from("faas_function"), self.to_name())].into()
+        let (Ok(serde_json::Value::Object(current)), Ok(serde_json::Value::Object(previous))) =
+            (serde_json::to_value(self), serde_json::to_value(prev_spec))
+        else {
+            return None;
+        };
+
+        Some(
+            current
+                .into_iter()
+                .filter(|(key, value)| previous.get(key) != Some(value))
+                .map(|(key, _)| key)
+                .collect(),
+        )
     }
 
-    fn to_spec_meta_labels(&self) -> BTreeMap<String, String> {
-        self.labels
-            .clone()
-            .map(|lables| {
-                let mut labels: BTreeMap<String, String> = lables.into_iter().collect();
+    /// The parts of the deployment that drifted from what the spec generates, e.g. after the deployment was edited by hand.
+    ///
+    /// Only fields the operator sets are compared, so defaults filled in by the API server and
+    /// labels, annotations or image pull secrets added by others are no drift.
+    /// Replicas are not compared, they may be managed by an autoscaler
+    pub fn deployment_diffs(&self, deployment: &Deployment) -> Vec<DeploymentDiff> {
+        let Ok(desired) = Deployment::try_from(self) else {
+            return vec![];
+        };
 
-                labels.extend(self.to_meta_labels());
+        let mut diffs = vec![];
 
-                labels
-            })
-            .unwrap_or(self.to_meta_labels())
-    }
+        if !is_subset(&desired.metadata.labels, &deployment.metadata.labels) {
+            diffs.push(DeploymentDiff::Labels);
+        }
 
-    fn to_service_selector_labels(&self) -> BTreeMap<String, String> {
-        self.to_meta_labels()
-    }
+        if !is_subset(
+            &desired.metadata.annotations,
+            &deployment.metadata.annotations,
+        ) {
+            diffs.push(DeploymentDiff::Annotations);
+        }
 
-    fn to_annotations(&self) -> Option<BTreeMap<String, String>> {
-        self.annotations.clone().map(|a| a.into_iter().collect())
-    }
+        let (Some(desired_spec), Some(live_spec)) = (desired.spec, deployment.spec.as_ref()) else {
+            diffs.push(DeploymentDiff::Spec);
+            return diffs;
+        };
 
-    fn to_meta_annotations(&self) -> Result<BTreeMap<String, String>, SerdeJsonError> {
-        let mut meta_annotaions = BTreeMap::new();
+        let desired_template_meta = desired_spec.template.metadata.unwrap_or_default();
+        let live_template_meta = live_spec.template.metadata.clone().unwrap_or_default();
 
-        if let Some(annotations) = self.to_annotations() {
-            meta_annotaions.extend(annotations);
+        if !is_subset(&desired_template_meta.labels, &live_template_meta.labels) {
+            diffs.push(DeploymentDiff::PodLabels);
         }
 
-        meta_annotaions.insert(
-            String::from(LAST_APPLIED_ANNOTATION),
-            serde_json::to_string(self)?,
-        );
+        if !is_subset(
+            &desired_template_meta.annotations,
+            &live_template_meta.annotations,
+        ) {
+            diffs.push(DeploymentDiff::PodAnnotations);
+        }
 
-        Ok(meta_annotaions)
-    }
+        if desired_spec.paused.unwrap_or_default() != live_spec.paused.unwrap_or_default() {
+            diffs.push(DeploymentDiff::Paused);
+        }
 
-    fn to_node_selector(&self) -> Option<BTreeMap<String, String>> {
-        let constraints = self.get_constraints_vec();
+        let desired_pod_spec = desired_spec.template.spec.unwrap_or_default();
+        let live_pod_spec = live_spec.template.spec.clone().unwrap_or_default();
 
-        if constraints.is_empty() {
-            return None;
+        if desired_pod_spec.node_selector.unwrap_or_default()
+            != live_pod_spec.node_selector.unwrap_or_default()
+        {
+            diffs.push(DeploymentDiff::Constraints);
         }
 
-        let node_selector: BTreeMap<String, String> = constraints
-            .iter()
-            .map(|c| c.split("==").collect::<Vec<&str>>())
-            .filter(|v| v.len() == 2)
-            .map(|v| {
-                (
-                    utils::remove_whitespace(v[0]),
-                    utils::remove_whitespace(v[1]),
-                )
-            })
-            .unique()
-            .collect();
+        if desired_pod_spec.affinity != live_pod_spec.affinity {
+            diffs.push(DeploymentDiff::Affinity);
+        }
 
-        Some(node_selector)
-    }
+        if desired_pod_spec.tolerations.unwrap_or_default()
+            != live_pod_spec.tolerations.unwrap_or_default()
+        {
+            diffs.push(DeploymentDiff::Tolerations);
+        }
 
-    fn to_deployment_meta(&self) -> Result<ObjectMeta, SerdeJsonError> {
-        Ok(ObjectMeta {
-            name: Some(self.to_name()),
-            namespace: self.to_namespace(),
-            labels: Some(self.to_meta_labels()),
-            annotations: Some(self.to_meta_annotations()?),
-            ..Default::default()
-        })
-    }
+        if desired_pod_spec.runtime_class_name != live_pod_spec.runtime_class_name {
+            diffs.push(DeploymentDiff::RuntimeClassName);
+        }
 
-    fn to_service_meta(&self) -> Result<ObjectMeta, SerdeJsonError> {
-        self.to_deployment_meta()
-    }
+        if self.deployment_secrets_changed(deployment) {
+            diffs.push(DeploymentDiff::Secrets);
+        }
 
-    fn to_spec_template_meta(&self) -> ObjectMeta {
-        ObjectMeta {
-            name: Some(self.to_name()),
-            labels: Some(self.to_spec_meta_labels()),
-            annotations: self.to_annotations(),
-            ..Default::default()
+        let Some(desired_container) = desired_pod_spec.containers.first() else {
+            return diffs;
+        };
+
+        let Some(live_container) = live_pod_spec
+            .containers
+            .iter()
+            .find(|container| container.name == desired_container.name)
+        else {
+            diffs.push(DeploymentDiff::Container);
+            return diffs;
+        };
+
+        if !same_image(&desired_container.image, &live_container.image) {
+            diffs.push(DeploymentDiff::Image);
         }
-    }
 
-    fn try_to_limits(&self) -> Result<Option<BTreeMap<String, Quantity>>, IntoQuantityError> {
-        if let Some(ref limits) = self.limits {
-            return limits.try_to_k8s_resources();
+        let env_process_name = self.to_env_process_name();
+
+        if env_value(desired_container, &env_process_name)
+            != env_value(live_container, &env_process_name)
+        {
+            diffs.push(DeploymentDiff::EnvProcess);
         }
 
-        Ok(None)
-    }
+        if env_values(desired_container, &env_process_name)
+            != env_values(live_container, &env_process_name)
+        {
+            diffs.push(DeploymentDiff::EnvVars);
+        }
 
-    fn try_to_requests(&self) -> Result<Option<BTreeMap<String, Quantity>>, IntoQuantityError> {
-        if let Some(ref requests) = self.requests {
-            return requests.try_to_k8s_resources();
+        let read_only_root_filesystem = |container: &Container| {
+            container
+                .security_context
+                .as_ref()
+                .and_then(|security_context| security_context.read_only_root_filesystem)
+                .unwrap_or_default()
+        };
+
+        if read_only_root_filesystem(desired_container) != read_only_root_filesystem(live_container)
+        {
+            diffs.push(DeploymentDiff::ReadOnlyRootFilesystem);
         }
 
-        Ok(None)
-    }
+        let resources = |container: &Container| container.resources.clone().unwrap_or_default();
+        let (desired_resources, live_resources) =
+            (resources(desired_container), resources(live_container));
 
-    fn to_tmp_volume_name(&self) -> String {
-        String::from("tmp")
-    }
+        if !same_quantities(&desired_resources.limits, &live_resources.limits) {
+            diffs.push(DeploymentDiff::Limits);
+        }
 
-    fn to_tmp_volume(&self) -> Volume {
-        Volume {
-            name: self.to_tmp_volume_name(),
-            empty_dir: Some(Default::default()),
-            ..Default::default()
+        if !same_quantities(&desired_resources.requests, &live_resources.requests) {
+            diffs.push(DeploymentDiff::Requests);
         }
+
+        diffs
     }
 
-    fn to_tmp_volume_mount_path(&self) -> String {
-        String::from("/tmp")
+    /// True if the secrets projected into the deployment's pods differ from the spec's,
+    /// e.g. if the deployment's secrets volume was edited
+    pub fn deployment_secrets_changed(&self, deployment: &Deployment) -> bool {
+        let mut secrets = self.get_secrets_unique_vec();
+        secrets.sort();
+
+        let volume_name = self.to_secrets_volume_name();
+
+        let mut deployment_secrets: Vec<String> = deployment
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.template.spec.as_ref())
+            .and_then(|pod_spec| pod_spec.volumes.as_ref())
+            .and_then(|volumes| volumes.iter().find(|volume| volume.name == volume_name))
+            .and_then(|volume| volume.projected.as_ref())
+            .and_then(|projected| projected.sources.as_ref())
+            .map(|sources| {
+                sources
+                    .iter()
+                    .filter_map(|source| source.secret.as_ref())
+                    .filter_map(|secret| secret.name.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        deployment_secrets.sort();
+
+        secrets != deployment_secrets
     }
 
-    fn to_tmp_volume_mount(&self) -> VolumeMount {
-        VolumeMount {
-            name: self.to_tmp_volume_name(),
-            mount_path: self.to_tmp_volume_mount_path(),
-            ..Default::default()
+    /// The spec without the fields that can be patched instead of replacing the deployment, metadata and replicas.
+    ///
+    /// Service annotations only affect the service, which applies them on its own
+    fn without_metadata(&self) -> Self {
+        Self {
+            labels: None,
+            annotations: None,
+            service_annotations: None,
+            replicas: None,
+            traffic_weight: None,
+            ..self.clone()
         }
     }
 
-    fn to_secrets_volume_name(&self) -> String {
-        format!("{}-projected-secrets", self.to_name())
+    /// True if the spec differs from the previously applied one only in labels, annotations or replicas
+    pub fn deployment_needs_metadata_patch_only(&self, deployment: &Deployment) -> bool {
+        !self.deployment_secrets_changed(deployment)
+            && Self::previous_spec(deployment)
+                .is_some_and(|prev_spec| self.without_metadata() == prev_spec.without_metadata())
     }
 
-    fn to_secrets_projected_volume_source(&self) -> Option<ProjectedVolumeSource> {
-        let secrets = self.get_secrets_unique_vec();
+    /// A merge patch updating the deployment's annotations, including the last applied spec, and its replicas if they changed.
+    ///
+    /// The pod template's labels and annotations are only patched if they changed, which rolls the pods
+    pub fn to_deployment_metadata_patch(
+        &self,
+        deployment: &Deployment,
+    ) -> Result<serde_json::Value, FunctionSpecIntoDeploymentError> {
+        self.validate_metadata()?;
 
-        if secrets.is_empty() {
-            return None;
+        let mut annotations: serde_json::Map<String, serde_json::Value> = self
+            .to_meta_annotations()?
+            .into_iter()
+            .map(|(key, value)| (key, serde_json::Value::String(value)))
+            .collect();
+
+        let prev_spec = Self::previous_spec(deployment);
+
+        // Annotations removed from the spec are removed from the deployment
+        for key in prev_spec
+            .as_ref()
+            .and_then(|prev_spec| prev_spec.annotations.clone())
+            .unwrap_or_default()
+            .into_keys()
+        {
+            annotations.entry(key).or_insert(serde_json::Value::Null);
         }
 
-        let sources = secrets
-            .iter()
-            .map(|secret| {
-                let items = vec![KeyToPath {
-                    key: secret.clone(),
-                    path: secret.clone(),
-                    ..Default::default()
-                }];
+        let mut patch = serde_json::json!({
+            "metadata": {
+                "annotations": annotations,
+            }
+        });
 
-                VolumeProjection {
-                    secret: Some(SecretProjection {
-                        name: Some(secret.clone()),
-                        items: Some(items),
-                        ..Default::default()
-                    }),
-                    ..Default::default()
-                }
-            })
-            .collect();
+        let mut spec_patch = serde_json::Map::new();
 
-        Some(ProjectedVolumeSource {
-            sources: Some(sources),
-            ..Default::default()
-        })
-    }
+        // Replicas are only patched if changed, to not override e.g. an autoscaler otherwise
+        let replicas = self.to_replicas();
+        if prev_spec.as_ref().map(|prev_spec| prev_spec.to_replicas()) != Some(replicas) {
+            spec_patch.insert(String::from("replicas"), serde_json::json!(replicas));
+        }
 
-    fn to_secrets_volume(&self) -> Volume {
-        Volume {
-            name: self.to_secrets_volume_name(),
-            projected: self.to_secrets_projected_volume_source(),
-            ..Default::default()
+        let pod_metadata_changed = prev_spec.as_ref().map_or(true, |prev_spec| {
+            prev_spec.labels != self.labels || prev_spec.annotations != self.annotations
+        });
+
+        if pod_metadata_changed {
+            spec_patch.insert(
+                String::from("template"),
+                serde_json::json!({
+                    "metadata": self.to_pod_metadata_patch(deployment, prev_spec.as_ref()),
+                }),
+            );
         }
-    }
 
-    fn to_default_secrets_mount_path(&self) -> String {
-        String::from("/var/openfaas/secrets")
-    }
+        if !spec_patch.is_empty() {
+            patch["spec"] = serde_json::Value::Object(spec_patch);
+        }
 
-    fn to_secrets_mount_path(&self) -> String {
-        self.secrets_mount_path
-            .clone()
-            .unwrap_or(self.to_default_secrets_mount_path())
+        Ok(patch)
     }
 
-    fn to_secrets_volume_mount(&self) -> VolumeMount {
-        VolumeMount {
-            name: self.to_secrets_volume_name(),
-            mount_path: self.to_secrets_mount_path(),
-            read_only: Some(true),
-            ..Default::default()
+    /// The pod template's labels and annotations for a merge patch.
+    ///
+    /// Labels removed from the spec fall back to the operator's default pod label of the same key, if applied
+    fn to_pod_metadata_patch(
+        &self,
+        deployment: &Deployment,
+        prev_spec: Option<&OpenFaasFunctionSpec>,
+    ) -> serde_json::Value {
+        let default_pod_labels: BTreeMap<String, String> = deployment
+            .annotations()
+            .get(DEFAULT_POD_LABELS_ANNOTATION)
+            .and_then(|labels| serde_json::from_str(labels).ok())
+            .unwrap_or_default();
+
+        let mut labels: serde_json::Map<String, serde_json::Value> = self
+            .to_spec_meta_labels()
+            .into_iter()
+            .map(|(key, value)| (key, serde_json::Value::String(value)))
+            .collect();
+
+        for key in prev_spec
+            .and_then(|prev_spec| prev_spec.labels.clone())
+            .unwrap_or_default()
+            .into_keys()
+        {
+            let value = match default_pod_labels.get(&key) {
+                Some(value) => serde_json::Value::String(value.clone()),
+                None => serde_json::Value::Null,
+            };
+
+            labels.entry(key).or_insert(value);
         }
-    }
 
-    pub fn to_yaml_string(&self) -> Result<String, FunctionSpecIntoYamlError> {
-        let mut string = String::new();
-        let deployment =
-            Deployment::try_from(self).map_err(FunctionSpecIntoYamlError::Deployment)?;
-        let deplyoment_str =
-            serde_yaml::to_string(&deployment).map_err(FunctionSpecIntoYamlError::Serialize)?;
-        let service = Service::try_from(self).map_err(FunctionSpecIntoYamlError::Service)?;
-        let service_str =
-            serde_yaml::to_string(&service).map_err(FunctionSpecIntoYamlError::Serialize)?;
+        let mut annotations: serde_json::Map<String, serde_json::Value> = self
+            .to_annotations()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, value)| (key, serde_json::Value::String(value)))
+            .collect();
 
-        string.push_str(&deplyoment_str);
-        string.push_str("---\n");
-        string.push_str(&service_str);
+        for key in prev_spec
+            .and_then(|prev_spec| prev_spec.annotations.clone())
+            .unwrap_or_default()
+            .into_keys()
+        {
+            annotations.entry(key).or_insert(serde_json::Value::Null);
+        }
 
-        Ok(string)
+        serde_json::json!({
+            "labels": labels,
+            "annotations": annotations,
+        })
     }
-}
 
-impl From<&OpenFaasFunctionSpec> for Probe {
-    fn from(_value: &OpenFaasFunctionSpec) -> Self {
-        Probe {
-            http_get: Some(HTTPGetAction {
-                path: Some(String::from("/_/health")),
-                port: IntOrString::Int(8080),
-                scheme: Some(String::from("HTTP")),
-                ..Default::default()
-            }),
-            ..Default::default()
+    /// A merge patch writing the spec into the deployment's last applied annotation,
+    /// for deployments created without it, e.g. by an older operator.
+    ///
+    /// Non empty default pod labels are recorded as well, as [`Self::apply_default_pod_labels`] would
+    pub fn to_last_applied_patch(
+        &self,
+        default_pod_labels: &BTreeMap<String, String>,
+    ) -> Result<serde_json::Value, SerdeJsonError> {
+        let mut annotations = serde_json::Map::new();
+
+        annotations.insert(
+            String::from(LAST_APPLIED_ANNOTATION),
+            serde_json::Value::String(serde_json::to_string(self)?),
+        );
+
+        if !default_pod_labels.is_empty() {
+            annotations.insert(
+                String::from(DEFAULT_POD_LABELS_ANNOTATION),
+                serde_json::Value::String(serde_json::to_string(default_pod_labels)?),
+            );
         }
+
+        Ok(serde_json::json!({
+            "metadata": {
+                "annotations": annotations,
+            }
+        }))
     }
-}
 
-impl From<&OpenFaasFunctionSpec> for Option<Probe> {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        Some(Probe::from(value))
-    }
-}
+    /// Adds the operator wide default labels to the deployment's pod template.
+    ///
+    /// Labels of the function take precedence, the applied defaults are recorded in an annotation
+    pub fn apply_default_pod_labels(
+        deployment: &mut Deployment,
+        default_pod_labels: &BTreeMap<String, String>,
+    ) {
+        if default_pod_labels.is_empty() {
+            return;
+        }
 
-impl From<&OpenFaasFunctionSpec> for ContainerPort {
-    fn from(_value: &OpenFaasFunctionSpec) -> Self {
-        ContainerPort {
-            name: Some(String::from("http")),
-            container_port: 8080,
-            protocol: Some(String::from("TCP")),
-            ..Default::default()
+        if let Some(spec) = deployment.spec.as_mut() {
+            let labels = spec
+                .template
+                .metadata
+                .get_or_insert_with(ObjectMeta::default)
+                .labels
+                .get_or_insert_with(BTreeMap::new);
+
+            for (key, value) in default_pod_labels {
+                labels.entry(key.clone()).or_insert_with(|| value.clone());
+            }
         }
-    }
-}
 
-impl From<&OpenFaasFunctionSpec> for Vec<ContainerPort> {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        vec![ContainerPort::from(value)]
+        deployment.annotations_mut().insert(
+            String::from(DEFAULT_POD_LABELS_ANNOTATION),
+            serde_json::to_string(default_pod_labels).expect("Failed to serialize labels"),
+        );
     }
-}
 
-impl From<&OpenFaasFunctionSpec> for Option<Vec<ContainerPort>> {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        Some(Vec::<ContainerPort>::from(value))
+    /// True if the default pod labels differ from the ones applied to the deployment
+    pub fn default_pod_labels_changed(
+        deployment: &Deployment,
+        default_pod_labels: &BTreeMap<String, String>,
+    ) -> bool {
+        let applied_default_pod_labels: BTreeMap<String, String> = deployment
+            .annotations()
+            .get(DEFAULT_POD_LABELS_ANNOTATION)
+            .and_then(|labels| serde_json::from_str(labels).ok())
+            .unwrap_or_default();
+
+        &applied_default_pod_labels != default_pod_labels
     }
-}
 
-impl From<&OpenFaasFunctionSpec> for SecurityContext {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        SecurityContext {
-            read_only_root_filesystem: value.read_only_root_filesystem,
-            ..Default::default()
-        }
+    fn should_create_tmp_volume(&self) -> bool {
+        self.tmp_volume
+            .unwrap_or(self.read_only_root_filesystem.unwrap_or(false))
     }
-}
 
-impl From<&OpenFaasFunctionSpec> for Option<SecurityContext> {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        Some(SecurityContext::from(value))
+    fn should_create_secrets_volume(&self) -> bool {
+        !self.secrets.as_ref().unwrap_or(&vec![]).is_empty()
     }
-}
 
-impl From<&OpenFaasFunctionSpec> for Vec<EnvVar> {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        let mut env_vars = Vec::new();
+    pub fn get_image_pull_secrets_unique_vec(&self) -> Vec<String> {
+        self.image_pull_secrets
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .unique()
+            .collect()
+    }
 
-        if let Some(env_process) = value.env_process.clone() {
-            env_vars.push(EnvVar {
-                name: value.to_env_process_name(),
-                value: Some(env_process),
-                ..Default::default()
-            });
+    fn to_image_pull_secrets(&self) -> Option<Vec<LocalObjectReference>> {
+        let image_pull_secrets = self.get_image_pull_secrets_unique_vec();
+        if image_pull_secrets.is_empty() {
+            return None;
         }
 
-        if let Some(env_vars_map) = value.env_vars.clone() {
-            for (k, v) in env_vars_map {
-                env_vars.push(EnvVar {
-                    name: k,
-                    value: Some(v),
-                    ..Default::default()
-                });
-            }
-        }
+        Some(
+            image_pull_secrets
+                .into_iter()
+                .map(|name| LocalObjectReference { name: Some(name) })
+                .collect(),
+        )
+    }
 
-        env_vars
+    pub fn get_secrets_unique_vec(&self) -> Vec<String> {
+        self.secrets
+            .clone()
+            .unwrap_or(vec![])
+            .into_iter()
+            .unique()
+            .collect()
     }
-}
 
-impl From<&OpenFaasFunctionSpec> for Option<Vec<EnvVar>> {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        let env_vars = Vec::<EnvVar>::from(value);
+    fn should_create_ca_bundle_volume(&self) -> bool {
+        self.ca_bundle_config_map.is_some()
+    }
 
-        if env_vars.is_empty() {
-            return None;
-        }
+    pub fn get_config_maps_unique_vec(&self) -> Vec<String> {
+        self.ca_bundle_config_map
+            .clone()
+            .into_iter()
+            .unique()
+            .collect()
+    }
 
-        Some(env_vars)
+    pub fn get_constraints_vec(&self) -> Vec<String> {
+        self.constraints.clone().unwrap_or(vec![])
     }
-}
 
-impl TryFrom<&OpenFaasFunctionSpec> for ResourceRequirements {
-    type Error = IntoQuantityError;
+    fn get_downward_env_vec(&self) -> Vec<String> {
+        self.downward_env
+            .clone()
+            .unwrap_or(vec![])
+            .into_iter()
+            .unique()
+            .collect()
+    }
 
-    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
-        Ok(ResourceRequirements {
-            limits: value.try_to_limits()?,
-            requests: value.try_to_requests()?,
-        })
+    /// Sizes of the env vars with literal values as passed to the function's process, NAME=value
+    fn env_var_sizes(&self) -> Vec<(String, usize)> {
+        Vec::<EnvVar>::from(self)
+            .into_iter()
+            .filter_map(|env_var| {
+                let size = env_var.name.len() + 1 + env_var.value?.len();
+                Some((env_var.name, size))
+            })
+            .collect()
     }
-}
 
-impl TryFrom<&OpenFaasFunctionSpec> for Option<ResourceRequirements> {
-    type Error = IntoQuantityError;
+    /// Checks that the summed size of the function's env vars does not exceed `max` bytes.
+    ///
+    /// Large inlined configuration makes pods fail to start long after the deployment was accepted
+    pub fn validate_env_size(&self, max: usize) -> Result<(), SpecValidationError> {
+        let size = self.env_var_sizes().into_iter().map(|(_, size)| size).sum();
 
-    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
-        Ok(Some(ResourceRequirements::try_from(value)?))
-    }
-}
+        if size > max {
+            return Err(SpecValidationError::EnvTooLarge { size, max });
+        }
 
-impl TryFrom<&OpenFaasFunctionSpec> for Container {
-    type Error = IntoQuantityError;
+        Ok(())
+    }
 
-    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
-        Ok(Container {
-            name: value.to_name(),
-            image: Some(value.to_image()),
-            ports: Option::<Vec<ContainerPort>>::from(value),
-            liveness_probe: Option::<Probe>::from(value),
-            readiness_probe: Option::<Probe>::from(value),
-            security_context: Option::<SecurityContext>::from(value),
-            volume_mounts: Option::<Vec<VolumeMount>>::from(value),
-            resources: Option::<ResourceRequirements>::try_from(value)?,
-            env: Option::<Vec<EnvVar>>::from(value),
-            ..Default::default()
+    /// Returns the first requested downward env var that is not supported.
+    fn find_unsupported_downward_env(&self) -> Option<String> {
+        self.get_downward_env_vec().into_iter().find(|name| {
+            !DOWNWARD_ENV_FIELDS
+                .iter()
+                .any(|(supported, _)| supported == name)
         })
     }
-}
 
-impl From<&OpenFaasFunctionSpec> for Vec<VolumeMount> {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        let mut volume_mounts = Vec::new();
+    /// The podSpecOverlay must be an object that deserializes into a pod spec without dropping any field
+    fn validate_pod_spec_overlay(&self) -> Result<(), String> {
+        let Some(ref overlay) = self.pod_spec_overlay else {
+            return Ok(());
+        };
 
-        if value.should_create_tmp_volume() {
-            volume_mounts.push(value.to_tmp_volume_mount());
+        if !overlay.is_object() {
+            return Err(String::from("must be an object"));
         }
 
-        if value.should_create_secrets_volume() {
-            volume_mounts.push(value.to_secrets_volume_mount());
+        let known = serde_json::from_value::<PodSpec>(overlay.clone())
+            .and_then(serde_json::to_value)
+            .map_err(|error| error.to_string())?;
+
+        let unknown_fields = utils::collect_unknown_json_fields(overlay, &known, "");
+        if !unknown_fields.is_empty() {
+            return Err(format!("unknown fields {}", unknown_fields.join(", ")));
         }
 
-        volume_mounts
+        Ok(())
     }
-}
 
-impl From<&OpenFaasFunctionSpec> for Option<Vec<VolumeMount>> {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        let volume_mounts = Vec::<VolumeMount>::from(value);
+    /// Merges the podSpecOverlay onto the generated pod spec
+    fn apply_pod_spec_overlay(
+        &self,
+        pod_spec: PodSpec,
+    ) -> Result<PodSpec, FunctionSpecIntoDeploymentError> {
+        let Some(ref overlay) = self.pod_spec_overlay else {
+            return Ok(pod_spec);
+        };
 
-        if volume_mounts.is_empty() {
-            return None;
-        }
+        self.validate_pod_spec_overlay()
+            .map_err(FunctionSpecIntoDeploymentError::PodSpecOverlay)?;
 
-        Some(volume_mounts)
+        let mut merged = serde_json::to_value(pod_spec)?;
+        utils::strategic_merge(&mut merged, overlay);
+
+        serde_json::from_value(merged)
+            .map_err(|error| FunctionSpecIntoDeploymentError::PodSpecOverlay(error.to_string()))
     }
-}
 
-impl TryFrom<&OpenFaasFunctionSpec> for Vec<Container> {
-    type Error = IntoQuantityError;
+    fn to_downward_env_vars(&self) -> Vec<EnvVar> {
+        self.get_downward_env_vec()
+            .into_iter()
+            .filter_map(|name| {
+                let (_, field_path) = DOWNWARD_ENV_FIELDS
+                    .iter()
+                    .find(|(supported, _)| *supported == name)?;
+
+                Some(EnvVar {
+                    name,
+                    value_from: Some(EnvVarSource {
+                        field_ref: Some(ObjectFieldSelector {
+                            field_path: String::from(*field_path),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
 
-    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
-        Ok(vec![Container::try_from(value)?])
+    /// Validates user provided labels and annotations against the Kubernetes naming rules.
+    fn validate_metadata(&self) -> Result<(), InvalidMetadataError> {
+        match self.metadata_errors().into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
     }
-}
 
-impl From<&OpenFaasFunctionSpec> for Vec<Volume> {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        let mut volumes = Vec::new();
+    fn metadata_errors(&self) -> Vec<InvalidMetadataError> {
+        let mut errors = vec![];
 
-        if value.should_create_tmp_volume() {
-            volumes.push(value.to_tmp_volume());
+        for (key, value) in self.labels.iter().flatten() {
+            if !utils::is_qualified_name(key) {
+                errors.push(InvalidMetadataError::LabelKey(key.clone()));
+            }
+
+            if !utils::is_label_value(value) {
+                errors.push(InvalidMetadataError::LabelValue {
+                    key: key.clone(),
+                    value: value.clone(),
+                });
+            }
         }
 
-        if value.should_create_secrets_volume() {
-            volumes.push(value.to_secrets_volume());
+        for key in self
+            .annotations
+            .iter()
+            .chain(self.service_annotations.iter())
+            .flatten()
+            .map(|(key, _)| key)
+        {
+            if !utils::is_qualified_name(key) {
+                errors.push(InvalidMetadataError::AnnotationKey(key.clone()));
+            }
+
+            // Operator managed annotations are inserted after the user's ones and must never be overwritten by them
+            if key.starts_with(RESERVED_ANNOTATION_PREFIX) {
+                errors.push(InvalidMetadataError::ReservedAnnotationKey(key.clone()));
+            }
         }
 
-        volumes
+        errors
     }
-}
 
-impl From<&OpenFaasFunctionSpec> for Option<Vec<Volume>> {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        let volumes = Vec::<Volume>::from(value);
+    /// Runs all static checks of the spec and returns every problem found, not only the first one.
+    ///
+    /// Nothing is checked against the cluster, e.g. whether secrets exist
+    pub fn validate(&self) -> Result<(), Vec<SpecValidationError>> {
+        let mut errors = vec![];
 
-        if volumes.is_empty() {
-            return None;
+        if !utils::is_dns_label(&self.service) {
+            errors.push(SpecValidationError::Name(self.service.clone()));
         }
 
-        Some(volumes)
-    }
-}
+        if let Err(error) = self.image.parse::<ImageReference>() {
+            errors.push(SpecValidationError::ImageReference(error));
+        }
 
-impl TryFrom<&OpenFaasFunctionSpec> for PodSpec {
-    type Error = IntoQuantityError;
+        for resources in [&self.limits, &self.requests].into_iter().flatten() {
+            if let Err(error) = FunctionResourcesQuantity::try_from(resources) {
+                errors.push(SpecValidationError::Quantity(error));
+            }
+        }
 
-    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
-        Ok(PodSpec {
-            containers: Vec::<Container>::try_from(value)?,
-            volumes: Option::<Vec<Volume>>::from(value),
-            node_selector: value.to_node_selector(),
-            ..Default::default()
-        })
-    }
-}
+        let env_names = Vec::<EnvVar>::from(self)
+            .into_iter()
+            .map(|env_var| env_var.name);
+        for name in env_names.duplicates() {
+            errors.push(SpecValidationError::EnvCollision(name));
+        }
 
-impl TryFrom<&OpenFaasFunctionSpec> for Option<PodSpec> {
-    type Error = IntoQuantityError;
+        for secret in self
+            .get_secrets_unique_vec()
+            .into_iter()
+            .chain(self.get_image_pull_secrets_unique_vec())
+        {
+            if !utils::is_dns_subdomain(&secret) {
+                errors.push(SpecValidationError::SecretName(secret));
+            }
+        }
 
-    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
-        Ok(Some(PodSpec::try_from(value)?))
-    }
-}
+        for constraint in self.get_constraints_vec() {
+            let valid = match parse_constraint(&constraint) {
+                Some(
+                    NodeConstraint::Equals(key, value) | NodeConstraint::NotEquals(key, value),
+                ) => utils::is_qualified_name(&key) && utils::is_label_value(&value),
+                None => false,
+            };
 
-impl From<&OpenFaasFunctionSpec> for LabelSelector {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        LabelSelector {
-            match_labels: Some(value.to_meta_labels()),
-            ..Default::default()
+            if !valid {
+                errors.push(SpecValidationError::Constraint(constraint));
+            }
         }
-    }
-}
 
-impl From<&OpenFaasFunctionSpec> for RollingUpdateDeployment {
-    fn from(_value: &OpenFaasFunctionSpec) -> Self {
-        RollingUpdateDeployment {
-            max_surge: Some(IntOrString::Int(1)),
-            max_unavailable: Some(IntOrString::Int(0)),
+        if let Some(name) = self.find_unsupported_downward_env() {
+            errors.push(SpecValidationError::DownwardEnv(name));
         }
-    }
-}
 
-impl From<&OpenFaasFunctionSpec> for Option<RollingUpdateDeployment> {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        Some(RollingUpdateDeployment::from(value))
-    }
-}
+        if let Some(ref policy) = self.termination_message_policy {
+            if !TERMINATION_MESSAGE_POLICIES.contains(&policy.as_str()) {
+                errors.push(SpecValidationError::TerminationMessagePolicy(
+                    policy.clone(),
+                ));
+            }
+        }
 
-impl From<&OpenFaasFunctionSpec> for DeploymentStrategy {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        DeploymentStrategy {
-            rolling_update: Option::<RollingUpdateDeployment>::from(value),
-            ..Default::default()
+        if let Some(ref policy) = self.image_pull_policy {
+            if !IMAGE_PULL_POLICIES.contains(&policy.as_str()) {
+                errors.push(SpecValidationError::ImagePullPolicy(policy.clone()));
+            }
         }
-    }
-}
 
-impl From<&OpenFaasFunctionSpec> for Option<DeploymentStrategy> {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        Some(DeploymentStrategy::from(value))
-    }
-}
+        errors.extend(
+            self.metadata_errors()
+                .into_iter()
+                .map(SpecValidationError::Metadata),
+        );
 
-impl TryFrom<&OpenFaasFunctionSpec> for PodTemplateSpec {
-    type Error = IntoQuantityError;
+        errors.extend(
+            self.service_errors()
+                .into_iter()
+                .map(SpecValidationError::Service),
+        );
 
-    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
-        Ok(PodTemplateSpec {
-            metadata: Some(value.to_spec_template_meta()),
-            spec: Option::<PodSpec>::try_from(value)?,
-        })
-    }
-}
+        if let Err(error) = Option::<PodDisruptionBudgetSpec>::try_from(self) {
+            errors.push(SpecValidationError::PodDisruptionBudget(error));
+        }
 
-impl TryFrom<&OpenFaasFunctionSpec> for DeploymentSpec {
-    type Error = IntoQuantityError;
+        if self.traffic_weight == Some(0) {
+            errors.push(SpecValidationError::TrafficWeight);
+        }
 
-    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
-        Ok(DeploymentSpec {
-            replicas: Some(1),
-            selector: LabelSelector::from(value),
-            strategy: Option::<DeploymentStrategy>::from(value),
-            template: PodTemplateSpec::try_from(value)?,
-            ..Default::default()
-        })
-    }
-}
+        if let Some(port) = self.container_port {
+            if !(1..=65535).contains(&port) {
+                errors.push(SpecValidationError::ContainerPort(port));
+            }
+        }
 
-impl TryFrom<&OpenFaasFunctionSpec> for Option<DeploymentSpec> {
-    type Error = IntoQuantityError;
+        if let Some(ref path) = self.http_probe_path {
+            if !path.starts_with('/') {
+                errors.push(SpecValidationError::HttpProbePath(path.clone()));
+            }
+        }
 
-    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
-        Ok(Some(DeploymentSpec::try_from(value)?))
-    }
-}
+        for (probe, config) in [
+            ("livenessProbe", &self.liveness_probe),
+            ("readinessProbe", &self.readiness_probe),
+        ] {
+            if let Some(config) = config {
+                errors.extend(config.errors(probe));
+            }
+        }
 
-/// Generate a fresh deployment
-impl TryFrom<&OpenFaasFunctionSpec> for Deployment {
-    type Error = FunctionSpecIntoDeploymentError;
+        if let Err(error) = self.validate_pod_spec_overlay() {
+            errors.push(SpecValidationError::PodSpecOverlay(error));
+        }
 
-    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
-        let deployment = Deployment {
-            metadata: value.to_deployment_meta()?,
-            spec: Option::<DeploymentSpec>::try_from(value)?,
-            ..Default::default()
-        };
+        errors.extend(self.deployment_strategy_errors());
 
-        Ok(deployment)
-    }
-}
+        match (self.replicas, self.traffic_weight) {
+            (Some(replicas), _) if replicas < 0 => {
+                errors.push(SpecValidationError::Replicas(replicas))
+            }
+            (Some(_), Some(_)) => errors.push(SpecValidationError::ConflictingReplicas),
+            _ => {}
+        }
 
-impl From<&OpenFaasFunctionSpec> for ServicePort {
-    fn from(_value: &OpenFaasFunctionSpec) -> Self {
-        ServicePort {
-            name: Some(String::from("http")),
-            port: 8080,
-            target_port: Some(IntOrString::Int(8080)),
-            protocol: Some(String::from("TCP")),
-            ..Default::default()
+        for (name, size) in self.env_var_sizes() {
+            if size > MAX_ENV_VAR_BYTES {
+                errors.push(SpecValidationError::EnvVarTooLarge {
+                    name,
+                    size,
+                    max: MAX_ENV_VAR_BYTES,
+                });
+            }
         }
+
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        Err(errors)
     }
-}
 
-impl From<&OpenFaasFunctionSpec> for Vec<ServicePort> {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        vec![ServicePort::from(value)]
+    fn validate_app_protocol(&self) -> Result<(), FunctionSpecIntoServiceError> {
+        let Some(ref app_protocol) = self.app_protocol else {
+            return Ok(());
+        };
+
+        if APP_PROTOCOLS.contains(&app_protocol.as_str()) {
+            return Ok(());
+        }
+
+        // Custom protocols must be prefixed with a domain that is not reserved for Kubernetes
+        let is_custom = match app_protocol.split_once('/') {
+            Some((prefix, _)) => {
+                utils::is_qualified_name(app_protocol)
+                    && prefix != "kubernetes.io"
+                    && !prefix.ends_with(".kubernetes.io")
+                    && prefix != "k8s.io"
+                    && !prefix.ends_with(".k8s.io")
+            }
+            None => false,
+        };
+
+        if !is_custom {
+            return Err(FunctionSpecIntoServiceError::AppProtocol(
+                app_protocol.clone(),
+            ));
+        }
+
+        Ok(())
     }
-}
 
-impl From<&OpenFaasFunctionSpec> for Option<Vec<ServicePort>> {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        Some(Vec::<ServicePort>::from(value))
+    fn to_service_type(&self) -> &str {
+        self.service_type.as_deref().unwrap_or("ClusterIP")
     }
-}
 
-impl From<&OpenFaasFunctionSpec> for ServiceSpec {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        ServiceSpec {
-            selector: Some(value.to_service_selector_labels()),
-            ports: Option::<Vec<ServicePort>>::from(value),
-            ..Default::default()
+    /// Every problem with the service's type, ports and protocol
+    fn service_errors(&self) -> Vec<FunctionSpecIntoServiceError> {
+        let mut errors = vec![];
+        let service_type = self.to_service_type();
+
+        if !SERVICE_TYPES.contains(&service_type) {
+            errors.push(FunctionSpecIntoServiceError::ServiceType(String::from(
+                service_type,
+            )));
         }
-    }
-}
 
-impl From<&OpenFaasFunctionSpec> for Option<ServiceSpec> {
-    fn from(value: &OpenFaasFunctionSpec) -> Self {
-        Some(ServiceSpec::from(value))
+        if self.load_balancer_class.is_some() && service_type != "LoadBalancer" {
+            errors.push(FunctionSpecIntoServiceError::LoadBalancerClass);
+        }
+
+        if let Some(node_port) = self.node_port {
+            if !["NodePort", "LoadBalancer"].contains(&service_type) {
+                errors.push(FunctionSpecIntoServiceError::NodePort);
+            } else if !(1..=65535).contains(&node_port) {
+                errors.push(FunctionSpecIntoServiceError::NodePortRange(node_port));
+            }
+        }
+
+        if let Err(error) = self.validate_app_protocol() {
+            errors.push(error);
+        }
+
+        errors
     }
-}
 
-/// Generate a fresh service
-impl TryFrom<&OpenFaasFunctionSpec> for Service {
-    type Error = FunctionSpecIntoServiceError;
+    /// Checks if the type or load balancer class of the service differ from the spec
+    pub fn service_type_changed(&self, service: &Service) -> bool {
+        let Some(ref spec) = service.spec else {
+            return true;
+        };
 
-    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
-        Ok(Service {
-            metadata: value.to_service_meta()?,
-            spec: Option::<ServiceSpec>::from(value),
-            ..Default::default()
-        })
+        spec.type_.as_deref().unwrap_or("ClusterIP") != self.to_service_type()
+            || spec.load_balancer_class != self.load_balancer_class
     }
-}
 
-/// Generate a fresh deployment with refs
-impl TryFrom<&OpenFaaSFunction> for Deployment {
-    type Error = FunctionIntoDeploymentError;
+    /// Service annotations that were applied by a previous spec and are no longer set
+    fn removed_service_annotations(&self, service: &Service) -> Vec<String> {
+        let service_annotations = self.to_service_annotations().unwrap_or_default();
 
-    fn try_from(value: &OpenFaaSFunction) -> Result<Self, Self::Error> {
-        let oref = value
-            .controller_owner_ref(&())
-            .ok_or(FunctionIntoDeploymentError::OwnerReference)?;
+        Self::previous_spec_of(&service.metadata)
+            .and_then(|prev_spec| prev_spec.to_service_annotations())
+            .unwrap_or_default()
+            .into_keys()
+            .filter(|key| !service_annotations.contains_key(key))
+            .collect()
+    }
 
-        let mut dep =
-            Deployment::try_from(&value.spec).map_err(FunctionIntoDeploymentError::FunctionSpec)?;
+    /// Checks if the service misses one of the spec's service annotations, or still has one the spec no longer sets
+    pub fn service_annotations_changed(&self, service: &Service) -> bool {
+        let annotations = service.annotations();
 
-        dep.metadata.owner_references = Some(vec![oref]);
+        let missing = self
+            .to_service_annotations()
+            .unwrap_or_default()
+            .iter()
+            .any(|(key, value)| annotations.get(key) != Some(value));
 
-        Ok(dep)
+        let removed = self
+            .removed_service_annotations(service)
+            .iter()
+            .any(|key| annotations.contains_key(key));
+
+        missing || removed
     }
-}
 
-/// Generate a fresh service with refs
-impl TryFrom<&OpenFaaSFunction> for Service {
-    type Error = FunctionIntoServiceError;
+    /// The service's annotations for a merge patch: the spec's service annotations,
+    /// removed ones set to null and the last applied spec
+    pub fn to_service_annotations_patch(
+        &self,
+        service: &Service,
+    ) -> Result<serde_json::Map<String, serde_json::Value>, SerdeJsonError> {
+        let mut annotations: serde_json::Map<String, serde_json::Value> = self
+            .to_service_annotations()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, value)| (key, serde_json::Value::String(value)))
+            .collect();
 
-    fn try_from(value: &OpenFaaSFunction) -> Result<Self, Self::Error> {
-        let oref = value
-            .controller_owner_ref(&())
-            .ok_or(FunctionIntoServiceError::OwnerReference)?;
+        for key in self.removed_service_annotations(service) {
+            annotations.entry(key).or_insert(serde_json::Value::Null);
+        }
 
-        let mut svc = Service::try_from(&value.spec)?;
+        annotations.insert(
+            String::from(LAST_APPLIED_ANNOTATION),
+            serde_json::Value::String(serde_json::to_string(self)?),
+        );
 
-        svc.metadata.owner_references = Some(vec![oref]);
+        Ok(annotations)
+    }
 
-        Ok(svc)
+    /// Checks if the service's ports differ from the spec, e.g. after the service was edited by hand
+    pub fn service_needs_ports_patch(&self, service: &Service) -> bool {
+        // Node ports allocated by Kubernetes are only compared if the spec sets one
+        let current: Vec<ServicePort> = service
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.ports.clone())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|port| ServicePort {
+                node_port: port.node_port.filter(|_| self.node_port.is_some()),
+                ..port
+            })
+            .collect();
+
+        current != Vec::<ServicePort>::from(self)
     }
-}
 
-impl OpenFaasFunctionStatus {
-    pub fn possible_status(&self) -> Option<OpenFaasFunctionPossibleStatus> {
-        Some(self.conditions.first()?.reason.clone())
+    fn to_container_port(&self) -> i32 {
+        self.container_port.unwrap_or(DEFAULT_CONTAINER_PORT)
     }
-}
 
-impl From<&OpenFaasFunctionPossibleStatus> for OpenFaasFunctionStatusConditionStatus {
-    fn from(status: &OpenFaasFunctionPossibleStatus) -> Self {
-        match status {
-            OpenFaasFunctionPossibleStatus::Ok => OpenFaasFunctionStatusConditionStatus {
-                status: String::from("True"),
-            },
-            _ => OpenFaasFunctionStatusConditionStatus {
-                status: String::from("False"),
-            },
-        }
+    fn to_http_probe_path(&self) -> String {
+        self.http_probe_path
+            .clone()
+            .unwrap_or_else(|| String::from(DEFAULT_HTTP_PROBE_PATH))
     }
-}
 
-impl From<&OpenFaasFunctionPossibleStatus> for OpenFaasFunctionStatusConditionMessage {
-    fn from(status: &OpenFaasFunctionPossibleStatus) -> Self {
-        match status {
-            OpenFaasFunctionPossibleStatus::Ok => {
-                OpenFaasFunctionStatusConditionMessage { message: None }
-            }
-            OpenFaasFunctionPossibleStatus::InvalidCRDNamespace => {
-                OpenFaasFunctionStatusConditionMessage {
-                    message: Some(String::from(
-                        "The CRD's namespace does not match the functions namespace",
-                    )),
-                }
-            }
-            OpenFaasFunctionPossibleStatus::InvalidFunctionNamespace => {
-                OpenFaasFunctionStatusConditionMessage {
-                    message: Some(String::from(
-                        "The function's namespace does not match the functions namespace",
-                    )),
-                }
-            }
-            OpenFaasFunctionPossibleStatus::CPUQuantity => OpenFaasFunctionStatusConditionMessage {
-                message: Some(String::from("A function's cpu quantity is invalid")),
-            },
-            OpenFaasFunctionPossibleStatus::MemoryQuantity => {
-                OpenFaasFunctionStatusConditionMessage {
-                    message: Some(String::from("A function's memory quantity is invalid")),
-                }
-            }
-            OpenFaasFunctionPossibleStatus::DeploymentAlreadyExists => {
-                OpenFaasFunctionStatusConditionMessage {
-                    message: Some(String::from(
-                        "The function's deployment already deployed by third party",
-                    )),
-                }
-            }
-            OpenFaasFunctionPossibleStatus::DeploymentNotReady => {
-                OpenFaasFunctionStatusConditionMessage {
-                    message: Some(String::from("The function's deployment is not ready")),
-                }
-            }
-            OpenFaasFunctionPossibleStatus::ServiceAlreadyExists => {
-                OpenFaasFunctionStatusConditionMessage {
-                    message: Some(String::from(
-                        "The function's service already deployed by third party",
-                    )),
-                }
-            }
-            OpenFaasFunctionPossibleStatus::SecretsNotFound => {
-                OpenFaasFunctionStatusConditionMessage {
-                    message: Some(String::from("The given secrets to mount do not exist")),
-                }
-            }
-        }
+    fn to_env_process_name(&self) -> String {
+        String::from("fprocess")
     }
-}
 
-impl From<OpenFaasFunctionPossibleStatus> for OpenFaasFunctionStatusCondition {
-    fn from(status: OpenFaasFunctionPossibleStatus) -> Self {
-        OpenFaasFunctionStatusCondition {
-            type_: OpenFaasFunctionStatusConditionType::Ready,
-            status: OpenFaasFunctionStatusConditionStatus::from(&status),
-            message: OpenFaasFunctionStatusConditionMessage::from(&status),
-            reason: status,
-            last_update_time: Some(Time(chrono::Utc::now())),
-        }
+    pub fn to_name(&self) -> String {
+        self.service.clone()
     }
-}
 
-impl From<OpenFaasFunctionPossibleStatus> for OpenFaasFunctionStatus {
-    fn from(status: OpenFaasFunctionPossibleStatus) -> Self {
-        OpenFaasFunctionStatus {
-            conditions: vec![OpenFaasFunctionStatusCondition::from(status)],
-        }
+    fn to_namespace(&self) -> Option<String> {
+        self.namespace.clone()
     }
-}
 
-impl From<&FunctionIntoDeploymentError> for Option<OpenFaasFunctionPossibleStatus> {
-    fn from(e: &FunctionIntoDeploymentError) -> Self {
-        match e {
-            FunctionIntoDeploymentError::FunctionSpec(
-                FunctionSpecIntoDeploymentError::Quantity(e),
-            ) => match e {
-                IntoQuantityError::Memory(_) => {
-                    Some(OpenFaasFunctionPossibleStatus::MemoryQuantity)
-                }
-                IntoQuantityError::CPU(_) => Some(OpenFaasFunctionPossibleStatus::CPUQuantity),
-            },
-            _ => None,
-        }
+    fn to_image(&self) -> String {
+        self.image.clone()
     }
-}
 
-impl OpenFaasFunctionSpec {
-    pub fn debug_compare_deployment(&self, deployment: &Deployment) {
-        tracing::debug!("Starting deployment comparison");
-        tracing::debug!("Missing, edited or corrupted '{LAST_APPLIED_ANNOTATION}' annotation can cause unexpected behaviour");
-        // first we get the prev spec
+    fn to_meta_labels(&self) -> BTreeMap<String, String> {
+        [(String::from(FUNCTION_LABEL), self.to_name())].into()
+    }
 
-        let dep_meta_annotations = deployment
-            .metadata
-            .annotations
-            .as_ref()
-            .unwrap_or(&BTreeMap::new())
+    fn to_spec_meta_labels(&self) -> BTreeMap<String, String> {
+        self.labels
+            .clone()
+            .map(|lables| {
+                let mut labels: BTreeMap<String, String> = lables.into_iter().collect();
+
+                labels.extend(self.to_meta_labels());
+
+                labels
+            })
+            .unwrap_or(self.to_meta_labels())
+    }
+
+    fn to_service_selector_labels(&self) -> BTreeMap<String, String> {
+        self.to_meta_labels()
+    }
+
+    /// Replicas are passed through as set, including 0, trafficWeight is the fallback
+    pub fn to_replicas(&self) -> i32 {
+        self.replicas
+            .or_else(|| {
+                self.traffic_weight
+                    .map(|weight| i32::try_from(weight).unwrap_or(i32::MAX))
+            })
+            .unwrap_or(1)
+    }
+
+    /// The value of the com.openfaas.scale.min label if it disagrees with `replicas`, which takes precedence
+    pub fn scale_min_conflict(&self) -> Option<String> {
+        let replicas = self.replicas?;
+        let scale_min = self.labels.as_ref()?.get(SCALE_MIN_LABEL)?;
+
+        match scale_min.trim().parse::<i32>() {
+            Ok(scale_min) if scale_min == replicas => None,
+            _ => Some(scale_min.clone()),
+        }
+    }
+
+    fn to_annotations(&self) -> Option<BTreeMap<String, String>> {
+        self.annotations.clone().map(|a| a.into_iter().collect())
+    }
+
+    fn to_meta_annotations(&self) -> Result<BTreeMap<String, String>, SerdeJsonError> {
+        let mut meta_annotaions = BTreeMap::new();
+
+        if let Some(annotations) = self.to_annotations() {
+            meta_annotaions.extend(annotations);
+        }
+
+        meta_annotaions.insert(
+            String::from(LAST_APPLIED_ANNOTATION),
+            serde_json::to_string(self)?,
+        );
+
+        Ok(meta_annotaions)
+    }
+
+    fn is_recreate_strategy(&self) -> bool {
+        self.deployment_strategy_type.as_deref() == Some("Recreate")
+    }
+
+    fn deployment_strategy_errors(&self) -> Vec<SpecValidationError> {
+        if let Some(ref strategy_type) = self.deployment_strategy_type {
+            if !DEPLOYMENT_STRATEGY_TYPES.contains(&strategy_type.as_str()) {
+                return vec![SpecValidationError::DeploymentStrategyType(
+                    strategy_type.clone(),
+                )];
+            }
+        }
+
+        if self.is_recreate_strategy() {
+            if self.max_surge.is_some() || self.max_unavailable.is_some() {
+                return vec![SpecValidationError::RollingUpdateWithRecreate];
+            }
+
+            return vec![];
+        }
+
+        /// The value as a number or percentage, `None` if invalid
+        fn parse(value: &IntOrString) -> Option<i32> {
+            match value {
+                IntOrString::Int(value) => Some(*value),
+                IntOrString::String(value) => value.strip_suffix('%')?.parse::<i32>().ok(),
+            }
+            .filter(|value| *value >= 0)
+        }
+
+        let RollingUpdateDeployment {
+            max_surge,
+            max_unavailable,
+        } = RollingUpdateDeployment::from(self);
+
+        let mut errors = vec![];
+        let mut values = vec![];
+
+        for (field, value) in [
+            ("maxSurge", max_surge.unwrap_or(IntOrString::Int(1))),
+            (
+                "maxUnavailable",
+                max_unavailable.unwrap_or(IntOrString::Int(0)),
+            ),
+        ] {
+            match parse(&value) {
+                Some(value) => values.push(value),
+                None => errors.push(SpecValidationError::RollingUpdateValue(field)),
+            }
+        }
+
+        if values == [0, 0] {
+            errors.push(SpecValidationError::RollingUpdateZero);
+        }
+
+        errors
+    }
+
+    /// The node selector from the key==value constraints
+    fn to_node_selector(&self) -> Option<BTreeMap<String, String>> {
+        let node_selector: BTreeMap<String, String> = self
+            .get_constraints_vec()
+            .iter()
+            .filter_map(|constraint| match parse_constraint(constraint) {
+                Some(NodeConstraint::Equals(key, value)) => Some((key, value)),
+                _ => None,
+            })
+            .collect();
+
+        if node_selector.is_empty() {
+            return None;
+        }
+
+        Some(node_selector)
+    }
+
+    /// The key!=value constraints as NotIn requirements, one per key
+    fn to_node_anti_affinity_requirements(&self) -> Vec<NodeSelectorRequirement> {
+        self.get_constraints_vec()
+            .iter()
+            .filter_map(|constraint| match parse_constraint(constraint) {
+                Some(NodeConstraint::NotEquals(key, value)) => Some((key, value)),
+                _ => None,
+            })
+            .into_group_map()
+            .into_iter()
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(key, values)| NodeSelectorRequirement {
+                key,
+                operator: String::from("NotIn"),
+                values: Some(values.into_iter().unique().collect()),
+            })
+            .collect()
+    }
+
+    /// The affinity of the spec, with the key!=value constraints added to every required node selector term.
+    ///
+    /// Terms are ORed, so the requirements must be part of each of them to always apply
+    fn to_affinity(&self) -> Option<Affinity> {
+        let requirements = self.to_node_anti_affinity_requirements();
+        if requirements.is_empty() {
+            return self.affinity.clone();
+        }
+
+        let mut affinity = self.affinity.clone().unwrap_or_default();
+        let node_selector = affinity
+            .node_affinity
+            .get_or_insert_with(NodeAffinity::default)
+            .required_during_scheduling_ignored_during_execution
+            .get_or_insert_with(NodeSelector::default);
+
+        if node_selector.node_selector_terms.is_empty() {
+            node_selector
+                .node_selector_terms
+                .push(NodeSelectorTerm::default());
+        }
+
+        for term in node_selector.node_selector_terms.iter_mut() {
+            term.match_expressions
+                .get_or_insert_with(Vec::new)
+                .extend(requirements.clone());
+        }
+
+        Some(affinity)
+    }
+
+    fn to_deployment_meta(&self) -> Result<ObjectMeta, SerdeJsonError> {
+        Ok(ObjectMeta {
+            name: Some(self.to_name()),
+            namespace: self.to_namespace(),
+            labels: Some(self.to_meta_labels()),
+            annotations: Some(self.to_meta_annotations()?),
+            ..Default::default()
+        })
+    }
+
+    fn to_service_annotations(&self) -> Option<BTreeMap<String, String>> {
+        self.service_annotations
+            .clone()
+            .map(|a| a.into_iter().collect())
+    }
+
+    fn to_service_meta(&self) -> Result<ObjectMeta, SerdeJsonError> {
+        let mut meta = self.to_deployment_meta()?;
+
+        if let Some(service_annotations) = self.to_service_annotations() {
+            meta.annotations
+                .get_or_insert_with(BTreeMap::new)
+                .extend(service_annotations);
+        }
+
+        Ok(meta)
+    }
+
+    fn to_pod_disruption_budget_meta(&self) -> Result<ObjectMeta, SerdeJsonError> {
+        self.to_deployment_meta()
+    }
+
+    fn to_network_policy_meta(&self) -> Result<ObjectMeta, SerdeJsonError> {
+        self.to_deployment_meta()
+    }
+
+    fn to_spec_template_meta(&self) -> ObjectMeta {
+        ObjectMeta {
+            name: Some(self.to_name()),
+            labels: Some(self.to_spec_meta_labels()),
+            annotations: self.to_annotations(),
+            ..Default::default()
+        }
+    }
+
+    fn try_to_limits(&self) -> Result<Option<BTreeMap<String, Quantity>>, IntoQuantityError> {
+        if let Some(ref limits) = self.limits {
+            return limits.try_to_k8s_resources();
+        }
+
+        Ok(None)
+    }
+
+    fn try_to_requests(&self) -> Result<Option<BTreeMap<String, Quantity>>, IntoQuantityError> {
+        if let Some(ref requests) = self.requests {
+            return requests.try_to_k8s_resources();
+        }
+
+        Ok(None)
+    }
+
+    fn to_tmp_volume_name(&self) -> String {
+        String::from("tmp")
+    }
+
+    fn to_tmp_volume(&self) -> Volume {
+        Volume {
+            name: self.to_tmp_volume_name(),
+            empty_dir: Some(Default::default()),
+            ..Default::default()
+        }
+    }
+
+    fn to_tmp_volume_mount_path(&self) -> String {
+        self.tmp_mount_path.clone().unwrap_or(String::from("/tmp"))
+    }
+
+    fn to_tmp_volume_mount(&self) -> VolumeMount {
+        VolumeMount {
+            name: self.to_tmp_volume_name(),
+            mount_path: self.to_tmp_volume_mount_path(),
+            ..Default::default()
+        }
+    }
+
+    fn to_secrets_volume_name(&self) -> String {
+        format!("{}-projected-secrets", self.to_name())
+    }
+
+    fn to_secrets_projected_volume_source(&self) -> Option<ProjectedVolumeSource> {
+        let secrets = self.get_secrets_unique_vec();
+
+        if secrets.is_empty() {
+            return None;
+        }
+
+        let sources = secrets
+            .iter()
+            .map(|secret| {
+                let items = vec![KeyToPath {
+                    key: secret.clone(),
+                    path: secret.clone(),
+                    ..Default::default()
+                }];
+
+                VolumeProjection {
+                    secret: Some(SecretProjection {
+                        name: Some(secret.clone()),
+                        items: Some(items),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        Some(ProjectedVolumeSource {
+            sources: Some(sources),
+            ..Default::default()
+        })
+    }
+
+    fn to_secrets_volume(&self) -> Volume {
+        Volume {
+            name: self.to_secrets_volume_name(),
+            projected: self.to_secrets_projected_volume_source(),
+            ..Default::default()
+        }
+    }
+
+    fn to_default_secrets_mount_path(&self) -> String {
+        String::from("/var/openfaas/secrets")
+    }
+
+    fn to_secrets_mount_path(&self) -> String {
+        self.secrets_mount_path
+            .clone()
+            .unwrap_or(self.to_default_secrets_mount_path())
+    }
+
+    fn to_secrets_volume_mount(&self) -> VolumeMount {
+        VolumeMount {
+            name: self.to_secrets_volume_name(),
+            mount_path: self.to_secrets_mount_path(),
+            read_only: Some(true),
+            ..Default::default()
+        }
+    }
+
+    fn to_ca_bundle_volume_name(&self) -> String {
+        format!("{}-ca-bundle", self.to_name())
+    }
+
+    fn to_ca_bundle_key(&self) -> String {
+        self.ca_bundle_key.clone().unwrap_or(String::from("ca.crt"))
+    }
+
+    fn to_ca_bundle_file_name(&self) -> String {
+        String::from("custom-ca.pem")
+    }
+
+    fn to_ca_bundle_volume(&self) -> Volume {
+        Volume {
+            name: self.to_ca_bundle_volume_name(),
+            config_map: Some(ConfigMapVolumeSource {
+                name: self.ca_bundle_config_map.clone(),
+                items: Some(vec![KeyToPath {
+                    key: self.to_ca_bundle_key(),
+                    path: self.to_ca_bundle_file_name(),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn to_default_ca_bundle_mount_path(&self) -> String {
+        String::from("/etc/ssl/certs/custom-ca.pem")
+    }
+
+    fn to_ca_bundle_mount_path(&self) -> String {
+        self.ca_bundle_mount_path
+            .clone()
+            .unwrap_or(self.to_default_ca_bundle_mount_path())
+    }
+
+    /// Mounts only the bundle file, so the rest of the directory is not shadowed
+    fn to_ca_bundle_volume_mount(&self) -> VolumeMount {
+        VolumeMount {
+            name: self.to_ca_bundle_volume_name(),
+            mount_path: self.to_ca_bundle_mount_path(),
+            sub_path: Some(self.to_ca_bundle_file_name()),
+            read_only: Some(true),
+            ..Default::default()
+        }
+    }
+
+    fn to_ca_bundle_env_var(&self) -> Option<EnvVar> {
+        if !self.should_create_ca_bundle_volume() {
+            return None;
+        }
+
+        Some(EnvVar {
+            name: String::from("SSL_CERT_FILE"),
+            value: Some(self.to_ca_bundle_mount_path()),
+            ..Default::default()
+        })
+    }
+
+    pub fn to_yaml_string(&self) -> Result<String, FunctionSpecIntoYamlError> {
+        let mut string = String::new();
+        let deployment =
+            Deployment::try_from(self).map_err(FunctionSpecIntoYamlError::Deployment)?;
+        let deplyoment_str =
+            serde_yaml::to_string(&deployment).map_err(FunctionSpecIntoYamlError::Serialize)?;
+        let service = Service::try_from(self).map_err(FunctionSpecIntoYamlError::Service)?;
+        let service_str =
+            serde_yaml::to_string(&service).map_err(FunctionSpecIntoYamlError::Serialize)?;
+
+        string.push_str(&deplyoment_str);
+        string.push_str("---\n");
+        string.push_str(&service_str);
+
+        if let Some(pdb) = Option::<PodDisruptionBudget>::try_from(self)
+            .map_err(FunctionSpecIntoYamlError::PodDisruptionBudget)?
+        {
+            let pdb_str =
+                serde_yaml::to_string(&pdb).map_err(FunctionSpecIntoYamlError::Serialize)?;
+
+            string.push_str("---\n");
+            string.push_str(&pdb_str);
+        }
+
+        if let Some(network_policy) = Option::<NetworkPolicy>::try_from(self)
+            .map_err(FunctionSpecIntoYamlError::NetworkPolicy)?
+        {
+            let network_policy_str = serde_yaml::to_string(&network_policy)
+                .map_err(FunctionSpecIntoYamlError::Serialize)?;
+
+            string.push_str("---\n");
+            string.push_str(&network_policy_str);
+        }
+
+        Ok(string)
+    }
+}
+
+/// Every key of `desired` exists in `live` with the same value
+fn is_subset(
+    desired: &Option<BTreeMap<String, String>>,
+    live: &Option<BTreeMap<String, String>>,
+) -> bool {
+    utils::a_key_is_missing_or_diffirent_btree(
+        desired.as_ref().unwrap_or(&BTreeMap::new()),
+        live.as_ref().unwrap_or(&BTreeMap::new()),
+    )
+    .is_none()
+}
+
+/// Compares images by their normalized references, the operator may normalize them on creation
+fn same_image(desired: &Option<String>, live: &Option<String>) -> bool {
+    let normalized = |image: &Option<String>| {
+        image.as_ref().map(|image| {
+            image
+                .parse::<ImageReference>()
+                .map(|reference| reference.normalized())
+                .unwrap_or_else(|_| image.clone())
+        })
+    };
+
+    normalized(desired) == normalized(live)
+}
+
+/// The literal value of the container's env var with the given name
+fn env_value(container: &Container, name: &str) -> Option<String> {
+    container
+        .env
+        .iter()
+        .flatten()
+        .find(|env_var| env_var.name == name)
+        .and_then(|env_var| env_var.value.clone())
+}
+
+/// The names and literal values of the container's env vars except `except`, env vars populated from fields are defaulted by the API server
+fn env_values(container: &Container, except: &str) -> Vec<(String, Option<String>)> {
+    container
+        .env
+        .iter()
+        .flatten()
+        .filter(|env_var| env_var.name != except)
+        .map(|env_var| (env_var.name.clone(), env_var.value.clone()))
+        .sorted()
+        .collect()
+}
+
+/// Compares quantities by their values, the API server stores them in canonical form, e.g. 0.5 as 500m
+fn same_quantities(
+    desired: &Option<BTreeMap<String, Quantity>>,
+    live: &Option<BTreeMap<String, Quantity>>,
+) -> bool {
+    let parsed = |quantities: &Option<BTreeMap<String, Quantity>>| {
+        quantities
+            .iter()
+            .flatten()
+            .map(|(name, quantity)| {
+                (
+                    name.clone(),
+                    ParsedQuantity::try_from(quantity).map_err(|_| quantity.0.clone()),
+                )
+            })
+            .collect::<BTreeMap<_, _>>()
+    };
+
+    let (desired, live) = (parsed(desired), parsed(live));
+
+    desired.len() == live.len()
+        && desired
+            .iter()
+            .all(|(name, quantity)| match (quantity, live.get(name)) {
+                (Ok(desired), Some(Ok(live))) => desired == live,
+                (Err(desired), Some(Err(live))) => desired == live,
+                _ => false,
+            })
+}
+
+/// A parsed constraint, whitespace removed
+enum NodeConstraint {
+    /// key==value
+    Equals(String, String),
+    /// key!=value
+    NotEquals(String, String),
+}
+
+fn parse_constraint(constraint: &str) -> Option<NodeConstraint> {
+    let constraint = utils::remove_whitespace(constraint);
+
+    if let Some((key, value)) = constraint.split_once("!=") {
+        return Some(NodeConstraint::NotEquals(
+            key.to_string(),
+            value.to_string(),
+        ));
+    }
+
+    constraint
+        .split_once("==")
+        .map(|(key, value)| NodeConstraint::Equals(key.to_string(), value.to_string()))
+}
+
+impl From<&OpenFaasFunctionSpec> for HTTPGetAction {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        HTTPGetAction {
+            path: Some(value.to_http_probe_path()),
+            port: IntOrString::Int(value.to_container_port()),
+            scheme: Some(String::from("HTTP")),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for Probe {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        Probe {
+            http_get: Some(HTTPGetAction::from(value)),
+            ..Default::default()
+        }
+    }
+}
+
+impl ProbeConfig {
+    /// Overrides the timings of the probe that are set in the config
+    fn apply(&self, probe: &mut Probe) {
+        if let Some(initial_delay_seconds) = self.initial_delay_seconds {
+            probe.initial_delay_seconds = Some(initial_delay_seconds);
+        }
+        if let Some(period_seconds) = self.period_seconds {
+            probe.period_seconds = Some(period_seconds);
+        }
+        if let Some(timeout_seconds) = self.timeout_seconds {
+            probe.timeout_seconds = Some(timeout_seconds);
+        }
+        if let Some(failure_threshold) = self.failure_threshold {
+            probe.failure_threshold = Some(failure_threshold);
+        }
+    }
+
+    fn errors(&self, probe: &'static str) -> Vec<SpecValidationError> {
+        [
+            ("initialDelaySeconds", self.initial_delay_seconds, 0),
+            ("periodSeconds", self.period_seconds, 1),
+            ("timeoutSeconds", self.timeout_seconds, 1),
+            ("failureThreshold", self.failure_threshold, 1),
+        ]
+        .into_iter()
+        .filter_map(|(field, value, min)| {
+            value
+                .filter(|value| *value < min)
+                .map(|value| SpecValidationError::ProbeConfig {
+                    probe,
+                    field,
+                    min,
+                    value,
+                })
+        })
+        .collect()
+    }
+}
+
+impl OpenFaasFunctionSpec {
+    /// The liveness probe, with the timings of livenessProbe if set
+    fn to_liveness_probe(&self) -> Probe {
+        let mut probe = Probe::from(self);
+
+        if let Some(ref config) = self.liveness_probe {
+            config.apply(&mut probe);
+        }
+
+        probe
+    }
+
+    /// The readiness probe, with timings derived from readinessGateSeconds if set.
+    ///
+    /// A quarter of the window passes before the first probe,
+    /// the rest is covered by up to 10 probes at least a second and at most 10 seconds apart.
+    /// The timings of readinessProbe take precedence
+    fn to_readiness_probe(&self) -> Probe {
+        let mut probe = Probe::from(self);
+
+        if let Some(window) = self.readiness_gate_seconds {
+            let window = window.max(1) as i32;
+            let initial_delay_seconds = window / 4;
+            let remaining = window - initial_delay_seconds;
+            let period_seconds = (remaining / 10).clamp(1, 10);
+
+            probe.initial_delay_seconds = Some(initial_delay_seconds);
+            probe.period_seconds = Some(period_seconds);
+            probe.failure_threshold = Some((remaining + period_seconds - 1) / period_seconds);
+        }
+
+        if let Some(ref config) = self.readiness_probe {
+            config.apply(&mut probe);
+        }
+
+        probe
+    }
+
+    /// The startup probe if readinessGateSeconds is set, probing for the whole window.
+    ///
+    /// Kubernetes holds back the liveness probe until the startup probe succeeds,
+    /// so a function using the whole window to start is not restarted by its liveness probe
+    fn to_startup_probe(&self) -> Option<Probe> {
+        let window = self.readiness_gate_seconds?.max(1) as i32;
+        let period_seconds = (window / 10).clamp(1, 10);
+
+        let mut probe = Probe::from(self);
+        probe.period_seconds = Some(period_seconds);
+        probe.failure_threshold = Some((window + period_seconds - 1) / period_seconds);
+
+        Some(probe)
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for ContainerPort {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        ContainerPort {
+            name: Some(String::from("http")),
+            container_port: value.to_container_port(),
+            protocol: Some(String::from("TCP")),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for Vec<ContainerPort> {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        vec![ContainerPort::from(value)]
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for Option<Vec<ContainerPort>> {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        Some(Vec::<ContainerPort>::from(value))
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for SecurityContext {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        SecurityContext {
+            read_only_root_filesystem: value.read_only_root_filesystem,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for Option<SecurityContext> {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        Some(SecurityContext::from(value))
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for Vec<EnvVar> {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        let mut env_vars = Vec::new();
+
+        if let Some(env_process) = value.env_process.clone() {
+            env_vars.push(EnvVar {
+                name: value.to_env_process_name(),
+                value: Some(env_process),
+                ..Default::default()
+            });
+        }
+
+        if let Some(env_vars_map) = value.env_vars.clone() {
+            for (k, v) in env_vars_map {
+                env_vars.push(EnvVar {
+                    name: k,
+                    value: Some(v),
+                    ..Default::default()
+                });
+            }
+        }
+
+        env_vars.extend(value.to_downward_env_vars());
+        env_vars.extend(value.to_ca_bundle_env_var());
+
+        env_vars
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for Option<Vec<EnvVar>> {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        let env_vars = Vec::<EnvVar>::from(value);
+
+        if env_vars.is_empty() {
+            return None;
+        }
+
+        Some(env_vars)
+    }
+}
+
+impl TryFrom<&OpenFaasFunctionSpec> for ResourceRequirements {
+    type Error = IntoQuantityError;
+
+    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
+        Ok(ResourceRequirements {
+            limits: value.try_to_limits()?,
+            requests: value.try_to_requests()?,
+        })
+    }
+}
+
+impl TryFrom<&OpenFaasFunctionSpec> for Option<ResourceRequirements> {
+    type Error = IntoQuantityError;
+
+    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
+        Ok(Some(ResourceRequirements::try_from(value)?))
+    }
+}
+
+impl TryFrom<&OpenFaasFunctionSpec> for Container {
+    type Error = IntoQuantityError;
+
+    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
+        Ok(Container {
+            name: value.to_name(),
+            image: Some(value.to_image()),
+            image_pull_policy: value.image_pull_policy.clone(),
+            ports: Option::<Vec<ContainerPort>>::from(value),
+            liveness_probe: Some(value.to_liveness_probe()),
+            readiness_probe: Some(value.to_readiness_probe()),
+            startup_probe: value.to_startup_probe(),
+            security_context: Option::<SecurityContext>::from(value),
+            volume_mounts: Option::<Vec<VolumeMount>>::from(value),
+            resources: Option::<ResourceRequirements>::try_from(value)?,
+            env: Option::<Vec<EnvVar>>::from(value),
+            termination_message_policy: value.termination_message_policy.clone(),
+            termination_message_path: value.termination_message_path.clone(),
+            ..Default::default()
+        })
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for Vec<VolumeMount> {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        let mut volume_mounts = Vec::new();
+
+        if value.should_create_tmp_volume() {
+            volume_mounts.push(value.to_tmp_volume_mount());
+        }
+
+        if value.should_create_secrets_volume() {
+            volume_mounts.push(value.to_secrets_volume_mount());
+        }
+
+        if value.should_create_ca_bundle_volume() {
+            volume_mounts.push(value.to_ca_bundle_volume_mount());
+        }
+
+        volume_mounts
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for Option<Vec<VolumeMount>> {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        let volume_mounts = Vec::<VolumeMount>::from(value);
+
+        if volume_mounts.is_empty() {
+            return None;
+        }
+
+        Some(volume_mounts)
+    }
+}
+
+impl TryFrom<&OpenFaasFunctionSpec> for Vec<Container> {
+    type Error = IntoQuantityError;
+
+    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
+        Ok(vec![Container::try_from(value)?])
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for Vec<Volume> {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        let mut volumes = Vec::new();
+
+        if value.should_create_tmp_volume() {
+            volumes.push(value.to_tmp_volume());
+        }
+
+        if value.should_create_secrets_volume() {
+            volumes.push(value.to_secrets_volume());
+        }
+
+        if value.should_create_ca_bundle_volume() {
+            volumes.push(value.to_ca_bundle_volume());
+        }
+
+        volumes
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for Option<Vec<Volume>> {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        let volumes = Vec::<Volume>::from(value);
+
+        if volumes.is_empty() {
+            return None;
+        }
+
+        Some(volumes)
+    }
+}
+
+impl TryFrom<&OpenFaasFunctionSpec> for PodSpec {
+    type Error = FunctionSpecIntoDeploymentError;
+
+    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
+        let pod_spec = PodSpec {
+            containers: Vec::<Container>::try_from(value)?,
+            volumes: Option::<Vec<Volume>>::from(value),
+            image_pull_secrets: value.to_image_pull_secrets(),
+            node_selector: value.to_node_selector(),
+            tolerations: value.tolerations.clone(),
+            affinity: value.to_affinity(),
+            enable_service_links: value.enable_service_links,
+            runtime_class_name: value.runtime_class_name.clone(),
+            ..Default::default()
+        };
+
+        value.apply_pod_spec_overlay(pod_spec)
+    }
+}
+
+impl TryFrom<&OpenFaasFunctionSpec> for Option<PodSpec> {
+    type Error = FunctionSpecIntoDeploymentError;
+
+    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
+        Ok(Some(PodSpec::try_from(value)?))
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for LabelSelector {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        LabelSelector {
+            match_labels: Some(value.to_meta_labels()),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for RollingUpdateDeployment {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        RollingUpdateDeployment {
+            max_surge: Some(value.max_surge.clone().unwrap_or(IntOrString::Int(1))),
+            max_unavailable: Some(value.max_unavailable.clone().unwrap_or(IntOrString::Int(0))),
+        }
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for Option<RollingUpdateDeployment> {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        Some(RollingUpdateDeployment::from(value))
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for DeploymentStrategy {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        if value.is_recreate_strategy() {
+            return DeploymentStrategy {
+                type_: Some(String::from("Recreate")),
+                rolling_update: None,
+            };
+        }
+
+        DeploymentStrategy {
+            rolling_update: Option::<RollingUpdateDeployment>::from(value),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for Option<DeploymentStrategy> {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        Some(DeploymentStrategy::from(value))
+    }
+}
+
+impl TryFrom<&OpenFaasFunctionSpec> for PodTemplateSpec {
+    type Error = FunctionSpecIntoDeploymentError;
+
+    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
+        Ok(PodTemplateSpec {
+            metadata: Some(value.to_spec_template_meta()),
+            spec: Option::<PodSpec>::try_from(value)?,
+        })
+    }
+}
+
+impl TryFrom<&OpenFaasFunctionSpec> for DeploymentSpec {
+    type Error = FunctionSpecIntoDeploymentError;
+
+    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
+        Ok(DeploymentSpec {
+            replicas: Some(value.to_replicas()),
+            paused: value.deployment_paused,
+            selector: LabelSelector::from(value),
+            strategy: Option::<DeploymentStrategy>::from(value),
+            template: PodTemplateSpec::try_from(value)?,
+            ..Default::default()
+        })
+    }
+}
+
+impl TryFrom<&OpenFaasFunctionSpec> for Option<DeploymentSpec> {
+    type Error = FunctionSpecIntoDeploymentError;
+
+    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
+        Ok(Some(DeploymentSpec::try_from(value)?))
+    }
+}
+
+/// Generate a fresh deployment
+impl TryFrom<&OpenFaasFunctionSpec> for Deployment {
+    type Error = FunctionSpecIntoDeploymentError;
+
+    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
+        if let Some(name) = value.find_unsupported_downward_env() {
+            return Err(FunctionSpecIntoDeploymentError::DownwardEnv(name));
+        }
+
+        value.image.parse::<ImageReference>()?;
+
+        if let Some(ref policy) = value.termination_message_policy {
+            if !TERMINATION_MESSAGE_POLICIES.contains(&policy.as_str()) {
+                return Err(FunctionSpecIntoDeploymentError::TerminationMessagePolicy(
+                    policy.clone(),
+                ));
+            }
+        }
+
+        if let Some(ref policy) = value.image_pull_policy {
+            if !IMAGE_PULL_POLICIES.contains(&policy.as_str()) {
+                return Err(FunctionSpecIntoDeploymentError::ImagePullPolicy(
+                    policy.clone(),
+                ));
+            }
+        }
+
+        if let Some(ref strategy_type) = value.deployment_strategy_type {
+            if !DEPLOYMENT_STRATEGY_TYPES.contains(&strategy_type.as_str()) {
+                return Err(FunctionSpecIntoDeploymentError::DeploymentStrategyType(
+                    strategy_type.clone(),
+                ));
+            }
+        }
+
+        value.validate_metadata()?;
+
+        let deployment = Deployment {
+            metadata: value.to_deployment_meta()?,
+            spec: Option::<DeploymentSpec>::try_from(value)?,
+            ..Default::default()
+        };
+
+        Ok(deployment)
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for ServicePort {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        ServicePort {
+            name: Some(String::from("http")),
+            port: DEFAULT_CONTAINER_PORT,
+            target_port: Some(IntOrString::Int(value.to_container_port())),
+            protocol: Some(String::from("TCP")),
+            app_protocol: value.app_protocol.clone(),
+            node_port: value.node_port,
+        }
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for Vec<ServicePort> {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        vec![ServicePort::from(value)]
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for Option<Vec<ServicePort>> {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        Some(Vec::<ServicePort>::from(value))
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for ServiceSpec {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        ServiceSpec {
+            selector: Some(value.to_service_selector_labels()),
+            ports: Option::<Vec<ServicePort>>::from(value),
+            type_: value.service_type.clone(),
+            load_balancer_class: value.load_balancer_class.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for Option<ServiceSpec> {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        Some(ServiceSpec::from(value))
+    }
+}
+
+/// Generate a fresh service
+impl TryFrom<&OpenFaasFunctionSpec> for Service {
+    type Error = FunctionSpecIntoServiceError;
+
+    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
+        if let Some(error) = value.service_errors().into_iter().next() {
+            return Err(error);
+        }
+
+        Ok(Service {
+            metadata: value.to_service_meta()?,
+            spec: Option::<ServiceSpec>::from(value),
+            ..Default::default()
+        })
+    }
+}
+
+impl TryFrom<&OpenFaasFunctionSpec> for Option<PodDisruptionBudgetSpec> {
+    type Error = FunctionSpecIntoPodDisruptionBudgetError;
+
+    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
+        let Some(ref pdb) = value.pod_disruption_budget else {
+            return Ok(None);
+        };
+
+        if pdb.min_available.is_some() == pdb.max_unavailable.is_some() {
+            return Err(FunctionSpecIntoPodDisruptionBudgetError::MinAvailableOrMaxUnavailable);
+        }
+
+        Ok(Some(PodDisruptionBudgetSpec {
+            selector: Some(LabelSelector::from(value)),
+            min_available: pdb.min_available.clone(),
+            max_unavailable: pdb.max_unavailable.clone(),
+        }))
+    }
+}
+
+/// Generate a fresh pod disruption budget, if the spec requests one
+impl TryFrom<&OpenFaasFunctionSpec> for Option<PodDisruptionBudget> {
+    type Error = FunctionSpecIntoPodDisruptionBudgetError;
+
+    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
+        let Some(spec) = Option::<PodDisruptionBudgetSpec>::try_from(value)? else {
+            return Ok(None);
+        };
+
+        Ok(Some(PodDisruptionBudget {
+            metadata: value.to_pod_disruption_budget_meta()?,
+            spec: Some(spec),
+            ..Default::default()
+        }))
+    }
+}
+
+/// The OpenFaaS gateway's pods, allowed to reach functions if a network policy sets no ingress peers
+fn gateway_network_policy_peer() -> NetworkPolicyPeer {
+    NetworkPolicyPeer {
+        namespace_selector: Some(LabelSelector {
+            match_labels: Some(
+                [(
+                    String::from("kubernetes.io/metadata.name"),
+                    String::from("openfaas"),
+                )]
+                .into(),
+            ),
+            ..Default::default()
+        }),
+        pod_selector: Some(LabelSelector {
+            match_labels: Some([(String::from("app"), String::from("gateway"))].into()),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+impl From<&OpenFaasFunctionSpec> for Option<NetworkPolicySpec> {
+    fn from(value: &OpenFaasFunctionSpec) -> Self {
+        let network_policy = value.network_policy.as_ref()?;
+
+        let ingress_from = network_policy
+            .ingress_from
+            .clone()
+            .unwrap_or_else(|| vec![gateway_network_policy_peer()]);
+
+        let mut policy_types = vec![String::from("Ingress")];
+        if network_policy.egress.is_some() {
+            policy_types.push(String::from("Egress"));
+        }
+
+        Some(NetworkPolicySpec {
+            pod_selector: LabelSelector::from(value),
+            ingress: Some(vec![NetworkPolicyIngressRule {
+                from: Some(ingress_from),
+                ports: Some(vec![NetworkPolicyPort {
+                    port: Some(IntOrString::Int(value.to_container_port())),
+                    protocol: Some(String::from("TCP")),
+                    ..Default::default()
+                }]),
+            }]),
+            egress: network_policy.egress.clone(),
+            policy_types: Some(policy_types),
+        })
+    }
+}
+
+impl OpenFaasFunctionSpec {
+    /// Compares the generated network policy with the stored one, ignoring the defaults the API server
+    /// fills into the user supplied rules
+    pub fn network_policy_is_up_to_date(&self, network_policy: &NetworkPolicy) -> bool {
+        Option::<NetworkPolicySpec>::from(self).map(normalized_network_policy_spec)
+            == network_policy
+                .spec
+                .clone()
+                .map(normalized_network_policy_spec)
+    }
+}
+
+/// The network policy spec as stored by the API server, which defaults the protocol of ports to TCP
+/// and drops empty lists and maps
+fn normalized_network_policy_spec(mut spec: NetworkPolicySpec) -> NetworkPolicySpec {
+    normalize_label_selector(&mut spec.pod_selector);
+
+    for rule in spec.ingress.iter_mut().flatten() {
+        normalize_network_policy_peers(&mut rule.from);
+        normalize_network_policy_ports(&mut rule.ports);
+    }
+
+    for rule in spec.egress.iter_mut().flatten() {
+        normalize_network_policy_peers(&mut rule.to);
+        normalize_network_policy_ports(&mut rule.ports);
+    }
+
+    none_if_empty(&mut spec.ingress);
+    none_if_empty(&mut spec.egress);
+
+    spec
+}
+
+fn normalize_network_policy_peers(peers: &mut Option<Vec<NetworkPolicyPeer>>) {
+    for peer in peers.iter_mut().flatten() {
+        for selector in [&mut peer.pod_selector, &mut peer.namespace_selector]
+            .into_iter()
+            .flatten()
+        {
+            normalize_label_selector(selector);
+        }
+
+        if let Some(ip_block) = peer.ip_block.as_mut() {
+            none_if_empty(&mut ip_block.except);
+        }
+    }
+
+    none_if_empty(peers);
+}
+
+fn normalize_network_policy_ports(ports: &mut Option<Vec<NetworkPolicyPort>>) {
+    for port in ports.iter_mut().flatten() {
+        port.protocol.get_or_insert_with(|| String::from("TCP"));
+    }
+
+    none_if_empty(ports);
+}
+
+fn normalize_label_selector(selector: &mut LabelSelector) {
+    if selector
+        .match_labels
+        .as_ref()
+        .is_some_and(|labels| labels.is_empty())
+    {
+        selector.match_labels = None;
+    }
+
+    none_if_empty(&mut selector.match_expressions);
+}
+
+fn none_if_empty<T>(list: &mut Option<Vec<T>>) {
+    if list.as_ref().is_some_and(|list| list.is_empty()) {
+        *list = None;
+    }
+}
+
+/// Generate a fresh network policy, if the spec requests one
+impl TryFrom<&OpenFaasFunctionSpec> for Option<NetworkPolicy> {
+    type Error = SerdeJsonError;
+
+    fn try_from(value: &OpenFaasFunctionSpec) -> Result<Self, Self::Error> {
+        let Some(spec) = Option::<NetworkPolicySpec>::from(value) else {
+            return Ok(None);
+        };
+
+        Ok(Some(NetworkPolicy {
+            metadata: value.to_network_policy_meta()?,
+            spec: Some(spec),
+        }))
+    }
+}
+
+impl OptionalResourcePlan {
+    /// `existing_owned` is whether the function owns the existing resource, if there is one.
+    /// `up_to_date` is only considered for an owned resource that is still requested
+    pub fn new(requested: bool, existing_owned: Option<bool>, up_to_date: bool) -> Self {
+        match (requested, existing_owned) {
+            (true, None) => OptionalResourcePlan::Create,
+            (true, Some(false)) => OptionalResourcePlan::Conflict,
+            (true, Some(true)) if up_to_date => OptionalResourcePlan::UpToDate,
+            (true, Some(true)) => OptionalResourcePlan::Replace,
+            (false, Some(true)) => OptionalResourcePlan::Delete,
+            (false, _) => OptionalResourcePlan::NotRequested,
+        }
+    }
+}
+
+impl OpenFaaSFunction {
+    /// Distinguishes resources owned by this function from those claimed by another function or a third party
+    pub fn ownership(&self, owner_references: &[OwnerReference]) -> ResourceOwnership {
+        let owned = self
+            .controller_owner_ref(&())
+            .is_some_and(|oref| owner_references.contains(&oref));
+
+        if owned {
+            return ResourceOwnership::Owned;
+        }
+
+        let api_version = OpenFaaSFunction::api_version(&());
+        let kind = OpenFaaSFunction::kind(&());
+
+        owner_references
+            .iter()
+            .find(|oref| oref.api_version == api_version && oref.kind == kind)
+            .map(|oref| ResourceOwnership::OwnedByAnotherFunction(oref.name.clone()))
+            .unwrap_or(ResourceOwnership::ThirdParty)
+    }
+
+    /// Checks if a resource without this function's owner reference clearly belongs to the function,
+    /// e.g. one created by `crd convert apply`.
+    ///
+    /// It must be named after the function, carry the function's label and have no controller
+    pub fn can_adopt(&self, meta: &ObjectMeta) -> bool {
+        let name = self.spec.to_name();
+
+        let labeled = meta
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.get(FUNCTION_LABEL))
+            .is_some_and(|label| label == &name);
+
+        let controlled = meta
+            .owner_references
+            .iter()
+            .flatten()
+            .any(|oref| oref.controller == Some(true));
+
+        meta.name.as_ref() == Some(&name) && labeled && !controlled
+    }
+
+    /// A merge patch adding this function as the controller of an adoptable resource
+    pub fn to_adoption_patch(&self, meta: &ObjectMeta) -> Option<serde_json::Value> {
+        let oref = self.controller_owner_ref(&())?;
+
+        let mut owner_references = meta.owner_references.clone().unwrap_or_default();
+        owner_references.push(oref);
+
+        // Lists are replaced as a whole by a merge patch
+        Some(serde_json::json!({
+            "metadata": {
+                "ownerReferences": owner_references,
+            }
+        }))
+    }
+
+    /// Combines the readiness of the function's deployment and service.
+    ///
+    /// The service must exist, be owned by this function and select the function's pods.
+    pub fn readiness(
+        &self,
+        deployment: Option<&Deployment>,
+        service: Option<&Service>,
+    ) -> FunctionReadiness {
+        let Some(deployment) = deployment else {
+            return FunctionReadiness::DeploymentMissing;
+        };
+
+        let scaled_to_zero = deployment.spec.as_ref().and_then(|spec| spec.replicas) == Some(0);
+
+        let deployment_ready = scaled_to_zero
+            || deployment
+                .status
+                .as_ref()
+                .and_then(|status| status.ready_replicas)
+                .is_some();
+
+        if !deployment_ready {
+            return FunctionReadiness::DeploymentNotReady;
+        }
+
+        let Some(service) = service else {
+            return FunctionReadiness::ServiceMissing;
+        };
+
+        let owned = self
+            .controller_owner_ref(&())
+            .is_some_and(|oref| service.owner_references().contains(&oref));
+
+        if !owned {
+            return FunctionReadiness::ServiceNotOwned;
+        }
+
+        let selector = service
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.selector.as_ref());
+
+        if selector != Some(&self.service_selector_labels()) {
+            return FunctionReadiness::ServiceSelectorMismatch;
+        }
+
+        FunctionReadiness::Ready
+    }
+
+    /// The value of the restarted-at annotation, set by users to force a rolling restart
+    pub fn restarted_at(&self) -> Option<&String> {
+        self.annotations().get(RESTARTED_AT_ANNOTATION)
+    }
+
+    /// A changed restarted-at annotation requires a rollout even if the spec is unchanged
+    pub fn deployment_needs_restart(&self, deployment: &Deployment) -> bool {
+        let deployed_restarted_at = deployment
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.template.metadata.as_ref())
+            .and_then(|meta| meta.annotations.as_ref())
+            .and_then(|annotations| annotations.get(RESTARTED_AT_ANNOTATION));
+
+        self.restarted_at() != deployed_restarted_at
+    }
+
+    /// Propagates the restarted-at annotation to the pod template, like `kubectl rollout restart`
+    fn annotate_restarted_at(&self, deployment: &mut Deployment) {
+        let (Some(restarted_at), Some(spec)) = (self.restarted_at(), deployment.spec.as_mut())
+        else {
+            return;
+        };
+
+        spec.template
+            .metadata
+            .get_or_insert_with(ObjectMeta::default)
+            .annotations
+            .get_or_insert_with(BTreeMap::new)
+            .insert(String::from(RESTARTED_AT_ANNOTATION), restarted_at.clone());
+    }
+
+    /// The service group of the function, set by users to share one service between functions
+    pub fn service_group(&self) -> Option<&String> {
+        self.annotations().get(SERVICE_GROUP_ANNOTATION)
+    }
+
+    /// The labels selected by the function's service.
+    ///
+    /// The service of a group's primary function, the one named after the group,
+    /// selects the pods of every function in the group
+    pub fn service_selector_labels(&self) -> BTreeMap<String, String> {
+        match self.service_group() {
+            Some(group) if group == &self.spec.to_name() => {
+                [(String::from(SERVICE_GROUP_LABEL), group.clone())].into()
+            }
+            _ => self.spec.to_service_selector_labels(),
+        }
+    }
+
+    /// The parts of the service that differ from what the function generates
+    pub fn service_diffs(&self, service: &Service) -> Vec<ServiceDiff> {
+        let mut diffs = vec![];
+
+        if self.spec.service_type_changed(service) {
+            diffs.push(ServiceDiff::Type);
+        }
+
+        if self.spec.service_needs_ports_patch(service) {
+            diffs.push(ServiceDiff::Ports);
+        }
+
+        if self.service_needs_selector_patch(service) {
+            diffs.push(ServiceDiff::Selector);
+        }
+
+        if self.spec.service_annotations_changed(service) {
+            diffs.push(ServiceDiff::Annotations);
+        }
+
+        diffs
+    }
+
+    /// Checks if the service selects other pods than the function's, e.g. after the service group changed
+    pub fn service_needs_selector_patch(&self, service: &Service) -> bool {
+        let selector = service
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.selector.as_ref());
+
+        selector != Some(&self.service_selector_labels())
+    }
+
+    /// A changed service group requires a rollout to relabel the pods
+    pub fn deployment_service_group_changed(&self, deployment: &Deployment) -> bool {
+        let deployed_service_group = deployment
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.template.metadata.as_ref())
+            .and_then(|meta| meta.labels.as_ref())
+            .and_then(|labels| labels.get(SERVICE_GROUP_LABEL));
+
+        self.service_group() != deployed_service_group
+    }
+
+    /// Checks if a merge patch with the generated deployment leaves the live deployment out of line with the function.
+    ///
+    /// A merge patch keeps the fields the generated deployment no longer sets, e.g. a removed affinity,
+    /// and keeps the rolling update parameters when the strategy type changes, which the API server rejects.
+    /// Such changes require replacing the deployment instead.
+    pub fn deployment_merge_cannot_converge(&self, deployment: &Deployment) -> bool {
+        let Ok(desired) = Deployment::try_from(self) else {
+            return false;
+        };
+
+        let (Some(desired_spec), Some(live_spec)) =
+            (desired.spec.as_ref(), deployment.spec.as_ref())
+        else {
+            return false;
+        };
+
+        let strategy_type = |spec: &DeploymentSpec| {
+            spec.strategy
+                .as_ref()
+                .and_then(|strategy| strategy.type_.clone())
+                .unwrap_or_else(|| String::from("RollingUpdate"))
+        };
+
+        if strategy_type(desired_spec) != strategy_type(live_spec) {
+            return true;
+        }
+
+        if desired_spec.paused.is_none() && live_spec.paused == Some(true) {
+            return true;
+        }
+
+        if let (Some(desired_pod), Some(live_pod)) = (
+            desired_spec.template.spec.as_ref(),
+            live_spec.template.spec.as_ref(),
+        ) {
+            let removed = (desired_pod.affinity.is_none() && live_pod.affinity.is_some())
+                || (desired_pod.tolerations.is_none() && live_pod.tolerations.is_some())
+                || (desired_pod.node_selector.is_none() && live_pod.node_selector.is_some())
+                || (desired_pod.runtime_class_name.is_none()
+                    && live_pod.runtime_class_name.is_some())
+                || (desired_pod.volumes.is_none() && live_pod.volumes.is_some());
+
+            if removed {
+                return true;
+            }
+        }
+
+        // Only keys the operator generated are considered, keys added by third parties are left alone
+        let previous = OpenFaasFunctionSpec::previous_spec(deployment)
+            .and_then(|spec| Deployment::try_from(&spec).ok())
+            .and_then(|deployment| deployment.spec)
+            .and_then(|spec| spec.template.metadata)
+            .unwrap_or_default();
+
+        let desired_meta = desired_spec.template.metadata.clone().unwrap_or_default();
+        let live_meta = live_spec.template.metadata.clone().unwrap_or_default();
+
+        let key_removed = |previous: Option<BTreeMap<String, String>>,
+                           managed: &str,
+                           desired: Option<BTreeMap<String, String>>,
+                           live: Option<BTreeMap<String, String>>| {
+            let desired = desired.unwrap_or_default();
+            let live = live.unwrap_or_default();
+
+            previous
+                .unwrap_or_default()
+                .into_keys()
+                .chain(std::iter::once(String::from(managed)))
+                .any(|key| live.contains_key(&key) && !desired.contains_key(&key))
+        };
+
+        key_removed(
+            previous.labels,
+            SERVICE_GROUP_LABEL,
+            desired_meta.labels,
+            live_meta.labels,
+        ) || key_removed(
+            previous.annotations,
+            RESTARTED_AT_ANNOTATION,
+            desired_meta.annotations,
+            live_meta.annotations,
+        )
+    }
+
+    /// Labels the pod template with the service group, so the group's primary service selects the pods
+    fn label_service_group(&self, deployment: &mut Deployment) {
+        let (Some(service_group), Some(spec)) = (self.service_group(), deployment.spec.as_mut())
+        else {
+            return;
+        };
+
+        spec.template
+            .metadata
+            .get_or_insert_with(ObjectMeta::default)
+            .labels
+            .get_or_insert_with(BTreeMap::new)
+            .insert(String::from(SERVICE_GROUP_LABEL), service_group.clone());
+    }
+
+    /// Records the generation of the function that produced a resource.
+    /// Only the last applied spec is used for drift comparison, so this annotation never triggers an update on its own
+    fn annotate_source_generation(&self, meta: &mut ObjectMeta) {
+        if let Some(generation) = self.metadata.generation {
+            meta.annotations.get_or_insert_with(BTreeMap::new).insert(
+                String::from(SOURCE_GENERATION_ANNOTATION),
+                generation.to_string(),
+            );
+        }
+    }
+}
+
+/// Generate a fresh deployment with refs
+impl TryFrom<&OpenFaaSFunction> for Deployment {
+    type Error = FunctionIntoDeploymentError;
+
+    fn try_from(value: &OpenFaaSFunction) -> Result<Self, Self::Error> {
+        let oref = value
+            .controller_owner_ref(&())
+            .ok_or(FunctionIntoDeploymentError::OwnerReference)?;
+
+        let mut dep =
+            Deployment::try_from(&value.spec).map_err(FunctionIntoDeploymentError::FunctionSpec)?;
+
+        dep.metadata.owner_references = Some(vec![oref]);
+        value.annotate_source_generation(&mut dep.metadata);
+        value.annotate_restarted_at(&mut dep);
+        value.label_service_group(&mut dep);
+
+        Ok(dep)
+    }
+}
+
+/// Generate a fresh service with refs
+impl TryFrom<&OpenFaaSFunction> for Service {
+    type Error = FunctionIntoServiceError;
+
+    fn try_from(value: &OpenFaaSFunction) -> Result<Self, Self::Error> {
+        let oref = value
+            .controller_owner_ref(&())
+            .ok_or(FunctionIntoServiceError::OwnerReference)?;
+
+        let mut svc = Service::try_from(&value.spec)?;
+
+        svc.metadata.owner_references = Some(vec![oref]);
+        value.annotate_source_generation(&mut svc.metadata);
+
+        if let Some(spec) = svc.spec.as_mut() {
+            spec.selector = Some(value.service_selector_labels());
+        }
+
+        Ok(svc)
+    }
+}
+
+/// Generate a fresh pod disruption budget with refs, if the spec requests one
+impl TryFrom<&OpenFaaSFunction> for Option<PodDisruptionBudget> {
+    type Error = FunctionIntoPodDisruptionBudgetError;
+
+    fn try_from(value: &OpenFaaSFunction) -> Result<Self, Self::Error> {
+        let oref = value
+            .controller_owner_ref(&())
+            .ok_or(FunctionIntoPodDisruptionBudgetError::OwnerReference)?;
+
+        let pdb = Option::<PodDisruptionBudget>::try_from(&value.spec)?.map(|mut pdb| {
+            pdb.metadata.owner_references = Some(vec![oref]);
+            pdb
+        });
+
+        Ok(pdb)
+    }
+}
+
+/// Generate a fresh network policy with refs, if the spec requests one
+impl TryFrom<&OpenFaaSFunction> for Option<NetworkPolicy> {
+    type Error = FunctionIntoNetworkPolicyError;
+
+    fn try_from(value: &OpenFaaSFunction) -> Result<Self, Self::Error> {
+        let oref = value
+            .controller_owner_ref(&())
+            .ok_or(FunctionIntoNetworkPolicyError::OwnerReference)?;
+
+        let network_policy =
+            Option::<NetworkPolicy>::try_from(&value.spec)?.map(|mut network_policy| {
+                network_policy.metadata.owner_references = Some(vec![oref]);
+                network_policy
+            });
+
+        Ok(network_policy)
+    }
+}
+
+impl OpenFaasFunctionStatus {
+    /// Appends the given detail to the condition's message, e.g. the offending field.
+    pub fn with_detail(status: OpenFaasFunctionPossibleStatus, detail: Option<String>) -> Self {
+        let mut function_status = OpenFaasFunctionStatus::from(status);
+
+        if let Some(detail) = detail {
+            for condition in function_status.conditions.iter_mut() {
+                condition.message.message = Some(match condition.message.message.take() {
+                    Some(message) => format!("{message}: {detail}"),
+                    None => detail.clone(),
+                });
+            }
+        }
+
+        function_status
+    }
+
+    pub fn message(&self) -> Option<String> {
+        self.conditions.first()?.message.message.clone()
+    }
+
+    pub fn possible_status(&self) -> Option<OpenFaasFunctionPossibleStatus> {
+        Some(self.conditions.first()?.reason.clone())
+    }
+
+    /// Falls back to the last update time for conditions written before the transition time was tracked.
+    pub fn last_transition_time(&self) -> Option<Time> {
+        let condition = self.conditions.first()?;
+
+        condition
+            .last_transition_time
+            .clone()
+            .or(condition.last_update_time.clone())
+    }
+}
+
+impl From<&OpenFaasFunctionPossibleStatus> for OpenFaasFunctionStatusConditionStatus {
+    fn from(status: &OpenFaasFunctionPossibleStatus) -> Self {
+        match status {
+            OpenFaasFunctionPossibleStatus::Ok => OpenFaasFunctionStatusConditionStatus {
+                status: String::from("True"),
+            },
+            _ => OpenFaasFunctionStatusConditionStatus {
+                status: String::from("False"),
+            },
+        }
+    }
+}
+
+impl From<&OpenFaasFunctionPossibleStatus> for OpenFaasFunctionStatusConditionMessage {
+    fn from(status: &OpenFaasFunctionPossibleStatus) -> Self {
+        match status {
+            OpenFaasFunctionPossibleStatus::Ok => {
+                OpenFaasFunctionStatusConditionMessage { message: None }
+            }
+            OpenFaasFunctionPossibleStatus::InvalidCRDNamespace => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "The CRD's namespace does not match the functions namespace",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::InvalidFunctionNamespace => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "The function's namespace does not match the functions namespace",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::CPUQuantity => OpenFaasFunctionStatusConditionMessage {
+                message: Some(String::from("A function's cpu quantity is invalid")),
+            },
+            OpenFaasFunctionPossibleStatus::MemoryQuantity => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from("A function's memory quantity is invalid")),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::InvalidSpec => OpenFaasFunctionStatusConditionMessage {
+                message: Some(String::from("The function's spec is invalid")),
+            },
+            OpenFaasFunctionPossibleStatus::ImagePullSecretsNotFound => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "The given secrets to pull the function's image do not exist",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::EnvTooLarge => OpenFaasFunctionStatusConditionMessage {
+                message: Some(String::from(
+                    "The function's environment is too large. Move large values to a ConfigMap or a Secret",
+                )),
+            },
+            OpenFaasFunctionPossibleStatus::OwnedByAnotherFunction => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "The function's deployment is owned by another function with the same name",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::DeploymentAlreadyExists => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "The function's deployment already deployed by third party",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::DeploymentNotReady => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from("The function's deployment is not ready")),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::ServiceNotReady => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "The function's service is missing or does not select the function",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::ServiceAlreadyExists => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "The function's service already deployed by third party",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::SecretsNotFound => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from("The given secrets to mount do not exist")),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::SecretsNotMountable => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "The given secrets only exist in the secrets namespace and can not be mounted across namespaces",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::InvalidPodDisruptionBudget => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "Either minAvailable or maxUnavailable must be set in the function's pod disruption budget, but not both",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::NetworkPolicyAlreadyExists => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "The function's network policy already deployed by third party",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::PodDisruptionBudgetAlreadyExists => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "The function's pod disruption budget already deployed by third party",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::RuntimeClassNotFound => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from("The given runtime class does not exist")),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::ConfigMapsNotFound => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from("The given config maps to mount do not exist")),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::InvalidTerminationMessagePolicy => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "A function's terminationMessagePolicy must be File or FallbackToLogsOnError",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::InvalidDeploymentStrategy => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "A function's deploymentStrategyType must be RollingUpdate or Recreate, maxSurge and maxUnavailable are only allowed for RollingUpdate",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::InvalidImagePullPolicy => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "A function's imagePullPolicy must be Always, IfNotPresent or Never",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::InvalidImageReference => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from("A function's image is not a valid image reference")),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::InvalidAppProtocol => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "A function's appProtocol is neither a known protocol nor prefixed with a custom domain",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::InvalidServiceType => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "A function's serviceType must be ClusterIP, NodePort or LoadBalancer",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::InvalidNodePort => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "A function's nodePort must be a valid port and is only allowed for services of type NodePort or LoadBalancer",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::InvalidLoadBalancerClass => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "A function's loadBalancerClass is only allowed for services of type LoadBalancer",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::ReconcileBackoff => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "Reconciliation has been failing for too long. Backing off",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::ReservedAnnotationKey => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "A function's annotation uses a key reserved for the operator",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::InvalidMetadataKey => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "A function's label or annotation does not follow the Kubernetes naming rules",
+                    )),
+                }
+            }
+            OpenFaasFunctionPossibleStatus::InvalidDownwardEnv => {
+                OpenFaasFunctionStatusConditionMessage {
+                    message: Some(String::from(
+                        "A function's downward env var is not supported. Supported are POD_NAME, POD_NAMESPACE and NODE_NAME",
+                    )),
+                }
+            }
+        }
+    }
+}
+
+impl From<OpenFaasFunctionPossibleStatus> for OpenFaasFunctionStatusCondition {
+    fn from(status: OpenFaasFunctionPossibleStatus) -> Self {
+        let now = Time(chrono::Utc::now());
+
+        OpenFaasFunctionStatusCondition {
+            type_: OpenFaasFunctionStatusConditionType::Ready,
+            status: OpenFaasFunctionStatusConditionStatus::from(&status),
+            message: OpenFaasFunctionStatusConditionMessage::from(&status),
+            reason: status,
+            last_update_time: Some(now.clone()),
+            last_transition_time: Some(now),
+        }
+    }
+}
+
+impl From<OpenFaasFunctionPossibleStatus> for OpenFaasFunctionStatus {
+    fn from(status: OpenFaasFunctionPossibleStatus) -> Self {
+        OpenFaasFunctionStatus {
+            conditions: vec![OpenFaasFunctionStatusCondition::from(status)],
+        }
+    }
+}
+
+impl From<&FunctionIntoDeploymentError> for Option<OpenFaasFunctionPossibleStatus> {
+    fn from(e: &FunctionIntoDeploymentError) -> Self {
+        match e {
+            FunctionIntoDeploymentError::FunctionSpec(
+                FunctionSpecIntoDeploymentError::Quantity(e),
+            ) => match e {
+                IntoQuantityError::Memory(_) => {
+                    Some(OpenFaasFunctionPossibleStatus::MemoryQuantity)
+                }
+                IntoQuantityError::CPU(_) => Some(OpenFaasFunctionPossibleStatus::CPUQuantity),
+            },
+            FunctionIntoDeploymentError::FunctionSpec(
+                FunctionSpecIntoDeploymentError::DownwardEnv(_),
+            ) => Some(OpenFaasFunctionPossibleStatus::InvalidDownwardEnv),
+            FunctionIntoDeploymentError::FunctionSpec(
+                FunctionSpecIntoDeploymentError::Metadata(
+                    InvalidMetadataError::ReservedAnnotationKey(_),
+                ),
+            ) => Some(OpenFaasFunctionPossibleStatus::ReservedAnnotationKey),
+            FunctionIntoDeploymentError::FunctionSpec(
+                FunctionSpecIntoDeploymentError::ImageReference(_),
+            ) => Some(OpenFaasFunctionPossibleStatus::InvalidImageReference),
+            FunctionIntoDeploymentError::FunctionSpec(
+                FunctionSpecIntoDeploymentError::TerminationMessagePolicy(_),
+            ) => Some(OpenFaasFunctionPossibleStatus::InvalidTerminationMessagePolicy),
+            FunctionIntoDeploymentError::FunctionSpec(
+                FunctionSpecIntoDeploymentError::ImagePullPolicy(_),
+            ) => Some(OpenFaasFunctionPossibleStatus::InvalidImagePullPolicy),
+            FunctionIntoDeploymentError::FunctionSpec(
+                FunctionSpecIntoDeploymentError::DeploymentStrategyType(_),
+            ) => Some(OpenFaasFunctionPossibleStatus::InvalidDeploymentStrategy),
+            FunctionIntoDeploymentError::FunctionSpec(
+                FunctionSpecIntoDeploymentError::Metadata(_),
+            ) => Some(OpenFaasFunctionPossibleStatus::InvalidMetadataKey),
+            _ => None,
+        }
+    }
+}
+
+impl From<&FunctionIntoServiceError> for Option<OpenFaasFunctionPossibleStatus> {
+    fn from(value: &FunctionIntoServiceError) -> Self {
+        match value {
+            FunctionIntoServiceError::FunctionSpec(
+                FunctionSpecIntoServiceError::LoadBalancerClass,
+            ) => Some(OpenFaasFunctionPossibleStatus::InvalidLoadBalancerClass),
+            FunctionIntoServiceError::FunctionSpec(FunctionSpecIntoServiceError::ServiceType(
+                _,
+            )) => Some(OpenFaasFunctionPossibleStatus::InvalidServiceType),
+            FunctionIntoServiceError::FunctionSpec(
+                FunctionSpecIntoServiceError::NodePort
+                | FunctionSpecIntoServiceError::NodePortRange(_),
+            ) => Some(OpenFaasFunctionPossibleStatus::InvalidNodePort),
+            FunctionIntoServiceError::FunctionSpec(FunctionSpecIntoServiceError::AppProtocol(
+                _,
+            )) => Some(OpenFaasFunctionPossibleStatus::InvalidAppProtocol),
+            _ => None,
+        }
+    }
+}
+
+impl From<&SpecValidationError> for OpenFaasFunctionPossibleStatus {
+    fn from(value: &SpecValidationError) -> Self {
+        match value {
+            SpecValidationError::ImageReference(_) => {
+                OpenFaasFunctionPossibleStatus::InvalidImageReference
+            }
+            SpecValidationError::Quantity(IntoQuantityError::CPU(_)) => {
+                OpenFaasFunctionPossibleStatus::CPUQuantity
+            }
+            SpecValidationError::Quantity(IntoQuantityError::Memory(_)) => {
+                OpenFaasFunctionPossibleStatus::MemoryQuantity
+            }
+            SpecValidationError::DownwardEnv(_) => {
+                OpenFaasFunctionPossibleStatus::InvalidDownwardEnv
+            }
+            SpecValidationError::TerminationMessagePolicy(_) => {
+                OpenFaasFunctionPossibleStatus::InvalidTerminationMessagePolicy
+            }
+            SpecValidationError::ImagePullPolicy(_) => {
+                OpenFaasFunctionPossibleStatus::InvalidImagePullPolicy
+            }
+            SpecValidationError::DeploymentStrategyType(_)
+            | SpecValidationError::RollingUpdateWithRecreate => {
+                OpenFaasFunctionPossibleStatus::InvalidDeploymentStrategy
+            }
+            SpecValidationError::Metadata(InvalidMetadataError::ReservedAnnotationKey(_)) => {
+                OpenFaasFunctionPossibleStatus::ReservedAnnotationKey
+            }
+            SpecValidationError::Metadata(_) => OpenFaasFunctionPossibleStatus::InvalidMetadataKey,
+            SpecValidationError::Service(FunctionSpecIntoServiceError::LoadBalancerClass) => {
+                OpenFaasFunctionPossibleStatus::InvalidLoadBalancerClass
+            }
+            SpecValidationError::Service(FunctionSpecIntoServiceError::ServiceType(_)) => {
+                OpenFaasFunctionPossibleStatus::InvalidServiceType
+            }
+            SpecValidationError::Service(
+                FunctionSpecIntoServiceError::NodePort
+                | FunctionSpecIntoServiceError::NodePortRange(_),
+            ) => OpenFaasFunctionPossibleStatus::InvalidNodePort,
+            SpecValidationError::Service(FunctionSpecIntoServiceError::AppProtocol(_)) => {
+                OpenFaasFunctionPossibleStatus::InvalidAppProtocol
+            }
+            SpecValidationError::PodDisruptionBudget(_) => {
+                OpenFaasFunctionPossibleStatus::InvalidPodDisruptionBudget
+            }
+            SpecValidationError::EnvVarTooLarge { .. }
+            | SpecValidationError::EnvTooLarge { .. } => {
+                OpenFaasFunctionPossibleStatus::EnvTooLarge
+            }
+            _ => OpenFaasFunctionPossibleStatus::InvalidSpec,
+        }
+    }
+}
+
+impl From<&FunctionReadiness> for OpenFaasFunctionPossibleStatus {
+    fn from(value: &FunctionReadiness) -> Self {
+        match value {
+            FunctionReadiness::Ready => OpenFaasFunctionPossibleStatus::Ok,
+            FunctionReadiness::DeploymentMissing | FunctionReadiness::DeploymentNotReady => {
+                OpenFaasFunctionPossibleStatus::DeploymentNotReady
+            }
+            FunctionReadiness::ServiceMissing
+            | FunctionReadiness::ServiceNotOwned
+            | FunctionReadiness::ServiceSelectorMismatch => {
+                OpenFaasFunctionPossibleStatus::ServiceNotReady
+            }
+        }
+    }
+}
+
+impl FunctionIntoDeploymentError {
+    /// Details to append to the status message, naming the offending field
+    pub fn to_status_detail(&self) -> Option<String> {
+        match self {
+            FunctionIntoDeploymentError::FunctionSpec(
+                FunctionSpecIntoDeploymentError::Metadata(e),
+            ) => Some(e.to_string()),
+            FunctionIntoDeploymentError::FunctionSpec(
+                FunctionSpecIntoDeploymentError::DownwardEnv(name),
+            ) => Some(name.clone()),
+            FunctionIntoDeploymentError::FunctionSpec(
+                FunctionSpecIntoDeploymentError::ImageReference(e),
+            ) => Some(e.0.clone()),
+            FunctionIntoDeploymentError::FunctionSpec(
+                FunctionSpecIntoDeploymentError::TerminationMessagePolicy(policy)
+                | FunctionSpecIntoDeploymentError::ImagePullPolicy(policy)
+                | FunctionSpecIntoDeploymentError::DeploymentStrategyType(policy),
+            ) => Some(policy.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl From<&FunctionIntoPodDisruptionBudgetError> for Option<OpenFaasFunctionPossibleStatus> {
+    fn from(e: &FunctionIntoPodDisruptionBudgetError) -> Self {
+        match e {
+            FunctionIntoPodDisruptionBudgetError::FunctionSpec(
+                FunctionSpecIntoPodDisruptionBudgetError::MinAvailableOrMaxUnavailable,
+            ) => Some(OpenFaasFunctionPossibleStatus::InvalidPodDisruptionBudget),
+            _ => None,
+        }
+    }
+}
+
+impl OpenFaasFunctionSpec {
+    pub fn debug_compare_deployment(&self, deployment: &Deployment) {
+        tracing::debug!("Starting deployment comparison");
+        tracing::debug!("Missing, edited or corrupted '{LAST_APPLIED_ANNOTATION}' annotation can cause unexpected behaviour");
+        // first we get the prev spec
+
+        let dep_meta_annotations = deployment
+            .metadata
+            .annotations
+            .as_ref()
+            .unwrap_or(&BTreeMap::new())
+            .clone();
+
+        let prev_spec_json_string_opt = dep_meta_annotations.get(LAST_APPLIED_ANNOTATION);
+        let prev_spec = match prev_spec_json_string_opt {
+            None => {
+                tracing::debug!("No previous spec found => recreate!");
+                return;
+            }
+            Some(prev_spec_json_string) => {
+                match serde_json::from_str::<OpenFaasFunctionSpec>(prev_spec_json_string) {
+                    Ok(prev_spec) => prev_spec,
+                    Err(_) => {
+                        tracing::error!("Previous spec corrupted => recreate!");
+                        return;
+                    }
+                }
+            }
+        };
+
+        let mut replace = false;
+
+        // now we check meta_labels
+        let current_meta_labels = self.to_meta_labels();
+        let prev_spec_meta_labels = prev_spec.to_meta_labels();
+        let mut deployment_meta_labels = deployment
+            .metadata
+            .labels
+            .as_ref()
+            .unwrap_or(&BTreeMap::new())
+            .clone();
+
+        tracing::debug!("Checking meta labels");
+        let meta_labels_in_prev_but_not_in_current =
+            utils::collect_missing_keys_btree(&prev_spec_meta_labels, &current_meta_labels);
+        let meta_labels_in_dep_but_not_in_current =
+            utils::collect_missing_keys_btree(&deployment_meta_labels, &current_meta_labels);
+        let meta_labels_in_current_but_not_dep =
+            utils::collect_missing_keys_btree(&current_meta_labels, &deployment_meta_labels);
+        tracing::debug!(
+            "Meta labels in deployment but not in current spec: {:#?}",
+            meta_labels_in_dep_but_not_in_current
+        );
+        tracing::debug!(
+            "Meta labels to be added to deployment: {:#?}",
+            meta_labels_in_current_but_not_dep
+        );
+        tracing::debug!(
+            "Meta labels to be removed from deployment: {:#?}",
+            meta_labels_in_prev_but_not_in_current
+        );
+        if !meta_labels_in_prev_but_not_in_current.is_empty() {
+            tracing::debug!("Triggering replace");
+            replace = true;
+        }
+
+        // remove labels that are in prev_spec but not in current
+        for label in meta_labels_in_prev_but_not_in_current {
+            deployment_meta_labels.remove(label);
+        }
+        // add labels that are in current but not in deployment
+        deployment_meta_labels.extend(current_meta_labels);
+        tracing::debug!("Final meta labels: {:#?}", deployment_meta_labels);
+
+        // now we check meta_annotations. for the meta_annotations we will use to_annotations, since we don't want to compare the last applied annotation
+        let current_meta_annotations = self.to_annotations().unwrap_or_default();
+        let prev_spec_meta_annotations = prev_spec.to_annotations().unwrap_or_default();
+        let mut deployment_meta_annotations = deployment
+            .metadata
+            .annotations
+            .as_ref()
+            .unwrap_or(&BTreeMap::new())
+            .clone();
+        // remove the last applied annotation, since we don't want to compare it
+        deployment_meta_annotations.remove(LAST_APPLIED_ANNOTATION);
+        tracing::debug!("Checking meta annotations");
+        let meta_annotations_in_prev_but_not_in_current = utils::collect_missing_keys_btree(
+            &prev_spec_meta_annotations,
+            &current_meta_annotations,
+        );
+        let meta_annotations_in_dep_but_not_in_current = utils::collect_missing_keys_btree(
+            &deployment_meta_annotations,
+            &current_meta_annotations,
+        );
+        let meta_annotations_in_current_but_not_dep = utils::collect_missing_keys_btree(
+            &current_meta_annotations,
+            &deployment_meta_annotations,
+        );
+        tracing::debug!(
+            "Meta annotations in deployment but not in current spec: {:#?}",
+            meta_annotations_in_dep_but_not_in_current
+        );
+        tracing::debug!(
+            "Meta annotations to be added to deployment: {:#?}",
+            meta_annotations_in_current_but_not_dep
+        );
+        tracing::debug!(
+            "Meta annotations to be removed from deployment: {:#?}",
+            meta_annotations_in_prev_but_not_in_current
+        );
+        if !meta_annotations_in_prev_but_not_in_current.is_empty() {
+            tracing::debug!("Triggering replace");
+            replace = true;
+        }
+
+        // remove annotations that are in prev_spec but not in current
+        for annotation in meta_annotations_in_prev_but_not_in_current {
+            deployment_meta_annotations.remove(annotation);
+        }
+        // add annotations that are in current but not in deployment
+        deployment_meta_annotations.extend(current_meta_annotations);
+        // add the last applied annotation
+        deployment_meta_annotations.insert(
+            String::from(LAST_APPLIED_ANNOTATION),
+            serde_json::to_string(self).expect("Failed to serialize the current spec"),
+        );
+        tracing::debug!("Final meta annotations: {:#?}", deployment_meta_annotations);
+
+        tracing::debug!("Checking spec labels");
+        let current_spec_labels = self.to_spec_meta_labels();
+        let prev_spec_spec_labels = prev_spec.to_spec_meta_labels();
+        let mut deployment_spec_labels = deployment
+            .spec
+            .as_ref()
+            .unwrap_or(&DeploymentSpec::default())
+            .template
+            .metadata
+            .as_ref()
+            .unwrap_or(&ObjectMeta::default())
+            .labels
+            .as_ref()
+            .unwrap_or(&BTreeMap::new())
+            .clone();
+
+        let spec_labels_in_prev_but_not_in_current =
+            utils::collect_missing_keys_btree(&prev_spec_spec_labels, &current_spec_labels);
+        let spec_labels_in_dep_but_not_in_current =
+            utils::collect_missing_keys_btree(&deployment_spec_labels, &current_spec_labels);
+        let spec_labels_in_current_but_not_dep =
+            utils::collect_missing_keys_btree(&current_spec_labels, &deployment_spec_labels);
+        tracing::debug!(
+            "Spec labels in deployment but not in current spec: {:#?}",
+            spec_labels_in_dep_but_not_in_current
+        );
+        tracing::debug!(
+            "Spec labels to be added to deployment: {:#?}",
+            spec_labels_in_current_but_not_dep
+        );
+        tracing::debug!(
+            "Spec labels to be removed from deployment: {:#?}",
+            spec_labels_in_prev_but_not_in_current
+        );
+        if !spec_labels_in_prev_but_not_in_current.is_empty() {
+            tracing::debug!("Triggering replace");
+            replace = true;
+        }
+
+        // remove labels that are in prev_spec but not in current
+        for label in spec_labels_in_prev_but_not_in_current {
+            deployment_spec_labels.remove(label);
+        }
+        // add labels that are in current but not in deployment
+        deployment_spec_labels.extend(current_spec_labels);
+        tracing::debug!("Final spec labels: {:#?}", deployment_spec_labels);
+
+        tracing::debug!("Checking spec annotations");
+        let current_spec_annotations = self.to_annotations().unwrap_or_default();
+        let prev_spec_spec_annotations = prev_spec.to_annotations().unwrap_or_default();
+        let mut deployment_spec_annotations = deployment
+            .spec
+            .as_ref()
+            .unwrap_or(&DeploymentSpec::default())
+            .template
+            .metadata
+            .as_ref()
+            .unwrap_or(&ObjectMeta::default())
+            .annotations
+            .as_ref()
+            .unwrap_or(&BTreeMap::new())
+            .clone();
+
+        let spec_annotations_in_prev_but_not_in_current = utils::collect_missing_keys_btree(
+            &prev_spec_spec_annotations,
+            &current_spec_annotations,
+        );
+        let spec_annotations_in_dep_but_not_in_current = utils::collect_missing_keys_btree(
+            &deployment_spec_annotations,
+            &current_spec_annotations,
+        );
+        let spec_annotations_in_current_but_not_dep = utils::collect_missing_keys_btree(
+            &current_spec_annotations,
+            &deployment_spec_annotations,
+        );
+        tracing::debug!(
+            "Spec annotations in deployment but not in current spec: {:#?}",
+            spec_annotations_in_dep_but_not_in_current
+        );
+        tracing::debug!(
+            "Spec annotations to be added to deployment: {:#?}",
+            spec_annotations_in_current_but_not_dep
+        );
+        tracing::debug!(
+            "Spec annotations to be removed from deployment: {:#?}",
+            spec_annotations_in_prev_but_not_in_current
+        );
+        if !spec_annotations_in_prev_but_not_in_current.is_empty() {
+            tracing::debug!("Triggering replace");
+            replace = true;
+        }
+
+        // remove annotations that are in prev_spec but not in current
+        for annotation in spec_annotations_in_prev_but_not_in_current {
+            deployment_spec_annotations.remove(annotation);
+        }
+        // add annotations that are in current but not in deployment
+        deployment_spec_annotations.extend(current_spec_annotations);
+        tracing::debug!("Final spec annotations: {:#?}", deployment_spec_annotations);
+
+        tracing::debug!("Checking constraints");
+        let current_node_selector = self.to_node_selector().unwrap_or_default();
+        let prev_spec_node_selector = prev_spec.to_node_selector().unwrap_or_default();
+        let mut deployment_node_selector = deployment
+            .spec
+            .as_ref()
+            .unwrap_or(&DeploymentSpec::default())
+            .template
+            .spec
+            .as_ref()
+            .unwrap_or(&PodSpec::default())
+            .node_selector
+            .as_ref()
+            .unwrap_or(&BTreeMap::new())
             .clone();
 
-        let prev_spec_json_string_opt = dep_meta_annotations.get(LAST_APPLIED_ANNOTATION);
-        let prev_spec = match prev_spec_json_string_opt {
+        let node_selector_in_prev_but_not_in_current =
+            utils::collect_missing_keys_btree(&prev_spec_node_selector, &current_node_selector);
+        let node_selector_in_dep_but_not_in_current =
+            utils::collect_missing_keys_btree(&deployment_node_selector, &current_node_selector);
+        let node_selector_in_current_but_not_dep =
+            utils::collect_missing_keys_btree(&current_node_selector, &deployment_node_selector);
+        tracing::debug!(
+            "Node selector in deployment but not in current spec: {:#?}",
+            node_selector_in_dep_but_not_in_current
+        );
+        tracing::debug!(
+            "Node selector to be added to deployment: {:#?}",
+            node_selector_in_current_but_not_dep
+        );
+        tracing::debug!(
+            "Node selector to be removed from deployment: {:#?}",
+            node_selector_in_prev_but_not_in_current
+        );
+        if !node_selector_in_prev_but_not_in_current.is_empty() {
+            tracing::debug!("May trigger replace");
+            replace = true;
+        }
+        // remove node selector that are in prev_spec but not in current
+        for node_selector in node_selector_in_prev_but_not_in_current {
+            deployment_node_selector.remove(node_selector);
+        }
+        // add node selector that are in current but not in deployment
+        deployment_node_selector.extend(current_node_selector);
+        tracing::debug!("Final node selector: {:#?}", deployment_node_selector);
+
+        tracing::debug!("Checking containers");
+        tracing::debug!("Checking if container is missing");
+        let deployment_containers = deployment
+            .spec
+            .as_ref()
+            .unwrap_or(&DeploymentSpec::default())
+            .template
+            .spec
+            .as_ref()
+            .unwrap_or(&PodSpec::default())
+            .containers
+            .clone();
+
+        let container_name = self.to_name();
+
+        let deployment_container = deployment_containers
+            .iter()
+            .find(|c| c.name == container_name);
+
+        match deployment_container {
             None => {
-                tracing::debug!("No previous spec found => recreate!");
+                tracing::debug!("Container is missing => recreate!");
                 return;
             }
-            Some(prev_spec_json_string) => {
-                match serde_json::from_str::<OpenFaasFunctionSpec>(prev_spec_json_string) {
-                    Ok(prev_spec) => prev_spec,
-                    Err(_) => {
-                        tracing::error!("Previous spec corrupted => recreate!");
-                        return;
-                    }
+            Some(deployment_container) => {
+                tracing::debug!("Checking image");
+                if deployment_container.image != Some(self.to_image()) {
+                    tracing::debug!("Image is different => recreate!");
+                    return;
+                }
+
+                tracing::debug!("Checking env vars");
+                let current_env_vars = Option::<Vec<EnvVar>>::from(self).unwrap_or_default();
+                let prev_spec_env_vars =
+                    Option::<Vec<EnvVar>>::from(&prev_spec).unwrap_or_default();
+                let deployment_env_vars = deployment_container.env.clone().unwrap_or_default();
+
+                let env_vars_in_prev_but_not_in_current =
+                    utils::collect_missing_keys_vec(&prev_spec_env_vars, &current_env_vars);
+                let env_vars_in_dep_but_not_in_current =
+                    utils::collect_missing_keys_vec(&deployment_env_vars, &current_env_vars);
+                let env_vars_in_current_but_not_dep =
+                    utils::collect_missing_keys_vec(&current_env_vars, &deployment_env_vars);
+                tracing::debug!(
+                    "Env vars in deployment but not in current spec: {:#?}",
+                    env_vars_in_dep_but_not_in_current
+                );
+                tracing::debug!(
+                    "Env vars to be added to deployment: {:#?}",
+                    env_vars_in_current_but_not_dep
+                );
+                tracing::debug!(
+                    "Env vars to be removed from deployment: {:#?}",
+                    env_vars_in_prev_but_not_in_current
+                );
+                // // remove env vars that are in prev_spec but not in current
+                // for env_var in env_vars_in_prev_but_not_in_current {
+                //     deployment_env_vars.retain(|e| e.name != env_var.name);
+                // }
+                // // add env vars that are in current but not in deployment
+                // deployment_env_vars.extend(current_env_vars);
+                // tracing::debug!("Final env vars: {:#?}", deployment_env_vars);
+
+                tracing::debug!("Checking secrets");
+                if self.deployment_secrets_changed(deployment) {
+                    tracing::debug!("Secrets are different => recreate!");
+                    return;
+                }
+
+                tracing::debug!("Checking read only root filesystem");
+                if deployment_container
+                    .security_context
+                    .as_ref()
+                    .unwrap_or(&SecurityContext::default())
+                    .read_only_root_filesystem
+                    != self.read_only_root_filesystem
+                {
+                    tracing::debug!("Read only root filesystem is different => recreate!");
+                    return;
+                }
+                tracing::debug!("Checking limits");
+                let current_limits = self.try_to_limits().unwrap_or_default().unwrap_or_default();
+                let deployment_limits = deployment_container
+                    .resources
+                    .as_ref()
+                    .unwrap_or(&ResourceRequirements::default())
+                    .limits
+                    .as_ref()
+                    .unwrap_or(&BTreeMap::new())
+                    .clone();
+
+                if current_limits != deployment_limits {
+                    tracing::debug!("Limits are different!");
+                }
+
+                tracing::debug!("Checking requests");
+                let current_requests = self
+                    .try_to_requests()
+                    .unwrap_or_default()
+                    .unwrap_or_default();
+                let deployment_requests = deployment_container
+                    .resources
+                    .as_ref()
+                    .unwrap_or(&ResourceRequirements::default())
+                    .requests
+                    .as_ref()
+                    .unwrap_or(&BTreeMap::new())
+                    .clone();
+
+                if current_requests != deployment_requests {
+                    tracing::debug!("Requests are different!");
                 }
             }
-        };
+        }
+
+        if replace {
+            tracing::debug!("Deployment needs to be replaced");
+        } else {
+            tracing::debug!("Deployment does not need to be replaced");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crds::defs::{FunctionNetworkPolicy, DEFAULT_MAX_ENV_BYTES};
+    use k8s_openapi::api::apps::v1::DeploymentStatus;
+    use k8s_openapi::api::core::v1::Toleration;
+    use std::collections::HashMap;
+
+    fn function() -> OpenFaaSFunction {
+        let spec: OpenFaasFunctionSpec = serde_json::from_value(serde_json::json!({
+            "service": "nodeinfo",
+            "image": "ghcr.io/openfaas/nodeinfo:latest",
+        }))
+        .expect("Invalid spec");
+
+        let mut function = OpenFaaSFunction::new("nodeinfo", spec);
+        function.metadata.namespace = Some(String::from("openfaas-fn"));
+        function.metadata.uid = Some(String::from("uid"));
+
+        function
+    }
+
+    #[test]
+    fn readiness_gate_covers_the_whole_window() {
+        for window in [1, 5, 30, 60, 600] {
+            let mut spec = function().spec;
+            spec.readiness_gate_seconds = Some(window);
+
+            let probe = spec.to_readiness_probe();
+            let covered = probe.initial_delay_seconds.unwrap_or_default()
+                + probe.period_seconds.unwrap_or_default()
+                    * probe.failure_threshold.unwrap_or_default();
+
+            assert!(covered >= window as i32, "{window}s not covered: {probe:?}");
+            assert!(covered < window as i32 + probe.period_seconds.unwrap_or_default());
+
+            let startup_probe = spec.to_startup_probe().expect("No startup probe");
+            let startup_covered = startup_probe.period_seconds.unwrap_or_default()
+                * startup_probe.failure_threshold.unwrap_or_default();
+
+            assert!(
+                startup_covered >= window as i32,
+                "{window}s not covered: {startup_probe:?}"
+            );
+            assert_eq!(startup_probe.http_get, probe.http_get);
+        }
+
+        assert_eq!(
+            function().spec.to_readiness_probe(),
+            Probe::from(&function().spec)
+        );
+        assert_eq!(function().spec.to_startup_probe(), None);
+    }
+
+    fn grouped_function(name: &str, traffic_weight: u32) -> OpenFaaSFunction {
+        let spec: OpenFaasFunctionSpec = serde_json::from_value(serde_json::json!({
+            "service": name,
+            "image": "ghcr.io/openfaas/nodeinfo:latest",
+            "trafficWeight": traffic_weight,
+        }))
+        .expect("Invalid spec");
+
+        let mut function = OpenFaaSFunction::new(name, spec);
+        function.metadata.namespace = Some(String::from("openfaas-fn"));
+        function.metadata.uid = Some(format!("{name}-uid"));
+        function.metadata.annotations = Some(
+            [(
+                String::from(SERVICE_GROUP_ANNOTATION),
+                String::from("nodeinfo"),
+            )]
+            .into(),
+        );
+
+        function
+    }
+
+    #[test]
+    fn primary_service_of_a_group_selects_all_weighted_functions() {
+        let primary = grouped_function("nodeinfo", 9);
+        let canary = grouped_function("nodeinfo-v2", 1);
+
+        let group_selector: BTreeMap<String, String> =
+            [(String::from(SERVICE_GROUP_LABEL), String::from("nodeinfo"))].into();
+
+        for (function, replicas) in [(&primary, 9), (&canary, 1)] {
+            let deployment = Deployment::try_from(function).expect("Failed to generate deployment");
+            let spec = deployment.spec.expect("Deployment has no spec");
+            let pod_labels = spec
+                .template
+                .metadata
+                .and_then(|meta| meta.labels)
+                .unwrap_or_default();
+
+            assert_eq!(spec.replicas, Some(replicas));
+            assert_eq!(
+                pod_labels.get(SERVICE_GROUP_LABEL),
+                Some(&String::from("nodeinfo"))
+            );
+        }
+
+        let service = Service::try_from(&primary).expect("Failed to generate service");
+        assert_eq!(
+            service
+                .spec
+                .as_ref()
+                .and_then(|spec| spec.selector.as_ref()),
+            Some(&group_selector)
+        );
+        assert!(!primary.service_needs_selector_patch(&service));
+
+        // The canary keeps its own service, e.g. for testing it directly
+        let canary_service = Service::try_from(&canary).expect("Failed to generate service");
+        assert_eq!(
+            canary_service
+                .spec
+                .and_then(|spec| spec.selector)
+                .and_then(|selector| selector.get(FUNCTION_LABEL).cloned()),
+            Some(String::from("nodeinfo-v2"))
+        );
+
+        let ungrouped = function();
+        let deployment = Deployment::try_from(&ungrouped).expect("Failed to generate deployment");
+        assert!(primary.deployment_service_group_changed(&deployment));
+        assert!(!ungrouped.deployment_service_group_changed(&deployment));
+        assert!(ungrouped.service_needs_selector_patch(&service));
+    }
+
+    #[test]
+    fn oversized_environment_is_rejected() {
+        let mut spec = function().spec;
+        spec.env_vars = Some(
+            [
+                (String::from("CONFIG"), "x".repeat(MAX_ENV_VAR_BYTES)),
+                (String::from("SMALL"), String::from("value")),
+            ]
+            .into(),
+        );
+
+        let errors = spec.validate().expect_err("Env var must be too large");
+        assert!(matches!(
+            errors.as_slice(),
+            [SpecValidationError::EnvVarTooLarge { name, .. }] if name == "CONFIG"
+        ));
+        assert_eq!(
+            OpenFaasFunctionPossibleStatus::from(&errors[0]),
+            OpenFaasFunctionPossibleStatus::EnvTooLarge
+        );
+
+        assert!(spec.validate_env_size(DEFAULT_MAX_ENV_BYTES).is_ok());
+        assert!(matches!(
+            spec.validate_env_size(MAX_ENV_VAR_BYTES),
+            Err(SpecValidationError::EnvTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_returns_all_errors() {
+        let spec: OpenFaasFunctionSpec = serde_json::from_value(serde_json::json!({
+            "service": "Node_Info",
+            "image": "ghcr.io/openfaas/nodeinfo:latest",
+            "envProcess": "node index.js",
+            "envVars": { "fprocess": "python index.py" },
+            "secrets": ["valid", "In_Valid"],
+            "constraints": ["kubernetes.io/arch==amd64", "arch=amd64"],
+            "limits": { "memory": "lots" },
+        }))
+        .expect("Invalid spec");
+
+        let errors = spec.validate().expect_err("Spec should be invalid");
+
+        assert!(matches!(errors[0], SpecValidationError::Name(ref name) if name == "Node_Info"));
+        assert!(matches!(
+            errors[1],
+            SpecValidationError::Quantity(IntoQuantityError::Memory(_))
+        ));
+        assert!(
+            matches!(errors[2], SpecValidationError::EnvCollision(ref name) if name == "fprocess")
+        );
+        assert!(
+            matches!(errors[3], SpecValidationError::SecretName(ref name) if name == "In_Valid")
+        );
+        assert!(matches!(errors[4], SpecValidationError::Constraint(ref c) if c == "arch=amd64"));
+        assert_eq!(errors.len(), 5);
+
+        assert!(function().spec.validate().is_ok());
+    }
+
+    #[test]
+    fn deployment_claimed_by_another_function_is_distinguished_from_third_party() {
+        let function = function();
+
+        // Same service name, so both generate a deployment with the same name
+        let mut other = function.clone();
+        other.metadata.name = Some(String::from("nodeinfo-copy"));
+        other.metadata.uid = Some(String::from("other-uid"));
+
+        let deployment = Deployment::try_from(&other).expect("Failed to generate deployment");
+        assert_eq!(
+            deployment.metadata.name,
+            Deployment::try_from(&function)
+                .expect("Failed to generate deployment")
+                .metadata
+                .name
+        );
+
+        assert_eq!(
+            other.ownership(deployment.owner_references()),
+            ResourceOwnership::Owned
+        );
+        assert_eq!(
+            function.ownership(deployment.owner_references()),
+            ResourceOwnership::OwnedByAnotherFunction(String::from("nodeinfo-copy"))
+        );
+        assert_eq!(function.ownership(&[]), ResourceOwnership::ThirdParty);
+    }
+
+    #[test]
+    fn only_unowned_and_labeled_resources_are_adoptable() {
+        let function = function();
+
+        let mut orphan =
+            Deployment::try_from(&function.spec).expect("Failed to generate deployment");
+        assert!(function.can_adopt(&orphan.metadata));
+
+        let patch = function
+            .to_adoption_patch(&orphan.metadata)
+            .expect("Function has no owner reference");
+        let adopted: ObjectMeta =
+            serde_json::from_value(patch["metadata"].clone()).expect("Invalid adoption patch");
+        assert_eq!(
+            function.ownership(adopted.owner_references.as_deref().unwrap_or_default()),
+            ResourceOwnership::Owned
+        );
+
+        orphan.metadata.labels = None;
+        assert!(!function.can_adopt(&orphan.metadata));
+
+        let controlled = Deployment::try_from(&function).expect("Failed to generate deployment");
+        assert!(!function.can_adopt(&controlled.metadata));
+    }
+
+    fn ready_deployment(function: &OpenFaaSFunction) -> Deployment {
+        let mut deployment = Deployment::try_from(function).expect("Failed to generate deployment");
+        deployment.status = Some(DeploymentStatus {
+            ready_replicas: Some(1),
+            ..Default::default()
+        });
+
+        deployment
+    }
+
+    #[test]
+    fn function_is_not_ready_if_service_is_missing() {
+        let function = function();
+        let deployment = ready_deployment(&function);
+
+        let readiness = function.readiness(Some(&deployment), None);
+
+        assert_eq!(readiness, FunctionReadiness::ServiceMissing);
+        assert_eq!(
+            OpenFaasFunctionPossibleStatus::from(&readiness),
+            OpenFaasFunctionPossibleStatus::ServiceNotReady
+        );
+    }
+
+    #[test]
+    fn function_is_ready_if_deployment_is_ready_and_service_is_owned() {
+        let function = function();
+        let deployment = ready_deployment(&function);
+        let service = Service::try_from(&function).expect("Failed to generate service");
+
+        assert_eq!(
+            function.readiness(Some(&deployment), Some(&service)),
+            FunctionReadiness::Ready
+        );
+    }
 
-        let mut replace = false;
+    #[test]
+    fn unknown_fields_of_previous_spec_do_not_trigger_recreation() {
+        let function = function();
+        let mut deployment = ready_deployment(&function);
 
-        // now we check meta_labels
-        let current_meta_labels = self.to_meta_labels();
-        let prev_spec_meta_labels = prev_spec.to_meta_labels();
-        let mut deployment_meta_labels = deployment
+        let annotations = deployment
             .metadata
-            .labels
-            .as_ref()
-            .unwrap_or(&BTreeMap::new())
-            .clone();
+            .annotations
+            .as_mut()
+            .expect("Deployment has no annotations");
+        let last_applied = annotations
+            .get_mut(LAST_APPLIED_ANNOTATION)
+            .expect("Deployment has no last applied spec");
+
+        let mut spec: serde_json::Value =
+            serde_json::from_str(last_applied).expect("Failed to parse last applied spec");
+        spec["fieldFromTheFuture"] = serde_json::json!({"enabled": true});
+        *last_applied = spec.to_string();
+
+        let prev_spec = serde_json::from_str::<OpenFaasFunctionSpec>(last_applied)
+            .expect("Unknown fields must be ignored");
+
+        assert_eq!(prev_spec, function.spec);
+        assert_eq!(
+            OpenFaasFunctionSpec::unknown_fields(last_applied),
+            vec![String::from("fieldFromTheFuture")]
+        );
+        assert!(!function.spec.deployment_needs_recreation(&deployment));
+    }
 
-        tracing::debug!("Checking meta labels");
-        let meta_labels_in_prev_but_not_in_current =
-            utils::collect_missing_keys_btree(&prev_spec_meta_labels, &current_meta_labels);
-        let meta_labels_in_dep_but_not_in_current =
-            utils::collect_missing_keys_btree(&deployment_meta_labels, &current_meta_labels);
-        let meta_labels_in_current_but_not_dep =
-            utils::collect_missing_keys_btree(&current_meta_labels, &deployment_meta_labels);
-        tracing::debug!(
-            "Meta labels in deployment but not in current spec: {:#?}",
-            meta_labels_in_dep_but_not_in_current
+    #[test]
+    fn replicas_take_precedence_over_the_scale_min_label() {
+        let mut function = function();
+        function.spec.labels = Some(HashMap::from([(
+            String::from(SCALE_MIN_LABEL),
+            String::from("2"),
+        )]));
+
+        assert_eq!(function.spec.scale_min_conflict(), None);
+
+        function.spec.replicas = Some(3);
+
+        let deployment = Deployment::try_from(&function).expect("Failed to generate deployment");
+        assert_eq!(
+            deployment.spec.as_ref().and_then(|spec| spec.replicas),
+            Some(3)
         );
-        tracing::debug!(
-            "Meta labels to be added to deployment: {:#?}",
-            meta_labels_in_current_but_not_dep
+        assert_eq!(function.spec.scale_min_conflict().as_deref(), Some("2"));
+
+        function.spec.replicas = Some(2);
+        assert_eq!(function.spec.scale_min_conflict(), None);
+    }
+
+    #[test]
+    fn replica_only_changes_are_patched_in_place() {
+        let mut function = function();
+        let deployment = Deployment::try_from(&function).expect("Failed to generate deployment");
+
+        function.spec.replicas = Some(3);
+        assert!(function.spec.deployment_needs_recreation(&deployment));
+        assert!(function
+            .spec
+            .deployment_needs_metadata_patch_only(&deployment));
+
+        let patch = function
+            .spec
+            .to_deployment_metadata_patch(&deployment)
+            .expect("Failed to generate patch");
+        assert_eq!(patch["spec"]["replicas"], 3);
+
+        function.spec.replicas = Some(1);
+        let patch = function
+            .spec
+            .to_deployment_metadata_patch(&deployment)
+            .expect("Failed to generate patch");
+        assert!(patch.get("spec").is_none());
+    }
+
+    fn parse_cpu(cpu: &str) -> Result<Option<Quantity>, IntoQuantityError> {
+        FunctionResourcesQuantity::try_from(&FunctionResources {
+            memory: None,
+            cpu: Some(String::from(cpu)),
+        })
+        .map(|quantity| quantity.cpu)
+    }
+
+    fn parse_memory(memory: &str) -> Result<Option<Quantity>, IntoQuantityError> {
+        FunctionResourcesQuantity::try_from(&FunctionResources {
+            memory: Some(String::from(memory)),
+            cpu: None,
+        })
+        .map(|quantity| quantity.memory)
+    }
+
+    #[test]
+    fn decimal_cpu_quantities_are_parsed() {
+        for cpu in ["0.5", "500m", "1"] {
+            assert_eq!(
+                parse_cpu(cpu).expect("Failed to parse cpu"),
+                Some(Quantity(String::from(cpu)))
+            );
+        }
+    }
+
+    #[test]
+    fn memory_quantities_are_parsed() {
+        for memory in ["1Gi", "1G"] {
+            assert_eq!(
+                parse_memory(memory).expect("Failed to parse memory"),
+                Some(Quantity(String::from(memory)))
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_quantities_are_rejected() {
+        assert!(matches!(parse_cpu("half"), Err(IntoQuantityError::CPU(_))));
+        assert!(matches!(
+            parse_memory("1Gb"),
+            Err(IntoQuantityError::Memory(_))
+        ));
+    }
+
+    fn tmp_volume_mount_paths(function: &OpenFaaSFunction) -> Vec<String> {
+        let deployment = Deployment::try_from(function).expect("Failed to generate deployment");
+        let pod_spec = deployment
+            .spec
+            .and_then(|spec| spec.template.spec)
+            .expect("Deployment has no pod spec");
+
+        assert_eq!(
+            pod_spec
+                .volumes
+                .unwrap_or_default()
+                .iter()
+                .filter(|volume| volume.name == "tmp")
+                .count(),
+            pod_spec.containers[0]
+                .volume_mounts
+                .as_ref()
+                .map_or(0, |mounts| mounts
+                    .iter()
+                    .filter(|m| m.name == "tmp")
+                    .count())
         );
-        tracing::debug!(
-            "Meta labels to be removed from deployment: {:#?}",
-            meta_labels_in_prev_but_not_in_current
+
+        pod_spec.containers[0]
+            .volume_mounts
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|mount| mount.name == "tmp")
+            .map(|mount| mount.mount_path)
+            .collect()
+    }
+
+    #[test]
+    fn tmp_volume_follows_read_only_root_filesystem_by_default() {
+        let mut function = function();
+        assert!(tmp_volume_mount_paths(&function).is_empty());
+
+        function.spec.read_only_root_filesystem = Some(true);
+        assert_eq!(tmp_volume_mount_paths(&function), vec!["/tmp"]);
+    }
+
+    #[test]
+    fn tmp_volume_can_be_disabled_or_relocated() {
+        let mut function = function();
+        function.spec.read_only_root_filesystem = Some(true);
+        function.spec.tmp_volume = Some(false);
+        assert!(tmp_volume_mount_paths(&function).is_empty());
+
+        function.spec.tmp_volume = None;
+        function.spec.tmp_mount_path = Some(String::from("/scratch"));
+        assert_eq!(tmp_volume_mount_paths(&function), vec!["/scratch"]);
+    }
+
+    #[test]
+    fn reserved_annotation_keys_are_rejected() {
+        let mut function = function();
+        function.spec.annotations = Some(HashMap::from([(
+            String::from(LAST_APPLIED_ANNOTATION),
+            String::from("{}"),
+        )]));
+
+        let error = Deployment::try_from(&function).expect_err("Reserved annotation was accepted");
+
+        assert_eq!(
+            Option::<OpenFaasFunctionPossibleStatus>::from(&error),
+            Some(OpenFaasFunctionPossibleStatus::ReservedAnnotationKey)
         );
-        if !meta_labels_in_prev_but_not_in_current.is_empty() {
-            tracing::debug!("Triggering replace");
-            replace = true;
+    }
+
+    #[test]
+    fn app_protocol_is_validated() {
+        let mut function = function();
+
+        for app_protocol in ["kubernetes.io/h2c", "grpc", "example.com/custom"] {
+            function.spec.app_protocol = Some(String::from(app_protocol));
+            let service = Service::try_from(&function).expect("Valid appProtocol was rejected");
+
+            assert_eq!(
+                service.spec.and_then(|spec| spec.ports).expect("No ports")[0].app_protocol,
+                Some(String::from(app_protocol))
+            );
         }
 
-        // remove labels that are in prev_spec but not in current
-        for label in meta_labels_in_prev_but_not_in_current {
-            deployment_meta_labels.remove(label);
+        for app_protocol in ["h2", "kubernetes.io/custom", "foo.k8s.io/custom"] {
+            function.spec.app_protocol = Some(String::from(app_protocol));
+            let error = Service::try_from(&function).expect_err("Invalid appProtocol was accepted");
+
+            assert_eq!(
+                Option::<OpenFaasFunctionPossibleStatus>::from(&error),
+                Some(OpenFaasFunctionPossibleStatus::InvalidAppProtocol)
+            );
         }
-        // add labels that are in current but not in deployment
-        deployment_meta_labels.extend(current_meta_labels);
-        tracing::debug!("Final meta labels: {:#?}", deployment_meta_labels);
+    }
 
-        // now we check meta_annotations. for the meta_annotations we will use to_annotations, since we don't want to compare the last applied annotation
-        let current_meta_annotations = self.to_annotations().unwrap_or_default();
-        let prev_spec_meta_annotations = prev_spec.to_annotations().unwrap_or_default();
-        let mut deployment_meta_annotations = deployment
-            .metadata
-            .annotations
+    #[test]
+    fn default_pod_labels_do_not_override_function_labels() {
+        let mut function = function();
+        function.spec.labels = Some(HashMap::from([(
+            String::from("cost-center"),
+            String::from("functions"),
+        )]));
+
+        let default_pod_labels = BTreeMap::from([
+            (String::from("cost-center"), String::from("platform")),
+            (
+                String::from("data-classification"),
+                String::from("internal"),
+            ),
+            (String::from(FUNCTION_LABEL), String::from("other")),
+        ]);
+
+        let mut deployment =
+            Deployment::try_from(&function).expect("Failed to generate deployment");
+        assert!(OpenFaasFunctionSpec::default_pod_labels_changed(
+            &deployment,
+            &default_pod_labels
+        ));
+
+        OpenFaasFunctionSpec::apply_default_pod_labels(&mut deployment, &default_pod_labels);
+
+        let labels = deployment
+            .spec
             .as_ref()
-            .unwrap_or(&BTreeMap::new())
-            .clone();
-        // remove the last applied annotation, since we don't want to compare it
-        deployment_meta_annotations.remove(LAST_APPLIED_ANNOTATION);
-        tracing::debug!("Checking meta annotations");
-        let meta_annotations_in_prev_but_not_in_current = utils::collect_missing_keys_btree(
-            &prev_spec_meta_annotations,
-            &current_meta_annotations,
+            .and_then(|spec| spec.template.metadata.as_ref())
+            .and_then(|metadata| metadata.labels.clone())
+            .expect("No pod labels");
+
+        assert_eq!(labels["cost-center"], "functions");
+        assert_eq!(labels["data-classification"], "internal");
+        assert_eq!(labels[FUNCTION_LABEL], "nodeinfo");
+
+        assert!(!OpenFaasFunctionSpec::default_pod_labels_changed(
+            &deployment,
+            &default_pod_labels
+        ));
+        assert!(OpenFaasFunctionSpec::default_pod_labels_changed(
+            &deployment,
+            &BTreeMap::new()
+        ));
+    }
+
+    #[test]
+    fn network_policy_allows_the_gateway_by_default() {
+        let mut function = function();
+        assert_eq!(
+            Option::<NetworkPolicy>::try_from(&function).expect("Failed to generate"),
+            None
         );
-        let meta_annotations_in_dep_but_not_in_current = utils::collect_missing_keys_btree(
-            &deployment_meta_annotations,
-            &current_meta_annotations,
+
+        function.spec.network_policy = Some(FunctionNetworkPolicy {
+            ingress_from: None,
+            egress: None,
+        });
+
+        let spec = Option::<NetworkPolicy>::try_from(&function)
+            .expect("Failed to generate")
+            .and_then(|network_policy| network_policy.spec)
+            .expect("No network policy");
+
+        assert_eq!(spec.pod_selector, LabelSelector::from(&function.spec));
+        assert_eq!(spec.policy_types, Some(vec![String::from("Ingress")]));
+        assert_eq!(
+            spec.ingress.expect("No ingress")[0].from,
+            Some(vec![gateway_network_policy_peer()])
         );
-        let meta_annotations_in_current_but_not_dep = utils::collect_missing_keys_btree(
-            &current_meta_annotations,
-            &deployment_meta_annotations,
+    }
+
+    #[test]
+    fn changed_secrets_trigger_recreation() {
+        let mut function = function();
+        function.spec.secrets = Some(vec![String::from("api-key")]);
+
+        let deployment = Deployment::try_from(&function).expect("Failed to generate deployment");
+        assert!(!function.spec.deployment_needs_recreation(&deployment));
+
+        function
+            .spec
+            .secrets
+            .get_or_insert_with(Vec::new)
+            .push(String::from("db-password"));
+        assert!(function.spec.deployment_secrets_changed(&deployment));
+        assert!(function.spec.deployment_needs_recreation(&deployment));
+
+        function.spec.secrets = None;
+        assert!(function.spec.deployment_needs_recreation(&deployment));
+    }
+
+    #[test]
+    fn replicas_are_passed_through_and_scale_to_zero() {
+        let mut function = function();
+        function.spec.replicas = Some(0);
+
+        let deployment = Deployment::try_from(&function).expect("Failed to generate deployment");
+        assert_eq!(
+            deployment.spec.as_ref().and_then(|spec| spec.replicas),
+            Some(0)
         );
-        tracing::debug!(
-            "Meta annotations in deployment but not in current spec: {:#?}",
-            meta_annotations_in_dep_but_not_in_current
+        assert_eq!(
+            function.readiness(Some(&deployment), None),
+            FunctionReadiness::ServiceMissing
         );
-        tracing::debug!(
-            "Meta annotations to be added to deployment: {:#?}",
-            meta_annotations_in_current_but_not_dep
+
+        let patch = function
+            .spec
+            .to_deployment_metadata_patch(&deployment)
+            .expect("Failed to generate patch");
+        assert!(patch.get("spec").is_none());
+
+        // Labels only live on the pod template and must be patched there
+        function.spec.labels = Some(HashMap::from([(
+            String::from(SCALE_MIN_LABEL),
+            String::from("2"),
+        )]));
+        assert!(function
+            .spec
+            .deployment_needs_metadata_patch_only(&deployment));
+
+        let patch = function
+            .spec
+            .to_deployment_metadata_patch(&deployment)
+            .expect("Failed to generate patch");
+        let pod_metadata = &patch["spec"]["template"]["metadata"];
+        assert_eq!(pod_metadata["labels"][SCALE_MIN_LABEL], "2");
+        assert_eq!(pod_metadata["labels"][FUNCTION_LABEL], "nodeinfo");
+        assert!(patch["spec"].get("replicas").is_none());
+
+        let mut patched = deployment.clone();
+        patched.annotations_mut().insert(
+            String::from(LAST_APPLIED_ANNOTATION),
+            serde_json::to_string(&function.spec).expect("Failed to serialize"),
         );
-        tracing::debug!(
-            "Meta annotations to be removed from deployment: {:#?}",
-            meta_annotations_in_prev_but_not_in_current
+        function.spec.labels = None;
+        function.spec.annotations = Some(HashMap::from([(
+            String::from("topic"),
+            String::from("cron"),
+        )]));
+
+        let patch = function
+            .spec
+            .to_deployment_metadata_patch(&patched)
+            .expect("Failed to generate patch");
+        let pod_metadata = &patch["spec"]["template"]["metadata"];
+        assert_eq!(
+            pod_metadata["labels"][SCALE_MIN_LABEL],
+            serde_json::Value::Null
         );
-        if !meta_annotations_in_prev_but_not_in_current.is_empty() {
-            tracing::debug!("Triggering replace");
-            replace = true;
-        }
+        assert_eq!(pod_metadata["annotations"]["topic"], "cron");
+        assert_eq!(patch["metadata"]["annotations"]["topic"], "cron");
+
+        function.spec.replicas = Some(-1);
+        assert!(matches!(
+            function.spec.validate().unwrap_err().as_slice(),
+            [SpecValidationError::Replicas(-1)]
+        ));
+
+        function.spec.replicas = Some(0);
+        function.spec.traffic_weight = Some(2);
+        assert!(matches!(
+            function.spec.validate().unwrap_err().as_slice(),
+            [SpecValidationError::ConflictingReplicas]
+        ));
+    }
 
-        // remove annotations that are in prev_spec but not in current
-        for annotation in meta_annotations_in_prev_but_not_in_current {
-            deployment_meta_annotations.remove(annotation);
-        }
-        // add annotations that are in current but not in deployment
-        deployment_meta_annotations.extend(current_meta_annotations);
-        // add the last applied annotation
-        deployment_meta_annotations.insert(
-            String::from(LAST_APPLIED_ANNOTATION),
-            serde_json::to_string(self).expect("Failed to serialize the current spec"),
+    #[test]
+    fn pod_spec_overlay_is_merged_and_validated() {
+        let mut function = function();
+        let deployment = Deployment::try_from(&function).expect("Failed to generate deployment");
+
+        function.spec.pod_spec_overlay = Some(serde_json::json!({
+            "hostAliases": [{ "ip": "10.0.0.1", "hostnames": ["db.local"] }],
+            "containers": [{ "name": "nodeinfo", "stdin": true }],
+            "enableServiceLinks": null,
+        }));
+        assert!(function.spec.validate().is_ok());
+        assert!(function.spec.deployment_needs_recreation(&deployment));
+        assert!(!function
+            .spec
+            .deployment_needs_metadata_patch_only(&deployment));
+
+        let pod_spec = PodSpec::try_from(&function.spec).expect("Failed to generate pod spec");
+        assert_eq!(pod_spec.host_aliases.map(|aliases| aliases.len()), Some(1));
+        assert_eq!(pod_spec.containers.len(), 1);
+        assert_eq!(pod_spec.containers[0].stdin, Some(true));
+        assert_eq!(
+            pod_spec.containers[0].image.as_deref(),
+            Some("ghcr.io/openfaas/nodeinfo:latest")
         );
-        tracing::debug!("Final meta annotations: {:#?}", deployment_meta_annotations);
 
-        tracing::debug!("Checking spec labels");
-        let current_spec_labels = self.to_spec_meta_labels();
-        let prev_spec_spec_labels = prev_spec.to_spec_meta_labels();
-        let mut deployment_spec_labels = deployment
+        function.spec.pod_spec_overlay = Some(serde_json::json!({
+            "containers": [{ "name": "nodeinfo", "imag": "typo" }],
+        }));
+        assert!(matches!(
+            function.spec.validate().unwrap_err().as_slice(),
+            [SpecValidationError::PodSpecOverlay(error)] if error.contains("containers[0].imag")
+        ));
+        assert!(matches!(
+            Deployment::try_from(&function.spec),
+            Err(FunctionSpecIntoDeploymentError::PodSpecOverlay(_))
+        ));
+    }
+
+    #[test]
+    fn service_targets_the_configured_container_port() {
+        let mut function = function();
+        let service = Service::try_from(&function).expect("Failed to generate service");
+        assert!(!function.spec.service_needs_ports_patch(&service));
+
+        function.spec.container_port = Some(3000);
+        function.spec.http_probe_path = Some(String::from("/ready"));
+        assert!(function.spec.validate().is_ok());
+        assert!(function.spec.service_needs_ports_patch(&service));
+
+        let container = Container::try_from(&function.spec).expect("Failed to generate container");
+        let http_get = container
+            .readiness_probe
+            .and_then(|probe| probe.http_get)
+            .expect("No readiness probe");
+        assert_eq!(http_get.path.as_deref(), Some("/ready"));
+        assert_eq!(http_get.port, IntOrString::Int(3000));
+        assert_eq!(container.ports.expect("No ports")[0].container_port, 3000);
+
+        let port = &Service::try_from(&function)
+            .expect("Failed to generate service")
             .spec
-            .as_ref()
-            .unwrap_or(&DeploymentSpec::default())
-            .template
-            .metadata
-            .as_ref()
-            .unwrap_or(&ObjectMeta::default())
-            .labels
-            .as_ref()
-            .unwrap_or(&BTreeMap::new())
-            .clone();
+            .and_then(|spec| spec.ports)
+            .expect("No ports")[0];
+        assert_eq!(port.port, 8080);
+        assert_eq!(port.target_port, Some(IntOrString::Int(3000)));
+
+        function.spec.container_port = Some(0);
+        function.spec.http_probe_path = Some(String::from("ready"));
+        assert_eq!(function.spec.validate().unwrap_err().len(), 2);
+    }
 
-        let spec_labels_in_prev_but_not_in_current =
-            utils::collect_missing_keys_btree(&prev_spec_spec_labels, &current_spec_labels);
-        let spec_labels_in_dep_but_not_in_current =
-            utils::collect_missing_keys_btree(&deployment_spec_labels, &current_spec_labels);
-        let spec_labels_in_current_but_not_dep =
-            utils::collect_missing_keys_btree(&current_spec_labels, &deployment_spec_labels);
-        tracing::debug!(
-            "Spec labels in deployment but not in current spec: {:#?}",
-            spec_labels_in_dep_but_not_in_current
+    #[test]
+    fn probes_are_tuned_separately() {
+        let mut spec = function().spec;
+        spec.readiness_gate_seconds = Some(60);
+        spec.liveness_probe = Some(ProbeConfig {
+            initial_delay_seconds: Some(120),
+            ..Default::default()
+        });
+
+        let gated_readiness_probe = spec.to_readiness_probe();
+        let container = Container::try_from(&spec).expect("Failed to generate container");
+        let liveness_probe = container.liveness_probe.expect("No liveness probe");
+
+        assert_eq!(liveness_probe.initial_delay_seconds, Some(120));
+        assert_eq!(liveness_probe.period_seconds, None);
+        assert_eq!(container.readiness_probe, Some(gated_readiness_probe));
+        assert_eq!(container.startup_probe, spec.to_startup_probe());
+
+        spec.readiness_probe = Some(ProbeConfig {
+            period_seconds: Some(2),
+            timeout_seconds: Some(0),
+            ..Default::default()
+        });
+
+        let readiness_probe = spec.to_readiness_probe();
+        assert_eq!(readiness_probe.period_seconds, Some(2));
+        assert_eq!(readiness_probe.initial_delay_seconds, Some(15));
+        assert!(matches!(
+            spec.validate().unwrap_err().as_slice(),
+            [SpecValidationError::ProbeConfig {
+                probe: "readinessProbe",
+                field: "timeoutSeconds",
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn image_pull_secrets_are_referenced_by_the_pod_spec() {
+        let mut function = function();
+        assert_eq!(
+            PodSpec::try_from(&function.spec)
+                .expect("Failed to generate pod spec")
+                .image_pull_secrets,
+            None
         );
-        tracing::debug!(
-            "Spec labels to be added to deployment: {:#?}",
-            spec_labels_in_current_but_not_dep
+
+        function.spec.image_pull_secrets = Some(vec![
+            String::from("registry-credentials"),
+            String::from("registry-credentials"),
+        ]);
+        assert_eq!(
+            PodSpec::try_from(&function.spec)
+                .expect("Failed to generate pod spec")
+                .image_pull_secrets,
+            Some(vec![LocalObjectReference {
+                name: Some(String::from("registry-credentials")),
+            }])
         );
-        tracing::debug!(
-            "Spec labels to be removed from deployment: {:#?}",
-            spec_labels_in_prev_but_not_in_current
+
+        function.spec.image_pull_secrets = Some(vec![String::from("Registry")]);
+        assert!(matches!(
+            function.spec.validate().unwrap_err().as_slice(),
+            [SpecValidationError::SecretName(name)] if name == "Registry"
+        ));
+    }
+
+    #[test]
+    fn invalid_image_pull_policy_has_its_own_status() {
+        let mut function = function();
+        function.spec.image_pull_policy = Some(String::from("Always"));
+
+        let container = Container::try_from(&function.spec).expect("Failed to generate container");
+        assert_eq!(container.image_pull_policy.as_deref(), Some("Always"));
+
+        function.spec.image_pull_policy = Some(String::from("always"));
+        let errors = function
+            .spec
+            .validate()
+            .expect_err("Spec should be invalid");
+        assert_eq!(
+            OpenFaasFunctionPossibleStatus::from(&errors[0]),
+            OpenFaasFunctionPossibleStatus::InvalidImagePullPolicy
         );
-        if !spec_labels_in_prev_but_not_in_current.is_empty() {
-            tracing::debug!("Triggering replace");
-            replace = true;
-        }
 
-        // remove labels that are in prev_spec but not in current
-        for label in spec_labels_in_prev_but_not_in_current {
-            deployment_spec_labels.remove(label);
+        let error = Deployment::try_from(&function).expect_err("Deployment should be invalid");
+        assert_eq!(
+            Option::<OpenFaasFunctionPossibleStatus>::from(&error),
+            Some(OpenFaasFunctionPossibleStatus::InvalidImagePullPolicy)
+        );
+        assert_eq!(error.to_status_detail().as_deref(), Some("always"));
+    }
+
+    #[test]
+    fn service_type_and_node_port_are_validated_and_patched() {
+        let mut function = function();
+        let service = Service::try_from(&function).expect("Failed to generate service");
+        assert!(!function.spec.service_type_changed(&service));
+
+        function.spec.service_type = Some(String::from("NodePort"));
+        function.spec.node_port = Some(31112);
+        assert!(function.spec.validate().is_ok());
+        assert!(function.spec.service_type_changed(&service));
+        assert!(function.spec.service_needs_ports_patch(&service));
+
+        let node_port_service = Service::try_from(&function).expect("Failed to generate service");
+        let spec = node_port_service.spec.as_ref().expect("No spec");
+        assert_eq!(spec.type_.as_deref(), Some("NodePort"));
+        assert_eq!(
+            spec.ports.as_ref().expect("No ports")[0].node_port,
+            Some(31112)
+        );
+        assert!(!function.spec.service_type_changed(&node_port_service));
+        assert!(!function.spec.service_needs_ports_patch(&node_port_service));
+
+        function.spec.load_balancer_class = Some(String::from("internal"));
+        assert!(matches!(
+            Service::try_from(&function.spec),
+            Err(FunctionSpecIntoServiceError::LoadBalancerClass)
+        ));
+
+        function.spec.service_type = Some(String::from("LoadBalancer"));
+        assert!(function.spec.validate().is_ok());
+
+        function.spec.service_type = Some(String::from("ExternalName"));
+        function.spec.load_balancer_class = None;
+        let statuses: Vec<OpenFaasFunctionPossibleStatus> = function
+            .spec
+            .validate()
+            .expect_err("Spec should be invalid")
+            .iter()
+            .map(OpenFaasFunctionPossibleStatus::from)
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                OpenFaasFunctionPossibleStatus::InvalidServiceType,
+                OpenFaasFunctionPossibleStatus::InvalidNodePort
+            ]
+        );
+    }
+
+    #[test]
+    fn changed_tolerations_trigger_recreation() {
+        let mut function = function();
+        let toleration = Toleration {
+            key: Some(String::from("nvidia.com/gpu")),
+            operator: Some(String::from("Exists")),
+            effect: Some(String::from("NoSchedule")),
+            ..Default::default()
+        };
+        function.spec.tolerations = Some(vec![toleration.clone()]);
+
+        let deployment = Deployment::try_from(&function).expect("Failed to generate deployment");
+        assert_eq!(
+            deployment
+                .spec
+                .as_ref()
+                .and_then(|spec| spec.template.spec.as_ref())
+                .and_then(|pod_spec| pod_spec.tolerations.clone()),
+            Some(vec![toleration])
+        );
+        assert!(!function.spec.deployment_needs_recreation(&deployment));
+
+        function.spec.tolerations = None;
+        assert!(function.spec.deployment_needs_recreation(&deployment));
+        assert!(!function
+            .spec
+            .deployment_needs_metadata_patch_only(&deployment));
+    }
+
+    #[test]
+    fn removed_fields_can_not_be_merged() {
+        let mut function = function();
+        function.spec.tolerations = Some(vec![Toleration {
+            key: Some(String::from("nvidia.com/gpu")),
+            operator: Some(String::from("Exists")),
+            ..Default::default()
+        }]);
+        function.spec.affinity = Some(Affinity {
+            pod_anti_affinity: Some(Default::default()),
+            ..Default::default()
+        });
+        function.spec.deployment_paused = Some(true);
+
+        let deployment = Deployment::try_from(&function).expect("Failed to generate deployment");
+        assert!(!function.deployment_merge_cannot_converge(&deployment));
+
+        let mut without_tolerations = function.clone();
+        without_tolerations.spec.tolerations = None;
+        assert!(without_tolerations.deployment_merge_cannot_converge(&deployment));
+
+        let mut without_affinity = function.clone();
+        without_affinity.spec.affinity = None;
+        assert!(without_affinity.deployment_merge_cannot_converge(&deployment));
+
+        let mut unpaused = function.clone();
+        unpaused.spec.deployment_paused = None;
+        assert!(unpaused.deployment_merge_cannot_converge(&deployment));
+
+        // Setting the field explicitly is merged
+        unpaused.spec.deployment_paused = Some(false);
+        assert!(!unpaused.deployment_merge_cannot_converge(&deployment));
+    }
+
+    #[test]
+    fn changed_strategy_type_can_not_be_merged() {
+        let rolling_update = function();
+        let mut recreate = function();
+        recreate.spec.deployment_strategy_type = Some(String::from("Recreate"));
+
+        let rolling_update_deployment =
+            Deployment::try_from(&rolling_update).expect("Failed to generate deployment");
+        let recreate_deployment =
+            Deployment::try_from(&recreate).expect("Failed to generate deployment");
+
+        assert!(!rolling_update.deployment_merge_cannot_converge(&rolling_update_deployment));
+        assert!(!recreate.deployment_merge_cannot_converge(&recreate_deployment));
+        assert!(recreate.deployment_merge_cannot_converge(&rolling_update_deployment));
+        assert!(rolling_update.deployment_merge_cannot_converge(&recreate_deployment));
+    }
+
+    #[test]
+    fn affinity_is_emitted_next_to_the_constraint_node_selector() {
+        let mut function = function();
+        function.spec.constraints = Some(vec![String::from("kubernetes.io/arch==amd64")]);
+
+        let affinity: Affinity = serde_json::from_value(serde_json::json!({
+            "podAntiAffinity": {
+                "preferredDuringSchedulingIgnoredDuringExecution": [{
+                    "weight": 100,
+                    "podAffinityTerm": {
+                        "labelSelector": { "matchLabels": { "faas_function": "nodeinfo" } },
+                        "topologyKey": "kubernetes.io/hostname",
+                    },
+                }],
+            },
+        }))
+        .expect("Invalid affinity");
+        function.spec.affinity = Some(affinity.clone());
+
+        let pod_spec = PodSpec::try_from(&function.spec).expect("Failed to generate pod spec");
+
+        assert_eq!(pod_spec.affinity, Some(affinity));
+        assert_eq!(
+            pod_spec.node_selector,
+            Some(BTreeMap::from([(
+                String::from("kubernetes.io/arch"),
+                String::from("amd64")
+            )]))
+        );
+    }
+
+    #[test]
+    fn not_equal_constraints_become_node_anti_affinity() {
+        let mut function = function();
+        function.spec.constraints = Some(vec![
+            String::from("kubernetes.io/arch == amd64"),
+            String::from("node.kubernetes.io/instance-type!=t2.micro"),
+            String::from("node.kubernetes.io/instance-type != t2.nano"),
+            String::from("topology.kubernetes.io/zone!=eu-west-1a"),
+        ]);
+        assert!(function.spec.validate().is_ok());
+
+        let pod_spec = PodSpec::try_from(&function.spec).expect("Failed to generate pod spec");
+
+        assert_eq!(
+            pod_spec.node_selector,
+            Some(BTreeMap::from([(
+                String::from("kubernetes.io/arch"),
+                String::from("amd64")
+            )]))
+        );
+
+        let terms = pod_spec
+            .affinity
+            .and_then(|affinity| affinity.node_affinity)
+            .and_then(|node_affinity| {
+                node_affinity.required_during_scheduling_ignored_during_execution
+            })
+            .map(|node_selector| node_selector.node_selector_terms)
+            .expect("No required node affinity");
+        assert_eq!(terms.len(), 1);
+        assert_eq!(
+            terms[0].match_expressions,
+            Some(vec![
+                NodeSelectorRequirement {
+                    key: String::from("node.kubernetes.io/instance-type"),
+                    operator: String::from("NotIn"),
+                    values: Some(vec![String::from("t2.micro"), String::from("t2.nano")]),
+                },
+                NodeSelectorRequirement {
+                    key: String::from("topology.kubernetes.io/zone"),
+                    operator: String::from("NotIn"),
+                    values: Some(vec![String::from("eu-west-1a")]),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn not_equal_constraints_apply_to_every_node_affinity_term() {
+        let mut function = function();
+        function.spec.constraints = Some(vec![String::from("kubernetes.io/arch!=arm64")]);
+        function.spec.affinity = Some(
+            serde_json::from_value(serde_json::json!({
+                "nodeAffinity": {
+                    "requiredDuringSchedulingIgnoredDuringExecution": {
+                        "nodeSelectorTerms": [
+                            { "matchExpressions": [{ "key": "gpu", "operator": "Exists" }] },
+                            { "matchExpressions": [{ "key": "tpu", "operator": "Exists" }] },
+                        ],
+                    },
+                },
+            }))
+            .expect("Invalid affinity"),
+        );
+
+        let pod_spec = PodSpec::try_from(&function.spec).expect("Failed to generate pod spec");
+        assert_eq!(pod_spec.node_selector, None);
+
+        let terms = pod_spec
+            .affinity
+            .and_then(|affinity| affinity.node_affinity)
+            .and_then(|node_affinity| {
+                node_affinity.required_during_scheduling_ignored_during_execution
+            })
+            .map(|node_selector| node_selector.node_selector_terms)
+            .expect("No required node affinity");
+        assert_eq!(terms.len(), 2);
+        for term in terms {
+            assert!(term
+                .match_expressions
+                .unwrap_or_default()
+                .iter()
+                .any(|requirement| requirement.key == "kubernetes.io/arch"
+                    && requirement.operator == "NotIn"));
         }
-        // add labels that are in current but not in deployment
-        deployment_spec_labels.extend(current_spec_labels);
-        tracing::debug!("Final spec labels: {:#?}", deployment_spec_labels);
+    }
 
-        tracing::debug!("Checking spec annotations");
-        let current_spec_annotations = self.to_annotations().unwrap_or_default();
-        let prev_spec_spec_annotations = prev_spec.to_annotations().unwrap_or_default();
-        let mut deployment_spec_annotations = deployment
+    #[test]
+    fn malformed_constraints_are_rejected_and_ignored() {
+        let mut function = function();
+        function.spec.constraints = Some(vec![
+            String::from("kubernetes.io/arch==amd64"),
+            String::from("arch=amd64"),
+            String::from("gpu"),
+            String::from("!=amd64"),
+        ]);
+
+        let errors = function
             .spec
-            .as_ref()
-            .unwrap_or(&DeploymentSpec::default())
-            .template
-            .metadata
-            .as_ref()
-            .unwrap_or(&ObjectMeta::default())
-            .annotations
-            .as_ref()
-            .unwrap_or(&BTreeMap::new())
-            .clone();
+            .validate()
+            .expect_err("Spec should be invalid");
+        let invalid: Vec<&str> = errors
+            .iter()
+            .filter_map(|error| match error {
+                SpecValidationError::Constraint(constraint) => Some(constraint.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(invalid, vec!["arch=amd64", "gpu", "!=amd64"]);
 
-        let spec_annotations_in_prev_but_not_in_current = utils::collect_missing_keys_btree(
-            &prev_spec_spec_annotations,
-            &current_spec_annotations,
-        );
-        let spec_annotations_in_dep_but_not_in_current = utils::collect_missing_keys_btree(
-            &deployment_spec_annotations,
-            &current_spec_annotations,
+        let pod_spec = PodSpec::try_from(&function.spec).expect("Failed to generate pod spec");
+        assert_eq!(
+            pod_spec.node_selector.map(|selector| selector.len()),
+            Some(1)
         );
-        let spec_annotations_in_current_but_not_dep = utils::collect_missing_keys_btree(
-            &current_spec_annotations,
-            &deployment_spec_annotations,
+    }
+
+    #[test]
+    fn rolling_update_defaults_and_overrides() {
+        let mut function = function();
+
+        let rolling_update = RollingUpdateDeployment::from(&function.spec);
+        assert_eq!(rolling_update.max_surge, Some(IntOrString::Int(1)));
+        assert_eq!(rolling_update.max_unavailable, Some(IntOrString::Int(0)));
+
+        function.spec.max_surge = Some(IntOrString::Int(0));
+        function.spec.max_unavailable = Some(IntOrString::String(String::from("25%")));
+        assert!(function.spec.validate().is_ok());
+
+        let rolling_update = RollingUpdateDeployment::from(&function.spec);
+        assert_eq!(rolling_update.max_surge, Some(IntOrString::Int(0)));
+        assert_eq!(
+            rolling_update.max_unavailable,
+            Some(IntOrString::String(String::from("25%")))
         );
-        tracing::debug!(
-            "Spec annotations in deployment but not in current spec: {:#?}",
-            spec_annotations_in_dep_but_not_in_current
+
+        function.spec.max_unavailable = Some(IntOrString::String(String::from("0%")));
+        assert!(matches!(
+            function.spec.validate().unwrap_err().as_slice(),
+            [SpecValidationError::RollingUpdateZero]
+        ));
+
+        function.spec.max_surge = Some(IntOrString::String(String::from("a quarter")));
+        assert!(matches!(
+            function.spec.validate().unwrap_err().as_slice(),
+            [SpecValidationError::RollingUpdateValue("maxSurge")]
+        ));
+    }
+
+    #[test]
+    fn recreate_strategy_has_no_rolling_update() {
+        let mut function = function();
+        function.spec.deployment_strategy_type = Some(String::from("Recreate"));
+        assert!(function.spec.validate().is_ok());
+
+        assert_eq!(
+            DeploymentStrategy::from(&function.spec),
+            DeploymentStrategy {
+                type_: Some(String::from("Recreate")),
+                rolling_update: None,
+            }
         );
-        tracing::debug!(
-            "Spec annotations to be added to deployment: {:#?}",
-            spec_annotations_in_current_but_not_dep
+
+        function.spec.max_surge = Some(IntOrString::Int(1));
+        let errors = function
+            .spec
+            .validate()
+            .expect_err("Spec should be invalid");
+        assert!(matches!(
+            errors.as_slice(),
+            [SpecValidationError::RollingUpdateWithRecreate]
+        ));
+
+        function.spec.deployment_strategy_type = Some(String::from("BlueGreen"));
+        let errors = function
+            .spec
+            .validate()
+            .expect_err("Spec should be invalid");
+        assert_eq!(
+            OpenFaasFunctionPossibleStatus::from(&errors[0]),
+            OpenFaasFunctionPossibleStatus::InvalidDeploymentStrategy
         );
-        tracing::debug!(
-            "Spec annotations to be removed from deployment: {:#?}",
-            spec_annotations_in_prev_but_not_in_current
+
+        let error = Deployment::try_from(&function).expect_err("Deployment should be invalid");
+        assert_eq!(
+            Option::<OpenFaasFunctionPossibleStatus>::from(&error),
+            Some(OpenFaasFunctionPossibleStatus::InvalidDeploymentStrategy)
         );
-        if !spec_annotations_in_prev_but_not_in_current.is_empty() {
-            tracing::debug!("Triggering replace");
-            replace = true;
-        }
+    }
 
-        // remove annotations that are in prev_spec but not in current
-        for annotation in spec_annotations_in_prev_but_not_in_current {
-            deployment_spec_annotations.remove(annotation);
-        }
-        // add annotations that are in current but not in deployment
-        deployment_spec_annotations.extend(current_spec_annotations);
-        tracing::debug!("Final spec annotations: {:#?}", deployment_spec_annotations);
+    #[test]
+    fn deployment_diffs_ignore_defaults_and_additions() {
+        let mut function = function();
+        function.spec.limits = Some(FunctionResources {
+            memory: Some(String::from("128Mi")),
+            cpu: Some(String::from("0.5")),
+        });
 
-        tracing::debug!("Checking constraints");
-        let current_node_selector = self.to_node_selector().unwrap_or_default();
-        let prev_spec_node_selector = prev_spec.to_node_selector().unwrap_or_default();
-        let mut deployment_node_selector = deployment
-            .spec
-            .as_ref()
-            .unwrap_or(&DeploymentSpec::default())
-            .template
+        let mut deployment =
+            Deployment::try_from(&function).expect("Failed to generate deployment");
+        assert!(function.spec.deployment_diffs(&deployment).is_empty());
+
+        // Canonicalized by the API server and added by others
+        let pod_spec = deployment
             .spec
-            .as_ref()
-            .unwrap_or(&PodSpec::default())
-            .node_selector
-            .as_ref()
-            .unwrap_or(&BTreeMap::new())
-            .clone();
+            .as_mut()
+            .and_then(|spec| spec.template.spec.as_mut())
+            .expect("No pod spec");
+        let container = &mut pod_spec.containers[0];
+        container.image = Some(String::from("ghcr.io/openfaas/nodeinfo:latest"));
+        container
+            .resources
+            .as_mut()
+            .and_then(|resources| resources.limits.as_mut())
+            .expect("No limits")
+            .insert(String::from("cpu"), Quantity(String::from("500m")));
+        container.termination_message_path = Some(String::from("/dev/termination-log"));
+        deployment
+            .metadata
+            .labels
+            .get_or_insert_with(BTreeMap::new)
+            .insert(String::from("team"), String::from("a"));
+        assert!(function.spec.deployment_diffs(&deployment).is_empty());
 
-        let node_selector_in_prev_but_not_in_current =
-            utils::collect_missing_keys_btree(&prev_spec_node_selector, &current_node_selector);
-        let node_selector_in_dep_but_not_in_current =
-            utils::collect_missing_keys_btree(&deployment_node_selector, &current_node_selector);
-        let node_selector_in_current_but_not_dep =
-            utils::collect_missing_keys_btree(&current_node_selector, &deployment_node_selector);
-        tracing::debug!(
-            "Node selector in deployment but not in current spec: {:#?}",
-            node_selector_in_dep_but_not_in_current
+        let pod_spec = deployment
+            .spec
+            .as_mut()
+            .and_then(|spec| spec.template.spec.as_mut())
+            .expect("No pod spec");
+        pod_spec.containers[0].image = Some(String::from("ghcr.io/openfaas/nodeinfo:edited"));
+        pod_spec.node_selector = Some(BTreeMap::from([(
+            String::from("disk"),
+            String::from("ssd"),
+        )]));
+        deployment
+            .metadata
+            .labels
+            .get_or_insert_with(BTreeMap::new)
+            .remove(FUNCTION_LABEL);
+
+        assert_eq!(
+            function.spec.deployment_diffs(&deployment),
+            vec![
+                DeploymentDiff::Labels,
+                DeploymentDiff::Constraints,
+                DeploymentDiff::Image
+            ]
         );
-        tracing::debug!(
-            "Node selector to be added to deployment: {:#?}",
-            node_selector_in_current_but_not_dep
+    }
+
+    #[test]
+    fn service_diffs_name_each_edited_part() {
+        let mut function = function();
+        let service = Service::try_from(&function).expect("Failed to generate service");
+        assert!(function.service_diffs(&service).is_empty());
+
+        let mut edited = service.clone();
+        let spec = edited.spec.as_mut().expect("No spec");
+        spec.ports.as_mut().expect("No ports")[0].port = 80;
+        assert_eq!(function.service_diffs(&edited), vec![ServiceDiff::Ports]);
+
+        let mut edited = service.clone();
+        let spec = edited.spec.as_mut().expect("No spec");
+        spec.selector = Some(BTreeMap::from([(
+            String::from("app"),
+            String::from("other"),
+        )]));
+        assert_eq!(function.service_diffs(&edited), vec![ServiceDiff::Selector]);
+
+        // A node port allocated by Kubernetes is not a diff
+        let mut allocated = service.clone();
+        let spec = allocated.spec.as_mut().expect("No spec");
+        spec.ports.as_mut().expect("No ports")[0].node_port = Some(30080);
+        assert!(function.service_diffs(&allocated).is_empty());
+
+        function.spec.service_type = Some(String::from("NodePort"));
+        assert_eq!(function.service_diffs(&allocated), vec![ServiceDiff::Type]);
+
+        // Annotations are compared with the spec the service was generated from
+        let mut function = self::function();
+        function.spec.service_annotations = Some(HashMap::from([(
+            String::from("lb.example.com/scheme"),
+            String::from("internal"),
+        )]));
+        let service = Service::try_from(&function).expect("Failed to generate service");
+        assert!(function.service_diffs(&service).is_empty());
+
+        let mut changed = function.clone();
+        changed.spec.service_annotations = Some(HashMap::from([(
+            String::from("lb.example.com/scheme"),
+            String::from("internet-facing"),
+        )]));
+        assert_eq!(
+            changed.service_diffs(&service),
+            vec![ServiceDiff::Annotations]
         );
-        tracing::debug!(
-            "Node selector to be removed from deployment: {:#?}",
-            node_selector_in_prev_but_not_in_current
+
+        let mut removed = function.clone();
+        removed.spec.service_annotations = None;
+        assert_eq!(
+            removed.service_diffs(&service),
+            vec![ServiceDiff::Annotations]
         );
-        if !node_selector_in_prev_but_not_in_current.is_empty() {
-            tracing::debug!("May trigger replace");
-            replace = true;
+
+        let patch = removed
+            .spec
+            .to_service_annotations_patch(&service)
+            .expect("Failed to generate patch");
+        assert_eq!(patch["lb.example.com/scheme"], serde_json::Value::Null);
+        assert!(patch.contains_key(LAST_APPLIED_ANNOTATION));
+    }
+
+    #[test]
+    fn deployment_diffs_name_each_drifted_part() {
+        let spec: OpenFaasFunctionSpec = serde_json::from_value(serde_json::json!({
+            "service": "nodeinfo",
+            "image": "ghcr.io/openfaas/nodeinfo:latest",
+            "envProcess": "node index.js",
+            "envVars": { "mode": "fast" },
+            "constraints": ["disk==ssd"],
+            "secrets": ["api-key"],
+            "labels": { "team": "a" },
+            "annotations": { "owner": "b" },
+            "limits": { "memory": "128Mi" },
+            "requests": { "cpu": "100m" },
+            "readOnlyRootFilesystem": true,
+        }))
+        .expect("Invalid spec");
+        let function = OpenFaaSFunction { spec, ..function() };
+
+        let deployment = Deployment::try_from(&function).expect("Failed to generate deployment");
+        assert!(function.spec.deployment_diffs(&deployment).is_empty());
+
+        fn pod_spec(deployment: &mut Deployment) -> &mut PodSpec {
+            deployment
+                .spec
+                .as_mut()
+                .and_then(|spec| spec.template.spec.as_mut())
+                .expect("No pod spec")
         }
-        // remove node selector that are in prev_spec but not in current
-        for node_selector in node_selector_in_prev_but_not_in_current {
-            deployment_node_selector.remove(node_selector);
+
+        fn container(deployment: &mut Deployment) -> &mut Container {
+            &mut pod_spec(deployment).containers[0]
         }
-        // add node selector that are in current but not in deployment
-        deployment_node_selector.extend(current_node_selector);
-        tracing::debug!("Final node selector: {:#?}", deployment_node_selector);
 
-        tracing::debug!("Checking containers");
-        tracing::debug!("Checking if container is missing");
-        let deployment_containers = deployment
-            .spec
-            .as_ref()
-            .unwrap_or(&DeploymentSpec::default())
-            .template
-            .spec
-            .as_ref()
-            .unwrap_or(&PodSpec::default())
-            .containers
-            .clone();
+        fn env_var<'a>(deployment: &'a mut Deployment, name: &str) -> &'a mut EnvVar {
+            container(deployment)
+                .env
+                .iter_mut()
+                .flatten()
+                .find(|env_var| env_var.name == name)
+                .expect("No env var")
+        }
 
-        let container_name = self.to_name();
+        fn resources(deployment: &mut Deployment) -> &mut ResourceRequirements {
+            container(deployment)
+                .resources
+                .as_mut()
+                .expect("No resources")
+        }
 
-        let deployment_container = deployment_containers
-            .iter()
-            .find(|c| c.name == container_name);
+        type Edit = fn(&mut Deployment);
 
-        match deployment_container {
-            None => {
-                tracing::debug!("Container is missing => recreate!");
-                return;
-            }
-            Some(deployment_container) => {
-                tracing::debug!("Checking image");
-                if deployment_container.image != Some(self.to_image()) {
-                    tracing::debug!("Image is different => recreate!");
-                    return;
-                }
+        let edits: Vec<(DeploymentDiff, Edit)> = vec![
+            (DeploymentDiff::Labels, |deployment| {
+                deployment.metadata.labels = None;
+            }),
+            (DeploymentDiff::Annotations, |deployment| {
+                deployment.metadata.annotations = None;
+            }),
+            (DeploymentDiff::Spec, |deployment| {
+                deployment.spec = None;
+            }),
+            (DeploymentDiff::PodLabels, |deployment| {
+                let spec = deployment.spec.as_mut().expect("No spec");
+                spec.template.metadata.as_mut().expect("No metadata").labels = None;
+            }),
+            (DeploymentDiff::PodAnnotations, |deployment| {
+                let spec = deployment.spec.as_mut().expect("No spec");
+                spec.template
+                    .metadata
+                    .as_mut()
+                    .expect("No metadata")
+                    .annotations = None;
+            }),
+            (DeploymentDiff::Paused, |deployment| {
+                deployment.spec.as_mut().expect("No spec").paused = Some(true);
+            }),
+            (DeploymentDiff::Constraints, |deployment| {
+                pod_spec(deployment).node_selector = None;
+            }),
+            (DeploymentDiff::Affinity, |deployment| {
+                pod_spec(deployment).affinity = Some(Affinity::default());
+            }),
+            (DeploymentDiff::Tolerations, |deployment| {
+                pod_spec(deployment).tolerations = Some(vec![Toleration::default()]);
+            }),
+            (DeploymentDiff::RuntimeClassName, |deployment| {
+                pod_spec(deployment).runtime_class_name = Some(String::from("gvisor"));
+            }),
+            (DeploymentDiff::Secrets, |deployment| {
+                pod_spec(deployment).volumes = None;
+            }),
+            (DeploymentDiff::Container, |deployment| {
+                container(deployment).name = String::from("sidecar");
+            }),
+            (DeploymentDiff::Image, |deployment| {
+                container(deployment).image =
+                    Some(String::from("ghcr.io/openfaas/nodeinfo:edited"));
+            }),
+            (DeploymentDiff::EnvProcess, |deployment| {
+                env_var(deployment, "fprocess").value = Some(String::from("node other.js"));
+            }),
+            (DeploymentDiff::EnvVars, |deployment| {
+                env_var(deployment, "mode").value = Some(String::from("slow"));
+            }),
+            (DeploymentDiff::ReadOnlyRootFilesystem, |deployment| {
+                container(deployment).security_context = None;
+            }),
+            (DeploymentDiff::Limits, |deployment| {
+                resources(deployment).limits = None;
+            }),
+            (DeploymentDiff::Requests, |deployment| {
+                resources(deployment).requests = None;
+            }),
+        ];
+
+        for (diff, edit) in edits {
+            let mut edited = deployment.clone();
+            edit(&mut edited);
+            assert_eq!(
+                function.spec.deployment_diffs(&edited),
+                vec![diff],
+                "Unexpected diffs for {diff:?}"
+            );
+        }
+    }
 
-                tracing::debug!("Checking env vars");
-                let current_env_vars = Option::<Vec<EnvVar>>::from(self).unwrap_or_default();
-                let prev_spec_env_vars =
-                    Option::<Vec<EnvVar>>::from(&prev_spec).unwrap_or_default();
-                let deployment_env_vars = deployment_container.env.clone().unwrap_or_default();
+    #[test]
+    fn unowned_optional_resource_conflicts_only_if_requested() {
+        assert_eq!(
+            OptionalResourcePlan::new(false, Some(false), false),
+            OptionalResourcePlan::NotRequested
+        );
+        assert_eq!(
+            OptionalResourcePlan::new(true, Some(false), true),
+            OptionalResourcePlan::Conflict
+        );
+        assert_eq!(
+            OptionalResourcePlan::new(false, Some(true), true),
+            OptionalResourcePlan::Delete
+        );
+        assert_eq!(
+            OptionalResourcePlan::new(false, None, false),
+            OptionalResourcePlan::NotRequested
+        );
+        assert_eq!(
+            OptionalResourcePlan::new(true, None, false),
+            OptionalResourcePlan::Create
+        );
+        assert_eq!(
+            OptionalResourcePlan::new(true, Some(true), false),
+            OptionalResourcePlan::Replace
+        );
+        assert_eq!(
+            OptionalResourcePlan::new(true, Some(true), true),
+            OptionalResourcePlan::UpToDate
+        );
+    }
 
-                let env_vars_in_prev_but_not_in_current =
-                    utils::collect_missing_keys_vec(&prev_spec_env_vars, &current_env_vars);
-                let env_vars_in_dep_but_not_in_current =
-                    utils::collect_missing_keys_vec(&deployment_env_vars, &current_env_vars);
-                let env_vars_in_current_but_not_dep =
-                    utils::collect_missing_keys_vec(&current_env_vars, &deployment_env_vars);
-                tracing::debug!(
-                    "Env vars in deployment but not in current spec: {:#?}",
-                    env_vars_in_dep_but_not_in_current
-                );
-                tracing::debug!(
-                    "Env vars to be added to deployment: {:#?}",
-                    env_vars_in_current_but_not_dep
-                );
-                tracing::debug!(
-                    "Env vars to be removed from deployment: {:#?}",
-                    env_vars_in_prev_but_not_in_current
-                );
-                // // remove env vars that are in prev_spec but not in current
-                // for env_var in env_vars_in_prev_but_not_in_current {
-                //     deployment_env_vars.retain(|e| e.name != env_var.name);
-                // }
-                // // add env vars that are in current but not in deployment
-                // deployment_env_vars.extend(current_env_vars);
-                // tracing::debug!("Final env vars: {:#?}", deployment_env_vars);
+    #[test]
+    fn network_policy_with_api_server_defaults_is_up_to_date() {
+        let mut spec = function().spec;
+        spec.network_policy = Some(
+            serde_json::from_value(serde_json::json!({
+                "egress": [{
+                    "to": [{"namespaceSelector": {"matchLabels": {}}}],
+                    "ports": [{"port": 53, "protocol": "UDP"}, {"port": 443}]
+                }]
+            }))
+            .expect("Invalid network policy"),
+        );
 
-                tracing::debug!("Checking read only root filesystem");
-                if deployment_container
-                    .security_context
-                    .as_ref()
-                    .unwrap_or(&SecurityContext::default())
-                    .read_only_root_filesystem
-                    != self.read_only_root_filesystem
-                {
-                    tracing::debug!("Read only root filesystem is different => recreate!");
-                    return;
-                }
-                tracing::debug!("Checking limits");
-                let current_limits = self.try_to_limits().unwrap_or_default().unwrap_or_default();
-                let deployment_limits = deployment_container
-                    .resources
-                    .as_ref()
-                    .unwrap_or(&ResourceRequirements::default())
-                    .limits
-                    .as_ref()
-                    .unwrap_or(&BTreeMap::new())
-                    .clone();
+        let desired = Option::<NetworkPolicySpec>::from(&spec).expect("Missing network policy");
 
-                if current_limits != deployment_limits {
-                    tracing::debug!("Limits are different!");
-                }
+        let mut stored = desired.clone();
+        let egress = &mut stored.egress.as_mut().expect("Missing egress")[0];
+        egress.ports.as_mut().expect("Missing ports")[1].protocol = Some(String::from("TCP"));
+        egress.to.as_mut().expect("Missing peers")[0]
+            .namespace_selector
+            .as_mut()
+            .expect("Missing selector")
+            .match_labels = None;
 
-                tracing::debug!("Checking requests");
-                let current_requests = self
-                    .try_to_requests()
-                    .unwrap_or_default()
-                    .unwrap_or_default();
-                let deployment_requests = deployment_container
-                    .resources
-                    .as_ref()
-                    .unwrap_or(&ResourceRequirements::default())
-                    .requests
-                    .as_ref()
-                    .unwrap_or(&BTreeMap::new())
-                    .clone();
+        assert_ne!(desired, stored);
+        assert!(spec.network_policy_is_up_to_date(&NetworkPolicy {
+            spec: Some(stored.clone()),
+            ..Default::default()
+        }));
 
-                if current_requests != deployment_requests {
-                    tracing::debug!("Requests are different!");
-                }
-            }
-        }
+        stored.egress.as_mut().expect("Missing egress")[0]
+            .ports
+            .as_mut()
+            .expect("Missing ports")[0]
+            .port = Some(IntOrString::Int(5353));
 
-        if replace {
-            tracing::debug!("Deployment needs to be replaced");
-        } else {
-            tracing::debug!("Deployment does not need to be replaced");
-        }
+        assert!(!spec.network_policy_is_up_to_date(&NetworkPolicy {
+            spec: Some(stored),
+            ..Default::default()
+        }));
     }
 }
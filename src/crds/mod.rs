@@ -1,2 +1,3 @@
 pub mod defs;
+mod example;
 mod impls;
@@ -7,48 +7,240 @@ use openfaas_functions_operato_rs::docker_actions::*;
 use openfaas_functions_operato_rs::main_actions::*;
 use openfaas_functions_operato_rs::{
     cli::{
-        Cli, Commands, CrdCommands, CrdConvertCommands, OperatorCommands, OperatorDeployCommands,
-        OperatorSubCommands,
+        Cli, ClientSubCommands, Commands, CrdCommands, CrdConvertCommands, LogFormat,
+        OperatorCommands, OperatorDeployCommands, OperatorSubCommands,
     },
     consts::PKG_NAME,
-    operator::controller::deplyoment::DeploymentBuilder,
+    operator::{
+        client::openfaas_client::{client::OpenFaaSClientConfig, request::functions::Invocation},
+        controller::{deplyoment::DeploymentBuilder, preserve::PreserveFields, OperatorConfig},
+    },
+};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    runtime,
+    trace::{self, Sampler},
+    Resource,
 };
+use std::time::Duration;
 use tracing::{trace_span, Instrument};
-use tracing_subscriber::EnvFilter;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+use url::Url;
 
-fn init_tracing() {
+fn init_tracing(
+    quiet: bool,
+    log_format: LogFormat,
+    json_logs_include_span_list: bool,
+    otlp_endpoint: Option<Url>,
+    trace_sample_rate: f64,
+) -> AnyResult<()> {
     if std::env::var_os("RUST_LOG").is_none() {
-        std::env::set_var("RUST_LOG", format!("{PKG_NAME}=info,kube=off"));
+        let level = if quiet { "warn" } else { "info" };
+        std::env::set_var("RUST_LOG", format!("{PKG_NAME}={level},kube=off"));
     }
 
-    tracing_subscriber::fmt()
+    let fmt_layer = tracing_subscriber::fmt::layer()
         //.with_span_events(tracing_subscriber::fmt::format::FmtSpan::ACTIVE)
         //.with_line_number(true)
         .with_target(false)
         .with_timer(tracing_subscriber::fmt::time::UtcTime::rfc_3339())
-        .with_level(true)
-        .with_ansi(true)
-        .with_env_filter(EnvFilter::from_default_env())
+        .with_level(true);
+
+    let fmt_layer = match log_format {
+        LogFormat::Text => fmt_layer.with_ansi(true).boxed(),
+        LogFormat::Json => fmt_layer
+            .json()
+            .with_current_span(true)
+            .with_span_list(json_logs_include_span_list)
+            .boxed(),
+    };
+
+    let otlp_layer = otlp_endpoint
+        .map(|otlp_endpoint| {
+            // Only a fraction of the root spans (reconciliations) is sampled, child spans follow their parent
+            let sampler =
+                Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(trace_sample_rate)));
+
+            let tracer =
+                opentelemetry_otlp::new_pipeline()
+                    .tracing()
+                    .with_exporter(
+                        opentelemetry_otlp::new_exporter()
+                            .tonic()
+                            .with_endpoint(otlp_endpoint.as_str()),
+                    )
+                    .with_trace_config(trace::config().with_sampler(sampler).with_resource(
+                        Resource::new(vec![KeyValue::new("service.name", PKG_NAME)]),
+                    ))
+                    .install_batch(runtime::Tokio)
+                    .context("Failed to install OTLP pipeline")?;
+
+            Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+        })
+        .transpose()?;
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::from_default_env())
+        .with(fmt_layer)
+        .with(otlp_layer)
         .init();
+
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() -> AnyResult<()> {
     let cli = Cli::parse();
 
-    init_tracing();
+    init_tracing(
+        cli.quiet,
+        cli.log_format.clone(),
+        cli.json_logs_include_span_list,
+        cli.otlp_endpoint.clone(),
+        cli.trace_sample_rate,
+    )?;
 
     match cli.command {
         Commands::Operator { command } => match *command {
             OperatorCommands::Controller {
                 functions_namespace,
+                all_namespaces,
+                functions_namespaces,
+                enable_leader_election,
+                leader_election_id,
                 update_strategy,
+                update_mode,
+                require_namespace,
+                require_rbac,
+                max_reconcile_duration,
+                secrets_namespace,
+                metrics_addr,
+                no_image_normalization,
+                default_pod_labels,
+                image_pull_secrets_service_account,
+                verify_runtime_class,
+                reconcile_concurrency,
+                reconcile_debounce_millis,
+                reconcile_jitter_startup,
+                watch_buffer_size,
+                max_env_bytes,
+                adopt_orphans,
+                preserve_fields,
+                preserve_annotations,
+                wait_for_ready_on_rename,
+                error_requeue_seconds,
+                max_error_requeue_seconds,
                 command,
-            } => match command {
+            } => match *command {
                 OperatorSubCommands::Run {} => {
-                    print_disply_name();
+                    if !cli.quiet {
+                        print_disply_name();
+                    }
+
+                    let config = OperatorConfig {
+                        functions_namespace,
+                        update_strategy,
+                        update_mode,
+                        field_manager: cli.field_manager,
+                        max_reconcile_duration: max_reconcile_duration.map(Duration::from_secs),
+                        secrets_namespace,
+                        normalize_image_references: !no_image_normalization,
+                        default_pod_labels: default_pod_labels.into_iter().collect(),
+                        image_pull_secrets_service_account,
+                        verify_runtime_class,
+                        reconcile_concurrency,
+                        reconcile_debounce: Duration::from_millis(reconcile_debounce_millis),
+                        reconcile_jitter_startup: Duration::from_secs(reconcile_jitter_startup),
+                        watch_buffer_size,
+                        max_env_bytes,
+                        adopt_orphans,
+                        preserve_fields: PreserveFields {
+                            fields: preserve_fields,
+                            annotation_prefixes: preserve_annotations,
+                        },
+                        wait_for_ready_on_rename,
+                        error_requeue: Duration::from_secs(error_requeue_seconds),
+                        max_error_requeue: Duration::from_secs(max_error_requeue_seconds),
+                        all_namespaces,
+                        functions_namespaces,
+                        leader_election_id: enable_leader_election.then_some(leader_election_id),
+                    };
+
+                    create_and_run_operator_controller(
+                        config,
+                        require_namespace,
+                        require_rbac,
+                        metrics_addr,
+                    )
+                    .instrument(trace_span!("Operator"))
+                    .await?;
+                }
+                OperatorSubCommands::ReconcileOnce { name } => {
+                    let config = OperatorConfig {
+                        functions_namespace,
+                        update_strategy,
+                        update_mode,
+                        field_manager: cli.field_manager,
+                        max_reconcile_duration: None,
+                        secrets_namespace,
+                        normalize_image_references: !no_image_normalization,
+                        default_pod_labels: default_pod_labels.into_iter().collect(),
+                        image_pull_secrets_service_account,
+                        verify_runtime_class,
+                        reconcile_concurrency,
+                        reconcile_debounce: Duration::from_millis(reconcile_debounce_millis),
+                        reconcile_jitter_startup: Duration::ZERO,
+                        watch_buffer_size,
+                        max_env_bytes,
+                        adopt_orphans,
+                        preserve_fields: PreserveFields {
+                            fields: preserve_fields,
+                            annotation_prefixes: preserve_annotations,
+                        },
+                        wait_for_ready_on_rename,
+                        error_requeue: Duration::from_secs(error_requeue_seconds),
+                        max_error_requeue: Duration::from_secs(max_error_requeue_seconds),
+                        all_namespaces,
+                        functions_namespaces,
+                        leader_election_id: None,
+                    };
 
-                    create_and_run_operator_controller(functions_namespace, update_strategy)
+                    reconcile_operator_controller_once(config, name)
+                        .instrument(trace_span!("Operator"))
+                        .await?;
+                }
+                OperatorSubCommands::Migrate { dry_run } => {
+                    let config = OperatorConfig {
+                        functions_namespace,
+                        update_strategy,
+                        update_mode,
+                        field_manager: cli.field_manager,
+                        max_reconcile_duration: None,
+                        secrets_namespace,
+                        normalize_image_references: !no_image_normalization,
+                        default_pod_labels: default_pod_labels.into_iter().collect(),
+                        image_pull_secrets_service_account,
+                        verify_runtime_class,
+                        reconcile_concurrency,
+                        reconcile_debounce: Duration::from_millis(reconcile_debounce_millis),
+                        reconcile_jitter_startup: Duration::ZERO,
+                        watch_buffer_size,
+                        max_env_bytes,
+                        adopt_orphans,
+                        preserve_fields: PreserveFields {
+                            fields: preserve_fields,
+                            annotation_prefixes: preserve_annotations,
+                        },
+                        wait_for_ready_on_rename,
+                        error_requeue: Duration::from_secs(error_requeue_seconds),
+                        max_error_requeue: Duration::from_secs(max_error_requeue_seconds),
+                        all_namespaces,
+                        functions_namespaces,
+                        leader_election_id: None,
+                    };
+
+                    migrate_operator_controller(config, dry_run)
                         .instrument(trace_span!("Operator"))
                         .await?;
                 }
@@ -65,22 +257,38 @@ async fn main() -> AnyResult<()> {
                         functions_namespace.clone(),
                         image,
                         update_strategy,
-                    );
-
-                    let yaml = deployment_builder.to_yaml_string()?;
+                        all_namespaces,
+                        functions_namespaces,
+                        verify_runtime_class,
+                    )
+                    .with_secrets_namespace(secrets_namespace);
 
                     match command {
-                        OperatorDeployCommands::Write { file } => {
+                        // --split and --dir require each other
+                        OperatorDeployCommands::Write { dir: Some(dir), .. } => {
+                            write_operator_controller_resources_to_dir(&deployment_builder, dir)
+                                .await?
+                        }
+                        OperatorDeployCommands::Write { file, .. } => {
+                            let yaml = deployment_builder.to_yaml_string()?;
+                            let file = file.context("No file to write resources to")?;
+
                             tokio::fs::write(file, yaml)
                                 .await
                                 .context("Failed to write resources to file")?;
                         }
                         OperatorDeployCommands::Print {} => {
+                            let yaml = deployment_builder.to_yaml_string()?;
+
                             println!("{}", yaml);
                         }
                         OperatorDeployCommands::Install {} => {
-                            install_operator_controller(deployment_builder, functions_namespace)
-                                .await?
+                            install_operator_controller(
+                                deployment_builder,
+                                functions_namespace,
+                                cli.field_manager,
+                            )
+                            .await?
                         }
                         OperatorDeployCommands::Uninstall {} => {
                             uninstall_operator_controller(deployment_builder, functions_namespace)
@@ -92,31 +300,192 @@ async fn main() -> AnyResult<()> {
                     }
                 }
             },
-            OperatorCommands::Client { .. } => {
-                unimplemented!("Client mode is not implemented yet");
+            OperatorCommands::Client {
+                gateway_url,
+                username,
+                password,
+                username_file,
+                password_file,
+                retries,
+                timeout_seconds,
+                retry_backoff_millis,
+                ca_cert_path,
+                accept_invalid_certs,
+                command,
+            } => {
+                let client_config = OpenFaaSClientConfig {
+                    timeout: Duration::from_secs(timeout_seconds),
+                    retries,
+                    retry_backoff: Duration::from_millis(retry_backoff_millis),
+                    ca_cert_path,
+                    accept_invalid_certs,
+                };
+
+                match *command {
+                    ClientSubCommands::Run {
+                        functions_namespace,
+                    } => {
+                        if !cli.quiet {
+                            print_disply_name();
+                        }
+
+                        let client = create_openfaas_client(
+                            gateway_url,
+                            username,
+                            password,
+                            username_file,
+                            password_file,
+                            client_config,
+                        )
+                        .await?;
+
+                        create_and_run_operator_client(client, functions_namespace)
+                            .instrument(trace_span!("Operator"))
+                            .await?;
+                    }
+                    ClientSubCommands::StatusAll { output } => {
+                        let client = create_openfaas_client(
+                            gateway_url,
+                            username,
+                            password,
+                            username_file,
+                            password_file,
+                            client_config,
+                        )
+                        .await?;
+
+                        print_functions_health(client, output).await?;
+                    }
+                    ClientSubCommands::Deploy {
+                        crd_file,
+                        create_namespace,
+                        expected_arch,
+                    } => {
+                        let crds = read_crds_from_file(crd_file).await?;
+
+                        if let Some(expected_arch) = expected_arch {
+                            check_nodes_architecture(&expected_arch).await;
+                        }
+
+                        let client = create_openfaas_client(
+                            gateway_url,
+                            username,
+                            password,
+                            username_file,
+                            password_file,
+                            client_config,
+                        )
+                        .await?;
+
+                        deploy_functions(client, crds, create_namespace).await?;
+                    }
+                    ClientSubCommands::Invoke {
+                        name,
+                        namespace,
+                        data,
+                        is_async,
+                        callback_url,
+                    } => {
+                        let client = create_openfaas_client(
+                            gateway_url,
+                            username,
+                            password,
+                            username_file,
+                            password_file,
+                            client_config,
+                        )
+                        .await?;
+
+                        let invocation = match is_async {
+                            true => Invocation::Async { callback_url },
+                            false => Invocation::Sync,
+                        };
+
+                        invoke_function(client, name, namespace, data, invocation).await?;
+                    }
+                    ClientSubCommands::RotateSecret {
+                        function,
+                        namespace,
+                        functions_namespace,
+                        secret,
+                        from_file,
+                        crd_file,
+                    } => {
+                        let client = create_openfaas_client(
+                            gateway_url,
+                            username,
+                            password,
+                            username_file,
+                            password_file,
+                            client_config,
+                        )
+                        .await?;
+
+                        // The operator watches the functions namespace, the gateway falls back to its own default
+                        let namespace =
+                            namespace.or(crd_file.is_none().then_some(functions_namespace));
+
+                        rotate_secret(
+                            client,
+                            function,
+                            namespace,
+                            secret,
+                            from_file,
+                            crd_file,
+                            cli.field_manager,
+                        )
+                        .await?;
+                    }
+                }
             }
         },
         Commands::Crd { command } => match command {
             CrdCommands::Write { file } => {
                 write_crd_to_file(file).await?;
             }
-            CrdCommands::Print {} => print_crd()?,
+            CrdCommands::Print {
+                helm,
+                helm_release_name,
+                helm_release_namespace,
+            } => match helm {
+                true => print_helm_crd(helm_release_name.zip(helm_release_namespace))?,
+                false => print_crd()?,
+            },
+            CrdCommands::Example {} => print_crd_example()?,
             CrdCommands::Install {} => {
-                install_crd().await?;
+                install_crd(cli.field_manager).await?;
             }
             CrdCommands::Uninstall {} => {
                 uninstall_crd().await?;
             }
             CrdCommands::Update {} => unimplemented!("Update is not implemented yet"),
+            CrdCommands::Validate { crd_file } => {
+                let crds = read_crds_from_file(crd_file).await?;
+                validate_crds(crds)?;
+            }
+            CrdCommands::PrintReconcilePlan {
+                crd_file,
+                deployment_file,
+            } => {
+                let crds = read_crds_from_file(crd_file).await?;
+                let deployments = match deployment_file {
+                    Some(deployment_file) => read_deployments_from_file(deployment_file).await?,
+                    None => vec![],
+                };
+
+                print_reconcile_plan(crds, deployments)?;
+            }
             CrdCommands::Convert { crd_file, command } => {
-                let crd = read_crd_from_file(crd_file).await?;
+                let crds = read_crds_from_file(crd_file).await?;
                 match command {
                     CrdConvertCommands::Write { resource_file } => {
-                        write_crd_resources_to_file(resource_file, crd).await?
+                        write_crd_resources_to_file(resource_file, crds).await?
+                    }
+                    CrdConvertCommands::Print {} => print_crd_resources(crds)?,
+                    CrdConvertCommands::Apply {} => {
+                        apply_crd_resources(crds, cli.field_manager).await?
                     }
-                    CrdConvertCommands::Print {} => print_crd_resources(crd)?,
-                    CrdConvertCommands::Apply {} => apply_crd_resources(crd).await?,
-                    CrdConvertCommands::Delete {} => delete_crd_resources(crd).await?,
+                    CrdConvertCommands::Delete {} => delete_crd_resources(crds).await?,
                 }
             }
         },
@@ -144,5 +513,8 @@ async fn main() -> AnyResult<()> {
         }
     }
 
+    // Export the remaining spans
+    opentelemetry::global::shutdown_tracer_provider();
+
     Ok(())
 }
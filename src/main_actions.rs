@@ -1,27 +1,60 @@
 use crate::{
+    cli::OutputFormat,
     consts::{DEFAULT_IMAGE_WITHOUT_TAG, DISPLAY_NAME},
-    crds::defs::{OpenFaaSFunction, NAME},
-    operator::controller::{deplyoment::DeploymentBuilder, Operator, UpdateStrategy},
+    crds::defs::{
+        OpenFaaSFunction, OpenFaasFunctionPossibleStatus, ResourceOwnership, DEFAULT_MAX_ENV_BYTES,
+        RESTARTED_AT_ANNOTATION,
+    },
+    operator::{
+        client::{
+            controller::GatewayOperator,
+            openfaas_client::{
+                client::{BasicAuth, OpenFaaSCleint, OpenFaaSClientConfig},
+                request::{
+                    functions::{FunctionDeployment, FunctionHealth, FunctionStatus, Invocation},
+                    secrets::SecretRequest,
+                },
+            },
+        },
+        controller::{
+            deplyoment::DeploymentBuilder, leader::LeaderElection, rbac::check_permissions,
+            server::MetricsServer, Operator, OperatorConfig,
+        },
+    },
 };
-use anyhow::{Context, Ok, Result as AnyResult};
+use anyhow::{anyhow, Context, Ok, Result as AnyResult};
 use cfonts::{say, Colors, Fonts, Options};
 use convert_case::{Case, Casing};
 use either::Either::Left;
+use itertools::Itertools;
 use k8s_openapi::{
     api::{
         apps::v1::Deployment,
-        core::v1::{Service, ServiceAccount},
-        rbac::v1::{Role, RoleBinding},
+        core::v1::{ConfigMap, Namespace, Node, Secret, Service, ServiceAccount},
+        networking::v1::NetworkPolicy,
+        policy::v1::PodDisruptionBudget,
+        rbac::v1::{ClusterRole, ClusterRoleBinding, Role, RoleBinding},
     },
     apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition,
 };
 use kube::{
-    api::{DeleteParams, PostParams},
+    api::{DeleteParams, ListParams, Patch, PatchParams, PostParams},
+    core::ObjectMeta,
     runtime::{conditions, wait::await_condition},
-    Api, Client as KubeClient, CustomResourceExt, ResourceExt,
+    Api, Client as KubeClient, CustomResourceExt, Error as KubeError, ResourceExt,
 };
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::{net::SocketAddr, path::PathBuf};
+use tokio::{io::AsyncReadExt, sync::oneshot};
 use tracing::{trace_span, Instrument};
+use url::Url;
+
+fn post_params(field_manager: String) -> PostParams {
+    PostParams {
+        field_manager: Some(field_manager),
+        ..Default::default()
+    }
+}
 
 pub fn print_disply_name() {
     say(Options {
@@ -33,21 +66,160 @@ pub fn print_disply_name() {
 }
 
 pub async fn create_and_run_operator_controller(
+    config: OperatorConfig,
+    require_namespace: bool,
+    require_rbac: bool,
+    metrics_addr: SocketAddr,
+) -> AnyResult<()> {
+    let client = KubeClient::try_default().await?;
+
+    tracing::info!(?config, %require_namespace, %require_rbac, %metrics_addr, "Running with current config.");
+
+    check_permissions(client.clone(), &config, require_rbac)
+        .instrument(trace_span!("CheckPermissions"))
+        .await
+        .context("Failed to check permissions")?;
+
+    let span = trace_span!("Create", functions_namespace = %config.functions_namespace);
+
+    let leader_election = config.leader_election_id.clone().map(|leader_election_id| {
+        LeaderElection::new(
+            client.clone(),
+            &config.functions_namespace,
+            leader_election_id,
+            config.field_manager.clone(),
+        )
+    });
+
+    let operator = Operator::new_with_check_functions_namespace(client, config, require_namespace)
+        .instrument(span)
+        .await
+        .context("Failed to create operator")?;
+
+    // The metrics and health server is shut down once the controller terminates
+    let (metrics_shutdown, metrics_shutdown_signal) = oneshot::channel::<()>();
+    let metrics_server = tokio::spawn(
+        MetricsServer::new(
+            operator.metrics(),
+            operator.health(),
+            operator.functions_store(),
+            operator.events(),
+        )
+        .serve(metrics_addr, async {
+            let _ = metrics_shutdown_signal.await;
+        })
+        .instrument(trace_span!("MetricsServer")),
+    );
+
+    let result = run_operator_controller(operator, leader_election).await;
+
+    let _ = metrics_shutdown.send(());
+
+    let served = metrics_server
+        .await
+        .context("Metrics server panicked")
+        .and_then(|served| served.context("Metrics server failed"));
+
+    if let Err(error) = served {
+        tracing::error!(%error, "Metrics server stopped.");
+    }
+
+    result
+}
+
+/// Runs the controller, only while holding the lease if leader election is enabled
+async fn run_operator_controller(
+    operator: Operator,
+    leader_election: Option<LeaderElection>,
+) -> AnyResult<()> {
+    let Some(leader_election) = leader_election else {
+        operator.run(None).await;
+
+        return Ok(());
+    };
+
+    operator.health().set_standby();
+
+    leader_election
+        .acquire()
+        .instrument(trace_span!("LeaderElection"))
+        .await;
+
+    tokio::select! {
+        _ = operator.run(None) => {}
+        error = leader_election.hold().instrument(trace_span!("LeaderElection")) => {
+            return Err(error).context("Lost leadership");
+        }
+    }
+
+    // The controller shut down gracefully, hand over to the next replica right away
+    leader_election
+        .release()
+        .instrument(trace_span!("LeaderElection"))
+        .await;
+
+    Ok(())
+}
+
+/// Runs the operator in client mode, reconciling the functions in the given namespace through the gateway
+pub async fn create_and_run_operator_client(
+    openfaas_client: OpenFaaSCleint,
     functions_namespace: String,
-    update_strategy: UpdateStrategy,
 ) -> AnyResult<()> {
     let client = KubeClient::try_default().await?;
 
-    tracing::info!(%functions_namespace, %update_strategy, "Running with current config.");
+    tracing::info!(%functions_namespace, "Running in client mode.");
+
+    GatewayOperator::new(client, openfaas_client, &functions_namespace)
+        .run()
+        .await;
+
+    Ok(())
+}
+
+pub async fn reconcile_operator_controller_once(
+    config: OperatorConfig,
+    name: String,
+) -> AnyResult<()> {
+    let client = KubeClient::try_default().await?;
+
+    let operator = Operator::new(client, config);
 
-    let span = trace_span!("Create", %functions_namespace);
+    let (action, status) = operator
+        .reconcile_once(&name)
+        .await
+        .context("Failed to reconcile function")?;
 
-    let operator =
-        Operator::new_with_check_functions_namespace(client, functions_namespace, update_strategy)
-            .instrument(span)
-            .await;
+    println!("Action: {:?}", action);
 
-    operator.run().await;
+    match status {
+        Some(status) => println!(
+            "Status:\n{}",
+            serde_yaml::to_string(&status).context("Failed to serialize status")?
+        ),
+        None => println!("Status: None"),
+    }
+
+    Ok(())
+}
+
+pub async fn migrate_operator_controller(config: OperatorConfig, dry_run: bool) -> AnyResult<()> {
+    let client = KubeClient::try_default().await?;
+
+    let operator = Operator::new(client, config);
+
+    let outcomes = operator
+        .migrate_last_applied(dry_run)
+        .await
+        .context("Failed to migrate deployments")?;
+
+    for (name, outcome) in outcomes {
+        println!("{name}: {outcome}");
+    }
+
+    if dry_run {
+        println!("Dry run, no deployment was patched.");
+    }
 
     Ok(())
 }
@@ -59,47 +231,117 @@ pub fn determin_image(image_name: String, image_version: Option<String>) -> Stri
     }
 }
 
+pub async fn write_operator_controller_resources_to_dir(
+    deployment_builder: &DeploymentBuilder,
+    dir: PathBuf,
+) -> AnyResult<()> {
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .context("Failed to create directory")?;
+
+    for (file_name, yaml) in deployment_builder.to_yaml_documents()? {
+        let file = dir.join(file_name);
+
+        tokio::fs::write(&file, yaml)
+            .await
+            .with_context(|| format!("Failed to write resource to {}", file.display()))?;
+    }
+
+    Ok(())
+}
+
 pub async fn install_operator_controller(
     deployment_builder: DeploymentBuilder,
     functions_namespace: String,
+    field_manager: String,
 ) -> AnyResult<()> {
     let client = KubeClient::try_default().await?;
+    let post_params = post_params(field_manager);
 
     let service_account_api =
         Api::<ServiceAccount>::namespaced(client.clone(), &functions_namespace);
     let service_account = ServiceAccount::from(&deployment_builder);
 
-    let role_api = Api::<Role>::namespaced(client.clone(), &functions_namespace);
-    let role = Role::from(&deployment_builder);
-
-    let role_binding_api = Api::<RoleBinding>::namespaced(client.clone(), &functions_namespace);
-    let role_binding = RoleBinding::from(&deployment_builder);
-
-    let deployment_api = Api::<Deployment>::namespaced(client, &functions_namespace);
+    let deployment_api = Api::<Deployment>::namespaced(client.clone(), &functions_namespace);
     let deployment = Deployment::from(&deployment_builder);
 
     if let Err(error) = service_account_api
-        .create(&PostParams::default(), &service_account)
+        .create(&post_params, &service_account)
         .await
     {
         tracing::error!(%error, "Failed to create service account");
     }
 
-    if let Err(error) = role_api.create(&PostParams::default(), &role).await {
-        tracing::error!(%error, "Failed to create role");
+    if deployment_builder.is_cluster_scoped() {
+        let cluster_role_api = Api::<ClusterRole>::all(client.clone());
+        let cluster_role = ClusterRole::from(&deployment_builder);
+
+        let cluster_role_binding_api = Api::<ClusterRoleBinding>::all(client.clone());
+        let cluster_role_binding = ClusterRoleBinding::from(&deployment_builder);
+
+        if let Err(error) = cluster_role_api.create(&post_params, &cluster_role).await {
+            tracing::error!(%error, "Failed to create cluster role");
+        }
+
+        if let Err(error) = cluster_role_binding_api
+            .create(&post_params, &cluster_role_binding)
+            .await
+        {
+            tracing::error!(%error, "Failed to create cluster role binding");
+        }
+    } else {
+        let role_api = Api::<Role>::namespaced(client.clone(), &functions_namespace);
+        let role = Role::from(&deployment_builder);
+
+        let role_binding_api = Api::<RoleBinding>::namespaced(client.clone(), &functions_namespace);
+        let role_binding = RoleBinding::from(&deployment_builder);
+
+        if let Err(error) = role_api.create(&post_params, &role).await {
+            tracing::error!(%error, "Failed to create role");
+        }
+
+        if let Err(error) = role_binding_api.create(&post_params, &role_binding).await {
+            tracing::error!(%error, "Failed to create role binding");
+        }
     }
 
-    if let Err(error) = role_binding_api
-        .create(&PostParams::default(), &role_binding)
-        .await
-    {
-        tracing::error!(%error, "Failed to create role binding");
+    for (role, role_binding) in deployment_builder.to_secrets_roles() {
+        let role_api =
+            Api::<Role>::namespaced(client.clone(), &role.namespace().unwrap_or_default());
+        let role_binding_api = Api::<RoleBinding>::namespaced(
+            client.clone(),
+            &role_binding.namespace().unwrap_or_default(),
+        );
+
+        if let Err(error) = role_api.create(&post_params, &role).await {
+            tracing::error!(%error, "Failed to create secrets role");
+        }
+
+        if let Err(error) = role_binding_api.create(&post_params, &role_binding).await {
+            tracing::error!(%error, "Failed to create secrets role binding");
+        }
     }
 
-    if let Err(error) = deployment_api
-        .create(&PostParams::default(), &deployment)
-        .await
-    {
+    if deployment_builder.has_runtime_class_cluster_role() {
+        let cluster_role_api = Api::<ClusterRole>::all(client.clone());
+        let cluster_role = deployment_builder.to_runtime_class_cluster_role();
+
+        let cluster_role_binding_api = Api::<ClusterRoleBinding>::all(client);
+        let cluster_role_binding = deployment_builder.to_runtime_class_cluster_role_binding();
+
+        if let Err(error) = cluster_role_api.create(&post_params, &cluster_role).await {
+            tracing::error!(%error, "Failed to create runtime class cluster role");
+        }
+
+        if let Err(error) = cluster_role_binding_api
+            .create(&post_params, &cluster_role_binding)
+            .await
+        {
+            tracing::error!(%error, "Failed to create runtime class cluster role binding");
+        }
+    }
+
+    if let Err(error) = deployment_api.create(&post_params, &deployment).await {
         tracing::error!(%error, "Failed to create deployment");
     }
 
@@ -116,13 +358,10 @@ pub async fn uninstall_operator_controller(
         Api::<ServiceAccount>::namespaced(client.clone(), &functions_namespace);
     let service_account_name = deployment_builder.to_service_account_name();
 
-    let role_api = Api::<Role>::namespaced(client.clone(), &functions_namespace);
     let role_name = deployment_builder.to_role_name();
-
-    let role_binding_api = Api::<RoleBinding>::namespaced(client.clone(), &functions_namespace);
     let role_binding_name = deployment_builder.to_role_binding_name();
 
-    let deployment_api = Api::<Deployment>::namespaced(client, &functions_namespace);
+    let deployment_api = Api::<Deployment>::namespaced(client.clone(), &functions_namespace);
     let deployment_name = deployment_builder.to_deployment_name();
 
     if let Err(error) = service_account_api
@@ -132,15 +371,85 @@ pub async fn uninstall_operator_controller(
         tracing::error!(%error, "Failed to delete service account");
     }
 
-    if let Err(error) = role_api.delete(&role_name, &DeleteParams::default()).await {
-        tracing::error!(%error, "Failed to delete role");
+    if deployment_builder.is_cluster_scoped() {
+        let cluster_role_api = Api::<ClusterRole>::all(client.clone());
+        let cluster_role_binding_api = Api::<ClusterRoleBinding>::all(client.clone());
+
+        if let Err(error) = cluster_role_api
+            .delete(&role_name, &DeleteParams::default())
+            .await
+        {
+            tracing::error!(%error, "Failed to delete cluster role");
+        }
+
+        if let Err(error) = cluster_role_binding_api
+            .delete(&role_binding_name, &DeleteParams::default())
+            .await
+        {
+            tracing::error!(%error, "Failed to delete cluster role binding");
+        }
+    } else {
+        let role_api = Api::<Role>::namespaced(client.clone(), &functions_namespace);
+        let role_binding_api = Api::<RoleBinding>::namespaced(client.clone(), &functions_namespace);
+
+        if let Err(error) = role_api.delete(&role_name, &DeleteParams::default()).await {
+            tracing::error!(%error, "Failed to delete role");
+        }
+
+        if let Err(error) = role_binding_api
+            .delete(&role_binding_name, &DeleteParams::default())
+            .await
+        {
+            tracing::error!(%error, "Failed to delete role binding");
+        }
     }
 
-    if let Err(error) = role_binding_api
-        .delete(&role_binding_name, &DeleteParams::default())
-        .await
-    {
-        tracing::error!(%error, "Failed to delete role binding");
+    for (role, role_binding) in deployment_builder.to_secrets_roles() {
+        let role_api =
+            Api::<Role>::namespaced(client.clone(), &role.namespace().unwrap_or_default());
+        let role_binding_api = Api::<RoleBinding>::namespaced(
+            client.clone(),
+            &role_binding.namespace().unwrap_or_default(),
+        );
+
+        if let Err(error) = role_api
+            .delete(&role.name_any(), &DeleteParams::default())
+            .await
+        {
+            tracing::error!(%error, "Failed to delete secrets role");
+        }
+
+        if let Err(error) = role_binding_api
+            .delete(&role_binding.name_any(), &DeleteParams::default())
+            .await
+        {
+            tracing::error!(%error, "Failed to delete secrets role binding");
+        }
+    }
+
+    if deployment_builder.has_runtime_class_cluster_role() {
+        let cluster_role_api = Api::<ClusterRole>::all(client.clone());
+        let cluster_role_binding_api = Api::<ClusterRoleBinding>::all(client);
+
+        if let Err(error) = cluster_role_api
+            .delete(
+                &deployment_builder.to_runtime_class_role_name(),
+                &DeleteParams::default(),
+            )
+            .await
+        {
+            tracing::error!(%error, "Failed to delete runtime class cluster role");
+        }
+
+        if let Err(error) = cluster_role_binding_api
+            .delete(
+                &deployment_builder.to_runtime_class_role_binding_name(),
+                &DeleteParams::default(),
+            )
+            .await
+        {
+            tracing::error!(%error, "Failed to delete runtime class cluster role binding");
+        }
     }
 
     if let Err(error) = deployment_api
@@ -153,65 +462,530 @@ pub async fn uninstall_operator_controller(
     Ok(())
 }
 
-pub async fn apply_crd_resources(crd: OpenFaaSFunction) -> AnyResult<()> {
+/// Applies the resources of all functions in a deterministic, dependency aware order:
+/// namespaces, referenced secrets and config maps (checked only), deployments, services and pod disruption budgets.
+///
+/// Functions are ordered by namespace and name. All errors are collected and reported at the end.
+pub async fn apply_crd_resources(
+    mut crds: Vec<OpenFaaSFunction>,
+    field_manager: String,
+) -> AnyResult<()> {
     let client = KubeClient::try_default().await?;
+    let post_params = post_params(field_manager);
+    let default_namespace = client.default_namespace().to_string();
 
-    let deployment_api = Api::<Deployment>::all(client.clone());
-    let service_api = Api::<Service>::all(client);
+    crds.sort_by_key(|crd| {
+        (
+            crd.spec
+                .namespace
+                .clone()
+                .unwrap_or(default_namespace.clone()),
+            crd.spec.to_name(),
+        )
+    });
 
-    let deployment = Deployment::try_from(&crd.spec)?;
-    let service = Service::try_from(&crd.spec)?;
+    let mut errors: Vec<String> = vec![];
 
-    if let Err(error) = deployment_api
-        .create(&PostParams::default(), &deployment)
-        .await
+    let mut resources = vec![];
+    for crd in crds {
+        let name = crd.spec.to_name();
+        let namespace = crd
+            .spec
+            .namespace
+            .clone()
+            .unwrap_or(default_namespace.clone());
+
+        let generated = Deployment::try_from(&crd.spec)
+            .map_err(|error| error.to_string())
+            .and_then(|deployment| {
+                let service = Service::try_from(&crd.spec).map_err(|error| error.to_string())?;
+                let pdb_opt = Option::<PodDisruptionBudget>::try_from(&crd.spec)
+                    .map_err(|error| error.to_string())?;
+                let network_policy_opt = Option::<NetworkPolicy>::try_from(&crd.spec)
+                    .map_err(|error| error.to_string())?;
+
+                Result::Ok((deployment, service, pdb_opt, network_policy_opt))
+            });
+
+        match generated {
+            Result::Ok((deployment, service, pdb_opt, network_policy_opt)) => resources.push((
+                crd,
+                namespace,
+                deployment,
+                service,
+                pdb_opt,
+                network_policy_opt,
+            )),
+            Err(error) => errors.push(format!("{namespace}/{name}: {error}")),
+        }
+    }
+
+    let namespace_api = Api::<Namespace>::all(client.clone());
+    for namespace in resources
+        .iter()
+        .map(|(_, namespace, ..)| namespace.clone())
+        .unique()
     {
-        tracing::error!(%error, "Failed to create deployment");
+        let namespace_resource = Namespace {
+            metadata: ObjectMeta {
+                name: Some(namespace.clone()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        match namespace_api
+            .create(&post_params, &namespace_resource)
+            .await
+        {
+            Result::Ok(_) => tracing::info!(%namespace, "Namespace created."),
+            Err(KubeError::Api(response)) if response.code == 409 => {}
+            Err(error) => errors.push(format!("namespace {namespace}: {error}")),
+        }
     }
 
-    if let Err(error) = service_api.create(&PostParams::default(), &service).await {
-        tracing::error!(%error, "Failed to create service");
+    for (crd, namespace, ..) in resources.iter() {
+        let secret_api = Api::<Secret>::namespaced(client.clone(), namespace);
+        for secret in crd
+            .spec
+            .get_secrets_unique_vec()
+            .into_iter()
+            .chain(crd.spec.get_image_pull_secrets_unique_vec())
+        {
+            if let Some(error) = check_exists(&secret_api, &secret).await {
+                errors.push(format!("secret {namespace}/{secret}: {error}"));
+            }
+        }
+
+        let config_map_api = Api::<ConfigMap>::namespaced(client.clone(), namespace);
+        for config_map in crd.spec.get_config_maps_unique_vec() {
+            if let Some(error) = check_exists(&config_map_api, &config_map).await {
+                errors.push(format!("config map {namespace}/{config_map}: {error}"));
+            }
+        }
+    }
+
+    for (_, namespace, deployment, ..) in resources.iter() {
+        let deployment_api = Api::<Deployment>::namespaced(client.clone(), namespace);
+        if let Err(error) = deployment_api.create(&post_params, deployment).await {
+            errors.push(format!(
+                "deployment {namespace}/{}: {error}",
+                deployment.name_any()
+            ));
+        }
+    }
+
+    for (_, namespace, _, service, ..) in resources.iter() {
+        let service_api = Api::<Service>::namespaced(client.clone(), namespace);
+        if let Err(error) = service_api.create(&post_params, service).await {
+            errors.push(format!(
+                "service {namespace}/{}: {error}",
+                service.name_any()
+            ));
+        }
+    }
+
+    for (_, namespace, _, _, pdb_opt, _) in resources.iter() {
+        let Some(pdb) = pdb_opt else {
+            continue;
+        };
+
+        let pdb_api = Api::<PodDisruptionBudget>::namespaced(client.clone(), namespace);
+        if let Err(error) = pdb_api.create(&post_params, pdb).await {
+            errors.push(format!(
+                "pod disruption budget {namespace}/{}: {error}",
+                pdb.name_any()
+            ));
+        }
+    }
+
+    for (_, namespace, _, _, _, network_policy_opt) in resources.iter() {
+        let Some(network_policy) = network_policy_opt else {
+            continue;
+        };
+
+        let network_policy_api = Api::<NetworkPolicy>::namespaced(client.clone(), namespace);
+        if let Err(error) = network_policy_api
+            .create(&post_params, network_policy)
+            .await
+        {
+            errors.push(format!(
+                "network policy {namespace}/{}: {error}",
+                network_policy.name_any()
+            ));
+        }
+    }
+
+    if !errors.is_empty() {
+        for error in errors.iter() {
+            tracing::error!(%error, "Failed to apply resource.");
+        }
+
+        anyhow::bail!("Failed to apply {} resource(s)", errors.len());
     }
 
     Ok(())
 }
 
-pub async fn delete_crd_resources(crd: OpenFaaSFunction) -> AnyResult<()> {
+/// Returns an error message if the resource does not exist or can not be fetched
+async fn check_exists<K>(api: &Api<K>, name: &str) -> Option<String>
+where
+    K: kube::Resource + Clone + serde::de::DeserializeOwned + std::fmt::Debug,
+{
+    match api.get_opt(name).await {
+        Result::Ok(Some(_)) => None,
+        Result::Ok(None) => Some(String::from("referenced but does not exist")),
+        Err(error) => Some(error.to_string()),
+    }
+}
+
+pub async fn delete_crd_resources(crds: Vec<OpenFaaSFunction>) -> AnyResult<()> {
     let client = KubeClient::try_default().await?;
+    let default_namespace = client.default_namespace().to_string();
 
-    let deployment_api = Api::<Deployment>::all(client.clone());
-    let service_api = Api::<Service>::all(client);
+    for crd in crds {
+        let name = crd.spec.to_name();
+        let namespace = crd
+            .spec
+            .namespace
+            .clone()
+            .unwrap_or(default_namespace.clone());
 
-    let name = crd.spec.to_name();
+        let deployment_api = Api::<Deployment>::namespaced(client.clone(), &namespace);
+        let service_api = Api::<Service>::namespaced(client.clone(), &namespace);
+        let pdb_api = Api::<PodDisruptionBudget>::namespaced(client.clone(), &namespace);
+        let network_policy_api = Api::<NetworkPolicy>::namespaced(client.clone(), &namespace);
 
-    if let Err(error) = deployment_api.delete(&name, &DeleteParams::default()).await {
-        tracing::error!(%error, "Failed to delete deployment");
+        if let Err(error) = deployment_api.delete(&name, &DeleteParams::default()).await {
+            tracing::error!(%error, "Failed to delete deployment");
+        }
+
+        if let Err(error) = service_api.delete(&name, &DeleteParams::default()).await {
+            tracing::error!(%error, "Failed to delete service");
+        }
+
+        if crd.spec.pod_disruption_budget.is_some() {
+            if let Err(error) = pdb_api.delete(&name, &DeleteParams::default()).await {
+                tracing::error!(%error, "Failed to delete pod disruption budget");
+            }
+        }
+
+        if crd.spec.network_policy.is_some() {
+            if let Err(error) = network_policy_api
+                .delete(&name, &DeleteParams::default())
+                .await
+            {
+                tracing::error!(%error, "Failed to delete network policy");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn crds_resources_to_yaml_string(crds: &[OpenFaaSFunction]) -> AnyResult<String> {
+    let resources = crds
+        .iter()
+        .map(|crd| crd.spec.to_yaml_string())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(resources.join("---\n"))
+}
+
+/// Prints every problem of every function, fails if any function is invalid
+pub fn validate_crds(crds: Vec<OpenFaaSFunction>) -> AnyResult<()> {
+    let mut invalid = 0;
+
+    for crd in crds {
+        let name = crd.spec.to_name();
+
+        let mut errors = crd.spec.validate().err().unwrap_or_default();
+
+        if let Err(error) = crd.spec.validate_env_size(DEFAULT_MAX_ENV_BYTES) {
+            errors.push(error);
+        }
+
+        match errors.is_empty() {
+            true => println!("{name}: valid"),
+            false => {
+                invalid += 1;
+                println!("{name}: invalid");
+
+                for error in errors {
+                    println!("  - {error}");
+                }
+            }
+        }
     }
 
-    if let Err(error) = service_api.delete(&name, &DeleteParams::default()).await {
-        tracing::error!(%error, "Failed to delete service");
+    if invalid > 0 {
+        return Err(anyhow!("{invalid} function(s) invalid"));
     }
+
     Ok(())
 }
 
-pub fn print_crd_resources(crd: OpenFaaSFunction) -> AnyResult<()> {
-    println!("{}", crd.spec.to_yaml_string()?);
+pub fn print_crd_resources(crds: Vec<OpenFaaSFunction>) -> AnyResult<()> {
+    println!("{}", crds_resources_to_yaml_string(&crds)?);
     Ok(())
 }
 
-pub async fn write_crd_resources_to_file(file: PathBuf, crd: OpenFaaSFunction) -> AnyResult<()> {
-    tokio::fs::write(file, crd.spec.to_yaml_string()?)
+pub async fn write_crd_resources_to_file(
+    file: PathBuf,
+    crds: Vec<OpenFaaSFunction>,
+) -> AnyResult<()> {
+    tokio::fs::write(file, crds_resources_to_yaml_string(&crds)?)
         .await
         .context("Failed to write crd to file")?;
     Ok(())
 }
 
-pub async fn read_crd_from_file(path: PathBuf) -> AnyResult<OpenFaaSFunction> {
-    let crds = tokio::fs::read_to_string(path)
+/// Reads the whole file, or stdin if the path is `-`.
+pub async fn read_file_or_stdin(path: PathBuf) -> AnyResult<String> {
+    if path.as_os_str() == "-" {
+        let mut input = String::new();
+        tokio::io::stdin()
+            .read_to_string(&mut input)
+            .await
+            .context("Failed to read from stdin")?;
+
+        return Ok(input);
+    }
+
+    tokio::fs::read_to_string(path)
+        .await
+        .context("Failed to read file")
+}
+
+/// Reads all the crds of a possibly multi-document yaml file, or stdin if the path is `-`.
+pub async fn read_crds_from_file(path: PathBuf) -> AnyResult<Vec<OpenFaaSFunction>> {
+    let crds = read_file_or_stdin(path)
         .await
         .context("Failed to read crd from file")?;
-    let crd = serde_yaml::from_str(&crds).context("Failed to parse crd")?;
-    Ok(crd)
+
+    serde_yaml::Deserializer::from_str(&crds)
+        .enumerate()
+        .map(|(index, document)| {
+            OpenFaaSFunction::deserialize(document).map_err(|error| crd_parse_error(index, error))
+        })
+        .collect()
+}
+
+/// Reads all the deployments of a possibly multi-document yaml file, or stdin if the path is `-`.
+pub async fn read_deployments_from_file(path: PathBuf) -> AnyResult<Vec<Deployment>> {
+    let deployments = read_file_or_stdin(path)
+        .await
+        .context("Failed to read deployments from file")?;
+
+    serde_yaml::Deserializer::from_str(&deployments)
+        .map(|document| Deployment::deserialize(document).context("Failed to parse deployment"))
+        .collect()
+}
+
+/// Prints the checks, generated resources and drift decisions of a reconciliation, without access to the cluster
+pub fn print_reconcile_plan(
+    crds: Vec<OpenFaaSFunction>,
+    deployments: Vec<Deployment>,
+) -> AnyResult<()> {
+    for mut crd in crds {
+        let name = crd.spec.to_name();
+        println!("{name}:");
+
+        let offline = crd.metadata.uid.is_none();
+        if offline {
+            println!(
+                "  [skip] ownership: function has no uid, live resources are assumed to be owned"
+            );
+            // Owned resources can only be generated with an owner reference
+            crd.metadata.uid = Some(String::from("offline"));
+        }
+
+        match crd.spec.namespace {
+            Some(ref namespace) => println!(
+                "  [skip] namespace: {namespace} is not compared to the operator's functions namespace"
+            ),
+            None => println!("  [pass] namespace: defaults to the operator's functions namespace"),
+        }
+
+        let mut errors = crd.spec.validate().err().unwrap_or_default();
+        if let Err(error) = crd.spec.validate_env_size(DEFAULT_MAX_ENV_BYTES) {
+            errors.push(error);
+        }
+
+        if let Some(error) = errors.first() {
+            let status = OpenFaasFunctionPossibleStatus::from(error);
+            println!(
+                "  [fail] spec: {} => status {status:?}",
+                errors.iter().join("; ")
+            );
+            continue;
+        }
+        println!("  [pass] spec is valid");
+
+        let secrets: Vec<String> = crd
+            .spec
+            .get_secrets_unique_vec()
+            .into_iter()
+            .chain(crd.spec.get_image_pull_secrets_unique_vec())
+            .unique()
+            .collect();
+        if !secrets.is_empty() {
+            println!(
+                "  [skip] secrets: existence of {} not checked",
+                secrets.join(", ")
+            );
+        }
+
+        if let Some(ref config_map) = crd.spec.ca_bundle_config_map {
+            println!("  [skip] config maps: existence of {config_map} not checked");
+        }
+
+        if let Some(ref runtime_class) = crd.spec.runtime_class_name {
+            println!("  [skip] runtime class: existence of {runtime_class} not checked");
+        }
+
+        let deployment = match Deployment::try_from(&crd) {
+            Result::Ok(deployment) => deployment,
+            Err(error) => {
+                let status = Option::<OpenFaasFunctionPossibleStatus>::from(&error);
+                println!("  [fail] deployment: {error} => status {status:?}");
+                continue;
+            }
+        };
+
+        let replicas = deployment
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.replicas)
+            .unwrap_or_default();
+        println!(
+            "  [plan] deployment {name}: {replicas} replica(s) of {}",
+            crd.spec.image
+        );
+
+        let live_deployment = deployments
+            .iter()
+            .find(|deployment| deployment.metadata.name.as_ref() == Some(&name));
+
+        match live_deployment {
+            None => println!("  [plan] deployment does not exist => create"),
+            Some(live_deployment) => {
+                let ownership = match offline {
+                    true => ResourceOwnership::Owned,
+                    false => crd.ownership(live_deployment.owner_references()),
+                };
+
+                match ownership {
+                    ResourceOwnership::Owned => print_deployment_drift(&crd, live_deployment),
+                    ResourceOwnership::OwnedByAnotherFunction(owner) => {
+                        println!(
+                            "  [fail] deployment is owned by function {owner} => status {:?}",
+                            OpenFaasFunctionPossibleStatus::OwnedByAnotherFunction
+                        );
+                        continue;
+                    }
+                    ResourceOwnership::ThirdParty if crd.can_adopt(&live_deployment.metadata) => {
+                        println!("  [plan] deployment has no owner reference => adopted with --adopt-orphans");
+                    }
+                    ResourceOwnership::ThirdParty => {
+                        println!(
+                            "  [fail] deployment has no owner reference => status {:?}",
+                            OpenFaasFunctionPossibleStatus::DeploymentAlreadyExists
+                        );
+                        continue;
+                    }
+                }
+            }
+        }
+
+        match Service::try_from(&crd) {
+            Result::Ok(service) => println!(
+                "  [plan] service {name}: selecting {}",
+                service
+                    .spec
+                    .and_then(|spec| spec.selector)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .join(", ")
+            ),
+            Err(error) => {
+                let status = Option::<OpenFaasFunctionPossibleStatus>::from(&error);
+                println!("  [fail] service: {error} => status {status:?}");
+                continue;
+            }
+        }
+
+        if let Result::Ok(Some(_)) = Option::<PodDisruptionBudget>::try_from(&crd) {
+            println!("  [plan] pod disruption budget {name}");
+        }
+
+        if let Result::Ok(Some(_)) = Option::<NetworkPolicy>::try_from(&crd) {
+            println!("  [plan] network policy {name}");
+        }
+
+        println!("  => status Ok once the deployment is ready");
+    }
+
+    Ok(())
+}
+
+/// Prints how the operator would update an owned deployment under the one-way update strategy
+fn print_deployment_drift(crd: &OpenFaaSFunction, live_deployment: &Deployment) {
+    if crd.deployment_needs_restart(live_deployment) {
+        println!("  [plan] drift: restarted-at annotation changed => rollout");
+        return;
+    }
+
+    if crd.deployment_service_group_changed(live_deployment) {
+        println!("  [plan] drift: service group changed => rollout");
+        return;
+    }
+
+    if !crd.spec.deployment_needs_recreation(live_deployment) {
+        println!("  [pass] drift: deployment is up to date");
+        return;
+    }
+
+    if crd
+        .spec
+        .deployment_needs_metadata_patch_only(live_deployment)
+    {
+        println!("  [plan] drift: only labels, annotations or replicas changed => patch");
+        return;
+    }
+
+    match crd.spec.changed_fields(live_deployment) {
+        None => println!("  [plan] drift: previously applied spec missing => recreate"),
+        Some(fields) if fields.is_empty() => {
+            println!("  [plan] drift: secrets volume differs from the spec => recreate")
+        }
+        Some(fields) => println!(
+            "  [plan] drift: recreate because {} changed",
+            fields.join(", ")
+        ),
+    }
+}
+
+/// Puts the document number and the location of a parse error in front of the message,
+/// e.g. "Failed to parse OpenFaaSFunction (document 2) at line 12 column 3: spec.image: invalid type: ..."
+fn crd_parse_error(index: usize, error: serde_yaml::Error) -> anyhow::Error {
+    let document = index + 1;
+    let message = error.to_string();
+
+    match error.location() {
+        Some(location) => {
+            let position = format!(" at line {} column {}", location.line(), location.column());
+            let message = message.replacen(&position, "", 1);
+
+            anyhow!(
+                "Failed to parse OpenFaaSFunction (document {document}) at line {} column {}: {message}",
+                location.line(),
+                location.column()
+            )
+        }
+        None => anyhow!("Failed to parse OpenFaaSFunction (document {document}): {message}"),
+    }
 }
 
 pub fn generate_crd_yaml() -> AnyResult<String> {
@@ -223,6 +997,46 @@ pub fn print_crd() -> AnyResult<()> {
     Ok(())
 }
 
+/// The CRD with the annotations and labels Helm uses to manage it.
+///
+/// `release` is the name and namespace of the Helm release owning the CRD
+pub fn generate_helm_crd_yaml(release: Option<(String, String)>) -> AnyResult<String> {
+    let mut crd = OpenFaaSFunction::crd();
+
+    crd.annotations_mut().insert(
+        String::from("helm.sh/resource-policy"),
+        String::from("keep"),
+    );
+    crd.labels_mut().insert(
+        String::from("app.kubernetes.io/managed-by"),
+        String::from("Helm"),
+    );
+
+    if let Some((release_name, release_namespace)) = release {
+        crd.annotations_mut()
+            .insert(String::from("meta.helm.sh/release-name"), release_name);
+        crd.annotations_mut().insert(
+            String::from("meta.helm.sh/release-namespace"),
+            release_namespace,
+        );
+    }
+
+    serde_yaml::to_string(&crd).context("Failed to generate crd")
+}
+
+pub fn print_helm_crd(release: Option<(String, String)>) -> AnyResult<()> {
+    println!("{}", generate_helm_crd_yaml(release)?);
+    Ok(())
+}
+
+pub fn print_crd_example() -> AnyResult<()> {
+    println!(
+        "{}",
+        OpenFaaSFunction::example_yaml().context("Failed to generate example")?
+    );
+    Ok(())
+}
+
 pub async fn write_crd_to_file(path: PathBuf) -> AnyResult<()> {
     let crds = generate_crd_yaml()?;
     tokio::fs::write(path, crds)
@@ -231,15 +1045,19 @@ pub async fn write_crd_to_file(path: PathBuf) -> AnyResult<()> {
     Ok(())
 }
 
-pub async fn install_crd() -> AnyResult<()> {
+pub async fn install_crd(field_manager: String) -> AnyResult<()> {
     let client = KubeClient::try_default().await?;
+    let post_params = post_params(field_manager);
 
     let api = Api::<CustomResourceDefinition>::all(client);
-    let _ = api
-        .create(&PostParams::default(), &OpenFaaSFunction::crd())
-        .await?;
+    let _ = api.create(&post_params, &OpenFaaSFunction::crd()).await?;
 
-    await_condition(api, NAME, conditions::is_crd_established()).await?;
+    await_condition(
+        api,
+        OpenFaaSFunction::crd_name(),
+        conditions::is_crd_established(),
+    )
+    .await?;
 
     Ok(())
 }
@@ -249,11 +1067,12 @@ pub async fn uninstall_crd() -> AnyResult<()> {
 
     let api = Api::<CustomResourceDefinition>::all(client);
 
-    let obj = api.delete(NAME, &Default::default()).await?;
+    let crd_name = OpenFaaSFunction::crd_name();
+    let obj = api.delete(crd_name, &Default::default()).await?;
     if let Left(o) = obj {
         match o.uid() {
             Some(uid) => {
-                await_condition(api, NAME, conditions::is_deleted(&uid)).await?;
+                await_condition(api, crd_name, conditions::is_deleted(&uid)).await?;
             }
             None => {
                 tracing::warn!("Could not find crd's uid");
@@ -264,6 +1083,354 @@ pub async fn uninstall_crd() -> AnyResult<()> {
     Ok(())
 }
 
+async fn read_credential(
+    value: Option<String>,
+    file: Option<PathBuf>,
+) -> AnyResult<Option<String>> {
+    match file {
+        Some(file) => {
+            let value = tokio::fs::read_to_string(file)
+                .await
+                .context("Failed to read credential from file")?;
+            Ok(Some(value.trim().to_string()))
+        }
+        None => Ok(value),
+    }
+}
+
+pub async fn create_openfaas_client(
+    gateway_url: Url,
+    username: Option<String>,
+    password: Option<String>,
+    username_file: Option<PathBuf>,
+    password_file: Option<PathBuf>,
+    config: OpenFaaSClientConfig,
+) -> AnyResult<OpenFaaSCleint> {
+    let username = read_credential(username, username_file).await?;
+    let password = read_credential(password, password_file).await?;
+
+    let basic_auth = match (username, password) {
+        (Some(username), Some(password)) => Some(BasicAuth::new(username, password)),
+        (None, None) => None,
+        _ => anyhow::bail!("Both username and password must be provided"),
+    };
+
+    OpenFaaSCleint::new(gateway_url, basic_auth, config).context("Failed to create gateway client")
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FunctionHealthReport {
+    name: String,
+    namespace: String,
+    replicas: u64,
+    available_replicas: u64,
+    health: FunctionHealth,
+}
+
+impl From<FunctionStatus> for FunctionHealthReport {
+    fn from(status: FunctionStatus) -> Self {
+        Self {
+            health: status.health(),
+            name: status.name,
+            namespace: status.namespace.unwrap_or_default(),
+            replicas: status.replicas.unwrap_or_default(),
+            available_replicas: status.available_replicas.unwrap_or_default(),
+        }
+    }
+}
+
+/// Best-effort pre-flight that warns if no node in the cluster runs the expected architecture.
+///
+/// Failing to reach the Kubernetes API only skips the check.
+pub async fn check_nodes_architecture(expected_arch: &str) {
+    let architectures = match list_nodes_architectures().await {
+        Result::Ok(architectures) => architectures,
+        Err(error) => {
+            tracing::warn!(%error, "Failed to list nodes, skipping architecture check.");
+            return;
+        }
+    };
+
+    if architectures.iter().any(|arch| arch == expected_arch) {
+        tracing::debug!(%expected_arch, "Found nodes with the expected architecture.");
+        return;
+    }
+
+    tracing::warn!(
+        %expected_arch,
+        node_architectures = %architectures.join(", "),
+        "ArchitectureMismatch: No node runs the expected architecture, the functions will likely fail to start."
+    );
+}
+
+async fn list_nodes_architectures() -> AnyResult<Vec<String>> {
+    let client = KubeClient::try_default().await?;
+    let node_api: Api<Node> = Api::all(client);
+
+    let architectures = node_api
+        .list(&ListParams::default())
+        .await?
+        .into_iter()
+        .filter_map(|node| node.status?.node_info.map(|info| info.architecture))
+        .unique()
+        .collect();
+
+    Ok(architectures)
+}
+
+pub async fn deploy_functions(
+    client: OpenFaaSCleint,
+    crds: Vec<OpenFaaSFunction>,
+    create_namespace: bool,
+) -> AnyResult<()> {
+    for crd in crds {
+        let name = crd.spec.to_name();
+
+        if create_namespace {
+            if let Some(namespace) = crd.spec.namespace.clone() {
+                client
+                    .create_namespace(namespace)
+                    .await
+                    .with_context(|| format!("Failed to create namespace for function {name}"))?;
+            }
+        }
+
+        client
+            .deploy_function(FunctionDeployment::from(crd.spec))
+            .await
+            .with_context(|| format!("Failed to deploy function {name}"))?;
+
+        tracing::info!(%name, "Function deployed.");
+    }
+
+    Ok(())
+}
+
+pub async fn invoke_function(
+    client: OpenFaaSCleint,
+    name: String,
+    namespace: Option<String>,
+    data: Option<String>,
+    invocation: Invocation,
+) -> AnyResult<()> {
+    let body = match data {
+        Some(data) => data,
+        None => read_file_or_stdin(PathBuf::from("-")).await?,
+    };
+
+    let response = client
+        .invoke_function(&name, namespace.as_deref(), body.into_bytes(), invocation)
+        .await
+        .with_context(|| format!("Failed to invoke function {name}"))?;
+
+    print!("{response}");
+
+    Ok(())
+}
+
+/// Updates the secret through the gateway, then restarts the function.
+///
+/// With a crd file the function is redeployed through the gateway,
+/// otherwise the operator is asked to restart it by setting the restarted-at annotation on the function's CRD.
+/// In that case the previous value of the secret is read from the cluster first and restored if the restart fails
+pub async fn rotate_secret(
+    client: OpenFaaSCleint,
+    function: String,
+    namespace: Option<String>,
+    secret: String,
+    from_file: PathBuf,
+    crd_file: Option<PathBuf>,
+    field_manager: String,
+) -> AnyResult<()> {
+    let value = read_file_or_stdin(from_file)
+        .await
+        .context("Failed to read secret value")?;
+
+    let secret_request = |value: String| SecretRequest {
+        name: secret.clone(),
+        namespace: namespace.clone(),
+        value,
+    };
+
+    let restarted_at = k8s_openapi::chrono::Utc::now().to_rfc3339();
+
+    match crd_file {
+        Some(crd_file) => {
+            // Resolve the function before touching the secret
+            let crd = read_crds_from_file(crd_file)
+                .await?
+                .into_iter()
+                .find(|crd| {
+                    crd.spec.service == function
+                        && (namespace.is_none() || crd.spec.namespace == namespace)
+                })
+                .ok_or_else(|| anyhow!("Function {function} not found in crd file"))?;
+
+            client
+                .update_secret(secret_request(value))
+                .await
+                .with_context(|| format!("Failed to update secret {secret}"))?;
+
+            tracing::info!(%secret, "Secret updated.");
+
+            let mut spec = crd.spec;
+            spec.annotations
+                .get_or_insert_with(Default::default)
+                .insert(String::from(RESTARTED_AT_ANNOTATION), restarted_at);
+
+            client
+                .update_function(FunctionDeployment::from(spec))
+                .await
+                .with_context(|| {
+                    format!("Secret {secret} updated, but failed to redeploy function {function}")
+                })?;
+        }
+        None => {
+            let kube_client = KubeClient::try_default()
+                .await
+                .context("Failed to create kubernetes client")?;
+
+            // The namespace is resolved from --functions-namespace if not set
+            let namespace = namespace.as_deref().unwrap_or_default();
+            let api: Api<OpenFaaSFunction> = Api::namespaced(kube_client.clone(), namespace);
+
+            api.get(&function)
+                .await
+                .with_context(|| format!("Failed to get function {function}"))?;
+
+            let previous_value = Api::<Secret>::namespaced(kube_client, namespace)
+                .get(&secret)
+                .await
+                .with_context(|| format!("Failed to read the current value of secret {secret}"))?
+                .data
+                .and_then(|mut data| data.remove(&secret))
+                .map(|value| String::from_utf8(value.0))
+                .transpose()
+                .with_context(|| format!("Secret {secret} is not valid UTF-8"))?
+                .ok_or_else(|| anyhow!("Secret {secret} has no value under its own name"))?;
+
+            client
+                .update_secret(secret_request(value))
+                .await
+                .with_context(|| format!("Failed to update secret {secret}"))?;
+
+            tracing::info!(%secret, "Secret updated.");
+
+            let patch = serde_json::json!({
+                "metadata": {
+                    "annotations": {
+                        RESTARTED_AT_ANNOTATION: restarted_at,
+                    }
+                }
+            });
+
+            let patch_params = PatchParams {
+                field_manager: Some(field_manager),
+                ..Default::default()
+            };
+
+            let restart = api
+                .patch(&function, &patch_params, &Patch::Merge(&patch))
+                .await
+                .with_context(|| format!("Failed to restart function {function}"))
+                .and_then(|crd| match crd.restarted_at() == Some(&restarted_at) {
+                    true => Ok(()),
+                    false => Err(anyhow!(
+                        "The restart of function {function} was not recorded"
+                    )),
+                });
+
+            if let Err(error) = restart {
+                client
+                    .update_secret(secret_request(previous_value))
+                    .await
+                    .with_context(|| {
+                        format!("{error:#}, and failed to restore the previous value of secret {secret}")
+                    })?;
+
+                tracing::info!(%secret, "Previous value of secret restored.");
+
+                return Err(error.context(format!("Secret {secret} was restored")));
+            }
+        }
+    }
+
+    tracing::info!(%function, %secret, "Secret rotated and function restarted.");
+
+    Ok(())
+}
+
+pub async fn print_functions_health(client: OpenFaaSCleint, output: OutputFormat) -> AnyResult<()> {
+    let mut reports: Vec<FunctionHealthReport> = Vec::new();
+
+    for namespace in client
+        .list_namespaces()
+        .await
+        .context("Failed to list namespaces")?
+    {
+        let functions = client
+            .list_functions(Some(namespace.clone()))
+            .await
+            .with_context(|| format!("Failed to list functions in namespace {namespace}"))?;
+
+        reports.extend(functions.into_iter().map(FunctionHealthReport::from));
+    }
+
+    match output {
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&reports).context("Failed to serialize report")?
+            );
+        }
+        OutputFormat::Table => {
+            let rows: Vec<[String; 4]> = reports
+                .into_iter()
+                .map(|report| {
+                    [
+                        report.name,
+                        report.namespace,
+                        format!("{}/{}", report.available_replicas, report.replicas),
+                        report.health.to_string(),
+                    ]
+                })
+                .collect();
+
+            print_table(["NAME", "NAMESPACE", "AVAILABLE", "HEALTH"], &rows);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_table<const N: usize>(header: [&str; N], rows: &[[String; N]]) {
+    let mut widths = header.map(str::len);
+
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let format_row = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<String>>()
+            .join("   ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!("{}", format_row(header.to_vec()));
+
+    for row in rows {
+        println!("{}", format_row(row.iter().map(String::as_str).collect()));
+    }
+}
+
 pub fn are_you_sure_you_want_to_run_this_command(message: &str) -> AnyResult<bool> {
     if !atty::is(atty::Stream::Stdin) {
         anyhow::bail!("Not a tty");
@@ -0,0 +1,209 @@
+use super::{
+    errors::{GatewayApplyError, GatewayReconcileError},
+    openfaas_client::{
+        client::{OpenFaaSCleint, OpenFaaSError, RequestExecutionError},
+        request::functions::{DeleteFunctionRequest, FunctionDeployment},
+    },
+};
+use crate::crds::defs::{OpenFaaSFunction, FINALIZER_NAME};
+use futures::stream::StreamExt;
+use kube::{
+    runtime::{
+        controller::Action,
+        finalizer::{finalizer, Event as FinalizerEvent},
+        watcher::Config,
+        Controller,
+    },
+    Api, Client as KubeClient, ResourceExt,
+};
+use std::sync::Arc;
+use tokio::time::Duration;
+
+/// Time after which a deployed function is reconciled again, recreating it if it was removed from the gateway
+const RESYNC_REQUEUE: Duration = Duration::from_secs(300);
+
+/// Time after which a failed reconciliation is retried
+const ERROR_REQUEUE: Duration = Duration::from_secs(10);
+
+struct GatewayOperatorInner {
+    client: KubeClient,
+    openfaas_client: OpenFaaSCleint,
+}
+
+/// Reconciles functions through the OpenFaaS gateway's `system/functions` API instead of creating the resources directly.
+///
+/// A finalizer keeps the function on the gateway until the function's resource is deleted
+pub struct GatewayOperator {
+    api: Api<OpenFaaSFunction>,
+    inner: Arc<GatewayOperatorInner>,
+}
+
+impl GatewayOperator {
+    pub fn new(client: KubeClient, openfaas_client: OpenFaaSCleint, namespace: &str) -> Self {
+        Self {
+            api: Api::namespaced(client.clone(), namespace),
+            inner: Arc::new(GatewayOperatorInner {
+                client,
+                openfaas_client,
+            }),
+        }
+    }
+
+    pub async fn run(self) {
+        tracing::info!("Starting.");
+
+        Controller::new(self.api, Config::default())
+            .shutdown_on_signal()
+            .run(reconcile, on_error, self.inner)
+            .for_each(|reconciliation_result| async move {
+                match reconciliation_result {
+                    Ok(_) => {
+                        tracing::info!("Reconciliation successful.");
+                    }
+                    Err(error) => {
+                        tracing::error!(%error, "Reconciliation failed.");
+                    }
+                }
+            })
+            .await;
+
+        tracing::info!("Terminated.");
+    }
+}
+
+impl GatewayOperatorInner {
+    /// Deploys the function if the gateway does not know it yet, updates it otherwise
+    async fn apply(&self, crd: &OpenFaaSFunction) -> Result<Action, GatewayApplyError> {
+        let function_deployment = to_function_deployment(crd);
+        let name = function_deployment.to_name();
+
+        let exists = self
+            .openfaas_client
+            .list_functions(function_deployment.namespace.clone())
+            .await
+            .map_err(GatewayApplyError::List)?
+            .iter()
+            .any(|function| function.name == name);
+
+        if exists {
+            tracing::info!(%name, "Updating function.");
+
+            self.openfaas_client
+                .update_function(function_deployment)
+                .await
+                .map_err(GatewayApplyError::Update)?;
+        } else {
+            tracing::info!(%name, "Deploying function.");
+
+            self.openfaas_client
+                .deploy_function(function_deployment)
+                .await
+                .map_err(GatewayApplyError::Deploy)?;
+        }
+
+        Ok(Action::requeue(RESYNC_REQUEUE))
+    }
+
+    /// Deletes the function from the gateway, a function that is already gone is not an error
+    async fn cleanup(&self, crd: &OpenFaaSFunction) -> Result<Action, GatewayApplyError> {
+        let function_deployment = to_function_deployment(crd);
+        let name = function_deployment.to_name();
+
+        tracing::info!(%name, "Deleting function.");
+
+        match self
+            .openfaas_client
+            .delete_function(
+                DeleteFunctionRequest::from(name),
+                function_deployment.namespace.as_deref(),
+            )
+            .await
+        {
+            Ok(()) => {}
+            Err(OpenFaaSError::ExecutionError(RequestExecutionError::NotFound)) => {
+                tracing::info!("Function does not exist on the gateway.");
+            }
+            Err(error) => return Err(GatewayApplyError::Delete(error)),
+        }
+
+        Ok(Action::await_change())
+    }
+}
+
+/// The function as deployed to the gateway, in the resource's namespace unless the spec names one
+fn to_function_deployment(crd: &OpenFaaSFunction) -> FunctionDeployment {
+    let mut function_deployment = FunctionDeployment::from(crd.spec.clone());
+
+    if function_deployment.namespace.is_none() {
+        function_deployment.namespace = crd.namespace();
+    }
+
+    function_deployment
+}
+
+async fn reconcile(
+    crd: Arc<OpenFaaSFunction>,
+    context: Arc<GatewayOperatorInner>,
+) -> Result<Action, GatewayReconcileError> {
+    let namespace = crd.namespace().ok_or(GatewayReconcileError::Namespace)?;
+    let api: Api<OpenFaaSFunction> = Api::namespaced(context.client.clone(), &namespace);
+
+    finalizer(&api, FINALIZER_NAME, crd, |event| async {
+        match event {
+            FinalizerEvent::Apply(crd) => context.apply(&crd).await,
+            FinalizerEvent::Cleanup(crd) => context.cleanup(&crd).await,
+        }
+    })
+    .await
+    .map_err(|error| GatewayReconcileError::Finalizer(Box::new(error)))
+}
+
+fn on_error(
+    _openfaas_function: Arc<OpenFaaSFunction>,
+    error: &GatewayReconcileError,
+    _context: Arc<GatewayOperatorInner>,
+) -> Action {
+    tracing::error!(%error, requeue = ?ERROR_REQUEUE, "Reconciliation failed. Requeuing.");
+
+    Action::requeue(ERROR_REQUEUE)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crds::defs::OpenFaasFunctionSpec;
+    use kube::core::ObjectMeta;
+
+    fn crd(spec_namespace: Option<&str>) -> OpenFaaSFunction {
+        let spec: OpenFaasFunctionSpec = serde_json::from_value(serde_json::json!({
+            "service": "nodeinfo",
+            "image": "ghcr.io/openfaas/nodeinfo:latest",
+            "namespace": spec_namespace,
+        }))
+        .expect("Invalid spec");
+
+        OpenFaaSFunction {
+            metadata: ObjectMeta {
+                name: Some(String::from("nodeinfo")),
+                namespace: Some(String::from("openfaas-fn")),
+                ..Default::default()
+            },
+            spec,
+            status: None,
+        }
+    }
+
+    #[test]
+    fn function_is_deployed_to_the_resource_namespace_by_default() {
+        assert_eq!(
+            to_function_deployment(&crd(None)).namespace.as_deref(),
+            Some("openfaas-fn")
+        );
+        assert_eq!(
+            to_function_deployment(&crd(Some("staging-fn")))
+                .namespace
+                .as_deref(),
+            Some("staging-fn")
+        );
+    }
+}
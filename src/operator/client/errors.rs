@@ -0,0 +1,23 @@
+use super::openfaas_client::client::OpenFaaSError;
+use kube::runtime::finalizer::Error as FinalizerError;
+use thiserror::Error as ThisError;
+
+#[derive(ThisError, Debug)]
+pub enum GatewayApplyError {
+    #[error("Failed to list functions: {0}")]
+    List(#[source] OpenFaaSError),
+    #[error("Failed to deploy function: {0}")]
+    Deploy(#[source] OpenFaaSError),
+    #[error("Failed to update function: {0}")]
+    Update(#[source] OpenFaaSError),
+    #[error("Failed to delete function: {0}")]
+    Delete(#[source] OpenFaaSError),
+}
+
+#[derive(ThisError, Debug)]
+pub enum GatewayReconcileError {
+    #[error("Resource has no namespace.")]
+    Namespace,
+    #[error("{0}")]
+    Finalizer(#[source] Box<FinalizerError<GatewayApplyError>>),
+}
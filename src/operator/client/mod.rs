@@ -1 +1,3 @@
-mod openfaas_client;
+pub mod controller;
+pub mod errors;
+pub mod openfaas_client;
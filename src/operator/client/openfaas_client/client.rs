@@ -1,10 +1,55 @@
-use super::request::functions::{DeleteFunctionRequest, FunctionDeployment};
-use reqwest::{Error as ReqwestError, Method, Request, Response, StatusCode};
-use serde::Serialize;
+use super::request::{
+    functions::{
+        DeleteFunctionRequest, FunctionDeployment, FunctionStatus, Invocation, ScaleFunctionRequest,
+    },
+    namespaces::NamespaceRequest,
+    secrets::SecretRequest,
+};
+use k8s_openapi::chrono::{DateTime, Utc};
+use reqwest::{
+    header::RETRY_AFTER, Certificate, Error as ReqwestError, Method, Request, RequestBuilder,
+    Response, StatusCode,
+};
+use serde::{de::DeserializeOwned, Serialize};
 use serde_json::Error as SerdeJsonError;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 use thiserror::Error as ThisError;
 use url::Url;
 
+/// Upper bound for the time waited before retrying
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Timeouts, retries and TLS settings of the requests sent to the gateway
+#[derive(Debug, Clone)]
+pub struct OpenFaaSClientConfig {
+    /// Time a request may take, from connecting until the response body is read
+    pub timeout: Duration,
+    /// Retries of requests that are rate limited, or of idempotent requests that time out or fail with a 5xx status code
+    pub retries: u32,
+    /// Time waited before the first retry, doubled on each further retry
+    pub retry_backoff: Duration,
+    /// PEM file of a CA certificate trusted in addition to the system's, e.g. of a gateway with a self-signed certificate
+    pub ca_cert_path: Option<PathBuf>,
+    /// Accepts any certificate the gateway presents. For development only
+    pub accept_invalid_certs: bool,
+}
+
+impl Default for OpenFaaSClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            retries: 0,
+            retry_backoff: Duration::from_secs(1),
+            ca_cert_path: None,
+            accept_invalid_certs: false,
+        }
+    }
+}
+
 pub struct BasicAuth {
     username: String,
     password: String,
@@ -33,6 +78,24 @@ pub enum RequestBuildError {
         #[from]
         ReqwestError,
     ),
+    #[error("Invalid URL: {0}")]
+    UrlError(#[source] url::ParseError),
+}
+
+#[derive(ThisError, Debug)]
+pub enum OpenFaaSClientBuildError {
+    #[error("Invalid URL: {0}")]
+    UrlError(
+        #[source]
+        #[from]
+        url::ParseError,
+    ),
+    #[error("HTTP client build error: {0}")]
+    HttpClientError(#[source] ReqwestError),
+    #[error("Failed to read CA certificate {0}: {1}")]
+    CaCertReadError(PathBuf, #[source] std::io::Error),
+    #[error("Invalid CA certificate {0}: {1}")]
+    CaCertParseError(PathBuf, #[source] ReqwestError),
 }
 
 #[derive(ThisError, Debug)]
@@ -43,14 +106,22 @@ pub enum RequestExecutionError {
         #[from]
         ReqwestError,
     ),
-    #[error("OpenFaaS: bad request")]
-    BadRequest,
+    #[error("OpenFaaS: bad request{}", with_body(.0))]
+    BadRequest(String),
+    #[error("OpenFaaS: unauthorized, check the provided --username and --password")]
+    Unauthorized,
+    #[error("OpenFaaS: forbidden, check that the provided --username and --password have access to this resource")]
+    Forbidden,
     #[error("OpenFaaS: not found")]
     NotFound,
-    #[error("OpenFaaS: internal server error")]
-    InternalServerError,
-    #[error("OpenFaaS: unexpected status code: {0}")]
-    UnexpectedStatusCode(u16),
+    #[error("OpenFaaS: internal server error{}", with_body(.0))]
+    InternalServerError(String),
+    #[error("OpenFaaS: too many requests{}", .0.map(|d| format!(", retry after {}s", d.as_secs())).unwrap_or_default())]
+    TooManyRequests(Option<Duration>),
+    #[error("OpenFaaS: unexpected status code: {0}{}", with_body(.1))]
+    UnexpectedStatusCode(u16, String),
+    #[error("Deserializing error: {0}")]
+    DeserializingError(#[source] SerdeJsonError),
 }
 
 #[derive(ThisError, Debug)]
@@ -69,13 +140,25 @@ pub enum OpenFaaSError {
     ),
 }
 
-impl From<StatusCode> for RequestExecutionError {
-    fn from(status_code: StatusCode) -> Self {
+/// Appends the gateway's response body to an error message, if there is one
+fn with_body(body: &str) -> String {
+    match body.trim() {
+        "" => String::new(),
+        body => format!(": {body}"),
+    }
+}
+
+impl RequestExecutionError {
+    /// The error for a failed response, keeping the body the gateway explained the failure with
+    fn from_response(status_code: StatusCode, body: String) -> Self {
         match status_code {
-            StatusCode::BAD_REQUEST => RequestExecutionError::BadRequest,
+            StatusCode::BAD_REQUEST => RequestExecutionError::BadRequest(body),
+            StatusCode::UNAUTHORIZED => RequestExecutionError::Unauthorized,
+            StatusCode::FORBIDDEN => RequestExecutionError::Forbidden,
             StatusCode::NOT_FOUND => RequestExecutionError::NotFound,
-            StatusCode::INTERNAL_SERVER_ERROR => RequestExecutionError::InternalServerError,
-            _ => RequestExecutionError::UnexpectedStatusCode(status_code.as_u16()),
+            StatusCode::INTERNAL_SERVER_ERROR => RequestExecutionError::InternalServerError(body),
+            StatusCode::TOO_MANY_REQUESTS => RequestExecutionError::TooManyRequests(None),
+            _ => RequestExecutionError::UnexpectedStatusCode(status_code.as_u16(), body),
         }
     }
 }
@@ -83,49 +166,198 @@ impl From<StatusCode> for RequestExecutionError {
 pub struct OpenFaaSCleint {
     client: reqwest::Client,
     functions_endpoint: Url,
+    namespaces_endpoint: Url,
+    namespace_endpoint: Url,
+    function_status_endpoint: Url,
+    scale_function_endpoint: Url,
+    function_endpoint: Url,
+    async_function_endpoint: Url,
+    secrets_endpoint: Url,
     basic_auth: Option<BasicAuth>,
+    config: OpenFaaSClientConfig,
+}
+
+/// Reads the `Retry-After` header, given either in seconds or as an HTTP date
+fn retry_after(res: &Response) -> Option<Duration> {
+    let value = res.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+
+    (date.with_timezone(&Utc) - Utc::now()).to_std().ok()
 }
 
 impl OpenFaaSCleint {
     /// Base URL of the OpenFaaS gateway
     /// e.g. http://gateway.openfaas:8080
-    pub fn new(base_url: Url, basic_auth: Option<BasicAuth>) -> Result<Self, url::ParseError> {
+    pub fn new(
+        base_url: Url,
+        basic_auth: Option<BasicAuth>,
+        config: OpenFaaSClientConfig,
+    ) -> Result<Self, OpenFaaSClientBuildError> {
         let functions_endpoint = base_url.join("system/functions")?;
+        let namespaces_endpoint = base_url.join("system/namespaces")?;
+        let namespace_endpoint = base_url.join("system/namespace/")?;
+        let function_status_endpoint = base_url.join("system/function/")?;
+        let scale_function_endpoint = base_url.join("system/scale-function/")?;
+        let function_endpoint = base_url.join("function/")?;
+        let async_function_endpoint = base_url.join("async-function/")?;
+        let secrets_endpoint = base_url.join("system/secrets")?;
+        let mut builder = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .danger_accept_invalid_certs(config.accept_invalid_certs);
+
+        if let Some(ca_cert_path) = &config.ca_cert_path {
+            builder = builder.add_root_certificate(Self::read_ca_cert(ca_cert_path)?);
+        }
+
+        let client = builder
+            .build()
+            .map_err(OpenFaaSClientBuildError::HttpClientError)?;
+
         Ok(Self {
-            client: reqwest::Client::new(),
+            client,
             functions_endpoint,
+            namespaces_endpoint,
+            namespace_endpoint,
+            function_status_endpoint,
+            scale_function_endpoint,
+            function_endpoint,
+            async_function_endpoint,
+            secrets_endpoint,
             basic_auth,
+            config,
         })
     }
 
-    fn status_code_into_openfaas_result(status_code: StatusCode) -> OpenFaaSResult {
-        match status_code {
-            StatusCode::OK => Ok(()),
-            StatusCode::ACCEPTED => Ok(()),
-            status_code => Err(OpenFaaSError::ExecutionError(status_code.into())),
+    fn read_ca_cert(path: &Path) -> Result<Certificate, OpenFaaSClientBuildError> {
+        let pem = std::fs::read(path).map_err(|error| {
+            OpenFaaSClientBuildError::CaCertReadError(path.to_path_buf(), error)
+        })?;
+
+        Certificate::from_pem(&pem)
+            .map_err(|error| OpenFaaSClientBuildError::CaCertParseError(path.to_path_buf(), error))
+    }
+
+    /// Passes successful responses through, reading the body of failed ones into the error
+    async fn response_into_openfaas_result(res: Response) -> Result<Response, OpenFaaSError> {
+        match res.status() {
+            StatusCode::OK => Ok(res),
+            StatusCode::CREATED => Ok(res),
+            StatusCode::ACCEPTED => Ok(res),
+            status_code => {
+                let body = res.text().await.unwrap_or_default();
+
+                Err(OpenFaaSError::ExecutionError(
+                    RequestExecutionError::from_response(status_code, body),
+                ))
+            }
+        }
+    }
+
+    fn authorize(&self, builder: RequestBuilder) -> RequestBuilder {
+        match &self.basic_auth {
+            Some(basic_auth) => {
+                builder.basic_auth(&basic_auth.username, Some(&basic_auth.password))
+            }
+            None => builder,
         }
     }
 
     pub fn build_request<T: Serialize>(&self, method: Method, body: &T) -> RequestBuildResult {
-        let mut builder = self.client.request(method, self.functions_endpoint.clone());
+        self.build_request_with_url(method, self.functions_endpoint.clone(), body)
+    }
+
+    pub fn build_request_with_url<T: Serialize>(
+        &self,
+        method: Method,
+        url: Url,
+        body: &T,
+    ) -> RequestBuildResult {
+        let builder = self.client.request(method, url);
         let body = serde_json::to_string(body)?;
 
-        builder = builder
+        let builder = builder
             .header("Content-Type", "application/json")
             .body(body);
 
-        if let Some(basic_auth) = &self.basic_auth {
-            builder = builder.basic_auth(&basic_auth.username, Some(&basic_auth.password));
-        }
+        let req = self.authorize(builder).build()?;
+
+        Ok(req)
+    }
 
-        let req = builder.build()?;
+    pub fn build_get_request(&self, url: Url) -> RequestBuildResult {
+        let builder = self.client.request(Method::GET, url);
+
+        let req = self.authorize(builder).build()?;
 
         Ok(req)
     }
 
+    /// Executes the request, retrying if the gateway is rate limiting.
+    ///
+    /// GET, PUT and DELETE requests are idempotent and are also retried if they time out or fail with a 5xx status code
     async fn execute_request(&self, req: Request) -> Result<Response, RequestExecutionError> {
-        let res = self.client.execute(req).await?;
-        Ok(res)
+        let idempotent = matches!(*req.method(), Method::GET | Method::PUT | Method::DELETE);
+
+        self.execute_request_with_retries(req, idempotent).await
+    }
+
+    /// Executes the request, retrying if the gateway is rate limiting, or if an `idempotent` request times out or fails with a 5xx status code.
+    ///
+    /// Requests that are not idempotent, e.g. deployments and invocations, may have taken effect even if they failed.
+    /// Waits as long as the `Retry-After` header asks for, or backs off exponentially if it is missing
+    async fn execute_request_with_retries(
+        &self,
+        req: Request,
+        idempotent: bool,
+    ) -> Result<Response, RequestExecutionError> {
+        let mut req = req;
+        let mut attempt = 0;
+
+        loop {
+            // Requests with streaming bodies can not be cloned and are not retried
+            let retry_req = req.try_clone().filter(|_| attempt < self.config.retries);
+
+            let (reason, retry_after) = match self.client.execute(req).await {
+                Ok(res) if res.status() == StatusCode::TOO_MANY_REQUESTS => {
+                    (String::from("Too many requests"), retry_after(&res))
+                }
+                Ok(res) if res.status().is_server_error() && idempotent && retry_req.is_some() => {
+                    (res.status().to_string(), None)
+                }
+                Ok(res) => return Ok(res),
+                Err(error) if error.is_timeout() && idempotent && retry_req.is_some() => {
+                    (error.to_string(), None)
+                }
+                Err(error) => return Err(error.into()),
+            };
+
+            // Only rate limited requests are left without a retry
+            let Some(retry_req) = retry_req else {
+                return Err(RequestExecutionError::TooManyRequests(retry_after));
+            };
+
+            let delay = retry_after
+                .unwrap_or(self.config.retry_backoff * (1 << attempt.min(6)))
+                .min(MAX_RETRY_AFTER);
+
+            attempt += 1;
+
+            tracing::warn!(
+                attempt,
+                delay_millis = delay.as_millis(),
+                %reason,
+                "Request failed. Retrying."
+            );
+
+            tokio::time::sleep(delay).await;
+
+            req = retry_req;
+        }
     }
 
     async fn build_and_execute_request<T: Serialize>(
@@ -136,7 +368,25 @@ impl OpenFaaSCleint {
         let req = self.build_request(method, body)?;
         let res = self.execute_request(req).await?;
 
-        Self::status_code_into_openfaas_result(res.status())
+        Self::response_into_openfaas_result(res).await?;
+
+        Ok(())
+    }
+
+    async fn build_and_execute_get_request<T: DeserializeOwned>(
+        &self,
+        url: Url,
+    ) -> Result<T, OpenFaaSError> {
+        let req = self.build_get_request(url)?;
+        let res = self.execute_request(req).await?;
+
+        let res = Self::response_into_openfaas_result(res).await?;
+
+        let body = res.text().await.map_err(RequestExecutionError::HttpError)?;
+        let value =
+            serde_json::from_str(&body).map_err(RequestExecutionError::DeserializingError)?;
+
+        Ok(value)
     }
 
     pub async fn deploy_function(&self, function_deployment: FunctionDeployment) -> OpenFaaSResult {
@@ -149,11 +399,619 @@ impl OpenFaaSCleint {
             .await
     }
 
+    /// The deployed function in the form accepted by `update_function`.
+    ///
+    /// The gateway reports the function's name, which is the deployment's service.
+    /// Only the fields known to the gateway are set
+    pub async fn get_function(
+        &self,
+        function_name: &str,
+        namespace: Option<&str>,
+    ) -> Result<FunctionDeployment, OpenFaaSError> {
+        let mut url = self
+            .function_status_endpoint
+            .join(function_name)
+            .map_err(RequestBuildError::UrlError)?;
+
+        if let Some(namespace) = namespace {
+            url.query_pairs_mut().append_pair("namespace", namespace);
+        }
+
+        let mut value: serde_json::Value = self.build_and_execute_get_request(url).await?;
+
+        if let Some(object) = value.as_object_mut() {
+            if let Some(name) = object.remove("name") {
+                object.insert(String::from("service"), name);
+            }
+        }
+
+        let function_deployment =
+            serde_json::from_value(value).map_err(RequestExecutionError::DeserializingError)?;
+
+        Ok(function_deployment)
+    }
+
+    /// Adds or overwrites labels and annotations of a deployed function, keeping the rest of the function.
+    ///
+    /// The gateway has no partial updates, so the function is read with `get_function`, modified and
+    /// written back with `update_function`. Changes made to the function in between are lost
+    pub async fn patch_function_metadata(
+        &self,
+        function_name: &str,
+        namespace: Option<&str>,
+        labels: HashMap<String, String>,
+        annotations: HashMap<String, String>,
+    ) -> OpenFaaSResult {
+        let mut function_deployment = self.get_function(function_name, namespace).await?;
+
+        if !labels.is_empty() {
+            function_deployment
+                .labels
+                .get_or_insert_with(HashMap::new)
+                .extend(labels);
+        }
+
+        if !annotations.is_empty() {
+            function_deployment
+                .annotations
+                .get_or_insert_with(HashMap::new)
+                .extend(annotations);
+        }
+
+        self.update_function(function_deployment).await
+    }
+
     pub async fn delete_function(
         &self,
         delete_function_request: DeleteFunctionRequest,
+        namespace: Option<&str>,
     ) -> OpenFaaSResult {
-        self.build_and_execute_request(Method::DELETE, &delete_function_request)
+        let mut url = self.functions_endpoint.clone();
+
+        if let Some(namespace) = namespace {
+            url.query_pairs_mut().append_pair("namespace", namespace);
+        }
+
+        let req = self.build_request_with_url(Method::DELETE, url, &delete_function_request)?;
+        let res = self.execute_request(req).await?;
+
+        Self::response_into_openfaas_result(res).await?;
+
+        Ok(())
+    }
+
+    /// Sets the desired amount of replicas of a deployed function
+    pub async fn scale_function(
+        &self,
+        function_name: &str,
+        namespace: Option<&str>,
+        replicas: u64,
+    ) -> OpenFaaSResult {
+        let url = self
+            .scale_function_endpoint
+            .join(function_name)
+            .map_err(RequestBuildError::UrlError)?;
+
+        let scale_function_request = ScaleFunctionRequest {
+            service_name: function_name.to_string(),
+            service_namespace: namespace.map(String::from),
+            replicas,
+        };
+
+        let req = self.build_request_with_url(Method::POST, url, &scale_function_request)?;
+        // Setting the replicas is idempotent even though it is a POST
+        let res = self.execute_request_with_retries(req, true).await?;
+
+        Self::response_into_openfaas_result(res).await?;
+
+        Ok(())
+    }
+
+    /// Invokes a function with the given body and returns the function's response.
+    ///
+    /// Async invocations return an empty body once the request is queued.
+    pub async fn invoke_function(
+        &self,
+        function_name: &str,
+        namespace: Option<&str>,
+        body: Vec<u8>,
+        invocation: Invocation,
+    ) -> Result<String, OpenFaaSError> {
+        let endpoint = match invocation {
+            Invocation::Sync => &self.function_endpoint,
+            Invocation::Async { .. } => &self.async_function_endpoint,
+        };
+
+        let path = match namespace {
+            Some(namespace) => format!("{function_name}.{namespace}"),
+            None => function_name.to_string(),
+        };
+
+        let url = endpoint.join(&path).map_err(RequestBuildError::UrlError)?;
+
+        let mut builder = self.client.request(Method::POST, url).body(body);
+
+        if let Invocation::Async {
+            callback_url: Some(callback_url),
+        } = invocation
+        {
+            builder = builder.header("X-Callback-Url", callback_url.as_str());
+        }
+
+        let req = self
+            .authorize(builder)
+            .build()
+            .map_err(RequestBuildError::HttpBuilderError)?;
+        let res = self.execute_request(req).await?;
+
+        let res = Self::response_into_openfaas_result(res).await?;
+
+        let body = res.text().await.map_err(RequestExecutionError::HttpError)?;
+
+        Ok(body)
+    }
+
+    pub async fn list_functions(
+        &self,
+        namespace: Option<String>,
+    ) -> Result<Vec<FunctionStatus>, OpenFaaSError> {
+        let mut url = self.functions_endpoint.clone();
+
+        if let Some(namespace) = namespace {
+            url.query_pairs_mut().append_pair("namespace", &namespace);
+        }
+
+        self.build_and_execute_get_request(url).await
+    }
+
+    pub async fn list_namespaces(&self) -> Result<Vec<String>, OpenFaaSError> {
+        self.build_and_execute_get_request(self.namespaces_endpoint.clone())
             .await
     }
+
+    /// Creates a namespace on the gateway.
+    ///
+    /// An already existing namespace is not an error.
+    pub async fn create_namespace(&self, namespace: String) -> OpenFaaSResult {
+        let req = self.build_request_with_url(
+            Method::POST,
+            self.namespace_endpoint.clone(),
+            &NamespaceRequest::from(namespace),
+        )?;
+        let res = self.execute_request(req).await?;
+
+        if res.status() == StatusCode::CONFLICT {
+            tracing::debug!("Namespace already exists.");
+            return Ok(());
+        }
+
+        Self::response_into_openfaas_result(res).await?;
+
+        Ok(())
+    }
+
+    /// Replaces the value of an existing secret
+    pub async fn update_secret(&self, secret_request: SecretRequest) -> OpenFaaSResult {
+        let req = self.build_request_with_url(
+            Method::PUT,
+            self.secrets_endpoint.clone(),
+            &secret_request,
+        )?;
+        let res = self.execute_request(req).await?;
+
+        Self::response_into_openfaas_result(res).await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_namespace(&self, namespace: String) -> OpenFaaSResult {
+        let url = self
+            .namespace_endpoint
+            .join(&namespace)
+            .map_err(RequestBuildError::UrlError)?;
+
+        let builder = self.client.request(Method::DELETE, url);
+        let req = self
+            .authorize(builder)
+            .build()
+            .map_err(RequestBuildError::HttpBuilderError)?;
+        let res = self.execute_request(req).await?;
+
+        Self::response_into_openfaas_result(res).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crds::defs::OpenFaasFunctionSpec;
+    use axum::{
+        extract::State,
+        http::{
+            header::AUTHORIZATION, HeaderMap, Method as HttpMethod, StatusCode as HttpStatus, Uri,
+        },
+        Router,
+    };
+    use std::{
+        net::{SocketAddr, TcpListener},
+        sync::{Arc, Mutex},
+    };
+
+    /// A request as received by the gateway
+    #[derive(Debug, Clone)]
+    struct Recorded {
+        method: HttpMethod,
+        path: String,
+        query: Option<String>,
+        authorization: Option<String>,
+        body: String,
+    }
+
+    impl Recorded {
+        fn json(&self) -> serde_json::Value {
+            serde_json::from_str(&self.body).expect("Body is not JSON")
+        }
+    }
+
+    type Requests = Arc<Mutex<Vec<Recorded>>>;
+
+    const FUNCTION_STATUS: &str = r#"{
+        "name": "nodeinfo",
+        "image": "ghcr.io/openfaas/nodeinfo:latest",
+        "namespace": "openfaas-fn",
+        "envProcess": "node index.js",
+        "labels": {"com.openfaas.scale.min": "1"},
+        "annotations": {"topic": "cron"},
+        "invocationCount": 3,
+        "replicas": 2,
+        "availableReplicas": 1
+    }"#;
+
+    /// Records every request and answers like the gateway would on success
+    async fn gateway(
+        State(requests): State<Requests>,
+        method: HttpMethod,
+        uri: Uri,
+        headers: HeaderMap,
+        body: String,
+    ) -> (HttpStatus, String) {
+        requests
+            .lock()
+            .expect("Requests lock poisoned")
+            .push(Recorded {
+                method: method.clone(),
+                path: uri.path().to_string(),
+                query: uri.query().map(String::from),
+                authorization: headers
+                    .get(AUTHORIZATION)
+                    .and_then(|value| value.to_str().ok())
+                    .map(String::from),
+                body,
+            });
+
+        let attempts = requests
+            .lock()
+            .expect("Requests lock poisoned")
+            .iter()
+            .filter(|request| request.path == uri.path())
+            .count();
+
+        match (method, uri.path()) {
+            (HttpMethod::GET, "/system/functions") => {
+                (HttpStatus::OK, format!("[{FUNCTION_STATUS}]"))
+            }
+            (HttpMethod::GET, "/system/function/nodeinfo") => {
+                (HttpStatus::OK, FUNCTION_STATUS.to_string())
+            }
+            (HttpMethod::DELETE, "/system/namespace/kube-system") => (
+                HttpStatus::BAD_REQUEST,
+                String::from("namespace kube-system is not allowed\n"),
+            ),
+            (HttpMethod::DELETE, "/system/namespace/flaky") if attempts < 3 => (
+                HttpStatus::SERVICE_UNAVAILABLE,
+                String::from("upstream unavailable"),
+            ),
+            (HttpMethod::DELETE, "/system/namespace/down")
+            | (HttpMethod::POST, "/function/down") => (
+                HttpStatus::SERVICE_UNAVAILABLE,
+                String::from("upstream unavailable"),
+            ),
+            (HttpMethod::GET, _) => (HttpStatus::NOT_FOUND, String::new()),
+            _ => (HttpStatus::ACCEPTED, String::new()),
+        }
+    }
+
+    /// Starts an in-memory gateway and returns a client authenticated against it
+    fn client_with_gateway() -> (OpenFaaSCleint, Requests) {
+        let requests = Requests::default();
+
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind gateway");
+        let addr: SocketAddr = listener.local_addr().expect("Gateway has no address");
+
+        let router = Router::new().fallback(gateway).with_state(requests.clone());
+
+        let server = axum::Server::from_tcp(listener)
+            .expect("Failed to create gateway")
+            .serve(router.into_make_service());
+
+        tokio::spawn(server);
+
+        let base_url = Url::parse(&format!("http://{addr}")).expect("Invalid gateway URL");
+        let basic_auth = BasicAuth::new(String::from("admin"), String::from("secret"));
+        let config = OpenFaaSClientConfig {
+            retries: 2,
+            retry_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let client =
+            OpenFaaSCleint::new(base_url, Some(basic_auth), config).expect("Invalid gateway URL");
+
+        (client, requests)
+    }
+
+    fn recorded(requests: &Requests) -> Vec<Recorded> {
+        requests.lock().expect("Requests lock poisoned").clone()
+    }
+
+    fn function_deployment() -> FunctionDeployment {
+        let spec: OpenFaasFunctionSpec = serde_json::from_value(serde_json::json!({
+            "service": "nodeinfo",
+            "image": "ghcr.io/openfaas/nodeinfo:latest",
+            "namespace": "openfaas-fn",
+            "envProcess": "node index.js",
+            "readOnlyRootFilesystem": true,
+        }))
+        .expect("Invalid spec");
+
+        FunctionDeployment::from(spec)
+    }
+
+    #[tokio::test]
+    async fn deploy_and_update_send_the_function_as_camel_case_json() {
+        let (client, requests) = client_with_gateway();
+
+        client
+            .deploy_function(function_deployment())
+            .await
+            .expect("Failed to deploy function");
+        client
+            .update_function(function_deployment())
+            .await
+            .expect("Failed to update function");
+
+        let recorded = recorded(&requests);
+        assert_eq!(recorded.len(), 2);
+
+        for (request, method) in recorded.iter().zip([HttpMethod::POST, HttpMethod::PUT]) {
+            assert_eq!(request.method, method);
+            assert_eq!(request.path, "/system/functions");
+            assert_eq!(
+                request.authorization.as_deref(),
+                Some("Basic YWRtaW46c2VjcmV0")
+            );
+
+            let body = request.json();
+            assert_eq!(body["service"], "nodeinfo");
+            assert_eq!(body["envProcess"], "node index.js");
+            assert_eq!(body["readOnlyRootFilesystem"], true);
+            assert!(body.get("env_process").is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn delete_sends_the_function_name_and_namespace() {
+        let (client, requests) = client_with_gateway();
+
+        client
+            .delete_function(
+                DeleteFunctionRequest::from(String::from("nodeinfo")),
+                Some("openfaas-fn"),
+            )
+            .await
+            .expect("Failed to delete function");
+
+        let recorded = recorded(&requests);
+        assert_eq!(recorded[0].method, HttpMethod::DELETE);
+        assert_eq!(recorded[0].path, "/system/functions");
+        assert_eq!(recorded[0].query.as_deref(), Some("namespace=openfaas-fn"));
+        assert_eq!(
+            recorded[0].json(),
+            serde_json::json!({"functionName": "nodeinfo"})
+        );
+    }
+
+    #[tokio::test]
+    async fn list_functions_filters_by_namespace() {
+        let (client, requests) = client_with_gateway();
+
+        let functions = client
+            .list_functions(Some(String::from("openfaas-fn")))
+            .await
+            .expect("Failed to list functions");
+
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0].name, "nodeinfo");
+        assert_eq!(functions[0].available_replicas, Some(1));
+
+        let recorded = recorded(&requests);
+        assert_eq!(recorded[0].method, HttpMethod::GET);
+        assert_eq!(recorded[0].path, "/system/functions");
+        assert_eq!(recorded[0].query.as_deref(), Some("namespace=openfaas-fn"));
+    }
+
+    #[tokio::test]
+    async fn patch_function_metadata_reads_and_writes_back_the_function() {
+        let (client, requests) = client_with_gateway();
+
+        client
+            .patch_function_metadata(
+                "nodeinfo",
+                Some("openfaas-fn"),
+                [(String::from("com.openfaas.scale.min"), String::from("2"))].into(),
+                HashMap::new(),
+            )
+            .await
+            .expect("Failed to patch function metadata");
+
+        let recorded = recorded(&requests);
+        assert_eq!(recorded.len(), 2);
+
+        assert_eq!(recorded[0].method, HttpMethod::GET);
+        assert_eq!(recorded[0].path, "/system/function/nodeinfo");
+        assert_eq!(recorded[0].query.as_deref(), Some("namespace=openfaas-fn"));
+
+        assert_eq!(recorded[1].method, HttpMethod::PUT);
+        assert_eq!(recorded[1].path, "/system/functions");
+
+        let body = recorded[1].json();
+        assert_eq!(body["service"], "nodeinfo");
+        assert_eq!(body["envProcess"], "node index.js");
+        assert_eq!(body["labels"]["com.openfaas.scale.min"], "2");
+        assert_eq!(body["annotations"]["topic"], "cron");
+    }
+
+    #[tokio::test]
+    async fn missing_function_is_not_found() {
+        let (client, _) = client_with_gateway();
+
+        let error = client
+            .get_function("missing", None)
+            .await
+            .expect_err("Function must not exist");
+
+        assert!(matches!(
+            error,
+            OpenFaaSError::ExecutionError(RequestExecutionError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejected_request_keeps_the_gateway_response_body() {
+        let (client, _) = client_with_gateway();
+
+        let error = client
+            .delete_namespace(String::from("kube-system"))
+            .await
+            .expect_err("Namespace must be rejected");
+
+        assert!(matches!(
+            &error,
+            OpenFaaSError::ExecutionError(RequestExecutionError::BadRequest(body))
+                if body.contains("kube-system is not allowed")
+        ));
+        assert!(error
+            .to_string()
+            .ends_with("bad request: namespace kube-system is not allowed"));
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_retried_for_idempotent_requests_only() {
+        let (client, requests) = client_with_gateway();
+
+        client
+            .delete_namespace(String::from("flaky"))
+            .await
+            .expect("Failed to delete namespace after retrying");
+
+        let error = client
+            .delete_namespace(String::from("down"))
+            .await
+            .expect_err("Namespace must fail after all retries");
+
+        assert!(matches!(
+            error,
+            OpenFaaSError::ExecutionError(RequestExecutionError::UnexpectedStatusCode(503, _))
+        ));
+
+        // Invocations are not idempotent and must not run again
+        client
+            .invoke_function("down", None, Vec::new(), Invocation::Sync)
+            .await
+            .expect_err("Invocation must fail without retrying");
+
+        let recorded = recorded(&requests);
+        let attempts = |path: &str| recorded.iter().filter(|r| r.path == path).count();
+        assert_eq!(attempts("/system/namespace/flaky"), 3);
+        assert_eq!(attempts("/system/namespace/down"), 3);
+        assert_eq!(attempts("/function/down"), 1);
+    }
+
+    #[test]
+    fn invalid_ca_cert_is_an_error() {
+        // Unique per process, concurrent test runs must not share the file
+        let path = std::env::temp_dir().join(format!(
+            "openfaas-operator-invalid_ca_cert_is_an_error-{}.pem",
+            std::process::id()
+        ));
+        std::fs::write(&path, "not a certificate").expect("Failed to write CA certificate");
+
+        let config = OpenFaaSClientConfig {
+            ca_cert_path: Some(path.clone()),
+            ..Default::default()
+        };
+        let result = OpenFaaSCleint::new(
+            Url::parse("https://gateway.openfaas:8080").expect("Invalid gateway URL"),
+            None,
+            config,
+        );
+
+        std::fs::remove_file(&path).expect("Failed to remove CA certificate");
+
+        assert!(matches!(
+            result,
+            Err(OpenFaaSClientBuildError::CaCertParseError(error_path, _)) if error_path == path
+        ));
+    }
+
+    #[tokio::test]
+    async fn scale_function_sends_the_replicas() {
+        let (client, requests) = client_with_gateway();
+
+        client
+            .scale_function("nodeinfo", Some("openfaas-fn"), 3)
+            .await
+            .expect("Failed to scale function");
+
+        let recorded = recorded(&requests);
+        assert_eq!(recorded[0].method, HttpMethod::POST);
+        assert_eq!(recorded[0].path, "/system/scale-function/nodeinfo");
+        assert_eq!(
+            recorded[0].authorization.as_deref(),
+            Some("Basic YWRtaW46c2VjcmV0")
+        );
+        assert_eq!(
+            recorded[0].json(),
+            serde_json::json!({
+                "serviceName": "nodeinfo",
+                "serviceNamespace": "openfaas-fn",
+                "replicas": 3
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn invoke_function_posts_the_body_to_the_function() {
+        let (client, requests) = client_with_gateway();
+
+        client
+            .invoke_function(
+                "nodeinfo",
+                Some("openfaas-fn"),
+                b"verbose".to_vec(),
+                Invocation::Sync,
+            )
+            .await
+            .expect("Failed to invoke function");
+
+        let recorded = recorded(&requests);
+        assert_eq!(recorded[0].method, HttpMethod::POST);
+        assert_eq!(recorded[0].path, "/function/nodeinfo.openfaas-fn");
+        assert_eq!(recorded[0].body, "verbose");
+        assert_eq!(
+            recorded[0].authorization.as_deref(),
+            Some("Basic YWRtaW46c2VjcmV0")
+        );
+    }
 }
@@ -1,3 +1,3 @@
 #[allow(dead_code)]
 pub mod client;
-mod request;
+pub mod request;
@@ -1,6 +1,11 @@
 use crate::crds::defs::OpenFaasFunctionSpec;
 use serde::{Deserialize, Serialize};
-use std::ops::{Deref, DerefMut};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    ops::{Deref, DerefMut},
+};
+use url::Url;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FunctionDeployment {
@@ -15,6 +20,90 @@ pub struct DeleteFunctionRequest {
     function_name: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScaleFunctionRequest {
+    /// Name of the function to scale
+    pub service_name: String,
+    /// Namespace of the function, the gateway's default functions namespace if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_namespace: Option<String>,
+    /// Desired amount of replicas
+    pub replicas: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionStatus {
+    /// Name of deployed function
+    pub name: String,
+    /// Fully-qualified container image
+    pub image: String,
+    /// Namespace of the function
+    pub namespace: Option<String>,
+    /// Labels of the function
+    pub labels: Option<HashMap<String, String>>,
+    /// Annotations of the function
+    pub annotations: Option<HashMap<String, String>>,
+    /// Number of invocations of the function
+    pub invocation_count: Option<f64>,
+    /// Desired amount of replicas
+    pub replicas: Option<u64>,
+    /// Amount of replicas that are available to serve requests
+    pub available_replicas: Option<u64>,
+}
+
+/// Health of a function derived from its replicas
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum FunctionHealth {
+    /// All desired replicas are available
+    Ready,
+    /// Some, but not all desired replicas are available
+    Degraded,
+    /// No replicas are available
+    Down,
+}
+
+impl Display for FunctionHealth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FunctionStatus {
+    pub fn health(&self) -> FunctionHealth {
+        let replicas = self.replicas.unwrap_or_default();
+        let available_replicas = self.available_replicas.unwrap_or_default();
+
+        if available_replicas == 0 {
+            return FunctionHealth::Down;
+        }
+
+        if available_replicas < replicas {
+            return FunctionHealth::Degraded;
+        }
+
+        FunctionHealth::Ready
+    }
+}
+
+/// How a function is invoked through the gateway
+#[derive(Debug, Clone, PartialEq)]
+pub enum Invocation {
+    /// The gateway waits for the function and returns its response
+    Sync,
+    /// The gateway queues the request and returns immediately with 202 Accepted
+    ///
+    /// If a callback URL is set, the queue worker POSTs the function's response to it (X-Callback-Url)
+    Async { callback_url: Option<Url> },
+}
+
+impl From<String> for DeleteFunctionRequest {
+    fn from(function_name: String) -> Self {
+        Self { function_name }
+    }
+}
+
 impl From<OpenFaasFunctionSpec> for FunctionDeployment {
     fn from(open_faas_function_spec: OpenFaasFunctionSpec) -> Self {
         Self {
@@ -1 +1,3 @@
 pub mod functions;
+pub mod namespaces;
+pub mod secrets;
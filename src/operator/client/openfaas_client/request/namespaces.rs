@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceRequest {
+    /// Name of the namespace
+    pub name: String,
+}
+
+impl From<String> for NamespaceRequest {
+    fn from(name: String) -> Self {
+        Self { name }
+    }
+}
@@ -1,16 +1,21 @@
 use super::UpdateStrategy;
 use crate::cli::Cli;
-use crate::consts::PKG_NAME;
-use crate::crds::defs::{GROUP, PLURAL};
+use crate::consts::{METRICS_DEFAULT_PORT, PKG_NAME};
+use crate::crds::defs::OpenFaaSFunction;
 use k8s_openapi::{
     api::{
         apps::v1::{Deployment, DeploymentSpec},
-        core::v1::{Container, EnvVar, PodSpec, PodTemplateSpec, ServiceAccount},
-        rbac::v1::{PolicyRule, Role, RoleBinding, RoleRef, Subject},
+        core::v1::{
+            Container, ContainerPort, EnvVar, HTTPGetAction, PodSpec, PodTemplateSpec, Probe,
+            ServiceAccount,
+        },
+        rbac::v1::{
+            ClusterRole, ClusterRoleBinding, PolicyRule, Role, RoleBinding, RoleRef, Subject,
+        },
     },
-    apimachinery::pkg::apis::meta::v1::LabelSelector,
+    apimachinery::pkg::{apis::meta::v1::LabelSelector, util::intstr::IntOrString},
 };
-use kube::core::ObjectMeta;
+use kube::{core::ObjectMeta, Resource};
 use std::collections::BTreeMap;
 
 pub struct DeploymentBuilder {
@@ -18,6 +23,14 @@ pub struct DeploymentBuilder {
     namespace: String,
     image: String,
     update_strategy: UpdateStrategy,
+    /// The operator reconciles functions in all namespaces and needs cluster wide permissions
+    all_namespaces: bool,
+    /// The operator reconciles functions in these namespaces and needs cluster wide permissions
+    functions_namespaces: Vec<String>,
+    /// The operator verifies runtime classes and needs permission to get them, which are cluster scoped
+    verify_runtime_class: bool,
+    /// The operator checks this namespace for shared secrets and needs permission to list them there
+    secrets_namespace: Option<String>,
 }
 
 impl DeploymentBuilder {
@@ -26,15 +39,58 @@ impl DeploymentBuilder {
         namespace: String,
         image: String,
         update_strategy: UpdateStrategy,
+        all_namespaces: bool,
+        functions_namespaces: Vec<String>,
+        verify_runtime_class: bool,
     ) -> Self {
         Self {
             app_name,
             namespace,
             image,
             update_strategy,
+            all_namespaces,
+            functions_namespaces,
+            verify_runtime_class,
+            secrets_namespace: None,
         }
     }
 
+    pub fn with_secrets_namespace(mut self, secrets_namespace: Option<String>) -> Self {
+        self.secrets_namespace = secrets_namespace;
+        self
+    }
+
+    /// Permissions are granted with a cluster role and cluster role binding instead of a role and role binding
+    pub fn is_cluster_scoped(&self) -> bool {
+        self.all_namespaces || !self.functions_namespaces.is_empty()
+    }
+
+    /// Runtime classes can only be granted cluster wide,
+    /// a namespaced operator verifying them gets an extra cluster role and cluster role binding for them
+    pub fn has_runtime_class_cluster_role(&self) -> bool {
+        self.verify_runtime_class && !self.is_cluster_scoped()
+    }
+
+    /// The namespaces in which the operator gets an extra role and role binding for secrets, with the granted verbs.
+    ///
+    /// A namespaced operator lists secrets in the secrets namespace, if it differs from the functions namespace.
+    /// A cluster scoped operator lists secrets cluster wide, but only watches them in the set of namespaces it reconciles
+    fn to_secrets_role_namespaces(&self) -> Vec<(&String, &'static str)> {
+        if self.is_cluster_scoped() {
+            return self
+                .functions_namespaces
+                .iter()
+                .map(|namespace| (namespace, "watch"))
+                .collect();
+        }
+
+        self.secrets_namespace
+            .iter()
+            .filter(|secrets_namespace| *secrets_namespace != &self.namespace)
+            .map(|secrets_namespace| (secrets_namespace, "list"))
+            .collect()
+    }
+
     fn to_labels(&self) -> BTreeMap<String, String> {
         [("app".to_string(), self.to_app_name())].into()
     }
@@ -59,30 +115,249 @@ impl DeploymentBuilder {
         format!("{}-rolebinding", self.app_name)
     }
 
-    pub fn to_yaml_string(&self) -> Result<String, serde_yaml::Error> {
-        let mut string = String::new();
+    pub fn to_runtime_class_role_name(&self) -> String {
+        format!("{}-runtimeclasses-role", self.app_name)
+    }
+
+    pub fn to_runtime_class_role_binding_name(&self) -> String {
+        format!("{}-runtimeclasses-rolebinding", self.app_name)
+    }
 
-        let service_account = ServiceAccount::from(self);
-        let service_account_str = serde_yaml::to_string(&service_account)?;
+    pub fn to_secrets_role_name(&self) -> String {
+        format!("{}-secrets-role", self.app_name)
+    }
 
-        let role = Role::from(self);
-        let role_str = serde_yaml::to_string(&role)?;
+    pub fn to_secrets_role_binding_name(&self) -> String {
+        format!("{}-secrets-rolebinding", self.app_name)
+    }
 
-        let role_binding = RoleBinding::from(self);
-        let role_binding_str = serde_yaml::to_string(&role_binding)?;
+    /// The Kubernetes resources as separate YAML documents, each with a file name in apply order
+    pub fn to_yaml_documents(&self) -> Result<Vec<(&'static str, String)>, serde_yaml::Error> {
+        let mut documents = vec![(
+            "serviceaccount.yaml",
+            serde_yaml::to_string(&ServiceAccount::from(self))?,
+        )];
 
-        let deployment = Deployment::from(self);
-        let deployment_str = serde_yaml::to_string(&deployment)?;
+        if self.is_cluster_scoped() {
+            documents.push((
+                "clusterrole.yaml",
+                serde_yaml::to_string(&ClusterRole::from(self))?,
+            ));
+            documents.push((
+                "clusterrolebinding.yaml",
+                serde_yaml::to_string(&ClusterRoleBinding::from(self))?,
+            ));
+        } else {
+            documents.push(("role.yaml", serde_yaml::to_string(&Role::from(self))?));
+            documents.push((
+                "rolebinding.yaml",
+                serde_yaml::to_string(&RoleBinding::from(self))?,
+            ));
+        }
+
+        let (roles, role_bindings): (Vec<Role>, Vec<RoleBinding>) =
+            self.to_secrets_roles().into_iter().unzip();
+
+        if !roles.is_empty() {
+            documents.push(("secrets-role.yaml", to_yaml_list(&roles)?));
+            documents.push(("secrets-rolebinding.yaml", to_yaml_list(&role_bindings)?));
+        }
+
+        if self.has_runtime_class_cluster_role() {
+            documents.push((
+                "runtimeclass-clusterrole.yaml",
+                serde_yaml::to_string(&self.to_runtime_class_cluster_role())?,
+            ));
+            documents.push((
+                "runtimeclass-clusterrolebinding.yaml",
+                serde_yaml::to_string(&self.to_runtime_class_cluster_role_binding())?,
+            ));
+        }
 
-        string.push_str(&service_account_str);
-        string.push_str("---\n");
-        string.push_str(&role_str);
-        string.push_str("---\n");
-        string.push_str(&role_binding_str);
-        string.push_str("---\n");
-        string.push_str(&deployment_str);
+        documents.push((
+            "deployment.yaml",
+            serde_yaml::to_string(&Deployment::from(self))?,
+        ));
 
-        Ok(string)
+        Ok(documents)
+    }
+
+    fn to_policy_rules(&self) -> Vec<PolicyRule> {
+        // A cluster scoped operator must not watch secrets cluster wide, see `to_secrets_roles`
+        let secrets_verbs = match self.is_cluster_scoped() {
+            true => vec![String::from("list")],
+            false => vec![String::from("list"), String::from("watch")],
+        };
+
+        vec![
+            PolicyRule {
+                api_groups: Some(vec![OpenFaaSFunction::group(&()).into_owned()]),
+                resources: Some(vec![
+                    OpenFaaSFunction::plural(&()).into_owned(),
+                    format!("{}/status", OpenFaaSFunction::plural(&())),
+                    format!("{}/finalizers", OpenFaaSFunction::plural(&())),
+                ]),
+                verbs: vec![String::from("*")],
+                ..Default::default()
+            },
+            PolicyRule {
+                api_groups: Some(vec![String::from("")]),
+                resources: Some(vec![String::from("namespaces")]),
+                verbs: vec![String::from("get")],
+                ..Default::default()
+            },
+            PolicyRule {
+                api_groups: Some(vec![String::from("")]),
+                resources: Some(vec![String::from("secrets")]),
+                verbs: secrets_verbs,
+                ..Default::default()
+            },
+            PolicyRule {
+                api_groups: Some(vec![String::from("")]),
+                resources: Some(vec![String::from("serviceaccounts")]),
+                verbs: vec![String::from("get")],
+                ..Default::default()
+            },
+            PolicyRule {
+                api_groups: Some(vec![String::from("")]),
+                resources: Some(vec![String::from("configmaps")]),
+                verbs: vec![String::from("list")],
+                ..Default::default()
+            },
+            PolicyRule {
+                api_groups: Some(vec![String::from("apps")]),
+                resources: Some(vec![String::from("deployments")]),
+                verbs: vec![String::from("*")],
+                ..Default::default()
+            },
+            PolicyRule {
+                api_groups: Some(vec![String::from("")]),
+                resources: Some(vec![String::from("services")]),
+                verbs: vec![String::from("*")],
+                ..Default::default()
+            },
+            PolicyRule {
+                api_groups: Some(vec![String::from("policy")]),
+                resources: Some(vec![String::from("poddisruptionbudgets")]),
+                verbs: vec![String::from("*")],
+                ..Default::default()
+            },
+            PolicyRule {
+                api_groups: Some(vec![String::from("autoscaling")]),
+                resources: Some(vec![String::from("horizontalpodautoscalers")]),
+                verbs: vec![String::from("list")],
+                ..Default::default()
+            },
+            PolicyRule {
+                api_groups: Some(vec![String::from("networking.k8s.io")]),
+                resources: Some(vec![String::from("networkpolicies")]),
+                verbs: vec![String::from("*")],
+                ..Default::default()
+            },
+            PolicyRule {
+                api_groups: Some(vec![String::from("coordination.k8s.io")]),
+                resources: Some(vec![String::from("leases")]),
+                verbs: vec![
+                    String::from("get"),
+                    String::from("create"),
+                    String::from("update"),
+                ],
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn to_runtime_class_policy_rule(&self) -> PolicyRule {
+        PolicyRule {
+            api_groups: Some(vec![String::from("node.k8s.io")]),
+            resources: Some(vec![String::from("runtimeclasses")]),
+            verbs: vec![String::from("get")],
+            ..Default::default()
+        }
+    }
+
+    /// The cluster role granting a namespaced operator access to runtime classes
+    pub fn to_runtime_class_cluster_role(&self) -> ClusterRole {
+        ClusterRole {
+            metadata: ObjectMeta {
+                name: Some(self.to_runtime_class_role_name()),
+                ..Default::default()
+            },
+            rules: Some(vec![self.to_runtime_class_policy_rule()]),
+            ..Default::default()
+        }
+    }
+
+    pub fn to_runtime_class_cluster_role_binding(&self) -> ClusterRoleBinding {
+        ClusterRoleBinding {
+            metadata: ObjectMeta {
+                name: Some(self.to_runtime_class_role_binding_name()),
+                ..Default::default()
+            },
+            subjects: Some(vec![self.to_subject()]),
+            role_ref: RoleRef {
+                kind: String::from("ClusterRole"),
+                name: self.to_runtime_class_role_name(),
+                api_group: String::from("rbac.authorization.k8s.io"),
+            },
+        }
+    }
+
+    /// The roles and role bindings granting the operator access to secrets outside of its role or cluster role
+    pub fn to_secrets_roles(&self) -> Vec<(Role, RoleBinding)> {
+        self.to_secrets_role_namespaces()
+            .into_iter()
+            .map(|(namespace, verb)| {
+                let role = Role {
+                    metadata: ObjectMeta {
+                        name: Some(self.to_secrets_role_name()),
+                        namespace: Some(namespace.clone()),
+                        ..Default::default()
+                    },
+                    rules: Some(vec![PolicyRule {
+                        api_groups: Some(vec![String::from("")]),
+                        resources: Some(vec![String::from("secrets")]),
+                        verbs: vec![String::from(verb)],
+                        ..Default::default()
+                    }]),
+                };
+
+                let role_binding = RoleBinding {
+                    metadata: ObjectMeta {
+                        name: Some(self.to_secrets_role_binding_name()),
+                        namespace: Some(namespace.clone()),
+                        ..Default::default()
+                    },
+                    subjects: Some(vec![self.to_subject()]),
+                    role_ref: RoleRef {
+                        kind: String::from("Role"),
+                        name: self.to_secrets_role_name(),
+                        api_group: String::from("rbac.authorization.k8s.io"),
+                    },
+                };
+
+                (role, role_binding)
+            })
+            .collect()
+    }
+
+    fn to_subject(&self) -> Subject {
+        Subject {
+            kind: String::from("ServiceAccount"),
+            name: self.to_service_account_name(),
+            namespace: Some(self.namespace.clone()),
+            ..Default::default()
+        }
+    }
+
+    pub fn to_yaml_string(&self) -> Result<String, serde_yaml::Error> {
+        let documents: Vec<String> = self
+            .to_yaml_documents()?
+            .into_iter()
+            .map(|(_, document)| document)
+            .collect();
+
+        Ok(documents.join("---\n"))
     }
 }
 
@@ -107,42 +382,26 @@ impl From<&DeploymentBuilder> for Role {
                 namespace: Some(value.namespace.clone()),
                 ..Default::default()
             },
-            rules: Some(vec![
-                PolicyRule {
-                    api_groups: Some(vec![String::from(GROUP)]),
-                    resources: Some(vec![
-                        String::from(PLURAL),
-                        format!("{}/status", PLURAL),
-                        format!("{}/finalizers", PLURAL),
-                    ]),
-                    verbs: vec![String::from("*")],
-                    ..Default::default()
-                },
-                PolicyRule {
-                    api_groups: Some(vec![String::from("")]),
-                    resources: Some(vec![String::from("namespaces")]),
-                    verbs: vec![String::from("get")],
-                    ..Default::default()
-                },
-                PolicyRule {
-                    api_groups: Some(vec![String::from("")]),
-                    resources: Some(vec![String::from("secrets")]),
-                    verbs: vec![String::from("list")],
-                    ..Default::default()
-                },
-                PolicyRule {
-                    api_groups: Some(vec![String::from("apps")]),
-                    resources: Some(vec![String::from("deployments")]),
-                    verbs: vec![String::from("*")],
-                    ..Default::default()
-                },
-                PolicyRule {
-                    api_groups: Some(vec![String::from("")]),
-                    resources: Some(vec![String::from("services")]),
-                    verbs: vec![String::from("*")],
-                    ..Default::default()
-                },
-            ]),
+            rules: Some(value.to_policy_rules()),
+        }
+    }
+}
+
+impl From<&DeploymentBuilder> for ClusterRole {
+    fn from(value: &DeploymentBuilder) -> Self {
+        let mut rules = value.to_policy_rules();
+
+        if value.verify_runtime_class {
+            rules.push(value.to_runtime_class_policy_rule());
+        }
+
+        ClusterRole {
+            metadata: ObjectMeta {
+                name: Some(value.to_role_name()),
+                ..Default::default()
+            },
+            rules: Some(rules),
+            ..Default::default()
         }
     }
 }
@@ -155,12 +414,7 @@ impl From<&DeploymentBuilder> for RoleBinding {
                 namespace: Some(value.namespace.clone()),
                 ..Default::default()
             },
-            subjects: Some(vec![Subject {
-                kind: String::from("ServiceAccount"),
-                name: value.to_service_account_name(),
-                namespace: Some(value.namespace.clone()),
-                ..Default::default()
-            }]),
+            subjects: Some(vec![value.to_subject()]),
             role_ref: RoleRef {
                 kind: String::from("Role"),
                 name: value.to_role_name(),
@@ -170,6 +424,23 @@ impl From<&DeploymentBuilder> for RoleBinding {
     }
 }
 
+impl From<&DeploymentBuilder> for ClusterRoleBinding {
+    fn from(value: &DeploymentBuilder) -> Self {
+        ClusterRoleBinding {
+            metadata: ObjectMeta {
+                name: Some(value.to_role_binding_name()),
+                ..Default::default()
+            },
+            subjects: Some(vec![value.to_subject()]),
+            role_ref: RoleRef {
+                kind: String::from("ClusterRole"),
+                name: value.to_role_name(),
+                api_group: String::from("rbac.authorization.k8s.io"),
+            },
+        }
+    }
+}
+
 impl From<&DeploymentBuilder> for Deployment {
     fn from(value: &DeploymentBuilder) -> Self {
         Deployment {
@@ -197,12 +468,23 @@ impl From<&DeploymentBuilder> for Deployment {
                             args: Some(Cli::operator_controller_run_args(
                                 value.namespace.clone(),
                                 value.update_strategy.clone(),
+                                value.all_namespaces,
+                                value.functions_namespaces.clone(),
+                                value.verify_runtime_class,
+                                value.secrets_namespace.clone(),
                             )),
                             env: Some(vec![EnvVar {
                                 name: String::from("RUST_LOG"),
                                 value: Some(format!("{PKG_NAME}=info,kube=off")),
                                 ..Default::default()
                             }]),
+                            ports: Some(vec![ContainerPort {
+                                name: Some(String::from("metrics")),
+                                container_port: METRICS_DEFAULT_PORT,
+                                ..Default::default()
+                            }]),
+                            liveness_probe: Some(http_probe("/healthz")),
+                            readiness_probe: Some(http_probe("/readyz")),
                             ..Default::default()
                         }],
                         ..Default::default()
@@ -214,3 +496,347 @@ impl From<&DeploymentBuilder> for Deployment {
         }
     }
 }
+
+/// Probes the operator's metrics server on the given path
+fn http_probe(path: &str) -> Probe {
+    Probe {
+        http_get: Some(HTTPGetAction {
+            path: Some(String::from(path)),
+            port: IntOrString::Int(METRICS_DEFAULT_PORT),
+            ..Default::default()
+        }),
+        period_seconds: Some(10),
+        failure_threshold: Some(3),
+        ..Default::default()
+    }
+}
+
+/// Resources of the same kind in one YAML file, as separate documents
+fn to_yaml_list<T: serde::Serialize>(resources: &[T]) -> Result<String, serde_yaml::Error> {
+    let documents = resources
+        .iter()
+        .map(serde_yaml::to_string)
+        .collect::<Result<Vec<String>, _>>()?;
+
+    Ok(documents.join("---\n"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use kube::CustomResourceExt;
+
+    #[test]
+    fn rbac_group_matches_crd_group() {
+        let crd = OpenFaaSFunction::crd();
+        let builder = DeploymentBuilder::new(
+            String::from("operator"),
+            String::from("openfaas-fn"),
+            String::from("image"),
+            UpdateStrategy::default(),
+            false,
+            vec![],
+            false,
+        );
+
+        let role = Role::from(&builder);
+        let rule = role
+            .rules
+            .unwrap_or_default()
+            .into_iter()
+            .find(|rule| {
+                rule.resources
+                    .as_ref()
+                    .is_some_and(|resources| resources.contains(&crd.spec.names.plural))
+            })
+            .expect("Role has no rule for the crd");
+
+        assert_eq!(rule.api_groups, Some(vec![crd.spec.group]));
+    }
+
+    #[test]
+    fn yaml_string_joins_yaml_documents() {
+        let builder = DeploymentBuilder::new(
+            String::from("operator"),
+            String::from("openfaas-fn"),
+            String::from("image"),
+            UpdateStrategy::default(),
+            false,
+            vec![],
+            false,
+        );
+
+        let documents = builder.to_yaml_documents().expect("Failed to serialize");
+        let names: Vec<&str> = documents.iter().map(|(name, _)| *name).collect();
+
+        assert_eq!(
+            names,
+            [
+                "serviceaccount.yaml",
+                "role.yaml",
+                "rolebinding.yaml",
+                "deployment.yaml"
+            ]
+        );
+        assert_eq!(
+            builder.to_yaml_string().expect("Failed to serialize"),
+            documents
+                .into_iter()
+                .map(|(_, document)| document)
+                .collect::<Vec<_>>()
+                .join("---\n")
+        );
+    }
+
+    #[test]
+    fn all_namespaces_grants_cluster_wide_permissions() {
+        let builder = DeploymentBuilder::new(
+            String::from("operator"),
+            String::from("openfaas-fn"),
+            String::from("image"),
+            UpdateStrategy::default(),
+            true,
+            vec![],
+            false,
+        );
+
+        let documents = builder.to_yaml_documents().expect("Failed to serialize");
+        let names: Vec<&str> = documents.iter().map(|(name, _)| *name).collect();
+
+        assert_eq!(
+            names,
+            [
+                "serviceaccount.yaml",
+                "clusterrole.yaml",
+                "clusterrolebinding.yaml",
+                "deployment.yaml"
+            ]
+        );
+
+        let binding = ClusterRoleBinding::from(&builder);
+        assert_eq!(binding.role_ref.kind, "ClusterRole");
+        assert_eq!(
+            binding.subjects.unwrap_or_default()[0].namespace.as_deref(),
+            Some("openfaas-fn")
+        );
+
+        let args = Deployment::from(&builder)
+            .spec
+            .and_then(|spec| spec.template.spec)
+            .and_then(|pod_spec| pod_spec.containers[0].args.clone())
+            .unwrap_or_default();
+        assert!(args.contains(&String::from("--all-namespaces")));
+    }
+
+    #[test]
+    fn runtime_classes_are_granted_cluster_wide_only() {
+        let builder = DeploymentBuilder::new(
+            String::from("operator"),
+            String::from("openfaas-fn"),
+            String::from("image"),
+            UpdateStrategy::default(),
+            false,
+            vec![],
+            true,
+        );
+
+        let grants_runtime_classes = |rules: Option<Vec<PolicyRule>>| {
+            rules.unwrap_or_default().iter().any(|rule| {
+                rule.resources
+                    .as_ref()
+                    .is_some_and(|resources| resources.contains(&String::from("runtimeclasses")))
+            })
+        };
+
+        assert!(!grants_runtime_classes(Role::from(&builder).rules));
+        assert!(grants_runtime_classes(
+            builder.to_runtime_class_cluster_role().rules
+        ));
+
+        let documents = builder.to_yaml_documents().expect("Failed to serialize");
+        let names: Vec<&str> = documents.iter().map(|(name, _)| *name).collect();
+
+        assert_eq!(
+            names,
+            [
+                "serviceaccount.yaml",
+                "role.yaml",
+                "rolebinding.yaml",
+                "runtimeclass-clusterrole.yaml",
+                "runtimeclass-clusterrolebinding.yaml",
+                "deployment.yaml"
+            ]
+        );
+
+        let binding = builder.to_runtime_class_cluster_role_binding();
+        assert_eq!(binding.role_ref.name, builder.to_runtime_class_role_name());
+    }
+
+    #[test]
+    fn secrets_namespace_is_granted_with_a_role() {
+        let builder = DeploymentBuilder::new(
+            String::from("operator"),
+            String::from("openfaas-fn"),
+            String::from("image"),
+            UpdateStrategy::default(),
+            false,
+            vec![],
+            false,
+        )
+        .with_secrets_namespace(Some(String::from("shared-secrets")));
+
+        let mut roles = builder.to_secrets_roles();
+        assert_eq!(roles.len(), 1);
+
+        let (role, binding) = roles.remove(0);
+        assert_eq!(role.metadata.namespace.as_deref(), Some("shared-secrets"));
+        assert_eq!(
+            role.rules.unwrap_or_default()[0].resources,
+            Some(vec![String::from("secrets")])
+        );
+
+        assert_eq!(
+            binding.metadata.namespace.as_deref(),
+            Some("shared-secrets")
+        );
+        assert_eq!(binding.role_ref.name, builder.to_secrets_role_name());
+        assert_eq!(
+            binding.subjects.unwrap_or_default()[0].namespace.as_deref(),
+            Some("openfaas-fn")
+        );
+
+        let documents = builder.to_yaml_documents().expect("Failed to serialize");
+        let names: Vec<&str> = documents.iter().map(|(name, _)| *name).collect();
+
+        assert_eq!(
+            names,
+            [
+                "serviceaccount.yaml",
+                "role.yaml",
+                "rolebinding.yaml",
+                "secrets-role.yaml",
+                "secrets-rolebinding.yaml",
+                "deployment.yaml"
+            ]
+        );
+
+        let args = Deployment::from(&builder)
+            .spec
+            .and_then(|spec| spec.template.spec)
+            .and_then(|pod_spec| pod_spec.containers[0].args.clone())
+            .unwrap_or_default();
+        assert!(args
+            .windows(2)
+            .any(|pair| pair == ["--secrets-namespace", "shared-secrets"]));
+
+        // The functions namespace and cluster wide operators are covered by the main role
+        let same_namespace = DeploymentBuilder::new(
+            String::from("operator"),
+            String::from("openfaas-fn"),
+            String::from("image"),
+            UpdateStrategy::default(),
+            false,
+            vec![],
+            false,
+        )
+        .with_secrets_namespace(Some(String::from("openfaas-fn")));
+        assert!(same_namespace.to_secrets_roles().is_empty());
+
+        let cluster_scoped = DeploymentBuilder::new(
+            String::from("operator"),
+            String::from("openfaas-fn"),
+            String::from("image"),
+            UpdateStrategy::default(),
+            true,
+            vec![],
+            false,
+        )
+        .with_secrets_namespace(Some(String::from("shared-secrets")));
+        assert!(cluster_scoped.to_secrets_roles().is_empty());
+    }
+
+    #[test]
+    fn secrets_are_watched_only_in_the_functions_namespaces() {
+        let builder = DeploymentBuilder::new(
+            String::from("operator"),
+            String::from("openfaas"),
+            String::from("image"),
+            UpdateStrategy::default(),
+            false,
+            vec![String::from("team-a"), String::from("team-b")],
+            false,
+        );
+
+        let secrets_rule = |rules: Option<Vec<PolicyRule>>| {
+            rules
+                .unwrap_or_default()
+                .into_iter()
+                .find(|rule| rule.resources == Some(vec![String::from("secrets")]))
+                .expect("Missing secrets rule")
+        };
+
+        assert_eq!(
+            secrets_rule(ClusterRole::from(&builder).rules).verbs,
+            [String::from("list")]
+        );
+
+        let roles = builder.to_secrets_roles();
+        let namespaces: Vec<_> = roles
+            .iter()
+            .map(|(role, binding)| {
+                assert_eq!(role.metadata.namespace, binding.metadata.namespace);
+                assert_eq!(
+                    secrets_rule(role.rules.clone()).verbs,
+                    [String::from("watch")]
+                );
+
+                role.metadata.namespace.as_deref()
+            })
+            .collect();
+        assert_eq!(namespaces, [Some("team-a"), Some("team-b")]);
+
+        let documents = builder.to_yaml_documents().expect("Failed to serialize");
+        let (_, roles_yaml) = documents
+            .iter()
+            .find(|(name, _)| *name == "secrets-role.yaml")
+            .expect("Missing secrets roles");
+        assert_eq!(roles_yaml.matches("kind: Role").count(), 2);
+    }
+
+    #[test]
+    fn container_probes_the_metrics_server() {
+        let builder = DeploymentBuilder::new(
+            String::from("operator"),
+            String::from("openfaas-fn"),
+            String::from("image"),
+            UpdateStrategy::default(),
+            false,
+            vec![],
+            false,
+        );
+
+        let container = Deployment::from(&builder)
+            .spec
+            .and_then(|spec| spec.template.spec)
+            .map(|pod_spec| pod_spec.containers[0].clone())
+            .expect("Missing container");
+
+        let probe_path = |probe: Option<Probe>| {
+            let http_get = probe
+                .and_then(|probe| probe.http_get)
+                .expect("Missing probe");
+            assert_eq!(http_get.port, IntOrString::Int(METRICS_DEFAULT_PORT));
+            http_get.path
+        };
+
+        assert_eq!(
+            probe_path(container.liveness_probe).as_deref(),
+            Some("/healthz")
+        );
+        assert_eq!(
+            probe_path(container.readiness_probe).as_deref(),
+            Some("/readyz")
+        );
+        assert!(crate::consts::METRICS_DEFAULT_ADDR.ends_with(&format!(":{METRICS_DEFAULT_PORT}")));
+    }
+}
@@ -1,9 +1,36 @@
 use crate::crds::defs::{
-    FunctionIntoDeploymentError, FunctionIntoServiceError, OpenFaasFunctionPossibleStatus,
+    FunctionIntoDeploymentError, FunctionIntoNetworkPolicyError,
+    FunctionIntoPodDisruptionBudgetError, FunctionIntoServiceError, OpenFaasFunctionPossibleStatus,
 };
 use kube::Error as KubeError;
 use thiserror::Error as ThisError;
 
+#[derive(ThisError, Debug)]
+pub enum CheckFunctionsNamespaceError {
+    #[error("Functions namespace {0} does not exist.")]
+    NotFound(String),
+    #[error("Failed to get functions namespace: {0}")]
+    Get(#[source] KubeError),
+}
+
+#[derive(ThisError, Debug)]
+pub enum CheckPermissionsError {
+    #[error("Failed to review permissions: {0}")]
+    Review(#[source] KubeError),
+    #[error("Missing permissions: {}", .0.join(", "))]
+    Missing(Vec<String>),
+}
+
+#[derive(ThisError, Debug)]
+pub enum LeaderElectionError {
+    #[error("Lease was taken by another replica.")]
+    Lost,
+    #[error("Failed to renew lease: {0}")]
+    Renew(#[source] KubeError),
+    #[error("Lease request timed out.")]
+    Timeout,
+}
+
 #[derive(ThisError, Debug)]
 pub enum ReconcileError {
     #[error("Resource has no namespace.")]
@@ -12,20 +39,86 @@ pub enum ReconcileError {
     Apply(#[source] ApplyError),
 }
 
+impl ReconcileError {
+    /// The failed step of the reconciliation, used as a metric label
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ReconcileError::Namespace => "namespace",
+            ReconcileError::Apply(error) => match error {
+                ApplyError::OwnerReference => "owner_reference",
+                ApplyError::ResourceNamespace(_) => "resource_namespace",
+                ApplyError::FunctionNamespace(_) => "function_namespace",
+                ApplyError::Spec(_) => "spec",
+                ApplyError::Deployment(_) => "deployment",
+                ApplyError::Service(_) => "service",
+                ApplyError::PodDisruptionBudget(_) => "pod_disruption_budget",
+                ApplyError::NetworkPolicy(_) => "network_policy",
+                ApplyError::Status(_) => "status",
+            },
+        }
+    }
+}
+
+#[derive(ThisError, Debug)]
+pub enum ReconcileOnceError {
+    #[error("Failed to get resource: {0}")]
+    Get(#[source] KubeError),
+    #[error("Resource {0} not found.")]
+    NotFound(String),
+    #[error("Failed to reconcile resource: {0}")]
+    Reconcile(#[source] ReconcileError),
+}
+
+#[derive(ThisError, Debug)]
+pub enum MigrateError {
+    #[error("Failed to list resources: {0}")]
+    List(#[source] KubeError),
+    #[error("Failed to get deployment: {0}")]
+    GetDeployment(#[source] KubeError),
+    #[error("Failed to serialize spec: {0}")]
+    Serialize(#[source] serde_json::Error),
+    #[error("Failed to patch deployment: {0}")]
+    Patch(#[source] KubeError),
+}
+
 #[derive(ThisError, Debug)]
 pub enum ApplyError {
+    #[error("Failed to get owner reference")]
+    OwnerReference,
     #[error("Failed to check resource namespace: {0}")]
     ResourceNamespace(#[source] CheckResourceNamespaceError),
     #[error("Failed to check function namespace: {0}")]
     FunctionNamespace(#[source] CheckFunctionNamespaceError),
+    #[error("Failed to check spec: {0}")]
+    Spec(#[source] CheckSpecError),
     #[error("Deployment error: {0}")]
     Deployment(#[source] DeploymentError),
     #[error("Service error: {0}")]
     Service(#[source] ServiceError),
+    #[error("Pod disruption budget error: {0}")]
+    PodDisruptionBudget(#[source] PodDisruptionBudgetError),
+    #[error("Network policy error: {0}")]
+    NetworkPolicy(#[source] NetworkPolicyError),
     #[error("Status error: {0}")]
     Status(#[source] DeployedStatusError),
 }
 
+#[derive(ThisError, Debug)]
+pub enum CheckSpecError {
+    #[error("Error getting status: {0}")]
+    GetStatus(#[source] KubeError),
+    #[error("Error setting status: {0}")]
+    SetStatus(#[source] StatusError),
+}
+
+#[derive(ThisError, Debug)]
+pub enum ReconcileBackoffError {
+    #[error("Error getting status: {0}")]
+    GetStatus(#[source] KubeError),
+    #[error("Error setting status: {0}")]
+    SetStatus(#[source] StatusError),
+}
+
 #[derive(ThisError, Debug)]
 pub enum CheckResourceNamespaceError {
     #[error("Error getting status: {0}")]
@@ -62,6 +155,28 @@ pub enum SetStatusError {
 pub enum CheckSecretsError {
     #[error("Error listing secrets: {0}")]
     List(#[source] KubeError),
+    #[error("Error listing secrets in the secrets namespace: {0}")]
+    ListShared(#[source] KubeError),
+    #[error("Error setting status: {0}")]
+    SetStatus(#[source] StatusError),
+}
+
+#[derive(ThisError, Debug)]
+pub enum CheckConfigMapsError {
+    #[error("Error listing config maps: {0}")]
+    List(#[source] KubeError),
+    #[error("Error getting status: {0}")]
+    GetStatus(#[source] KubeError),
+    #[error("Error setting status: {0}")]
+    SetStatus(#[source] StatusError),
+}
+
+#[derive(ThisError, Debug)]
+pub enum CheckRuntimeClassError {
+    #[error("Error getting runtime class: {0}")]
+    Get(#[source] KubeError),
+    #[error("Error getting status: {0}")]
+    GetStatus(#[source] KubeError),
     #[error("Error setting status: {0}")]
     SetStatus(#[source] StatusError),
 }
@@ -88,18 +203,32 @@ pub enum CheckDeploymentError {
     SetStatus(#[source] StatusError),
     #[error("Failed to create deployment: {0}")]
     Create(#[source] CreateDeploymentError),
+    #[error("Failed to patch deployment metadata: {0}")]
+    PatchMetadata(#[source] KubeError),
+    #[error("Failed to adopt deployment: {0}")]
+    Adopt(#[source] KubeError),
 }
 
 #[derive(ThisError, Debug)]
 pub enum CreateDeploymentError {
     #[error("Failed to check secrets: {0}")]
     Secrets(#[source] CheckSecretsError),
+    #[error("Failed to check config maps: {0}")]
+    ConfigMaps(#[source] CheckConfigMapsError),
+    #[error("Failed to check runtime class: {0}")]
+    RuntimeClass(#[source] CheckRuntimeClassError),
+    #[error("Failed to list horizontal pod autoscalers: {0}")]
+    Autoscaler(#[source] KubeError),
+    #[error("Failed to get image pull secrets service account: {0}")]
+    ServiceAccount(#[source] KubeError),
     #[error("Failed to generate deployment: {0}")]
     Generate(#[source] FunctionIntoDeploymentError),
     #[error("Failed to apply deployment: {0}")]
     Apply(#[source] KubeError),
     #[error("Failed to replace deployment: {0}")]
     Replace(#[source] KubeError),
+    #[error("Failed to patch deployment: {0}")]
+    Patch(#[source] KubeError),
     #[error("Error getting status: {0}")]
     GetStatus(#[source] KubeError),
     #[error("Error setting status: {0}")]
@@ -110,6 +239,8 @@ pub enum CreateDeploymentError {
 pub enum DeleteDeploymentsError {
     #[error("Error listing deployments: {0}")]
     List(#[source] KubeError),
+    #[error("Error getting deployment: {0}")]
+    Get(#[source] KubeError),
     #[error("Error deleting deployment: {0}")]
     Delete(#[source] KubeError),
 }
@@ -134,6 +265,10 @@ pub enum CreateServiceError {
     Generate(#[source] FunctionIntoServiceError),
     #[error("Failed to apply deployment: {0}")]
     Apply(#[source] KubeError),
+    #[error("Error getting status: {0}")]
+    GetStatus(#[source] KubeError),
+    #[error("Error setting status: {0}")]
+    SetStatus(#[source] StatusError),
 }
 
 #[derive(ThisError, Debug)]
@@ -142,6 +277,12 @@ pub enum CheckServiceError {
     GetStatus(#[source] KubeError),
     #[error("Error setting status: {0}")]
     SetStatus(#[source] StatusError),
+    #[error("Failed to generate service: {0}")]
+    Generate(#[source] FunctionIntoServiceError),
+    #[error("Failed to patch service: {0}")]
+    Patch(#[source] KubeError),
+    #[error("Failed to adopt service: {0}")]
+    Adopt(#[source] KubeError),
 }
 
 #[derive(ThisError, Debug)]
@@ -152,8 +293,50 @@ pub enum DeleteServicesError {
     Delete(#[source] KubeError),
 }
 
+#[derive(ThisError, Debug)]
+pub enum PodDisruptionBudgetError {
+    #[error("Failed to get pod disruption budget: {0}")]
+    Get(#[source] KubeError),
+    #[error("Failed to get owner reference")]
+    OwnerReference,
+    #[error("Failed to generate pod disruption budget: {0}")]
+    Generate(#[source] FunctionIntoPodDisruptionBudgetError),
+    #[error("Failed to create pod disruption budget: {0}")]
+    Create(#[source] KubeError),
+    #[error("Failed to replace pod disruption budget: {0}")]
+    Replace(#[source] KubeError),
+    #[error("Failed to delete pod disruption budget: {0}")]
+    Delete(#[source] KubeError),
+    #[error("Error getting status: {0}")]
+    GetStatus(#[source] KubeError),
+    #[error("Error setting status: {0}")]
+    SetStatus(#[source] StatusError),
+}
+
+#[derive(ThisError, Debug)]
+pub enum NetworkPolicyError {
+    #[error("Failed to get network policy: {0}")]
+    Get(#[source] KubeError),
+    #[error("Failed to generate network policy: {0}")]
+    Generate(#[source] FunctionIntoNetworkPolicyError),
+    #[error("Failed to create network policy: {0}")]
+    Create(#[source] KubeError),
+    #[error("Failed to replace network policy: {0}")]
+    Replace(#[source] KubeError),
+    #[error("Failed to delete network policy: {0}")]
+    Delete(#[source] KubeError),
+    #[error("Error getting status: {0}")]
+    GetStatus(#[source] KubeError),
+    #[error("Error setting status: {0}")]
+    SetStatus(#[source] StatusError),
+}
+
 #[derive(ThisError, Debug)]
 pub enum DeployedStatusError {
+    #[error("Failed to get deployment: {0}")]
+    GetDeployment(#[source] KubeError),
+    #[error("Failed to get service: {0}")]
+    GetService(#[source] KubeError),
     #[error("Error getting status: {0}")]
     GetStatus(#[source] KubeError),
     #[error("Error setting status: {0}")]
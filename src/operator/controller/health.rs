@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Health of the controller as reported by `/healthz` and `/readyz`
+#[derive(Debug, Default)]
+pub struct Health {
+    /// The replica is waiting for the lease to start the controller
+    standby: AtomicBool,
+    /// The controller has started streaming reconciliations
+    streaming: AtomicBool,
+    /// The controller has stopped streaming reconciliations
+    terminated: AtomicBool,
+}
+
+impl Health {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_standby(&self) {
+        self.standby.store(true, Ordering::Relaxed);
+    }
+
+    pub fn set_streaming(&self) {
+        self.streaming.store(true, Ordering::Relaxed);
+    }
+
+    pub fn set_terminated(&self) {
+        self.terminated.store(true, Ordering::Relaxed);
+    }
+
+    /// Alive until the controller terminates, a replica waiting for the lease is alive as well
+    pub fn is_alive(&self) -> bool {
+        !self.terminated.load(Ordering::Relaxed)
+    }
+
+    /// Ready while the controller is streaming reconciliations or the replica is waiting for the lease.
+    ///
+    /// A standby replica must be ready, otherwise a rolling update never gets past the replica holding the lease
+    pub fn is_ready(&self) -> bool {
+        (self.streaming.load(Ordering::Relaxed) || self.standby.load(Ordering::Relaxed))
+            && self.is_alive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn standby_replica_is_alive_and_ready() {
+        let health = Health::new();
+        assert!(health.is_alive());
+        assert!(!health.is_ready());
+
+        health.set_standby();
+        assert!(health.is_alive());
+        assert!(health.is_ready());
+
+        health.set_streaming();
+        assert!(health.is_ready());
+
+        health.set_terminated();
+        assert!(!health.is_alive());
+        assert!(!health.is_ready());
+    }
+}
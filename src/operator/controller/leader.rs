@@ -0,0 +1,235 @@
+use super::errors::LeaderElectionError;
+use k8s_openapi::{
+    api::coordination::v1::{Lease, LeaseSpec},
+    apimachinery::pkg::apis::meta::v1::MicroTime,
+    chrono::{self, Utc},
+};
+use kube::{api::PostParams, core::ObjectMeta, Api, Client as KubeClient, Error as KubeError};
+use tokio::time::{Duration, Instant};
+
+/// Time a lease is valid after its last renewal
+const LEASE_DURATION: Duration = Duration::from_secs(15);
+
+/// Time the leader keeps trying to renew the lease before giving it up.
+///
+/// Shorter than the lease duration, so the leader stops before another replica may take the expired lease
+const RENEW_DEADLINE: Duration = Duration::from_secs(10);
+
+/// Time between attempts to acquire or renew a lease
+const RETRY_PERIOD: Duration = Duration::from_secs(2);
+
+/// Elects a single leader among the replicas of the operator using a `Lease` object
+pub struct LeaderElection {
+    api: Api<Lease>,
+    lease_name: String,
+    identity: String,
+    post_params: PostParams,
+}
+
+impl LeaderElection {
+    /// The identity of this replica is the pod's name, taken from the POD_NAME or HOSTNAME env var
+    pub fn new(
+        client: KubeClient,
+        namespace: &str,
+        lease_name: String,
+        field_manager: String,
+    ) -> Self {
+        let identity = std::env::var("POD_NAME")
+            .or_else(|_| std::env::var("HOSTNAME"))
+            .unwrap_or_else(|_| format!("{}-{}", lease_name, std::process::id()));
+
+        Self {
+            api: Api::namespaced(client, namespace),
+            lease_name,
+            identity,
+            post_params: PostParams {
+                field_manager: Some(field_manager),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Waits until this replica holds the lease
+    pub async fn acquire(&self) {
+        tracing::info!(lease = %self.lease_name, identity = %self.identity, "Acquiring lease.");
+
+        loop {
+            match self.try_acquire_or_renew_within(RENEW_DEADLINE).await {
+                Ok(true) => {
+                    tracing::info!("Lease acquired.");
+                    return;
+                }
+                Ok(false) => {
+                    tracing::debug!("Lease is held by another replica. Retrying.");
+                }
+                Err(error) => {
+                    tracing::warn!(%error, "Failed to acquire lease. Retrying.");
+                }
+            }
+
+            tokio::time::sleep(RETRY_PERIOD).await;
+        }
+    }
+
+    /// Renews the lease until it is lost, either to another replica or because it could not be renewed in time
+    pub async fn hold(&self) -> LeaderElectionError {
+        let mut renewed_at = Instant::now();
+
+        loop {
+            tokio::time::sleep(RETRY_PERIOD).await;
+
+            let remaining = RENEW_DEADLINE.saturating_sub(renewed_at.elapsed());
+
+            match self.try_acquire_or_renew_within(remaining).await {
+                Ok(true) => {
+                    renewed_at = Instant::now();
+                }
+                Ok(false) => {
+                    return LeaderElectionError::Lost;
+                }
+                Err(error) if renewed_at.elapsed() >= RENEW_DEADLINE => {
+                    return error;
+                }
+                Err(error) => {
+                    tracing::warn!(%error, "Failed to renew lease. Retrying.");
+                }
+            }
+        }
+    }
+
+    /// Gives up the lease if this replica holds it, so another replica takes over without waiting for it to expire.
+    ///
+    /// Failures are only logged, the lease then expires on its own
+    pub async fn release(&self) {
+        match tokio::time::timeout(RENEW_DEADLINE, self.try_release()).await {
+            Ok(Ok(true)) => {
+                tracing::info!("Lease released.");
+            }
+            Ok(Ok(false)) => {
+                tracing::debug!("Lease is not held by this replica. Nothing to release.");
+            }
+            Ok(Err(error)) => {
+                tracing::warn!(%error, "Failed to release lease.");
+            }
+            Err(_) => {
+                tracing::warn!("Timed out releasing lease.");
+            }
+        }
+    }
+
+    /// Clears the holder of the lease if it is ours
+    async fn try_release(&self) -> Result<bool, KubeError> {
+        let Some(mut lease) = self.api.get_opt(&self.lease_name).await? else {
+            return Ok(false);
+        };
+
+        let spec = lease.spec.clone().unwrap_or_default();
+
+        if spec.holder_identity.as_deref() != Some(self.identity.as_str()) {
+            return Ok(false);
+        }
+
+        let now = Utc::now();
+
+        lease.spec = Some(LeaseSpec {
+            holder_identity: None,
+            lease_duration_seconds: Some(1),
+            acquire_time: Some(MicroTime(now)),
+            renew_time: Some(MicroTime(now)),
+            ..spec
+        });
+
+        match self
+            .api
+            .replace(&self.lease_name, &self.post_params, &lease)
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(KubeError::Api(response)) if response.code == 409 => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// [`Self::try_acquire_or_renew`], giving up after the timeout, e.g. if the api server hangs
+    async fn try_acquire_or_renew_within(
+        &self,
+        timeout: Duration,
+    ) -> Result<bool, LeaderElectionError> {
+        tokio::time::timeout(timeout, self.try_acquire_or_renew())
+            .await
+            .map_err(|_| LeaderElectionError::Timeout)?
+            .map_err(LeaderElectionError::Renew)
+    }
+
+    /// Takes the lease if it is missing, released, expired or already ours.
+    ///
+    /// Returns false if another replica holds the lease or took it concurrently
+    async fn try_acquire_or_renew(&self) -> Result<bool, KubeError> {
+        let now = Utc::now();
+
+        let Some(mut lease) = self.api.get_opt(&self.lease_name).await? else {
+            let lease = Lease {
+                metadata: ObjectMeta {
+                    name: Some(self.lease_name.clone()),
+                    ..Default::default()
+                },
+                spec: Some(self.to_lease_spec(now, 0)),
+            };
+
+            return match self.api.create(&self.post_params, &lease).await {
+                Ok(_) => Ok(true),
+                Err(KubeError::Api(response)) if response.code == 409 => Ok(false),
+                Err(error) => Err(error),
+            };
+        };
+
+        let spec = lease.spec.clone().unwrap_or_default();
+        let held_by_us = spec.holder_identity.as_deref() == Some(self.identity.as_str());
+        let released = spec.holder_identity.is_none();
+
+        let expired = spec
+            .renew_time
+            .as_ref()
+            .map_or(true, |MicroTime(renew_time)| {
+                let duration = spec
+                    .lease_duration_seconds
+                    .map_or(LEASE_DURATION.as_secs() as i64, i64::from);
+
+                *renew_time + chrono::Duration::seconds(duration) < now
+            });
+
+        if !held_by_us && !released && !expired {
+            return Ok(false);
+        }
+
+        lease.spec = Some(if held_by_us {
+            LeaseSpec {
+                renew_time: Some(MicroTime(now)),
+                ..spec
+            }
+        } else {
+            self.to_lease_spec(now, spec.lease_transitions.unwrap_or_default() + 1)
+        });
+
+        // The resource version of the read lease makes the replace fail if another replica took the lease meanwhile
+        match self
+            .api
+            .replace(&self.lease_name, &self.post_params, &lease)
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(KubeError::Api(response)) if response.code == 409 => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn to_lease_spec(&self, now: chrono::DateTime<Utc>, lease_transitions: i32) -> LeaseSpec {
+        LeaseSpec {
+            holder_identity: Some(self.identity.clone()),
+            lease_duration_seconds: Some(LEASE_DURATION.as_secs() as i32),
+            acquire_time: Some(MicroTime(now)),
+            renew_time: Some(MicroTime(now)),
+            lease_transitions: Some(lease_transitions),
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use prometheus::{
+    core::Collector, GaugeVec, Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGauge, Opts,
+    Registry,
+};
+
+const NAMESPACE: &str = "openfaas_functions_operator";
+
+/// Prometheus metrics of the OpenFaaS functions operator
+pub struct Metrics {
+    registry: Registry,
+    /// Seconds a function has been in the `DeploymentNotReady` state
+    pub function_not_ready_seconds: GaugeVec,
+    /// Reconciliations currently running, at the concurrency limit further changes are queued
+    pub reconciles_in_flight: IntGauge,
+    /// Reconciliations started while the concurrency limit was reached
+    pub reconciles_at_concurrency_limit_total: IntCounter,
+    /// Functions triggered for reconciliation that are waiting for their reconciliation to start
+    pub reconcile_queue_depth: IntGauge,
+    /// Finished reconciliations, failed or not
+    pub reconciles_total: IntCounter,
+    /// Failed reconciliations by the failed step
+    pub reconcile_errors_total: IntCounterVec,
+    /// Seconds a reconciliation took
+    pub reconcile_duration_seconds: Histogram,
+    /// Functions rejected because their resource's or spec's namespace is not the functions namespace
+    pub namespace_mismatch_total: IntCounterVec,
+    /// Functions waiting for secrets that do not exist in the functions namespace
+    pub secrets_not_found_total: IntCounterVec,
+    /// Functions whose deployment is owned by someone else
+    pub deployment_conflict_total: IntCounterVec,
+    /// Functions whose service is owned by someone else
+    pub service_conflict_total: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let function_not_ready_seconds = GaugeVec::new(
+            Opts::new(
+                "function_not_ready_seconds",
+                "Seconds a function's deployment has been not ready",
+            )
+            .namespace(NAMESPACE),
+            &["namespace", "function"],
+        )
+        .expect("Invalid metric");
+
+        let reconciles_in_flight = IntGauge::with_opts(
+            Opts::new(
+                "reconciles_in_flight",
+                "Number of reconciliations currently running",
+            )
+            .namespace(NAMESPACE),
+        )
+        .expect("Invalid metric");
+
+        let reconciles_at_concurrency_limit_total = IntCounter::with_opts(
+            Opts::new(
+                "reconciles_at_concurrency_limit_total",
+                "Number of reconciliations that brought the number of reconciliations in flight to the concurrency limit",
+            )
+            .namespace(NAMESPACE),
+        )
+        .expect("Invalid metric");
+
+        let reconcile_queue_depth = IntGauge::with_opts(
+            Opts::new(
+                "reconcile_queue_depth",
+                "Number of functions waiting for their reconciliation to start",
+            )
+            .namespace(NAMESPACE),
+        )
+        .expect("Invalid metric");
+
+        let reconciles_total = IntCounter::with_opts(
+            Opts::new("reconciles_total", "Number of finished reconciliations")
+                .namespace(NAMESPACE),
+        )
+        .expect("Invalid metric");
+
+        let reconcile_errors_total = IntCounterVec::new(
+            Opts::new(
+                "reconcile_errors_total",
+                "Number of failed reconciliations by the failed step",
+            )
+            .namespace(NAMESPACE),
+            &["error"],
+        )
+        .expect("Invalid metric");
+
+        let reconcile_duration_seconds = Histogram::with_opts(
+            HistogramOpts::new(
+                "reconcile_duration_seconds",
+                "Seconds a reconciliation took",
+            )
+            .namespace(NAMESPACE),
+        )
+        .expect("Invalid metric");
+
+        let namespace_mismatch_total = check_outcome_counter(
+            "namespace_mismatch_total",
+            "Number of reconciliations of functions outside the functions namespace",
+        );
+
+        let secrets_not_found_total = check_outcome_counter(
+            "secrets_not_found_total",
+            "Number of reconciliations of functions referencing missing secrets",
+        );
+
+        let deployment_conflict_total = check_outcome_counter(
+            "deployment_conflict_total",
+            "Number of reconciliations of functions whose deployment is owned by someone else",
+        );
+
+        let service_conflict_total = check_outcome_counter(
+            "service_conflict_total",
+            "Number of reconciliations of functions whose service is owned by someone else",
+        );
+
+        registry
+            .register(Box::new(function_not_ready_seconds.clone()))
+            .expect("Failed to register metric");
+
+        registry
+            .register(Box::new(reconciles_in_flight.clone()))
+            .expect("Failed to register metric");
+
+        registry
+            .register(Box::new(reconciles_at_concurrency_limit_total.clone()))
+            .expect("Failed to register metric");
+
+        registry
+            .register(Box::new(reconcile_queue_depth.clone()))
+            .expect("Failed to register metric");
+
+        registry
+            .register(Box::new(reconciles_total.clone()))
+            .expect("Failed to register metric");
+
+        registry
+            .register(Box::new(reconcile_errors_total.clone()))
+            .expect("Failed to register metric");
+
+        registry
+            .register(Box::new(reconcile_duration_seconds.clone()))
+            .expect("Failed to register metric");
+
+        for counter in [
+            &namespace_mismatch_total,
+            &secrets_not_found_total,
+            &deployment_conflict_total,
+            &service_conflict_total,
+        ] {
+            registry
+                .register(Box::new(counter.clone()))
+                .expect("Failed to register metric");
+        }
+
+        Self {
+            registry,
+            function_not_ready_seconds,
+            reconciles_in_flight,
+            reconciles_at_concurrency_limit_total,
+            reconcile_queue_depth,
+            reconciles_total,
+            reconcile_errors_total,
+            reconcile_duration_seconds,
+            namespace_mismatch_total,
+            secrets_not_found_total,
+            deployment_conflict_total,
+            service_conflict_total,
+        }
+    }
+
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Drops the series of a function that is gone
+    pub fn forget_function(&self, namespace: &str, name: &str) {
+        // Fails only if the function has no series
+        let _ = self
+            .function_not_ready_seconds
+            .remove_label_values(&[namespace, name]);
+    }
+
+    /// Drops the series of the functions for which `keep` returns false
+    pub fn retain_functions(&self, keep: impl Fn(&str, &str) -> bool) {
+        let series: Vec<(String, String)> = self
+            .function_not_ready_seconds
+            .collect()
+            .iter()
+            .flat_map(|family| family.get_metric())
+            .filter_map(|metric| {
+                let label = |name: &str| {
+                    metric
+                        .get_label()
+                        .iter()
+                        .find(|label| label.get_name() == name)
+                        .map(|label| label.get_value().to_string())
+                };
+
+                Some((label("namespace")?, label("function")?))
+            })
+            .collect();
+
+        for (namespace, name) in series {
+            if !keep(&namespace, &name) {
+                self.forget_function(&namespace, &name);
+            }
+        }
+    }
+}
+
+/// A counter of a check's outcome, labeled by the namespace of the function
+fn check_outcome_counter(name: &str, help: &str) -> IntCounterVec {
+    IntCounterVec::new(Opts::new(name, help).namespace(NAMESPACE), &["namespace"])
+        .expect("Invalid metric")
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn not_ready_series(metrics: &Metrics) -> usize {
+        metrics
+            .function_not_ready_seconds
+            .collect()
+            .iter()
+            .map(|family| family.get_metric().len())
+            .sum()
+    }
+
+    #[test]
+    fn deleted_functions_are_forgotten() {
+        let metrics = Metrics::new();
+
+        for name in ["first", "second", "third"] {
+            metrics
+                .function_not_ready_seconds
+                .with_label_values(&["openfaas-fn", name])
+                .set(1.0);
+        }
+
+        metrics.forget_function("openfaas-fn", "first");
+        assert_eq!(not_ready_series(&metrics), 2);
+
+        // Forgetting a function without series is not an error
+        metrics.forget_function("openfaas-fn", "first");
+
+        metrics.retain_functions(|_, name| name == "second");
+        assert_eq!(not_ready_series(&metrics), 1);
+        assert!(metrics
+            .function_not_ready_seconds
+            .get_metric_with_label_values(&["openfaas-fn", "second"])
+            .is_ok_and(|gauge| gauge.get() == 1.0));
+    }
+}
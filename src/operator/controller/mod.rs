@@ -1,28 +1,72 @@
 pub mod deplyoment;
-mod errors;
-
-use self::errors::*;
-use crate::crds::defs::{OpenFaaSFunction, OpenFaasFunctionPossibleStatus};
+pub mod errors;
+pub mod health;
+pub mod leader;
+pub mod metrics;
+pub mod outcome;
+pub mod preserve;
+pub mod queue;
+pub mod rbac;
+pub mod server;
+
+use self::{
+    errors::*,
+    health::Health,
+    metrics::Metrics,
+    outcome::{ReconcileOutcome, ReconcileOutcomeCallback},
+    preserve::{targets_deployment, PreserveFields, PreservedField},
+    queue::ReconcileQueue,
+    server::EVENTS_CAPACITY,
+};
+use crate::crds::defs::{
+    FunctionReadiness, FunctionSpecIntoServiceError, OpenFaaSFunction,
+    OpenFaasFunctionPossibleStatus, OpenFaasFunctionSpec, OpenFaasFunctionStatus,
+    OptionalResourcePlan, ResourceOwnership, FUNCTION_LABEL, LAST_APPLIED_ANNOTATION,
+};
+use crate::utils::ImageReference;
 use convert_case::{Case, Casing};
-use futures::stream::StreamExt;
+use futures::{
+    future,
+    stream::{self, BoxStream, Stream, StreamExt},
+    TryStreamExt,
+};
 use k8s_openapi::api::core::v1::Namespace;
 use k8s_openapi::api::{
     apps::v1::Deployment,
-    core::v1::{Secret, Service},
+    autoscaling::v2::HorizontalPodAutoscaler,
+    core::v1::{ConfigMap, LocalObjectReference, Secret, Service, ServiceAccount},
+    networking::v1::NetworkPolicy,
+    node::v1::RuntimeClass,
+    policy::v1::PodDisruptionBudget,
+};
+use k8s_openapi::{
+    apimachinery::pkg::apis::meta::v1::{ObjectMeta, OwnerReference},
+    chrono, NamespaceResourceScope,
 };
-use k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference;
 use kube::api::DeleteParams;
 use kube::{
-    api::{ListParams, PostParams},
+    api::{ListParams, PartialObjectMeta, Patch, PatchParams, PostParams},
     runtime::Controller,
-    runtime::{controller::Action, watcher::Config},
+    runtime::{
+        controller::{Action, Config as ControllerConfig, Error as ControllerError},
+        metadata_watcher, predicates,
+        reflector::{reflector, store::Writer, ObjectRef, Store},
+        watcher::{self, watcher, Config},
+        WatchStreamExt,
+    },
     Api, Client as KubeClient, Resource, ResourceExt,
 };
+use serde::de::DeserializeOwned;
 use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap, HashSet},
     fmt::{self, Display, Formatter},
-    sync::Arc,
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    sync::broadcast,
+    time::{Duration, Instant},
 };
-use tokio::time::Duration;
 use tracing::{trace_span, Instrument};
 
 /// The OpenFaaS functions operator update strategy
@@ -43,42 +87,443 @@ impl Display for UpdateStrategy {
     }
 }
 
-enum CreateDeploymentAction {
-    Create,
+/// How an outdated deployment is updated
+#[derive(Debug, Clone, Copy, clap::ValueEnum, Default, PartialEq)]
+pub enum UpdateMode {
+    /// Patch only the metadata if nothing else changed, replace the deployment otherwise
+    #[default]
+    Auto,
+    /// Always replace the deployment, guaranteeing it matches the function exactly
     Replace,
+    /// Always patch the deployment, keeping fields set by others
+    Patch,
+}
+
+impl Display for UpdateMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let debug_str = format!("{:?}", self);
+        let display_str = debug_str.to_case(Case::Kebab);
+        write!(f, "{}", display_str)
+    }
+}
+
+/// What migrating a function's deployment did, or would do on a dry run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MigrationOutcome {
+    /// The last applied annotation was written
+    Migrated,
+    /// The deployment already has the last applied annotation
+    AlreadyMigrated,
+    /// The function has no deployment yet
+    DeploymentMissing,
+    /// The deployment is not owned by the function and is left untouched
+    NotOwned,
+}
+
+impl Display for MigrationOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let debug_str = format!("{:?}", self);
+        let display_str = debug_str.to_case(Case::Kebab);
+        write!(f, "{}", display_str)
+    }
+}
+
+/// Requeue interval while the deployment of a renamed function is waited on to become ready
+const RENAME_READY_REQUEUE: Duration = Duration::from_secs(5);
+
+/// Requeue interval for functions whose reconciliation has been failing for longer than the max reconcile duration
+const RECONCILE_BACKOFF_REQUEUE: Duration = Duration::from_secs(300);
+
+/// Consecutive reconciliation errors of a single function
+struct ReconcileErrorRecord {
+    count: u32,
+    first_error_at: Instant,
+}
+
+enum CreateDeploymentAction<'a> {
+    Create,
+    /// Replaces the given live deployment
+    Replace(&'a Deployment),
+    /// Patches the live deployment with a strategic merge patch
+    Patch,
+    /// Patches the given live deployment with a JSON merge patch of the whole generated deployment
+    Merge(&'a Deployment),
+}
+
+/// Settings of the operator that apply to all functions
+#[derive(Debug, Clone)]
+pub struct OperatorConfig {
+    pub functions_namespace: String,
+    pub update_strategy: UpdateStrategy,
+    /// Overrides how outdated deployments are updated under the one-way update strategy
+    pub update_mode: UpdateMode,
+    pub field_manager: String,
+    pub max_reconcile_duration: Option<Duration>,
+    /// A namespace that is checked for secrets missing in the functions namespace
+    pub secrets_namespace: Option<String>,
+    /// Normalize image references the way Docker resolves them, e.g. nginx => docker.io/library/nginx:latest
+    pub normalize_image_references: bool,
+    /// Labels added to the pods of every function, unless the function sets them itself
+    pub default_pod_labels: BTreeMap<String, String>,
+    /// A service account in the functions namespace whose image pull secrets are added to every function's pods
+    pub image_pull_secrets_service_account: Option<String>,
+    /// Check that the runtime class of a function exists before creating its deployment
+    pub verify_runtime_class: bool,
+    /// Maximum number of functions reconciled at the same time, 0 for no limit
+    pub reconcile_concurrency: u16,
+    /// Time to wait for further changes of a function before reconciling it
+    pub reconcile_debounce: Duration,
+    /// Window over which the first reconciliations of functions created before the operator started are spread
+    pub reconcile_jitter_startup: Duration,
+    /// Number of changes per watched resource that are read ahead while the controller is busy, 0 to not read ahead
+    pub watch_buffer_size: usize,
+    /// Maximum summed size in bytes of a function's env vars
+    pub max_env_bytes: usize,
+    /// Take over resources that clearly belong to a function but lack its owner reference
+    pub adopt_orphans: bool,
+    /// Fields of the live deployment that are kept when it is replaced
+    pub preserve_fields: PreserveFields,
+    /// Keep the old deployment of a renamed function until the new one has a ready replica
+    pub wait_for_ready_on_rename: bool,
+    /// Time to wait before reconciling a function again after its reconciliation failed
+    pub error_requeue: Duration,
+    /// Cap of the exponential backoff of repeatedly failing functions
+    pub max_error_requeue: Duration,
+    /// Reconcile functions in all namespaces instead of only in the functions namespace
+    pub all_namespaces: bool,
+    /// Reconcile functions in these namespaces instead of only in the functions namespace
+    pub functions_namespaces: Vec<String>,
+    /// Name of the lease in the functions namespace used to elect a leader among replicas, no election if not set
+    pub leader_election_id: Option<String>,
+}
+
+impl OperatorConfig {
+    /// Functions are watched with cluster wide apis, either in all namespaces or in a set of namespaces
+    pub fn is_cluster_wide(&self) -> bool {
+        self.all_namespaces || !self.functions_namespaces.is_empty()
+    }
+
+    /// The namespaces whose secrets are watched, none if all namespaces are watched.
+    ///
+    /// Secrets are not watched cluster wide, functions waiting for a secret are retried instead
+    fn secrets_watch_namespaces(&self) -> Vec<String> {
+        self.required_namespaces()
+    }
+
+    /// The namespaces that are expected to exist, none if all namespaces are watched
+    fn required_namespaces(&self) -> Vec<String> {
+        match (self.all_namespaces, self.functions_namespaces.is_empty()) {
+            (true, _) => vec![],
+            (false, true) => vec![self.functions_namespace.clone()],
+            (false, false) => self.functions_namespaces.clone(),
+        }
+    }
 }
 
+#[derive(Clone)]
 struct OperatorInner {
+    client: KubeClient,
+    /// The namespace of the reconciled functions, the default namespace if all namespaces are watched
     functions_namespace: String,
     api: Api<OpenFaaSFunction>,
     deployment_api: Api<Deployment>,
     service_api: Api<Service>,
     secrets_api: Api<Secret>,
+    config_maps_api: Api<ConfigMap>,
+    pdb_api: Api<PodDisruptionBudget>,
+    network_policy_api: Api<NetworkPolicy>,
     update_strategy: UpdateStrategy,
+    update_mode: UpdateMode,
+    field_manager: String,
+    metrics: Arc<Metrics>,
+    max_reconcile_duration: Option<Duration>,
+    reconcile_errors: Arc<Mutex<HashMap<String, ReconcileErrorRecord>>>,
+    /// Secrets in the secrets namespace, if it differs from the functions namespace
+    shared_secrets_api: Option<Api<Secret>>,
+    normalize_image_references: bool,
+    default_pod_labels: BTreeMap<String, String>,
+    service_account_api: Api<ServiceAccount>,
+    image_pull_secrets_service_account: Option<String>,
+    /// Runtime classes are cluster scoped, only set if they should be verified
+    runtime_class_api: Option<Api<RuntimeClass>>,
+    reconcile_concurrency: u16,
+    reconcile_debounce: Duration,
+    reconcile_jitter_startup: Duration,
+    watch_buffer_size: usize,
+    /// Functions waiting for their reconciliation to start
+    reconcile_queue: Arc<ReconcileQueue>,
+    started_at: chrono::DateTime<chrono::Utc>,
+    /// Functions, keyed by namespace and name, whose first reconciliation after startup has already been delayed.
+    /// Cleared once the jitter window has passed
+    jittered: Arc<Mutex<HashSet<String>>>,
+    max_env_bytes: usize,
+    adopt_orphans: bool,
+    preserve_fields: PreserveFields,
+    hpa_api: Api<HorizontalPodAutoscaler>,
+    wait_for_ready_on_rename: bool,
+    error_requeue: Duration,
+    max_error_requeue: Duration,
+    /// Apis are cluster wide and have to be scoped to a function's namespace before reconciling it
+    cluster_wide: bool,
+    /// The watched namespaces, empty if not restricted to a set
+    functions_namespaces: Vec<String>,
+    /// The namespaces whose secrets are watched, empty if all namespaces are watched
+    secrets_watch_namespaces: Vec<String>,
 }
 
 impl OperatorInner {
-    fn new(
-        kubernetes_client: KubeClient,
-        functions_namespace: String,
-        update_strategy: UpdateStrategy,
-    ) -> Self {
-        let api: Api<OpenFaaSFunction> =
-            Api::namespaced(kubernetes_client.clone(), &functions_namespace);
-        let deployment_api: Api<Deployment> =
-            Api::namespaced(kubernetes_client.clone(), &functions_namespace);
-        let service_api: Api<Service> =
-            Api::namespaced(kubernetes_client.clone(), &functions_namespace);
-
-        let secrets_api: Api<Secret> = Api::namespaced(kubernetes_client, &functions_namespace);
+    fn new(kubernetes_client: KubeClient, config: OperatorConfig, metrics: Arc<Metrics>) -> Self {
+        let cluster_wide = config.is_cluster_wide();
+        let secrets_watch_namespaces = config.secrets_watch_namespaces();
+
+        let OperatorConfig {
+            functions_namespace,
+            update_strategy,
+            update_mode,
+            field_manager,
+            max_reconcile_duration,
+            secrets_namespace,
+            normalize_image_references,
+            default_pod_labels,
+            image_pull_secrets_service_account,
+            verify_runtime_class,
+            reconcile_concurrency,
+            reconcile_debounce,
+            reconcile_jitter_startup,
+            watch_buffer_size,
+            max_env_bytes,
+            adopt_orphans,
+            preserve_fields,
+            wait_for_ready_on_rename,
+            error_requeue,
+            max_error_requeue,
+            all_namespaces: _,
+            functions_namespaces,
+            leader_election_id: _,
+        } = config;
+
+        let shared_secrets_api: Option<Api<Secret>> = secrets_namespace
+            .filter(|secrets_namespace| secrets_namespace != &functions_namespace)
+            .map(|secrets_namespace| {
+                Api::namespaced(kubernetes_client.clone(), &secrets_namespace)
+            });
+
+        // Watching all namespaces requires cluster wide apis, reconciliations use apis scoped to the function's namespace
+        let namespace = (!cluster_wide).then_some(functions_namespace.as_str());
+
+        let api: Api<OpenFaaSFunction> = scoped_api(&kubernetes_client, namespace);
+        let deployment_api: Api<Deployment> = scoped_api(&kubernetes_client, namespace);
+        let service_api: Api<Service> = scoped_api(&kubernetes_client, namespace);
+
+        let secrets_api: Api<Secret> = scoped_api(&kubernetes_client, namespace);
+        let config_maps_api: Api<ConfigMap> = scoped_api(&kubernetes_client, namespace);
+        let pdb_api: Api<PodDisruptionBudget> = scoped_api(&kubernetes_client, namespace);
+        let network_policy_api: Api<NetworkPolicy> = scoped_api(&kubernetes_client, namespace);
+        let service_account_api: Api<ServiceAccount> = scoped_api(&kubernetes_client, namespace);
+        let hpa_api: Api<HorizontalPodAutoscaler> = scoped_api(&kubernetes_client, namespace);
+        let runtime_class_api: Option<Api<RuntimeClass>> =
+            verify_runtime_class.then(|| Api::all(kubernetes_client.clone()));
+
+        let reconcile_queue = Arc::new(ReconcileQueue::new(metrics.reconcile_queue_depth.clone()));
 
         Self {
+            client: kubernetes_client,
             functions_namespace,
             api,
             deployment_api,
             service_api,
             secrets_api,
+            config_maps_api,
+            pdb_api,
+            network_policy_api,
             update_strategy,
+            update_mode,
+            field_manager,
+            metrics,
+            max_reconcile_duration,
+            reconcile_errors: Arc::new(Mutex::new(HashMap::new())),
+            shared_secrets_api,
+            normalize_image_references,
+            default_pod_labels,
+            service_account_api,
+            image_pull_secrets_service_account,
+            runtime_class_api,
+            reconcile_concurrency,
+            reconcile_debounce,
+            reconcile_jitter_startup,
+            watch_buffer_size,
+            reconcile_queue,
+            started_at: chrono::Utc::now(),
+            jittered: Arc::new(Mutex::new(HashSet::new())),
+            max_env_bytes,
+            adopt_orphans,
+            preserve_fields,
+            hpa_api,
+            wait_for_ready_on_rename,
+            error_requeue,
+            max_error_requeue: max_error_requeue.max(error_requeue),
+            cluster_wide,
+            functions_namespaces,
+            secrets_watch_namespaces,
+        }
+    }
+
+    /// Functions are only reconciled in the functions namespace or in the set of watched namespaces
+    fn is_watched_namespace(&self, namespace: &str) -> bool {
+        if self.functions_namespaces.is_empty() {
+            return self.cluster_wide || namespace == self.functions_namespace;
+        }
+
+        self.functions_namespaces
+            .iter()
+            .any(|functions_namespace| functions_namespace == namespace)
+    }
+
+    /// A copy of the operator with its apis scoped to the given namespace, sharing metrics and error records
+    fn for_namespace(&self, namespace: &str) -> Self {
+        let namespace_opt = Some(namespace);
+
+        Self {
+            functions_namespace: namespace.to_string(),
+            api: scoped_api(&self.client, namespace_opt),
+            deployment_api: scoped_api(&self.client, namespace_opt),
+            service_api: scoped_api(&self.client, namespace_opt),
+            secrets_api: scoped_api(&self.client, namespace_opt),
+            config_maps_api: scoped_api(&self.client, namespace_opt),
+            pdb_api: scoped_api(&self.client, namespace_opt),
+            network_policy_api: scoped_api(&self.client, namespace_opt),
+            service_account_api: scoped_api(&self.client, namespace_opt),
+            hpa_api: scoped_api(&self.client, namespace_opt),
+            cluster_wide: false,
+            ..self.clone()
+        }
+    }
+
+    /// A delay for the first reconciliation of a function that existed before the operator started.
+    ///
+    /// The delay is derived from the function's namespace and name, spreading the functions evenly over the jitter window
+    fn startup_jitter(&self, crd: &OpenFaaSFunction) -> Option<Duration> {
+        if self.reconcile_jitter_startup.is_zero() {
+            return None;
+        }
+
+        let window_passed = (chrono::Utc::now() - self.started_at)
+            .to_std()
+            .is_ok_and(|elapsed| elapsed >= self.reconcile_jitter_startup);
+
+        if window_passed {
+            // Releases the memory of the set, it is not needed anymore
+            *self
+                .jittered
+                .lock()
+                .expect("Jittered functions lock poisoned") = HashSet::new();
+
+            return None;
+        }
+
+        let created_before_startup = crd
+            .metadata
+            .creation_timestamp
+            .as_ref()
+            .is_some_and(|created_at| created_at.0 < self.started_at);
+
+        if !created_before_startup {
+            return None;
+        }
+
+        let key = format!("{}/{}", crd.namespace().unwrap_or_default(), crd.name_any());
+
+        let first_reconcile = self
+            .jittered
+            .lock()
+            .expect("Jittered functions lock poisoned")
+            .insert(key.clone());
+
+        if !first_reconcile {
+            return None;
+        }
+
+        let window_millis = u64::try_from(self.reconcile_jitter_startup.as_millis())
+            .unwrap_or(u64::MAX)
+            .max(1);
+
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+
+        Some(Duration::from_millis(hasher.finish() % window_millis))
+    }
+
+    /// Adds the function as the controller of a resource that belongs to it but lacks its owner reference
+    async fn adopt<K>(
+        &self,
+        api: &Api<K>,
+        crd: &OpenFaaSFunction,
+        meta: &ObjectMeta,
+    ) -> Result<(), kube::Error>
+    where
+        K: Resource<DynamicType = ()> + Clone + DeserializeOwned + std::fmt::Debug,
+    {
+        let Some(patch) = crd.to_adoption_patch(meta) else {
+            return Ok(());
+        };
+
+        let name = meta.name.clone().unwrap_or_default();
+        let kind = K::kind(&()).to_string();
+
+        tracing::warn!(%name, %kind, "Resource belongs to the function but has no owner reference. Adopting.");
+
+        api.patch(&name, &self.patch_params(), &Patch::Merge(&patch))
+            .await?;
+
+        tracing::info!(%name, %kind, "Resource adopted.");
+
+        Ok(())
+    }
+
+    /// Writes the last applied annotation of an owned deployment, if missing
+    async fn migrate_deployment(
+        &self,
+        crd: &OpenFaaSFunction,
+        deployment: &Deployment,
+        dry_run: bool,
+    ) -> Result<MigrationOutcome, MigrateError> {
+        if deployment
+            .annotations()
+            .contains_key(LAST_APPLIED_ANNOTATION)
+        {
+            return Ok(MigrationOutcome::AlreadyMigrated);
+        }
+
+        if !dry_run {
+            let patch = crd
+                .spec
+                .to_last_applied_patch(&self.default_pod_labels)
+                .map_err(MigrateError::Serialize)?;
+
+            self.deployment_api
+                .patch(
+                    &deployment.name_any(),
+                    &self.patch_params(),
+                    &Patch::Merge(&patch),
+                )
+                .await
+                .map_err(MigrateError::Patch)?;
+        }
+
+        Ok(MigrationOutcome::Migrated)
+    }
+
+    fn post_params(&self) -> PostParams {
+        PostParams {
+            field_manager: Some(self.field_manager.clone()),
+            ..Default::default()
+        }
+    }
+
+    fn patch_params(&self) -> PatchParams {
+        PatchParams {
+            field_manager: Some(self.field_manager.clone()),
+            ..Default::default()
         }
     }
 
@@ -90,10 +535,144 @@ impl OperatorInner {
             return Err(ReconcileError::Namespace);
         };
 
-        self.apply(crd, &crd_namespace)
+        let key = reconcile_errors_key(&crd);
+
+        match self
+            .apply(crd, &crd_namespace)
             .instrument(trace_span!("ReconcileResource", %name, %crd_namespace))
             .await
-            .map_err(ReconcileError::Apply)
+        {
+            Ok(action) => {
+                self.reset_reconcile_errors(&key);
+                Ok(action)
+            }
+            Err(error) => {
+                let error = ReconcileError::Apply(error);
+
+                if !self.record_reconcile_error(&key) {
+                    return Err(error);
+                }
+
+                self.set_reconcile_backoff_status(&name, &error)
+                    .instrument(trace_span!("ReconcileBackoff", %name, %crd_namespace))
+                    .await
+                    .map_err(|backoff_error| {
+                        tracing::error!(%backoff_error, "Failed to back off.");
+                        error
+                    })
+            }
+        }
+    }
+
+    /// Forgets the reconciliation errors and the not-ready series of the functions that are gone.
+    ///
+    /// Functions carry no finalizer in this mode, so their deletion is observed on the watch
+    fn cleanup(&self, event: &watcher::Event<OpenFaaSFunction>) {
+        match event {
+            watcher::Event::Applied(_) => {}
+            watcher::Event::Deleted(crd) => {
+                self.reset_reconcile_errors(&reconcile_errors_key(crd));
+                self.metrics
+                    .forget_function(&crd.namespace().unwrap_or_default(), &crd.name_any());
+            }
+            watcher::Event::Restarted(crds) => {
+                // Deletions missed while the watch was down are not reported
+                let keys: HashSet<String> = crds.iter().map(reconcile_errors_key).collect();
+
+                self.reconcile_errors
+                    .lock()
+                    .expect("Reconcile errors lock poisoned")
+                    .retain(|key, _| keys.contains(key));
+
+                let functions: HashSet<(String, String)> = crds
+                    .iter()
+                    .map(|crd| (crd.namespace().unwrap_or_default(), crd.name_any()))
+                    .collect();
+
+                self.metrics.retain_functions(|namespace, name| {
+                    functions.contains(&(namespace.to_string(), name.to_string()))
+                });
+            }
+        }
+    }
+
+    fn reset_reconcile_errors(&self, key: &str) {
+        self.reconcile_errors
+            .lock()
+            .expect("Reconcile errors lock poisoned")
+            .remove(key);
+    }
+
+    /// Records a failed reconciliation.
+    ///
+    /// Returns true if the function has been failing for longer than the max reconcile duration.
+    fn record_reconcile_error(&self, key: &str) -> bool {
+        let mut reconcile_errors = self
+            .reconcile_errors
+            .lock()
+            .expect("Reconcile errors lock poisoned");
+
+        let record =
+            reconcile_errors
+                .entry(key.to_string())
+                .or_insert_with(|| ReconcileErrorRecord {
+                    count: 0,
+                    first_error_at: Instant::now(),
+                });
+
+        record.count += 1;
+
+        let failing_for = record.first_error_at.elapsed();
+
+        tracing::warn!(
+            errors = record.count,
+            failing_seconds = failing_for.as_secs(),
+            "Reconciliation failed."
+        );
+
+        self.max_reconcile_duration
+            .is_some_and(|max_reconcile_duration| failing_for > max_reconcile_duration)
+    }
+
+    /// The requeue interval after a failed reconciliation, doubled for every consecutive failure and capped
+    fn error_backoff(&self, key: &str) -> Duration {
+        let count = self
+            .reconcile_errors
+            .lock()
+            .expect("Reconcile errors lock poisoned")
+            .get(key)
+            .map(|record| record.count)
+            .unwrap_or(1);
+
+        let factor = 2u32.saturating_pow(count.saturating_sub(1));
+
+        self.error_requeue
+            .saturating_mul(factor)
+            .min(self.max_error_requeue)
+    }
+
+    async fn set_reconcile_backoff_status(
+        &self,
+        name: &str,
+        error: &ReconcileError,
+    ) -> Result<Action, ReconcileBackoffError> {
+        tracing::error!(%error, "Reconciliation has been failing for too long. Backing off.");
+
+        let mut crd_with_status = self
+            .api
+            .get_status(name)
+            .await
+            .map_err(ReconcileBackoffError::GetStatus)?;
+
+        self.replace_status_with_detail(
+            &mut crd_with_status,
+            OpenFaasFunctionPossibleStatus::ReconcileBackoff,
+            Some(error.to_string()),
+        )
+        .await
+        .map_err(ReconcileBackoffError::SetStatus)?;
+
+        Ok(Action::requeue(RECONCILE_BACKOFF_REQUEUE))
     }
 
     async fn apply(
@@ -124,7 +703,20 @@ impl OperatorInner {
         }
 
         if let Some(action) = self
-            .check_deployment(&crd)
+            .check_spec(&crd)
+            .instrument(trace_span!("CheckSpec"))
+            .await
+            .map_err(ApplyError::Spec)?
+        {
+            return Ok(action);
+        }
+
+        let crd_oref = crd
+            .controller_owner_ref(&())
+            .ok_or(ApplyError::OwnerReference)?;
+
+        if let Some(action) = self
+            .check_deployment(&crd, &crd_oref)
             .instrument(trace_span!("CheckDeployment"))
             .await
             .map_err(ApplyError::Deployment)?
@@ -133,7 +725,7 @@ impl OperatorInner {
         }
 
         if let Some(action) = self
-            .check_service(&crd)
+            .check_service(&crd, &crd_oref)
             .instrument(trace_span!("CheckService"))
             .await
             .map_err(ApplyError::Service)?
@@ -141,6 +733,24 @@ impl OperatorInner {
             return Ok(action);
         }
 
+        if let Some(action) = self
+            .check_pdb(&crd, &crd_oref)
+            .instrument(trace_span!("CheckPodDisruptionBudget"))
+            .await
+            .map_err(ApplyError::PodDisruptionBudget)?
+        {
+            return Ok(action);
+        }
+
+        if let Some(action) = self
+            .check_network_policy(&crd, &crd_oref)
+            .instrument(trace_span!("CheckNetworkPolicy"))
+            .await
+            .map_err(ApplyError::NetworkPolicy)?
+        {
+            return Ok(action);
+        }
+
         if let Some(action) = self
             .set_ready_status(&crd)
             .instrument(trace_span!("SetReadyStatus"))
@@ -159,13 +769,26 @@ impl OperatorInner {
         &self,
         crd_with_status: &mut OpenFaaSFunction,
         status: OpenFaasFunctionPossibleStatus,
+    ) -> Result<(), StatusError> {
+        self.replace_status_with_detail(crd_with_status, status, None)
+            .await
+    }
+
+    async fn replace_status_with_detail(
+        &self,
+        crd_with_status: &mut OpenFaaSFunction,
+        status: OpenFaasFunctionPossibleStatus,
+        detail: Option<String>,
     ) -> Result<(), StatusError> {
         let name = crd_with_status.name_any();
         let api = &self.api;
+        let new_status = OpenFaasFunctionStatus::with_detail(status.clone(), detail);
 
         if let Some(ref func_status) = crd_with_status.status {
             if let Some(current_possible_status) = func_status.possible_status() {
-                if status == current_possible_status {
+                if status == current_possible_status
+                    && func_status.message() == new_status.message()
+                {
                     tracing::info!("Resource already has {:?} status. Skipping.", status);
                     return Ok(());
                 }
@@ -174,10 +797,10 @@ impl OperatorInner {
 
         tracing::info!("Setting status to {:?}.", status);
 
-        crd_with_status.status = Some(status.clone().into());
+        crd_with_status.status = Some(new_status);
         api.replace_status(
             &name,
-            &PostParams::default(),
+            &self.post_params(),
             serde_json::to_vec(&crd_with_status).map_err(|error| StatusError {
                 error: SetStatusError::Serilization(error),
                 status: status.clone(),
@@ -202,12 +825,16 @@ impl OperatorInner {
         tracing::info!("Comparing resource's namespace to functions namespace.");
 
         let name = crd.name_any();
-        let functions_namespace = &self.functions_namespace;
         let api = &self.api;
 
-        if crd_namespace != functions_namespace {
+        if !self.is_watched_namespace(crd_namespace) {
             tracing::error!("Resource's namespace does not match functions namespace.");
 
+            self.metrics
+                .namespace_mismatch_total
+                .with_label_values(&[crd_namespace])
+                .inc();
+
             let mut crd_with_status = api
                 .get_status(&name)
                 .await
@@ -248,6 +875,11 @@ impl OperatorInner {
                 if function_namespace != functions_namespace {
                     tracing::error!(%function_namespace, "Function's namespace does not match functions namespace.");
 
+                    self.metrics
+                        .namespace_mismatch_total
+                        .with_label_values(&[functions_namespace])
+                        .inc();
+
                     let mut crd_with_status = api
                         .get_status(&name)
                         .await
@@ -268,28 +900,74 @@ impl OperatorInner {
         Ok(None)
     }
 
+    /// Validates the spec before any resource is generated, so that all problems are reported at once
+    async fn check_spec(&self, crd: &OpenFaaSFunction) -> Result<Option<Action>, CheckSpecError> {
+        tracing::info!("Validating spec.");
+
+        let mut errors = crd.spec.validate().err().unwrap_or_default();
+
+        if let Err(error) = crd.spec.validate_env_size(self.max_env_bytes) {
+            errors.push(error);
+        }
+
+        if errors.is_empty() {
+            tracing::info!("Spec is valid.");
+            return Ok(None);
+        }
+
+        let detail = errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("; ");
+        tracing::error!(%detail, "Spec is invalid.");
+
+        let mut crd_with_status = self
+            .api
+            .get_status(&crd.name_any())
+            .await
+            .map_err(CheckSpecError::GetStatus)?;
+
+        self.replace_status_with_detail(
+            &mut crd_with_status,
+            OpenFaasFunctionPossibleStatus::from(&errors[0]),
+            Some(detail),
+        )
+        .await
+        .map_err(CheckSpecError::SetStatus)?;
+
+        tracing::info!("Awaiting change.");
+        Ok(Some(Action::await_change()))
+    }
+
     async fn check_deployment(
         &self,
         crd: &OpenFaaSFunction,
+        crd_oref: &OwnerReference,
     ) -> Result<Option<Action>, DeploymentError> {
         tracing::info!("Checking if deployment exists.");
 
         let deployment_name = crd.spec.to_name();
         let deployment_api = &self.deployment_api;
 
+        // Checked for every deployment path, whether it is created, replaced, patched or up to date
+        if let Some(scale_min) = crd.spec.scale_min_conflict() {
+            tracing::warn!(
+                replicas = crd.spec.replicas,
+                %scale_min,
+                "replicas and the com.openfaas.scale.min label disagree. replicas takes precedence."
+            );
+        }
+
         let deployment_opt = deployment_api
             .get_opt(&deployment_name)
             .await
             .map_err(DeploymentError::Get)?;
 
-        let crd_oref = crd
-            .controller_owner_ref(&())
-            .ok_or(DeploymentError::OwnerReference)?;
-
         match deployment_opt {
             Some(ref deployment) => {
                 if let Some(action) = self
-                    .check_existing_deployment(crd, &crd_oref, deployment)
+                    .check_existing_deployment(crd, deployment)
                     .instrument(trace_span!("CheckExistingDeployment"))
                     .await
                     .map_err(DeploymentError::Check)?
@@ -310,7 +988,7 @@ impl OperatorInner {
         }
 
         if let Some(action) = self
-            .delete_old_deployments(crd, &crd_oref)
+            .delete_old_deployments(crd, crd_oref)
             .instrument(trace_span!("DeleteOldDeployments"))
             .await
             .map_err(DeploymentError::Delete)?
@@ -324,73 +1002,55 @@ impl OperatorInner {
     async fn check_existing_deployment(
         &self,
         crd: &OpenFaaSFunction,
-        crd_oref: &OwnerReference,
         deployment: &Deployment,
     ) -> Result<Option<Action>, CheckDeploymentError> {
         tracing::info!("Deployment exists. Comparing.");
 
         let crd_name = crd.name_any();
         let api = &self.api;
-        let deployment_orefs = deployment.owner_references();
-
-        if deployment_orefs.contains(crd_oref) {
-            tracing::info!("Deployment has owner reference. Checking if ready.");
-
-            match deployment.status {
-                None => {
-                    tracing::info!("Deployment has no status. Assuming not ready.");
-
-                    let mut crd_with_status = api
-                        .get_status(&crd_name)
-                        .await
-                        .map_err(CheckDeploymentError::GetStatus)?;
-
-                    let status = OpenFaasFunctionPossibleStatus::DeploymentNotReady;
-
-                    self.replace_status(&mut crd_with_status, status)
-                        .await
-                        .map_err(CheckDeploymentError::SetStatus)?;
-
-                    tracing::info!("Awaiting change.");
-                    return Ok(Some(Action::await_change()));
-                }
-                Some(ref status) => match status.ready_replicas {
-                    None => {
-                        tracing::info!("Deployment has no ready replicas. Assuming not ready.");
 
-                        let mut crd_with_status = api
-                            .get_status(&crd_name)
-                            .await
-                            .map_err(CheckDeploymentError::GetStatus)?;
+        let status = match crd.ownership(deployment.owner_references()) {
+            ResourceOwnership::Owned => None,
+            ResourceOwnership::OwnedByAnotherFunction(owner) => {
+                tracing::error!(%owner, "Deployment is owned by another function.");
 
-                        let status = OpenFaasFunctionPossibleStatus::DeploymentNotReady;
+                Some((
+                    OpenFaasFunctionPossibleStatus::OwnedByAnotherFunction,
+                    Some(owner),
+                ))
+            }
+            ResourceOwnership::ThirdParty
+                if self.adopt_orphans && crd.can_adopt(&deployment.metadata) =>
+            {
+                self.adopt(&self.deployment_api, crd, &deployment.metadata)
+                    .await
+                    .map_err(CheckDeploymentError::Adopt)?;
 
-                        self.replace_status(&mut crd_with_status, status)
-                            .await
-                            .map_err(CheckDeploymentError::SetStatus)?;
+                tracing::info!("Awaiting change.");
+                return Ok(Some(Action::await_change()));
+            }
+            ResourceOwnership::ThirdParty => {
+                tracing::error!("Deployment does not have owner reference.");
 
-                        tracing::info!("Awaiting change.");
-                        return Ok(Some(Action::await_change()));
-                    }
-                    Some(replicas) => {
-                        tracing::info!(
-                            replicas,
-                            "Deployment has {replicas} ready replica(s). Assuming ready."
-                        );
-                    }
-                },
+                Some((
+                    OpenFaasFunctionPossibleStatus::DeploymentAlreadyExists,
+                    None,
+                ))
             }
-        } else {
-            tracing::error!("Deployment does not have owner reference.");
+        };
+
+        if let Some((status, detail)) = status {
+            self.metrics
+                .deployment_conflict_total
+                .with_label_values(&[&self.functions_namespace])
+                .inc();
 
             let mut crd_with_status = api
                 .get_status(&crd_name)
                 .await
                 .map_err(CheckDeploymentError::GetStatus)?;
 
-            let status = OpenFaasFunctionPossibleStatus::DeploymentAlreadyExists;
-
-            self.replace_status(&mut crd_with_status, status)
+            self.replace_status_with_detail(&mut crd_with_status, status, detail)
                 .await
                 .map_err(CheckDeploymentError::SetStatus)?;
 
@@ -398,13 +1058,85 @@ impl OperatorInner {
             return Ok(Some(Action::await_change()));
         }
 
+        tracing::info!("Deployment has owner reference. Checking if ready.");
+
+        let scaled_to_zero = deployment.spec.as_ref().and_then(|spec| spec.replicas) == Some(0);
+
+        match deployment.status {
+            _ if scaled_to_zero => {
+                tracing::info!("Deployment is scaled to zero. Skipping readiness check.");
+            }
+            None => {
+                tracing::info!("Deployment has no status. Assuming not ready.");
+
+                self.set_deployment_not_ready_status(crd).await?;
+
+                tracing::info!("Awaiting change.");
+                return Ok(Some(Action::await_change()));
+            }
+            Some(ref status) => match status.ready_replicas {
+                None => {
+                    tracing::info!("Deployment has no ready replicas. Assuming not ready.");
+
+                    self.set_deployment_not_ready_status(crd).await?;
+
+                    tracing::info!("Awaiting change.");
+                    return Ok(Some(Action::await_change()));
+                }
+                Some(replicas) => {
+                    tracing::info!(
+                        replicas,
+                        "Deployment has {replicas} ready replica(s). Assuming ready."
+                    );
+
+                    self.metrics
+                        .forget_function(&self.functions_namespace, &crd_name);
+                }
+            },
+        }
+
+        let needs_restart = crd.deployment_needs_restart(deployment)
+            || crd.deployment_service_group_changed(deployment)
+            || OpenFaasFunctionSpec::default_pod_labels_changed(
+                deployment,
+                &self.default_pod_labels,
+            );
+
         match self.update_strategy {
             UpdateStrategy::OneWay => {
-                if crd.spec.deployment_needs_recreation(deployment) {
-                    tracing::info!("Deployment needs recreation.");
+                if !needs_restart
+                    && self.update_mode == UpdateMode::Auto
+                    && crd.spec.deployment_needs_recreation(deployment)
+                {
+                    if let Some(action) = self
+                        .patch_deployment_metadata(crd, deployment)
+                        .instrument(trace_span!("PatchDeploymentMetadata"))
+                        .await?
+                    {
+                        return Ok(Some(action));
+                    }
+                }
+
+                if needs_restart || crd.spec.deployment_needs_recreation(deployment) {
+                    if crd.spec.deployment_paused == Some(true) {
+                        tracing::info!(
+                            "Deployment is paused. Changes are rolled out once it is resumed."
+                        );
+                    }
+
+                    let action = match self.update_mode {
+                        UpdateMode::Auto | UpdateMode::Replace => {
+                            tracing::info!("Deployment needs recreation.");
+                            CreateDeploymentAction::Replace(deployment)
+                        }
+                        UpdateMode::Patch => {
+                            tracing::info!("Deployment needs update.");
+                            CreateDeploymentAction::Patch
+                        }
+                    };
 
                     if let Some(action) = self
-                        .create_deployment(crd, CreateDeploymentAction::Replace)
+                        .create_deployment(crd, action)
                         .instrument(trace_span!("CreateDeployment"))
                         .await
                         .map_err(CheckDeploymentError::Create)?
@@ -416,18 +1148,168 @@ impl OperatorInner {
                 }
             }
             UpdateStrategy::Strategic => {
-                tracing::warn!("Strategic update strategy is not implemented yet.");
-                // crd.spec.debug_compare_deployment(deployment);
-            }
-        }
+                // Only patch if something differs, a patch triggers a new reconciliation
+                let diffs = crd.spec.deployment_diffs(deployment);
 
-        Ok(None)
-    }
+                if diffs.is_empty()
+                    && !needs_restart
+                    && !crd.spec.deployment_needs_recreation(deployment)
+                {
+                    tracing::info!("Deployment is up to date.");
+                    return Ok(None);
+                }
+
+                if diffs.is_empty() {
+                    tracing::info!("Function changed. Patching deployment.");
+                } else {
+                    tracing::info!(
+                        ?diffs,
+                        "Deployment drifted from the function. Patching deployment."
+                    );
+                }
+
+                // A merge patch can not unset fields or switch the strategy type
+                let action = if crd.deployment_merge_cannot_converge(deployment) {
+                    tracing::info!("Merge patch can not remove fields. Replacing deployment.");
+                    CreateDeploymentAction::Replace(deployment)
+                } else {
+                    CreateDeploymentAction::Merge(deployment)
+                };
+
+                if let Some(action) = self
+                    .create_deployment(crd, action)
+                    .instrument(trace_span!("CreateDeployment"))
+                    .await
+                    .map_err(CheckDeploymentError::Create)?
+                {
+                    return Ok(Some(action));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Patches the deployment's metadata and replicas if only labels, annotations or replicas changed, instead of replacing it.
+    ///
+    /// Returns `None` if a full recreation is needed.
+    async fn patch_deployment_metadata(
+        &self,
+        crd: &OpenFaaSFunction,
+        deployment: &Deployment,
+    ) -> Result<Option<Action>, CheckDeploymentError> {
+        if !crd.spec.deployment_needs_metadata_patch_only(deployment) {
+            return Ok(None);
+        }
+
+        let patch = match crd.spec.to_deployment_metadata_patch(deployment) {
+            Ok(patch) => patch,
+            Err(error) => {
+                tracing::warn!(%error, "Failed to generate metadata patch. Falling back to recreation.");
+                return Ok(None);
+            }
+        };
+
+        tracing::info!("Only metadata or replicas changed. Patching deployment.");
+
+        self.deployment_api
+            .patch(
+                &deployment.name_any(),
+                &self.patch_params(),
+                &Patch::Merge(&patch),
+            )
+            .await
+            .map_err(CheckDeploymentError::PatchMetadata)?;
+
+        tracing::info!("Deployment patched.");
+
+        tracing::info!("Awaiting change.");
+        Ok(Some(Action::await_change()))
+    }
+
+    /// Image pull secrets of the configured service account.
+    ///
+    /// A missing service account only emits a warning
+    async fn find_image_pull_secrets(&self) -> Result<Vec<LocalObjectReference>, kube::Error> {
+        let Some(ref service_account_name) = self.image_pull_secrets_service_account else {
+            return Ok(vec![]);
+        };
+
+        match self
+            .service_account_api
+            .get_opt(service_account_name)
+            .await?
+        {
+            Some(service_account) => Ok(service_account.image_pull_secrets.unwrap_or_default()),
+            None => {
+                tracing::warn!(
+                    %service_account_name,
+                    "Service account does not exist. No image pull secrets added."
+                );
+
+                Ok(vec![])
+            }
+        }
+    }
+
+    /// Checks if a HorizontalPodAutoscaler scales the deployment.
+    ///
+    /// Only looked up if replicas are preserved
+    async fn is_autoscaled(&self, deployment_name: &str) -> Result<bool, kube::Error> {
+        if !self.preserve_fields.contains(PreservedField::Replicas) {
+            return Ok(false);
+        }
+
+        Ok(self
+            .hpa_api
+            .list(&ListParams::default())
+            .await?
+            .iter()
+            .any(|hpa| targets_deployment(hpa, deployment_name)))
+    }
+
+    async fn set_deployment_not_ready_status(
+        &self,
+        crd: &OpenFaaSFunction,
+    ) -> Result<(), CheckDeploymentError> {
+        let crd_name = crd.name_any();
+        let api = &self.api;
+
+        let mut crd_with_status = api
+            .get_status(&crd_name)
+            .await
+            .map_err(CheckDeploymentError::GetStatus)?;
+
+        let status = OpenFaasFunctionPossibleStatus::DeploymentNotReady;
+
+        self.replace_status(&mut crd_with_status, status)
+            .await
+            .map_err(CheckDeploymentError::SetStatus)?;
+
+        let not_ready_seconds = crd_with_status
+            .status
+            .as_ref()
+            .and_then(|status| status.last_transition_time())
+            .map(|time| (chrono::Utc::now() - time.0).num_seconds().max(0))
+            .unwrap_or_default();
+
+        tracing::info!(
+            not_ready_seconds,
+            "Deployment has been not ready for {not_ready_seconds} second(s)."
+        );
+
+        self.metrics
+            .function_not_ready_seconds
+            .with_label_values(&[&self.functions_namespace, &crd_name])
+            .set(not_ready_seconds as f64);
+
+        Ok(())
+    }
 
     async fn create_deployment(
         &self,
         crd: &OpenFaaSFunction,
-        action: CreateDeploymentAction,
+        action: CreateDeploymentAction<'_>,
     ) -> Result<Option<Action>, CreateDeploymentError> {
         tracing::info!("Deployment does not exist. Creating.");
 
@@ -445,24 +1327,90 @@ impl OperatorInner {
             return Ok(Some(action));
         }
 
+        if let Some(action) = self
+            .check_config_maps(crd)
+            .instrument(trace_span!("CheckConfigMaps"))
+            .await
+            .map_err(CreateDeploymentError::ConfigMaps)?
+        {
+            return Ok(Some(action));
+        }
+
+        if let Some(action) = self
+            .check_runtime_class(crd)
+            .instrument(trace_span!("CheckRuntimeClass"))
+            .await
+            .map_err(CreateDeploymentError::RuntimeClass)?
+        {
+            return Ok(Some(action));
+        }
+
+        let image_pull_secrets = self
+            .find_image_pull_secrets()
+            .instrument(trace_span!("FindImagePullSecrets"))
+            .await
+            .map_err(CreateDeploymentError::ServiceAccount)?;
+
         match Deployment::try_from(crd) {
-            Ok(deployment) => match action {
-                CreateDeploymentAction::Create => {
-                    tracing::info!("Deployment generated. Creating.");
-                    deployment_api
-                        .create(&PostParams::default(), &deployment)
-                        .await
-                        .map_err(CreateDeploymentError::Apply)?;
+            Ok(mut deployment) => {
+                if self.normalize_image_references {
+                    normalize_deployment_images(&mut deployment);
                 }
-                // TODO: How do we handle status here?
-                CreateDeploymentAction::Replace => {
-                    tracing::info!("Deployment generated. Replacing.");
-                    deployment_api
-                        .replace(&deployment_name, &PostParams::default(), &deployment)
-                        .await
-                        .map_err(CreateDeploymentError::Replace)?;
+
+                OpenFaasFunctionSpec::apply_default_pod_labels(
+                    &mut deployment,
+                    &self.default_pod_labels,
+                );
+
+                add_image_pull_secrets(&mut deployment, image_pull_secrets);
+
+                match action {
+                    CreateDeploymentAction::Create => {
+                        tracing::info!("Deployment generated. Creating.");
+                        deployment_api
+                            .create(&self.post_params(), &deployment)
+                            .await
+                            .map_err(CreateDeploymentError::Apply)?;
+                    }
+                    // TODO: How do we handle status here?
+                    CreateDeploymentAction::Replace(live_deployment) => {
+                        self.preserve_deployment_fields(&mut deployment, live_deployment)
+                            .await?;
+
+                        tracing::info!("Deployment generated. Replacing.");
+                        deployment_api
+                            .replace(&deployment_name, &self.post_params(), &deployment)
+                            .await
+                            .map_err(CreateDeploymentError::Replace)?;
+                    }
+                    CreateDeploymentAction::Patch => {
+                        tracing::info!("Deployment generated. Patching.");
+                        deployment_api
+                            .patch(
+                                &deployment_name,
+                                &self.patch_params(),
+                                &Patch::Strategic(&deployment),
+                            )
+                            .await
+                            .map_err(CreateDeploymentError::Patch)?;
+                    }
+                    CreateDeploymentAction::Merge(live_deployment) => {
+                        // Lists like containers are replaced as a whole by a merge patch
+                        self.preserve_deployment_fields(&mut deployment, live_deployment)
+                            .await?;
+
+                        tracing::info!("Deployment generated. Merging.");
+                        deployment_api
+                            .patch(
+                                &deployment_name,
+                                &self.patch_params(),
+                                &Patch::Merge(&deployment),
+                            )
+                            .await
+                            .map_err(CreateDeploymentError::Patch)?;
+                    }
                 }
-            },
+            }
 
             Err(error) => {
                 tracing::error!(%error, "Failed to generate deployment.");
@@ -477,9 +1425,13 @@ impl OperatorInner {
                             .await
                             .map_err(CreateDeploymentError::GetStatus)?;
 
-                        self.replace_status(&mut crd_with_status, error_status)
-                            .await
-                            .map_err(CreateDeploymentError::SetStatus)?;
+                        self.replace_status_with_detail(
+                            &mut crd_with_status,
+                            error_status,
+                            error.to_status_detail(),
+                        )
+                        .await
+                        .map_err(CreateDeploymentError::SetStatus)?;
                     }
                     None => {
                         tracing::debug!(%error, "Error cannot be converted to status. Skipping.");
@@ -492,13 +1444,33 @@ impl OperatorInner {
 
         tracing::info!("Deployment created.");
 
-        // reque to ensure deployment is ready before deleting old ones
-        // TODO: Add wait_for_ready_dep_on_name_change var.
-
         tracing::info!("Awaiting change.");
         Ok(Some(Action::await_change()))
     }
 
+    /// Copies the fields of the live deployment that are managed by others onto the generated one
+    async fn preserve_deployment_fields(
+        &self,
+        deployment: &mut Deployment,
+        live_deployment: &Deployment,
+    ) -> Result<(), CreateDeploymentError> {
+        if self.preserve_fields.is_empty() {
+            return Ok(());
+        }
+
+        let autoscaled = self
+            .is_autoscaled(&live_deployment.name_any())
+            .await
+            .map_err(CreateDeploymentError::Autoscaler)?;
+
+        tracing::info!(%autoscaled, "Preserving externally managed fields.");
+
+        self.preserve_fields
+            .apply(deployment, live_deployment, autoscaled);
+
+        Ok(())
+    }
+
     async fn delete_old_deployments(
         &self,
         crd: &OpenFaaSFunction,
@@ -511,28 +1483,46 @@ impl OperatorInner {
         let deployment_name = crd.spec.to_name();
         let deployment_api = &self.deployment_api;
 
-        for old_deployment in deployment_api
-            .list(&ListParams::default())
+        let old_deployments: Vec<Deployment> = deployment_api
+            .list(&old_resources_list_params(&deployment_name))
             .await
             .map_err(DeleteDeploymentsError::List)?
-            .iter()
-        {
-            let old_deployment_name = old_deployment.metadata.name.clone().unwrap_or_default();
-            let old_deployment_orefs = old_deployment
-                .metadata
-                .owner_references
-                .clone()
+            .into_iter()
+            .filter(|old_deployment| old_deployment.owner_references().contains(crd_oref))
+            .collect();
+
+        if old_deployments.is_empty() {
+            return Ok(None);
+        }
+
+        if self.wait_for_ready_on_rename {
+            let ready_replicas = deployment_api
+                .get_opt(&deployment_name)
+                .await
+                .map_err(DeleteDeploymentsError::Get)?
+                .and_then(|deployment| deployment.status)
+                .and_then(|status| status.ready_replicas)
                 .unwrap_or_default();
 
-            if old_deployment_name != deployment_name && old_deployment_orefs.contains(crd_oref) {
-                tracing::info!(%old_deployment_name, "Deleting old deployment.");
-                deployment_api
-                    .delete(&old_deployment_name, &DeleteParams::default())
-                    .await
-                    .map_err(DeleteDeploymentsError::Delete)?;
+            // A function scaled to zero never becomes ready
+            if ready_replicas < 1 && crd.spec.to_replicas() > 0 {
+                tracing::info!(
+                    "Deployment is not ready yet. Deferring deletion of old deployments."
+                );
+                return Ok(Some(Action::requeue(RENAME_READY_REQUEUE)));
             }
         }
 
+        for old_deployment in old_deployments.iter() {
+            let old_deployment_name = old_deployment.name_any();
+
+            tracing::info!(%old_deployment_name, "Deleting old deployment.");
+            deployment_api
+                .delete(&old_deployment_name, &DeleteParams::default())
+                .await
+                .map_err(DeleteDeploymentsError::Delete)?;
+        }
+
         Ok(None)
     }
 
@@ -543,51 +1533,228 @@ impl OperatorInner {
         tracing::info!("Checking if secrets exist.");
 
         let secrets = crd.spec.get_secrets_unique_vec();
-        if !secrets.is_empty() {
+        let image_pull_secrets = crd.spec.get_image_pull_secrets_unique_vec();
+        if secrets.is_empty() && image_pull_secrets.is_empty() {
+            tracing::info!("Secrets exist.");
+            return Ok(None);
+        }
+
+        let existing_secret_names: Vec<String> = self
+            .secrets_api
+            .list(&ListParams::default())
+            .await
+            .map_err(CheckSecretsError::List)?
+            .into_iter()
+            .map(|secret| secret.metadata.name.unwrap_or_default())
+            .collect();
+
+        let not_found = |secrets: &[String]| -> Vec<String> {
+            secrets
+                .iter()
+                .filter(|secret| !existing_secret_names.contains(secret))
+                .cloned()
+                .collect()
+        };
+
+        let not_found_secret_names = not_found(&secrets);
+        let not_found_image_pull_secret_names = not_found(&image_pull_secrets);
+
+        let (status, detail) = if !not_found_secret_names.is_empty() {
+            let not_found_secret_names_str = not_found_secret_names.join(", ");
+            tracing::error!("Secret(s) {} do(es) not exist.", not_found_secret_names_str);
+
+            self.metrics
+                .secrets_not_found_total
+                .with_label_values(&[&self.functions_namespace])
+                .inc();
+
+            let not_mountable_secret_names =
+                self.find_shared_secrets(&not_found_secret_names).await?;
+
+            if not_mountable_secret_names.is_empty() {
+                (
+                    OpenFaasFunctionPossibleStatus::SecretsNotFound,
+                    not_found_secret_names_str,
+                )
+            } else {
+                let not_mountable_secret_names_str = not_mountable_secret_names.join(", ");
+                tracing::error!(
+                    "Secret(s) {} only exist(s) in the secrets namespace and can not be mounted.",
+                    not_mountable_secret_names_str
+                );
+
+                (
+                    OpenFaasFunctionPossibleStatus::SecretsNotMountable,
+                    not_mountable_secret_names_str,
+                )
+            }
+        } else if !not_found_image_pull_secret_names.is_empty() {
+            let not_found_image_pull_secret_names_str =
+                not_found_image_pull_secret_names.join(", ");
+            tracing::error!(
+                "Image pull secret(s) {} do(es) not exist.",
+                not_found_image_pull_secret_names_str
+            );
+
+            self.metrics
+                .secrets_not_found_total
+                .with_label_values(&[&self.functions_namespace])
+                .inc();
+
+            (
+                OpenFaasFunctionPossibleStatus::ImagePullSecretsNotFound,
+                not_found_image_pull_secret_names_str,
+            )
+        } else {
+            tracing::info!("Secrets exist.");
+            return Ok(None);
+        };
+
+        let mut crd_with_status = self
+            .api
+            .get_status(&crd.name_any())
+            .await
+            .map_err(CheckSecretsError::List)?;
+
+        self.replace_status_with_detail(&mut crd_with_status, status, Some(detail))
+            .await
+            .map_err(CheckSecretsError::SetStatus)?;
+
+        if self.secrets_watch_namespaces.is_empty() {
+            tracing::info!(requeue = ?self.error_requeue, "Secrets are not watched. Requeuing.");
+            return Ok(Some(Action::requeue(self.error_requeue)));
+        }
+
+        tracing::info!("Awaiting change.");
+        Ok(Some(Action::await_change()))
+    }
+
+    /// Returns the given secrets that exist in the secrets namespace
+    async fn find_shared_secrets(
+        &self,
+        secret_names: &[String],
+    ) -> Result<Vec<String>, CheckSecretsError> {
+        let Some(ref shared_secrets_api) = self.shared_secrets_api else {
+            return Ok(vec![]);
+        };
+
+        let shared_secret_names: Vec<String> = shared_secrets_api
+            .list(&ListParams::default())
+            .await
+            .map_err(CheckSecretsError::ListShared)?
+            .into_iter()
+            .map(|secret| secret.name_any())
+            .collect();
+
+        Ok(secret_names
+            .iter()
+            .filter(|secret| shared_secret_names.contains(secret))
+            .cloned()
+            .collect())
+    }
+
+    async fn check_config_maps(
+        &self,
+        crd: &OpenFaaSFunction,
+    ) -> Result<Option<Action>, CheckConfigMapsError> {
+        tracing::info!("Checking if config maps exist.");
+
+        let config_maps = crd.spec.get_config_maps_unique_vec();
+        if !config_maps.is_empty() {
             let name = crd.name_any();
             let api = &self.api;
-            let secrets_api = &self.secrets_api;
+            let config_maps_api = &self.config_maps_api;
 
-            let existing_secret_names: Vec<String> = secrets_api
+            let existing_config_map_names: Vec<String> = config_maps_api
                 .list(&ListParams::default())
                 .await
-                .map_err(CheckSecretsError::List)?
+                .map_err(CheckConfigMapsError::List)?
                 .into_iter()
-                .map(|secret| secret.metadata.name.unwrap_or_default())
+                .map(|config_map| config_map.metadata.name.unwrap_or_default())
                 .collect();
 
-            let not_found_secret_names: Vec<String> = secrets
+            let not_found_config_map_names: Vec<String> = config_maps
                 .iter()
-                .filter(|secret| !existing_secret_names.contains(secret))
+                .filter(|config_map| !existing_config_map_names.contains(config_map))
                 .cloned()
                 .collect();
 
-            if !not_found_secret_names.is_empty() {
-                let not_found_secret_names_str = not_found_secret_names.join(", ");
-                tracing::error!("Secret(s) {} do(es) not exist.", not_found_secret_names_str);
+            if !not_found_config_map_names.is_empty() {
+                let not_found_config_map_names_str = not_found_config_map_names.join(", ");
+                tracing::error!(
+                    "Config map(s) {} do(es) not exist.",
+                    not_found_config_map_names_str
+                );
 
                 let mut crd_with_status = api
                     .get_status(&name)
                     .await
-                    .map_err(CheckSecretsError::List)?;
+                    .map_err(CheckConfigMapsError::GetStatus)?;
 
-                let status = OpenFaasFunctionPossibleStatus::SecretsNotFound;
+                let status = OpenFaasFunctionPossibleStatus::ConfigMapsNotFound;
 
                 self.replace_status(&mut crd_with_status, status)
                     .await
-                    .map_err(CheckSecretsError::SetStatus)?;
+                    .map_err(CheckConfigMapsError::SetStatus)?;
 
                 tracing::info!("Awaiting change.");
                 return Ok(Some(Action::await_change()));
             }
         }
 
-        tracing::info!("Secrets exist.");
+        tracing::info!("Config maps exist.");
+
+        Ok(None)
+    }
+
+    async fn check_runtime_class(
+        &self,
+        crd: &OpenFaaSFunction,
+    ) -> Result<Option<Action>, CheckRuntimeClassError> {
+        let (Some(runtime_class_api), Some(runtime_class_name)) =
+            (&self.runtime_class_api, &crd.spec.runtime_class_name)
+        else {
+            return Ok(None);
+        };
+
+        tracing::info!("Checking if runtime class exists.");
+
+        if runtime_class_api
+            .get_opt(runtime_class_name)
+            .await
+            .map_err(CheckRuntimeClassError::Get)?
+            .is_none()
+        {
+            tracing::error!(%runtime_class_name, "Runtime class does not exist.");
+
+            let mut crd_with_status = self
+                .api
+                .get_status(&crd.name_any())
+                .await
+                .map_err(CheckRuntimeClassError::GetStatus)?;
+
+            self.replace_status_with_detail(
+                &mut crd_with_status,
+                OpenFaasFunctionPossibleStatus::RuntimeClassNotFound,
+                Some(runtime_class_name.clone()),
+            )
+            .await
+            .map_err(CheckRuntimeClassError::SetStatus)?;
+
+            tracing::info!("Awaiting change.");
+            return Ok(Some(Action::await_change()));
+        }
+
+        tracing::info!("Runtime class exists.");
 
         Ok(None)
     }
 
-    async fn check_service(&self, crd: &OpenFaaSFunction) -> Result<Option<Action>, ServiceError> {
+    async fn check_service(
+        &self,
+        crd: &OpenFaaSFunction,
+        crd_oref: &OwnerReference,
+    ) -> Result<Option<Action>, ServiceError> {
         tracing::info!("Checking if service exists.");
 
         let service_name = crd.spec.to_name();
@@ -598,14 +1765,10 @@ impl OperatorInner {
             .await
             .map_err(ServiceError::Get)?;
 
-        let crd_oref = crd
-            .controller_owner_ref(&())
-            .ok_or(ServiceError::OwnerReference)?;
-
         match service_opt {
             Some(ref service) => {
                 if let Some(action) = self
-                    .check_existing_service(crd, &crd_oref, service)
+                    .check_existing_service(crd, crd_oref, service)
                     .instrument(trace_span!("CheckExistingService"))
                     .await
                     .map_err(ServiceError::Check)?
@@ -626,7 +1789,7 @@ impl OperatorInner {
         }
 
         if let Some(action) = self
-            .delete_old_services(crd, &crd_oref)
+            .delete_old_services(crd, crd_oref)
             .instrument(trace_span!("DeleteOldDeployments"))
             .await
             .map_err(ServiceError::Delete)?
@@ -649,9 +1812,26 @@ impl OperatorInner {
         let api = &self.api;
         let service_orefs = service.owner_references();
 
+        if !service_orefs.contains(crd_oref)
+            && self.adopt_orphans
+            && crd.can_adopt(&service.metadata)
+        {
+            self.adopt(&self.service_api, crd, &service.metadata)
+                .await
+                .map_err(CheckServiceError::Adopt)?;
+
+            tracing::info!("Awaiting change.");
+            return Ok(Some(Action::await_change()));
+        }
+
         if !service_orefs.contains(crd_oref) {
             tracing::error!("Service does not have owner reference.");
 
+            self.metrics
+                .service_conflict_total
+                .with_label_values(&[&self.functions_namespace])
+                .inc();
+
             let mut crd_with_status = api
                 .get_status(&crd_name)
                 .await
@@ -667,6 +1847,100 @@ impl OperatorInner {
             return Ok(Some(Action::await_change()));
         }
 
+        let diffs = crd.service_diffs(service);
+        if !diffs.is_empty() {
+            tracing::info!(?diffs, "Service differs from the function.");
+
+            return self
+                .patch_service(crd, service)
+                .instrument(trace_span!("PatchService"))
+                .await;
+        }
+
+        Ok(None)
+    }
+
+    /// Patches the service's ports and selector if they differ from the function,
+    /// e.g. after the appProtocol or the service group changed
+    async fn patch_service(
+        &self,
+        crd: &OpenFaaSFunction,
+        service: &Service,
+    ) -> Result<Option<Action>, CheckServiceError> {
+        tracing::info!("Service type, ports, selector or annotations changed. Patching service.");
+
+        let desired_service = match Service::try_from(crd) {
+            Ok(desired_service) => desired_service,
+            Err(error) => {
+                tracing::error!(%error, "Failed to generate service.");
+
+                if let Some(error_status) = Option::<OpenFaasFunctionPossibleStatus>::from(&error) {
+                    let mut crd_with_status = self
+                        .api
+                        .get_status(&crd.name_any())
+                        .await
+                        .map_err(CheckServiceError::GetStatus)?;
+
+                    self.replace_status(&mut crd_with_status, error_status)
+                        .await
+                        .map_err(CheckServiceError::SetStatus)?;
+                }
+
+                return Err(CheckServiceError::Generate(error));
+            }
+        };
+
+        let mut selector: serde_json::Map<String, serde_json::Value> = crd
+            .service_selector_labels()
+            .into_iter()
+            .map(|(key, value)| (key, serde_json::Value::String(value)))
+            .collect();
+
+        // Maps are merged by a merge patch, labels no longer selected are removed explicitly
+        for key in service
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.selector.clone())
+            .unwrap_or_default()
+            .into_keys()
+        {
+            selector.entry(key).or_insert(serde_json::Value::Null);
+        }
+
+        // Annotations no longer in the spec are removed explicitly as well
+        let annotations = crd
+            .spec
+            .to_service_annotations_patch(service)
+            .map_err(|error| {
+                CheckServiceError::Generate(FunctionSpecIntoServiceError::from(error).into())
+            })?;
+
+        let desired_spec = desired_service.spec.unwrap_or_default();
+
+        // Lists are replaced as a whole by a merge patch, so node ports of a former NodePort service are dropped
+        let patch = serde_json::json!({
+            "metadata": {
+                "annotations": annotations,
+            },
+            "spec": {
+                "type": desired_spec.type_.unwrap_or_else(|| String::from("ClusterIP")),
+                "loadBalancerClass": desired_spec.load_balancer_class,
+                "ports": desired_spec.ports,
+                "selector": selector,
+            }
+        });
+
+        self.service_api
+            .patch(
+                &service.name_any(),
+                &self.patch_params(),
+                &Patch::Merge(&patch),
+            )
+            .await
+            .map_err(CheckServiceError::Patch)?;
+
+        tracing::info!("Service patched.");
+
         Ok(None)
     }
 
@@ -678,10 +1952,29 @@ impl OperatorInner {
 
         let service_api = &self.service_api;
 
-        let service = Service::try_from(crd).map_err(CreateServiceError::Generate)?;
+        let service = match Service::try_from(crd) {
+            Ok(service) => service,
+            Err(error) => {
+                tracing::error!(%error, "Failed to generate service.");
+
+                if let Some(error_status) = Option::<OpenFaasFunctionPossibleStatus>::from(&error) {
+                    let mut crd_with_status = self
+                        .api
+                        .get_status(&crd.name_any())
+                        .await
+                        .map_err(CreateServiceError::GetStatus)?;
+
+                    self.replace_status(&mut crd_with_status, error_status)
+                        .await
+                        .map_err(CreateServiceError::SetStatus)?;
+                }
+
+                return Err(CreateServiceError::Generate(error));
+            }
+        };
 
         service_api
-            .create(&PostParams::default(), &service)
+            .create(&self.post_params(), &service)
             .await
             .map_err(CreateServiceError::Apply)?;
 
@@ -703,19 +1996,14 @@ impl OperatorInner {
         let service_api = &self.service_api;
 
         for old_service in service_api
-            .list(&ListParams::default())
+            .list(&old_resources_list_params(&service_name))
             .await
             .map_err(DeleteServicesError::List)?
             .iter()
         {
-            let old_service_name = old_service.metadata.name.clone().unwrap_or_default();
-            let old_service_orefs = old_service
-                .metadata
-                .owner_references
-                .clone()
-                .unwrap_or_default();
+            let old_service_name = old_service.name_any();
 
-            if old_service_name != service_name && old_service_orefs.contains(crd_oref) {
+            if old_service.owner_references().contains(crd_oref) {
                 tracing::info!(%old_service_name, "Deleting old service.");
                 service_api
                     .delete(&old_service_name, &DeleteParams::default())
@@ -727,119 +2015,794 @@ impl OperatorInner {
         Ok(None)
     }
 
+    async fn check_pdb(
+        &self,
+        crd: &OpenFaaSFunction,
+        crd_oref: &OwnerReference,
+    ) -> Result<Option<Action>, PodDisruptionBudgetError> {
+        tracing::info!("Checking pod disruption budget.");
+
+        let crd_name = crd.name_any();
+        let pdb_name = crd.spec.to_name();
+        let api = &self.api;
+        let pdb_api = &self.pdb_api;
+
+        let desired_pdb_opt = match Option::<PodDisruptionBudget>::try_from(crd) {
+            Ok(desired_pdb_opt) => desired_pdb_opt,
+            Err(error) => {
+                tracing::error!(%error, "Failed to generate pod disruption budget.");
+
+                if let Some(error_status) = Option::<OpenFaasFunctionPossibleStatus>::from(&error) {
+                    let mut crd_with_status = api
+                        .get_status(&crd_name)
+                        .await
+                        .map_err(PodDisruptionBudgetError::GetStatus)?;
+
+                    self.replace_status(&mut crd_with_status, error_status)
+                        .await
+                        .map_err(PodDisruptionBudgetError::SetStatus)?;
+                }
+
+                return Err(PodDisruptionBudgetError::Generate(error));
+            }
+        };
+
+        let pdb_opt = pdb_api
+            .get_opt(&pdb_name)
+            .await
+            .map_err(PodDisruptionBudgetError::Get)?;
+
+        let plan = OptionalResourcePlan::new(
+            desired_pdb_opt.is_some(),
+            pdb_opt
+                .as_ref()
+                .map(|pdb| pdb.owner_references().contains(crd_oref)),
+            desired_pdb_opt.as_ref().map(|pdb| &pdb.spec) == pdb_opt.as_ref().map(|pdb| &pdb.spec),
+        );
+
+        match (plan, desired_pdb_opt, pdb_opt) {
+            (OptionalResourcePlan::Conflict, _, _) => {
+                tracing::error!("Pod disruption budget does not have owner reference.");
+
+                let mut crd_with_status = api
+                    .get_status(&crd_name)
+                    .await
+                    .map_err(PodDisruptionBudgetError::GetStatus)?;
+
+                let status = OpenFaasFunctionPossibleStatus::PodDisruptionBudgetAlreadyExists;
+
+                self.replace_status(&mut crd_with_status, status)
+                    .await
+                    .map_err(PodDisruptionBudgetError::SetStatus)?;
+
+                tracing::info!("Awaiting change.");
+                return Ok(Some(Action::await_change()));
+            }
+            (OptionalResourcePlan::Create, Some(desired_pdb), _) => {
+                tracing::info!("Pod disruption budget does not exist. Creating.");
+
+                pdb_api
+                    .create(&self.post_params(), &desired_pdb)
+                    .await
+                    .map_err(PodDisruptionBudgetError::Create)?;
+
+                tracing::info!("Pod disruption budget created.");
+            }
+            (OptionalResourcePlan::Replace, Some(mut desired_pdb), Some(pdb)) => {
+                tracing::info!("Pod disruption budget is outdated. Replacing.");
+
+                desired_pdb.metadata.resource_version = pdb.resource_version();
+
+                pdb_api
+                    .replace(&pdb_name, &self.post_params(), &desired_pdb)
+                    .await
+                    .map_err(PodDisruptionBudgetError::Replace)?;
+
+                tracing::info!("Pod disruption budget replaced.");
+            }
+            (OptionalResourcePlan::Delete, _, _) => {
+                tracing::info!("Pod disruption budget is no longer requested. Deleting.");
+
+                pdb_api
+                    .delete(&pdb_name, &DeleteParams::default())
+                    .await
+                    .map_err(PodDisruptionBudgetError::Delete)?;
+
+                tracing::info!("Pod disruption budget deleted.");
+            }
+            (OptionalResourcePlan::UpToDate, _, _) => {
+                tracing::info!("Pod disruption budget is up to date.");
+            }
+            _ => {
+                tracing::info!("No pod disruption budget requested.");
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn check_network_policy(
+        &self,
+        crd: &OpenFaaSFunction,
+        crd_oref: &OwnerReference,
+    ) -> Result<Option<Action>, NetworkPolicyError> {
+        tracing::info!("Checking network policy.");
+
+        let crd_name = crd.name_any();
+        let network_policy_name = crd.spec.to_name();
+        let api = &self.api;
+        let network_policy_api = &self.network_policy_api;
+
+        let desired_network_policy_opt =
+            Option::<NetworkPolicy>::try_from(crd).map_err(NetworkPolicyError::Generate)?;
+
+        let network_policy_opt = network_policy_api
+            .get_opt(&network_policy_name)
+            .await
+            .map_err(NetworkPolicyError::Get)?;
+
+        let plan = OptionalResourcePlan::new(
+            desired_network_policy_opt.is_some(),
+            network_policy_opt
+                .as_ref()
+                .map(|network_policy| network_policy.owner_references().contains(crd_oref)),
+            network_policy_opt.as_ref().is_some_and(|network_policy| {
+                crd.spec.network_policy_is_up_to_date(network_policy)
+            }),
+        );
+
+        match (plan, desired_network_policy_opt, network_policy_opt) {
+            (OptionalResourcePlan::Conflict, _, _) => {
+                tracing::error!("Network policy does not have owner reference.");
+
+                let mut crd_with_status = api
+                    .get_status(&crd_name)
+                    .await
+                    .map_err(NetworkPolicyError::GetStatus)?;
+
+                let status = OpenFaasFunctionPossibleStatus::NetworkPolicyAlreadyExists;
+
+                self.replace_status(&mut crd_with_status, status)
+                    .await
+                    .map_err(NetworkPolicyError::SetStatus)?;
+
+                tracing::info!("Awaiting change.");
+                return Ok(Some(Action::await_change()));
+            }
+            (OptionalResourcePlan::Create, Some(desired_network_policy), _) => {
+                tracing::info!("Network policy does not exist. Creating.");
+
+                network_policy_api
+                    .create(&self.post_params(), &desired_network_policy)
+                    .await
+                    .map_err(NetworkPolicyError::Create)?;
+
+                tracing::info!("Network policy created.");
+            }
+            (
+                OptionalResourcePlan::Replace,
+                Some(mut desired_network_policy),
+                Some(network_policy),
+            ) => {
+                tracing::info!("Network policy is outdated. Replacing.");
+
+                desired_network_policy.metadata.resource_version =
+                    network_policy.resource_version();
+
+                network_policy_api
+                    .replace(
+                        &network_policy_name,
+                        &self.post_params(),
+                        &desired_network_policy,
+                    )
+                    .await
+                    .map_err(NetworkPolicyError::Replace)?;
+
+                tracing::info!("Network policy replaced.");
+            }
+            (OptionalResourcePlan::Delete, _, _) => {
+                tracing::info!("Network policy is no longer requested. Deleting.");
+
+                network_policy_api
+                    .delete(&network_policy_name, &DeleteParams::default())
+                    .await
+                    .map_err(NetworkPolicyError::Delete)?;
+
+                tracing::info!("Network policy deleted.");
+            }
+            (OptionalResourcePlan::UpToDate, _, _) => {
+                tracing::info!("Network policy is up to date.");
+            }
+            _ => {
+                tracing::info!("No network policy requested.");
+            }
+        }
+
+        Ok(None)
+    }
+
     async fn set_ready_status(
         &self,
         crd: &OpenFaaSFunction,
     ) -> Result<Option<Action>, DeployedStatusError> {
-        tracing::info!("Setting status.");
+        tracing::info!("Checking readiness.");
 
         let name = crd.name_any();
+        let resource_name = crd.spec.to_name();
         let api = &self.api;
 
+        let deployment_opt = self
+            .deployment_api
+            .get_opt(&resource_name)
+            .await
+            .map_err(DeployedStatusError::GetDeployment)?;
+
+        let service_opt = self
+            .service_api
+            .get_opt(&resource_name)
+            .await
+            .map_err(DeployedStatusError::GetService)?;
+
+        let readiness = crd.readiness(deployment_opt.as_ref(), service_opt.as_ref());
+
+        tracing::info!(?readiness, "Setting status.");
+
         let mut crd_with_status = api
             .get_status(&name)
             .await
             .map_err(DeployedStatusError::GetStatus)?;
 
-        let status = OpenFaasFunctionPossibleStatus::Ok;
+        let status = OpenFaasFunctionPossibleStatus::from(&readiness);
 
         self.replace_status(&mut crd_with_status, status)
             .await
             .map_err(DeployedStatusError::SetStatus)?;
 
+        if readiness != FunctionReadiness::Ready {
+            tracing::info!("Awaiting change.");
+            return Ok(Some(Action::await_change()));
+        }
+
         Ok(None)
     }
 }
 
 pub struct Operator {
     inner: Arc<OperatorInner>,
+    health: Arc<Health>,
+    /// Filled by the functions reflector once the controller runs
+    functions_writer: Writer<OpenFaaSFunction>,
+    events: broadcast::Sender<ReconcileOutcome>,
 }
 
 impl Operator {
-    pub fn new(
-        client: KubeClient,
-        functions_namespace: String,
-        update_strategy: UpdateStrategy,
-    ) -> Self {
-        let inner = Arc::new(OperatorInner::new(
-            client,
-            functions_namespace,
-            update_strategy,
-        ));
+    pub fn new(client: KubeClient, config: OperatorConfig) -> Self {
+        let inner = Arc::new(OperatorInner::new(client, config, Arc::new(Metrics::new())));
 
-        Self { inner }
+        Self {
+            inner,
+            health: Arc::new(Health::new()),
+            functions_writer: Writer::default(),
+            events: broadcast::channel(EVENTS_CAPACITY).0,
+        }
     }
 
+    /// Checks if the functions namespace, or each of the watched namespaces, exists before creating the operator.
+    ///
+    /// If `require_namespace` is set, a missing namespace is an error, otherwise only a warning is emitted.
     pub async fn new_with_check_functions_namespace(
         client: KubeClient,
-        functions_namespace: String,
-        update_strategy: UpdateStrategy,
-    ) -> Self {
-        tracing::info!("Checking if namespace exists.");
+        config: OperatorConfig,
+        require_namespace: bool,
+    ) -> Result<Self, CheckFunctionsNamespaceError> {
         let namespace_api: Api<Namespace> = Api::all(client.clone());
 
-        match namespace_api.get_opt(&functions_namespace).await {
-            Ok(namespace_opt) => match namespace_opt {
-                Some(_) => {
-                    tracing::info!("Namespace exists.");
+        for functions_namespace in config.required_namespaces() {
+            tracing::info!(%functions_namespace, "Checking if namespace exists.");
+
+            match namespace_api.get_opt(&functions_namespace).await {
+                Ok(namespace_opt) => match namespace_opt {
+                    Some(_) => {
+                        tracing::info!("Namespace exists.");
+                    }
+                    None if require_namespace => {
+                        tracing::error!("Namespace does not exist.");
+                        return Err(CheckFunctionsNamespaceError::NotFound(functions_namespace));
+                    }
+                    None => {
+                        tracing::warn!("Namespace does not exist.");
+                    }
+                },
+                Err(error) if require_namespace => {
+                    tracing::error!(%error, "Failed to check if namespace exists.");
+                    return Err(CheckFunctionsNamespaceError::Get(error));
                 }
-                None => {
-                    tracing::warn!("Namespace does not exist.");
+                Err(error) => {
+                    tracing::warn!(%error,"Failed to check if namespace exists.");
                 }
-            },
-            Err(error) => {
-                tracing::warn!(%error,"Failed to check if namespace exists.");
             }
         }
 
-        Self::new(client, functions_namespace, update_strategy)
+        Ok(Self::new(client, config))
     }
 
     pub fn functions_namespace(&self) -> &str {
         &self.inner.functions_namespace
     }
 
-    pub async fn run(self) {
+    pub fn metrics(&self) -> Arc<Metrics> {
+        self.inner.metrics.clone()
+    }
+
+    pub fn health(&self) -> Arc<Health> {
+        self.health.clone()
+    }
+
+    /// The operator's view of the watched functions, empty until the controller runs
+    pub fn functions_store(&self) -> Store<OpenFaaSFunction> {
+        self.functions_writer.as_reader()
+    }
+
+    /// The outcomes of the controller's reconciliations
+    pub fn events(&self) -> broadcast::Sender<ReconcileOutcome> {
+        self.events.clone()
+    }
+
+    /// Fetches the function with the given name and reconciles it exactly once, without watching.
+    ///
+    /// Returns the resulting action and the function's status after the reconciliation.
+    pub async fn reconcile_once(
+        &self,
+        name: &str,
+    ) -> Result<(Action, Option<OpenFaasFunctionStatus>), ReconcileOnceError> {
+        let inner = self.inner.for_namespace(&self.inner.functions_namespace);
+
+        let crd = inner
+            .api
+            .get_opt(name)
+            .await
+            .map_err(ReconcileOnceError::Get)?
+            .ok_or_else(|| ReconcileOnceError::NotFound(name.to_string()))?;
+
+        let action = inner
+            .reconcile(Arc::new(crd))
+            .await
+            .map_err(ReconcileOnceError::Reconcile)?;
+
+        let status = inner
+            .api
+            .get_status(name)
+            .await
+            .map_err(ReconcileOnceError::Get)?
+            .status;
+
+        Ok((action, status))
+    }
+
+    /// Writes the spec of every function into its deployment's last applied annotation, if missing.
+    ///
+    /// Deployments created without the annotation would otherwise be recreated on the first reconciliation.
+    /// Functions are migrated in every watched namespace, orphaned deployments are adopted first if adoption is enabled.
+    /// On a dry run nothing is patched.
+    pub async fn migrate_last_applied(
+        &self,
+        dry_run: bool,
+    ) -> Result<Vec<(String, MigrationOutcome)>, MigrateError> {
+        let crds = if self.inner.cluster_wide {
+            self.inner
+                .api
+                .list(&ListParams::default())
+                .await
+                .map_err(MigrateError::List)?
+                .into_iter()
+                .filter(|crd| {
+                    crd.namespace()
+                        .is_some_and(|namespace| self.inner.is_watched_namespace(&namespace))
+                })
+                .collect::<Vec<_>>()
+        } else {
+            self.inner
+                .for_namespace(&self.inner.functions_namespace)
+                .api
+                .list(&ListParams::default())
+                .await
+                .map_err(MigrateError::List)?
+                .items
+        };
+
+        let mut outcomes = vec![];
+
+        for crd in crds {
+            let namespace = crd
+                .namespace()
+                .unwrap_or_else(|| self.inner.functions_namespace.clone());
+            let inner = self.inner.for_namespace(&namespace);
+            let name = crd.spec.to_name();
+
+            let outcome = match inner
+                .deployment_api
+                .get_opt(&name)
+                .await
+                .map_err(MigrateError::GetDeployment)?
+            {
+                None => MigrationOutcome::DeploymentMissing,
+                Some(deployment) => match crd.ownership(deployment.owner_references()) {
+                    ResourceOwnership::Owned => {
+                        inner.migrate_deployment(&crd, &deployment, dry_run).await?
+                    }
+                    ResourceOwnership::ThirdParty
+                        if inner.adopt_orphans && crd.can_adopt(&deployment.metadata) =>
+                    {
+                        if !dry_run {
+                            inner
+                                .adopt(&inner.deployment_api, &crd, &deployment.metadata)
+                                .await
+                                .map_err(MigrateError::Patch)?;
+                        }
+
+                        inner.migrate_deployment(&crd, &deployment, dry_run).await?
+                    }
+                    _ => MigrationOutcome::NotOwned,
+                },
+            };
+
+            tracing::info!(%namespace, %name, %outcome, dry_run, "Deployment processed.");
+
+            outcomes.push((format!("{namespace}/{name}"), outcome));
+        }
+
+        Ok(outcomes)
+    }
+
+    /// Runs the controller until a shutdown signal is received.
+    ///
+    /// If `on_outcome` is set, it is called with the outcome of every processed reconciliation.
+    /// Every outcome is also sent to the receivers of [`Operator::events`].
+    pub async fn run(self, on_outcome: Option<ReconcileOutcomeCallback>) {
         tracing::info!("Starting.");
 
         let api = self.inner.api.clone();
         let deployment_api = self.inner.deployment_api.clone();
         let service_api = self.inner.service_api.clone();
+        let pdb_api = self.inner.pdb_api.clone();
+        let network_policy_api = self.inner.network_policy_api.clone();
+        let reconcile_queue = self.inner.reconcile_queue.clone();
+        let watch_buffer_size = self.inner.watch_buffer_size;
+
+        // Only changes to the generation (spec), the restarted-at or the service-group annotation of a function trigger a reconciliation,
+        // so that our own status writes do not requeue the function.
+        // Changes to owned resources still trigger a reconciliation.
+        // Functions outside of the watched namespaces are neither stored nor reconciled
+        let functions_namespaces = self.inner.functions_namespaces.clone();
+        let inner = self.inner.clone();
+        let functions_watcher = watcher(api, Config::default())
+            .try_filter_map(move |event| {
+                future::ready(Ok(retain_watched_namespaces(event, &functions_namespaces)))
+            })
+            .inspect_ok(move |event| inner.cleanup(event));
+
+        let functions_store = self.functions_writer.as_reader();
+        let queue = reconcile_queue.clone();
+        let functions_stream = reflector(self.functions_writer, functions_watcher)
+            .default_backoff()
+            .applied_objects()
+            .predicate_filter(generation_and_annotations)
+            .inspect_ok(move |crd| queue.push([ObjectRef::from_obj(crd)]));
+
+        let events = self.events.clone();
+        let on_outcome: ReconcileOutcomeCallback = Arc::new(move |outcome| {
+            if let Some(ref on_outcome) = on_outcome {
+                on_outcome(outcome.clone());
+            }
 
-        Controller::new(api, Config::default())
-            .owns(deployment_api, Config::default())
-            .owns(service_api, Config::default())
+            // Fails only if no client is listening
+            let _ = events.send(outcome);
+        });
+
+        let controller_config = ControllerConfig::default()
+            .concurrency(self.inner.reconcile_concurrency)
+            .debounce(self.inner.reconcile_debounce);
+
+        let controller = Controller::for_stream(
+            read_ahead(functions_stream, watch_buffer_size),
+            functions_store.clone(),
+        )
+        .with_config(controller_config);
+
+        let health = self.health.clone();
+
+        let queue = reconcile_queue.clone();
+        // Secrets are watched in each namespace separately instead of cluster wide
+        let secrets_watchers = self.inner.secrets_watch_namespaces.iter().map(|namespace| {
+            let secrets_api: Api<Secret> = Api::namespaced(self.inner.client.clone(), namespace);
+            watcher(secrets_api, Config::default())
+                .touched_objects()
+                .boxed()
+        });
+        let secrets_stream = read_ahead(stream::select_all(secrets_watchers), watch_buffer_size);
+
+        let reconciliations = controller
+            .owns_stream(watch_owned(
+                deployment_api,
+                reconcile_queue.clone(),
+                watch_buffer_size,
+            ))
+            .owns_stream(watch_owned(
+                service_api,
+                reconcile_queue.clone(),
+                watch_buffer_size,
+            ))
+            .owns_stream(watch_owned(
+                pdb_api,
+                reconcile_queue.clone(),
+                watch_buffer_size,
+            ))
+            .owns_stream(watch_owned(
+                network_policy_api,
+                reconcile_queue.clone(),
+                watch_buffer_size,
+            ))
+            .watches_stream(secrets_stream, move |secret| {
+                let functions = functions_referencing_secret(&functions_store, &secret);
+                queue.push(functions.clone());
+
+                functions
+            })
             .shutdown_on_signal()
-            .run(reconcile, on_error, self.inner)
-            .for_each(|reconciliation_result| async move {
-                match reconciliation_result {
-                    Ok(_) => {
-                        tracing::info!("Reconciliation successful.");
+            .run(reconcile, on_error, self.inner);
+
+        health.set_streaming();
+
+        reconciliations
+            .for_each(|reconciliation_result| {
+                let on_outcome = on_outcome.clone();
+
+                // Functions that no longer exist are never reconciled
+                if let Err(ControllerError::ObjectNotFound(ref object_ref)) = reconciliation_result
+                {
+                    let mut function = ObjectRef::<OpenFaaSFunction>::new(&object_ref.name);
+                    function.namespace = object_ref.namespace.clone();
+
+                    reconcile_queue.pop(&function);
+                }
+
+                async move {
+                    if let Some(outcome) =
+                        ReconcileOutcome::from_controller_result(&reconciliation_result)
+                    {
+                        on_outcome(outcome);
                     }
-                    Err(error) => {
-                        tracing::error!(%error, "Reconciliation failed.");
+
+                    match reconciliation_result {
+                        Ok(_) => {
+                            tracing::info!("Reconciliation successful.");
+                        }
+                        Err(error) => {
+                            tracing::error!(%error, "Reconciliation failed.");
+                        }
                     }
                 }
             })
             .await;
 
+        health.set_terminated();
+
         tracing::info!("Terminated.");
     }
 }
 
+/// Replaces the images of the deployment's containers with their normalized references
+fn normalize_deployment_images(deployment: &mut Deployment) {
+    let containers = deployment
+        .spec
+        .iter_mut()
+        .filter_map(|spec| spec.template.spec.as_mut())
+        .flat_map(|pod_spec| pod_spec.containers.iter_mut());
+
+    for container in containers {
+        if let Some(image) = container.image.as_mut() {
+            if let Ok(reference) = image.parse::<ImageReference>() {
+                *image = reference.normalized();
+            }
+        }
+    }
+}
+
+/// Adds the image pull secrets to the deployment's pod spec, skipping the ones already referenced
+fn add_image_pull_secrets(
+    deployment: &mut Deployment,
+    image_pull_secrets: Vec<LocalObjectReference>,
+) {
+    if image_pull_secrets.is_empty() {
+        return;
+    }
+
+    if let Some(pod_spec) = deployment
+        .spec
+        .as_mut()
+        .and_then(|spec| spec.template.spec.as_mut())
+    {
+        let references = pod_spec.image_pull_secrets.get_or_insert_with(Vec::new);
+
+        for image_pull_secret in image_pull_secrets {
+            if !references.contains(&image_pull_secret) {
+                references.push(image_pull_secret);
+            }
+        }
+    }
+}
+
+/// Drops the functions of a watch event that are not in the watched namespaces, all functions are kept if none are given
+fn retain_watched_namespaces(
+    event: watcher::Event<OpenFaaSFunction>,
+    namespaces: &[String],
+) -> Option<watcher::Event<OpenFaaSFunction>> {
+    let watched = |crd: &OpenFaaSFunction| {
+        namespaces.is_empty()
+            || crd
+                .namespace()
+                .is_some_and(|namespace| namespaces.contains(&namespace))
+    };
+
+    match event {
+        watcher::Event::Applied(crd) => watched(&crd).then_some(watcher::Event::Applied(crd)),
+        watcher::Event::Deleted(crd) => watched(&crd).then_some(watcher::Event::Deleted(crd)),
+        watcher::Event::Restarted(crds) => Some(watcher::Event::Restarted(
+            crds.into_iter().filter(watched).collect(),
+        )),
+    }
+}
+
+/// An api in the given namespace, or in all namespaces if none is given
+fn scoped_api<K>(client: &KubeClient, namespace: Option<&str>) -> Api<K>
+where
+    K: Resource<Scope = NamespaceResourceScope>,
+    <K as Resource>::DynamicType: Default,
+{
+    match namespace {
+        Some(namespace) => Api::namespaced(client.clone(), namespace),
+        None => Api::all(client.clone()),
+    }
+}
+
+/// Reconciliation errors are tracked per function instance,
+/// a function recreated under the same name starts without the errors of the deleted one
+fn reconcile_errors_key(crd: &OpenFaaSFunction) -> String {
+    crd.uid()
+        .unwrap_or_else(|| format!("{}/{}", crd.namespace().unwrap_or_default(), crd.name_any()))
+}
+
+/// Lists only function resources not named after the current function,
+/// the owner reference still has to be checked to find the ones belonging to the current function
+fn old_resources_list_params(name: &str) -> ListParams {
+    ListParams::default().labels(&format!("{FUNCTION_LABEL},{FUNCTION_LABEL}!={name}"))
+}
+
+/// Hashes the generation and the restarted-at and service-group annotations of a function
+fn generation_and_annotations(crd: &OpenFaaSFunction) -> Option<u64> {
+    let generation = predicates::generation(crd)?;
+
+    let mut hasher = DefaultHasher::new();
+    generation.hash(&mut hasher);
+    crd.restarted_at().hash(&mut hasher);
+    crd.service_group().hash(&mut hasher);
+
+    Some(hasher.finish())
+}
+
+/// Watches the metadata of resources owned by functions like `Controller::owns` does, queueing their owners
+fn watch_owned<K>(
+    api: Api<K>,
+    reconcile_queue: Arc<ReconcileQueue>,
+    watch_buffer_size: usize,
+) -> BoxStream<'static, Result<PartialObjectMeta<K>, watcher::Error>>
+where
+    K: Resource<DynamicType = ()> + Clone + DeserializeOwned + fmt::Debug + Send + 'static,
+{
+    let stream = metadata_watcher(api, Config::default())
+        .touched_objects()
+        .inspect_ok(move |object| reconcile_queue.push_owners(object.meta()));
+
+    read_ahead(stream, watch_buffer_size)
+}
+
+/// Reads up to `size` items of the stream ahead of its consumer, so a busy controller does not stall the watch.
+///
+/// A size of 0 leaves the stream as is
+fn read_ahead<S>(stream: S, size: usize) -> BoxStream<'static, S::Item>
+where
+    S: Stream + Send + 'static,
+    S::Item: Send + 'static,
+{
+    if size == 0 {
+        return stream.boxed();
+    }
+
+    let (sender, receiver) = futures::channel::mpsc::channel(size);
+
+    // Ends once the controller drops the receiver
+    tokio::spawn(stream.map(Ok).forward(sender));
+
+    receiver.boxed()
+}
+
+/// Maps a changed secret to the functions in its namespace that reference it,
+/// so that functions waiting for a missing secret are reconciled once it is created.
+fn functions_referencing_secret(
+    functions_store: &Store<OpenFaaSFunction>,
+    secret: &Secret,
+) -> Vec<ObjectRef<OpenFaaSFunction>> {
+    let secret_name = secret.name_any();
+    let secret_namespace = secret.namespace();
+
+    functions_store
+        .state()
+        .into_iter()
+        .filter(|crd| crd.namespace() == secret_namespace)
+        .filter(|crd| {
+            crd.spec.get_secrets_unique_vec().contains(&secret_name)
+                || crd
+                    .spec
+                    .get_image_pull_secrets_unique_vec()
+                    .contains(&secret_name)
+        })
+        .map(|crd| ObjectRef::from_obj(crd.as_ref()))
+        .collect()
+}
+
 async fn reconcile(
     crd: Arc<OpenFaaSFunction>,
     context: Arc<OperatorInner>,
 ) -> Result<Action, ReconcileError> {
-    context.reconcile(crd).await
+    context
+        .reconcile_queue
+        .pop(&ObjectRef::from_obj(crd.as_ref()));
+
+    if let Some(delay) = context.startup_jitter(&crd) {
+        tracing::info!(?delay, "Delaying initial reconciliation after startup.");
+        return Ok(Action::requeue(delay));
+    }
+
+    let metrics = context.metrics.clone();
+
+    metrics.reconciles_in_flight.inc();
+
+    let concurrency = context.reconcile_concurrency;
+    if concurrency > 0 && metrics.reconciles_in_flight.get() >= i64::from(concurrency) {
+        tracing::debug!(%concurrency, "Reconcile concurrency limit reached.");
+        metrics.reconciles_at_concurrency_limit_total.inc();
+    }
+
+    let started_at = Instant::now();
+
+    let result = match crd.namespace().filter(|_| context.cluster_wide) {
+        Some(namespace) => context.for_namespace(&namespace).reconcile(crd).await,
+        None => context.reconcile(crd).await,
+    };
+
+    metrics.reconciles_in_flight.dec();
+    metrics.reconciles_total.inc();
+    metrics
+        .reconcile_duration_seconds
+        .observe(started_at.elapsed().as_secs_f64());
+
+    result
 }
 
 fn on_error(
-    _openfaas_function: Arc<OpenFaaSFunction>,
+    openfaas_function: Arc<OpenFaaSFunction>,
     error: &ReconcileError,
-    _context: Arc<OperatorInner>,
+    context: Arc<OperatorInner>,
 ) -> Action {
-    tracing::error!(%error, "Reconciliation failed. Requeuing.");
+    let requeue = context.error_backoff(&reconcile_errors_key(&openfaas_function));
+
+    tracing::error!(%error, ?requeue, "Reconciliation failed. Requeuing.");
+
+    context
+        .metrics
+        .reconcile_errors_total
+        .with_label_values(&[error.kind()])
+        .inc();
 
-    Action::requeue(Duration::from_secs(10))
+    Action::requeue(requeue)
 }
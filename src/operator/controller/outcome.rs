@@ -0,0 +1,57 @@
+use super::errors::ReconcileError;
+use kube::{
+    core::DynamicObject,
+    runtime::{
+        controller::{Action, Error as ControllerError},
+        reflector::ObjectRef,
+        watcher::Error as WatcherError,
+    },
+};
+use std::sync::Arc;
+
+/// Receives the outcome of every processed reconciliation
+pub type ReconcileOutcomeCallback = Arc<dyn Fn(ReconcileOutcome) + Send + Sync>;
+
+/// The outcome of a single reconciliation of an OpenFaaSFunction
+#[derive(Debug, Clone)]
+pub struct ReconcileOutcome {
+    pub object_ref: ObjectRef<DynamicObject>,
+    pub result: Result<Action, ReconcileErrorKind>,
+}
+
+/// A cloneable summary of why a reconciliation failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReconcileErrorKind {
+    /// The reconciler returned an error with the given message
+    Reconciler(String),
+    /// The object was not found in the controller's cache
+    ObjectNotFound,
+}
+
+type ControllerResult<K> =
+    Result<(ObjectRef<K>, Action), ControllerError<ReconcileError, WatcherError>>;
+
+impl ReconcileOutcome {
+    /// Returns `None` for errors that are not tied to an object, e.g. watch errors.
+    pub fn from_controller_result<K>(result: &ControllerResult<K>) -> Option<Self>
+    where
+        K: kube::Resource,
+        K::DynamicType: Clone,
+    {
+        match result {
+            Ok((object_ref, action)) => Some(Self {
+                object_ref: object_ref.clone().erase(),
+                result: Ok(action.clone()),
+            }),
+            Err(ControllerError::ReconcilerFailed(error, object_ref)) => Some(Self {
+                object_ref: object_ref.clone(),
+                result: Err(ReconcileErrorKind::Reconciler(error.to_string())),
+            }),
+            Err(ControllerError::ObjectNotFound(object_ref)) => Some(Self {
+                object_ref: object_ref.clone(),
+                result: Err(ReconcileErrorKind::ObjectNotFound),
+            }),
+            Err(_) => None,
+        }
+    }
+}
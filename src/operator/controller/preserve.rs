@@ -0,0 +1,261 @@
+use k8s_openapi::api::{
+    apps::v1::Deployment,
+    autoscaling::v2::HorizontalPodAutoscaler,
+    core::v1::{Container, PodSpec, Volume},
+};
+use kube::core::ObjectMeta;
+use std::collections::BTreeMap;
+
+/// A field of a function's deployment that is managed by someone else than the operator
+#[derive(Debug, Clone, Copy, clap::ValueEnum, PartialEq)]
+pub enum PreservedField {
+    /// Containers and init containers injected into the pod template, e.g. by a service mesh, with the volumes they mount
+    Sidecars,
+    /// The number of replicas, if a HorizontalPodAutoscaler targets the deployment
+    Replicas,
+}
+
+/// Fields of the live deployment that are kept when the operator replaces it
+#[derive(Debug, Clone, Default)]
+pub struct PreserveFields {
+    pub fields: Vec<PreservedField>,
+    /// Annotations of the deployment and its pod template starting with one of these prefixes are kept
+    pub annotation_prefixes: Vec<String>,
+}
+
+impl PreserveFields {
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.annotation_prefixes.is_empty()
+    }
+
+    pub fn contains(&self, field: PreservedField) -> bool {
+        self.fields.contains(&field)
+    }
+
+    /// Copies the preserved fields of the live deployment onto the generated one.
+    ///
+    /// Fields the operator generates itself always take precedence.
+    /// Replicas are only kept if `autoscaled` is set.
+    pub fn apply(&self, generated: &mut Deployment, live: &Deployment, autoscaled: bool) {
+        preserve_annotations(
+            &mut generated.metadata,
+            &live.metadata,
+            &self.annotation_prefixes,
+        );
+
+        let (Some(generated_spec), Some(live_spec)) = (generated.spec.as_mut(), live.spec.as_ref())
+        else {
+            return;
+        };
+
+        if self.contains(PreservedField::Replicas) && autoscaled {
+            generated_spec.replicas = live_spec.replicas;
+        }
+
+        if let Some(ref live_meta) = live_spec.template.metadata {
+            preserve_annotations(
+                generated_spec
+                    .template
+                    .metadata
+                    .get_or_insert_with(ObjectMeta::default),
+                live_meta,
+                &self.annotation_prefixes,
+            );
+        }
+
+        if self.contains(PreservedField::Sidecars) {
+            if let (Some(generated_pod_spec), Some(live_pod_spec)) = (
+                generated_spec.template.spec.as_mut(),
+                live_spec.template.spec.as_ref(),
+            ) {
+                preserve_sidecars(generated_pod_spec, live_pod_spec);
+            }
+        }
+    }
+}
+
+fn preserve_annotations(generated: &mut ObjectMeta, live: &ObjectMeta, prefixes: &[String]) {
+    let Some(ref live_annotations) = live.annotations else {
+        return;
+    };
+
+    let preserved = live_annotations
+        .iter()
+        .filter(|(key, _)| prefixes.iter().any(|prefix| key.starts_with(prefix)))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect::<BTreeMap<_, _>>();
+
+    if preserved.is_empty() {
+        return;
+    }
+
+    let annotations = generated.annotations.get_or_insert_with(BTreeMap::new);
+
+    for (key, value) in preserved {
+        annotations.entry(key).or_insert(value);
+    }
+}
+
+/// Appends the containers missing in `generated` and every volume they mount
+fn preserve_sidecars(generated: &mut PodSpec, live: &PodSpec) {
+    let sidecars = missing_containers(&generated.containers, &live.containers);
+    let init_sidecars = missing_containers(
+        generated.init_containers.as_deref().unwrap_or_default(),
+        live.init_containers.as_deref().unwrap_or_default(),
+    );
+
+    let mounted_volumes: Vec<&String> = sidecars
+        .iter()
+        .chain(init_sidecars.iter())
+        .flat_map(|container| container.volume_mounts.iter().flatten())
+        .map(|volume_mount| &volume_mount.name)
+        .collect();
+
+    let volumes: Vec<Volume> = live
+        .volumes
+        .iter()
+        .flatten()
+        .filter(|volume| mounted_volumes.contains(&&volume.name))
+        .filter(|volume| {
+            !generated
+                .volumes
+                .iter()
+                .flatten()
+                .any(|generated_volume| generated_volume.name == volume.name)
+        })
+        .cloned()
+        .collect();
+
+    generated.containers.extend(sidecars);
+
+    if !init_sidecars.is_empty() {
+        generated
+            .init_containers
+            .get_or_insert_with(Vec::new)
+            .extend(init_sidecars);
+    }
+
+    if !volumes.is_empty() {
+        generated
+            .volumes
+            .get_or_insert_with(Vec::new)
+            .extend(volumes);
+    }
+}
+
+fn missing_containers(generated: &[Container], live: &[Container]) -> Vec<Container> {
+    live.iter()
+        .filter(|container| {
+            !generated
+                .iter()
+                .any(|generated_container| generated_container.name == container.name)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Checks if the autoscaler scales the deployment with the given name
+pub fn targets_deployment(hpa: &HorizontalPodAutoscaler, deployment_name: &str) -> bool {
+    hpa.spec.as_ref().is_some_and(|spec| {
+        spec.scale_target_ref.kind == "Deployment" && spec.scale_target_ref.name == deployment_name
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use k8s_openapi::api::{apps::v1::DeploymentSpec, core::v1::PodTemplateSpec};
+
+    fn deployment(
+        containers: Vec<&str>,
+        replicas: i32,
+        annotations: Vec<(&str, &str)>,
+    ) -> Deployment {
+        let annotations: BTreeMap<String, String> = annotations
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        Deployment {
+            metadata: ObjectMeta {
+                name: Some(String::from("nodeinfo")),
+                annotations: Some(annotations.clone()),
+                ..Default::default()
+            },
+            spec: Some(DeploymentSpec {
+                replicas: Some(replicas),
+                template: PodTemplateSpec {
+                    metadata: Some(ObjectMeta {
+                        annotations: Some(annotations),
+                        ..Default::default()
+                    }),
+                    spec: Some(PodSpec {
+                        containers: containers
+                            .into_iter()
+                            .map(|name| Container {
+                                name: name.to_string(),
+                                ..Default::default()
+                            })
+                            .collect(),
+                        ..Default::default()
+                    }),
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn container_names(deployment: &Deployment) -> Vec<String> {
+        deployment
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.template.spec.as_ref())
+            .map(|pod_spec| pod_spec.containers.iter().map(|c| c.name.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn preserves_allowlisted_fields_only() {
+        let live = deployment(
+            vec!["nodeinfo", "istio-proxy"],
+            5,
+            vec![("sidecar.istio.io/status", "injected"), ("other", "value")],
+        );
+
+        let preserve = PreserveFields {
+            fields: vec![PreservedField::Sidecars, PreservedField::Replicas],
+            annotation_prefixes: vec![String::from("sidecar.istio.io/")],
+        };
+
+        let mut generated = deployment(vec!["nodeinfo"], 1, vec![]);
+        preserve.apply(&mut generated, &live, true);
+
+        assert_eq!(container_names(&generated), vec!["nodeinfo", "istio-proxy"]);
+        assert_eq!(
+            generated.spec.as_ref().and_then(|spec| spec.replicas),
+            Some(5)
+        );
+
+        let annotations = generated.metadata.annotations.unwrap_or_default();
+        assert!(annotations.contains_key("sidecar.istio.io/status"));
+        assert!(!annotations.contains_key("other"));
+
+        let mut generated = deployment(vec!["nodeinfo"], 1, vec![]);
+        preserve.apply(&mut generated, &live, false);
+
+        assert_eq!(
+            generated.spec.as_ref().and_then(|spec| spec.replicas),
+            Some(1)
+        );
+
+        let mut generated = deployment(vec!["nodeinfo"], 1, vec![]);
+        PreserveFields::default().apply(&mut generated, &live, true);
+
+        assert_eq!(container_names(&generated), vec!["nodeinfo"]);
+        assert_eq!(
+            generated.spec.as_ref().and_then(|spec| spec.replicas),
+            Some(1)
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use crate::crds::defs::OpenFaaSFunction;
+use kube::{core::ObjectMeta, runtime::reflector::ObjectRef};
+use prometheus::IntGauge;
+use std::{collections::HashSet, sync::Mutex};
+
+/// Functions the controller was triggered to reconcile, but has not started reconciling yet.
+///
+/// The controller's scheduler does not expose its queue, so triggers are counted on their way into the controller
+/// and a function leaves the queue once its reconciliation starts.
+/// Like in the scheduler, a function triggered again before its reconciliation starts is queued once
+#[derive(Debug)]
+pub struct ReconcileQueue {
+    pending: Mutex<HashSet<ObjectRef<OpenFaaSFunction>>>,
+    depth: IntGauge,
+}
+
+impl ReconcileQueue {
+    /// The depth of the queue is reported on the given gauge
+    pub fn new(depth: IntGauge) -> Self {
+        Self {
+            pending: Mutex::new(HashSet::new()),
+            depth,
+        }
+    }
+
+    pub fn push(&self, functions: impl IntoIterator<Item = ObjectRef<OpenFaaSFunction>>) {
+        let mut pending = self.pending.lock().expect("Reconcile queue poisoned");
+
+        pending.extend(functions);
+        self.depth.set(pending.len() as i64);
+    }
+
+    /// Queues the functions owning a changed resource, the controller reconciles them the same way
+    pub fn push_owners(&self, meta: &ObjectMeta) {
+        let namespace = meta.namespace.as_deref();
+
+        self.push(
+            meta.owner_references
+                .iter()
+                .flatten()
+                .filter_map(|owner| ObjectRef::from_owner_ref(namespace, owner, ())),
+        );
+    }
+
+    /// Removes a function whose reconciliation started, or that no longer exists
+    pub fn pop(&self, function: &ObjectRef<OpenFaaSFunction>) {
+        let mut pending = self.pending.lock().expect("Reconcile queue poisoned");
+
+        pending.remove(function);
+        self.depth.set(pending.len() as i64);
+    }
+
+    pub fn depth(&self) -> i64 {
+        self.depth.get()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference;
+    use kube::Resource;
+
+    #[test]
+    fn repeated_triggers_are_queued_once() {
+        let queue = ReconcileQueue::new(IntGauge::new("depth", "depth").expect("Invalid metric"));
+        let function = ObjectRef::<OpenFaaSFunction>::new("nodeinfo").within("openfaas-fn");
+
+        queue.push([function.clone()]);
+        queue.push([function.clone()]);
+        assert_eq!(queue.depth(), 1);
+
+        let owned = ObjectMeta {
+            namespace: Some(String::from("openfaas-fn")),
+            owner_references: Some(vec![
+                OwnerReference {
+                    api_version: OpenFaaSFunction::api_version(&()).into_owned(),
+                    kind: OpenFaaSFunction::kind(&()).into_owned(),
+                    name: String::from("nodeinfo"),
+                    uid: String::from("uid"),
+                    ..Default::default()
+                },
+                OwnerReference {
+                    api_version: String::from("apps/v1"),
+                    kind: String::from("ReplicaSet"),
+                    name: String::from("nodeinfo"),
+                    uid: String::from("uid"),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        };
+
+        // Only owners that are functions are reconciled
+        queue.push_owners(&owned);
+        assert_eq!(queue.depth(), 1);
+
+        queue.pop(&function);
+        assert_eq!(queue.depth(), 0);
+    }
+}
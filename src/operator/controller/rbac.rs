@@ -0,0 +1,315 @@
+use super::{errors::CheckPermissionsError, preserve::PreservedField, OperatorConfig};
+use crate::crds::defs::OpenFaaSFunction;
+use k8s_openapi::api::authorization::v1::{
+    ResourceAttributes, SelfSubjectAccessReview, SelfSubjectAccessReviewSpec,
+};
+use kube::{api::PostParams, Api, Client as KubeClient, Resource};
+use std::fmt::{self, Display, Formatter};
+
+/// A verb the operator needs on a resource
+#[derive(Debug, Clone, PartialEq)]
+pub struct Permission {
+    pub group: String,
+    pub resource: String,
+    pub verb: &'static str,
+    /// `None` for cluster scoped resources
+    pub namespace: Option<String>,
+}
+
+impl Display for Permission {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let resource = match self.group.as_str() {
+            "" => self.resource.clone(),
+            group => format!("{}.{}", self.resource, group),
+        };
+
+        match self.namespace {
+            Some(ref namespace) => write!(f, "{} {} in {}", self.verb, resource, namespace),
+            None => write!(f, "{} {}", self.verb, resource),
+        }
+    }
+}
+
+fn permissions(
+    group: &str,
+    resource: &str,
+    verbs: &[&'static str],
+    namespace: Option<&str>,
+) -> Vec<Permission> {
+    verbs
+        .iter()
+        .map(|verb| Permission {
+            group: group.to_string(),
+            resource: resource.to_string(),
+            verb,
+            namespace: namespace.map(String::from),
+        })
+        .collect()
+}
+
+/// The permissions the controller needs to reconcile functions with the given config
+pub fn required_permissions(config: &OperatorConfig) -> Vec<Permission> {
+    let crd_group = OpenFaaSFunction::group(&());
+    let crd_plural = OpenFaaSFunction::plural(&());
+    // Permissions in all namespaces are checked without a namespace
+    let namespace = (!config.is_cluster_wide()).then_some(config.functions_namespace.as_str());
+
+    let mut required = [
+        permissions(
+            &crd_group,
+            &crd_plural,
+            &["get", "list", "watch", "patch"],
+            namespace,
+        ),
+        permissions(
+            &crd_group,
+            &format!("{crd_plural}/status"),
+            &["get", "update"],
+            namespace,
+        ),
+        permissions(
+            &crd_group,
+            &format!("{crd_plural}/finalizers"),
+            &["update"],
+            namespace,
+        ),
+        permissions("", "namespaces", &["get"], None),
+        permissions("", "secrets", &["list"], namespace),
+        permissions("", "configmaps", &["list"], namespace),
+        permissions(
+            "apps",
+            "deployments",
+            &[
+                "get", "list", "watch", "create", "update", "patch", "delete",
+            ],
+            namespace,
+        ),
+        permissions(
+            "",
+            "services",
+            &["get", "list", "watch", "create", "patch", "delete"],
+            namespace,
+        ),
+        permissions(
+            "policy",
+            "poddisruptionbudgets",
+            &["get", "list", "watch", "create", "update", "delete"],
+            namespace,
+        ),
+        permissions(
+            "networking.k8s.io",
+            "networkpolicies",
+            &["get", "list", "watch", "create", "update", "delete"],
+            namespace,
+        ),
+    ]
+    .concat();
+
+    // Secrets are only watched in the namespaces of the reconciled functions
+    for secrets_watch_namespace in config.secrets_watch_namespaces() {
+        required.extend(permissions(
+            "",
+            "secrets",
+            &["watch"],
+            Some(&secrets_watch_namespace),
+        ));
+    }
+
+    if let Some(ref secrets_namespace) = config.secrets_namespace {
+        if secrets_namespace != &config.functions_namespace {
+            required.extend(permissions(
+                "",
+                "secrets",
+                &["list"],
+                Some(secrets_namespace),
+            ));
+        }
+    }
+
+    if config.image_pull_secrets_service_account.is_some() {
+        required.extend(permissions("", "serviceaccounts", &["get"], namespace));
+    }
+
+    if config.preserve_fields.contains(PreservedField::Replicas) {
+        required.extend(permissions(
+            "autoscaling",
+            "horizontalpodautoscalers",
+            &["list"],
+            namespace,
+        ));
+    }
+
+    if config.leader_election_id.is_some() {
+        required.extend(permissions(
+            "coordination.k8s.io",
+            "leases",
+            &["get", "create", "update"],
+            Some(config.functions_namespace.as_str()),
+        ));
+    }
+
+    if config.verify_runtime_class {
+        required.extend(permissions("node.k8s.io", "runtimeclasses", &["get"], None));
+    }
+
+    required
+}
+
+/// Asks the API server which of the permissions the operator is missing
+async fn missing_permissions(
+    client: KubeClient,
+    required: Vec<Permission>,
+) -> Result<Vec<Permission>, kube::Error> {
+    let api: Api<SelfSubjectAccessReview> = Api::all(client);
+    let mut missing = vec![];
+
+    for permission in required {
+        let review = SelfSubjectAccessReview {
+            spec: SelfSubjectAccessReviewSpec {
+                resource_attributes: Some(ResourceAttributes {
+                    group: Some(permission.group.clone()),
+                    resource: Some(permission.resource.clone()),
+                    verb: Some(permission.verb.to_string()),
+                    namespace: permission.namespace.clone(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let allowed = api
+            .create(&PostParams::default(), &review)
+            .await?
+            .status
+            .is_some_and(|status| status.allowed);
+
+        if !allowed {
+            missing.push(permission);
+        }
+    }
+
+    Ok(missing)
+}
+
+/// Checks if the operator is allowed to perform everything it needs to reconcile functions.
+///
+/// Missing permissions are logged. If `require_rbac` is set, they are an error.
+pub async fn check_permissions(
+    client: KubeClient,
+    config: &OperatorConfig,
+    require_rbac: bool,
+) -> Result<(), CheckPermissionsError> {
+    tracing::info!("Checking permissions.");
+
+    let missing = match missing_permissions(client, required_permissions(config)).await {
+        Ok(missing) => missing,
+        Err(error) if require_rbac => {
+            tracing::error!(%error, "Failed to check permissions.");
+            return Err(CheckPermissionsError::Review(error));
+        }
+        Err(error) => {
+            tracing::warn!(%error, "Failed to check permissions.");
+            return Ok(());
+        }
+    };
+
+    if missing.is_empty() {
+        tracing::info!("Permissions granted.");
+        return Ok(());
+    }
+
+    for permission in missing.iter() {
+        if require_rbac {
+            tracing::error!(%permission, "Missing permission.");
+        } else {
+            tracing::warn!(%permission, "Missing permission.");
+        }
+    }
+
+    if require_rbac {
+        return Err(CheckPermissionsError::Missing(
+            missing.iter().map(ToString::to_string).collect(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crds::defs::DEFAULT_MAX_ENV_BYTES;
+    use crate::operator::controller::{
+        deplyoment::DeploymentBuilder, preserve::PreserveFields, UpdateMode, UpdateStrategy,
+    };
+    use k8s_openapi::api::rbac::v1::Role;
+    use std::time::Duration;
+
+    #[test]
+    fn operator_role_grants_required_permissions() {
+        let config = OperatorConfig {
+            functions_namespace: String::from("openfaas-fn"),
+            update_strategy: UpdateStrategy::default(),
+            update_mode: UpdateMode::default(),
+            field_manager: String::from("operator"),
+            max_reconcile_duration: None,
+            secrets_namespace: None,
+            normalize_image_references: true,
+            default_pod_labels: Default::default(),
+            image_pull_secrets_service_account: Some(String::from("default")),
+            verify_runtime_class: true,
+            reconcile_concurrency: 0,
+            reconcile_debounce: Duration::ZERO,
+            reconcile_jitter_startup: Duration::ZERO,
+            watch_buffer_size: 0,
+            max_env_bytes: DEFAULT_MAX_ENV_BYTES,
+            adopt_orphans: true,
+            preserve_fields: PreserveFields {
+                fields: vec![PreservedField::Sidecars, PreservedField::Replicas],
+                annotation_prefixes: vec![],
+            },
+            wait_for_ready_on_rename: false,
+            error_requeue: Duration::from_secs(10),
+            max_error_requeue: Duration::from_secs(300),
+            all_namespaces: false,
+            functions_namespaces: vec![],
+            leader_election_id: Some(String::from("operator")),
+        };
+
+        let builder = DeploymentBuilder::new(
+            String::from("operator"),
+            config.functions_namespace.clone(),
+            String::from("image"),
+            config.update_strategy.clone(),
+            config.all_namespaces,
+            config.functions_namespaces.clone(),
+            config.verify_runtime_class,
+        );
+
+        assert!(builder.has_runtime_class_cluster_role());
+
+        let rules: Vec<_> = Role::from(&builder)
+            .rules
+            .into_iter()
+            .chain(builder.to_runtime_class_cluster_role().rules)
+            .flatten()
+            .collect();
+
+        for permission in required_permissions(&config) {
+            let granted = rules.iter().any(|rule| {
+                let matches = |values: &Option<Vec<String>>, value: &str| {
+                    values
+                        .as_ref()
+                        .is_some_and(|values| values.iter().any(|v| v == "*" || v == value))
+                };
+
+                matches(&rule.api_groups, &permission.group)
+                    && matches(&rule.resources, &permission.resource)
+                    && matches(&Some(rule.verbs.clone()), permission.verb)
+            });
+
+            assert!(granted, "Role does not grant {permission}");
+        }
+    }
+}
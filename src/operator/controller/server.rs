@@ -0,0 +1,266 @@
+use super::{
+    health::Health,
+    metrics::Metrics,
+    outcome::{ReconcileErrorKind, ReconcileOutcome},
+};
+use crate::crds::defs::{OpenFaaSFunction, OpenFaasFunctionStatusCondition};
+use axum::{
+    extract::State,
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::sse::{Event, KeepAlive, Sse},
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use futures::stream::{self, Stream};
+use k8s_openapi::{apimachinery::pkg::apis::meta::v1::Time, chrono};
+use kube::{
+    runtime::reflector::{ObjectRef, Store},
+    ResourceExt,
+};
+use prometheus::{Encoder, TextEncoder};
+use serde::Serialize;
+use std::{convert::Infallible, future::Future, net::SocketAddr, sync::Arc};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    watch,
+};
+
+/// Number of reconcile outcomes buffered per `/events` client, slower clients skip the oldest ones
+pub const EVENTS_CAPACITY: usize = 128;
+
+/// The operator's view of a single managed function
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FunctionSummary {
+    pub name: String,
+    pub namespace: Option<String>,
+    pub image: String,
+    pub condition: Option<OpenFaasFunctionStatusCondition>,
+}
+
+impl From<&OpenFaaSFunction> for FunctionSummary {
+    fn from(crd: &OpenFaaSFunction) -> Self {
+        Self {
+            name: crd.name_any(),
+            namespace: crd.namespace(),
+            image: crd.spec.image.clone(),
+            condition: latest_condition(crd),
+        }
+    }
+}
+
+/// A reconcile outcome as streamed to `/events` clients
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FunctionEvent {
+    pub name: String,
+    pub namespace: Option<String>,
+    /// The error of a failed reconciliation
+    pub error: Option<String>,
+    /// The function's condition after the reconciliation
+    pub condition: Option<OpenFaasFunctionStatusCondition>,
+    pub timestamp: Time,
+}
+
+impl FunctionEvent {
+    fn new(outcome: &ReconcileOutcome, functions_store: &Store<OpenFaaSFunction>) -> Self {
+        let name = outcome.object_ref.name.clone();
+        let namespace = outcome.object_ref.namespace.clone();
+
+        let mut object_ref = ObjectRef::<OpenFaaSFunction>::new(&name);
+        object_ref.namespace = namespace.clone();
+
+        let error = match &outcome.result {
+            Ok(_) => None,
+            Err(ReconcileErrorKind::Reconciler(error)) => Some(error.clone()),
+            Err(ReconcileErrorKind::ObjectNotFound) => Some(String::from("Function not found")),
+        };
+
+        Self {
+            name,
+            namespace,
+            error,
+            condition: functions_store
+                .get(&object_ref)
+                .and_then(|crd| latest_condition(&crd)),
+            timestamp: Time(chrono::Utc::now()),
+        }
+    }
+}
+
+fn latest_condition(crd: &OpenFaaSFunction) -> Option<OpenFaasFunctionStatusCondition> {
+    crd.status
+        .as_ref()
+        .and_then(|status| status.conditions.last().cloned())
+}
+
+#[derive(Clone)]
+struct MetricsServerState {
+    metrics: Arc<Metrics>,
+    health: Arc<Health>,
+    functions_store: Store<OpenFaaSFunction>,
+    events: broadcast::Sender<ReconcileOutcome>,
+    /// Closed once the server shuts down, ending the otherwise endless event streams
+    shutdown: watch::Receiver<()>,
+}
+
+/// HTTP server exposing the operator's Prometheus metrics, health probes, view of the functions
+/// and stream of reconcile outcomes
+pub struct MetricsServer {
+    state: MetricsServerState,
+    shutdown: watch::Sender<()>,
+}
+
+impl MetricsServer {
+    pub fn new(
+        metrics: Arc<Metrics>,
+        health: Arc<Health>,
+        functions_store: Store<OpenFaaSFunction>,
+        events: broadcast::Sender<ReconcileOutcome>,
+    ) -> Self {
+        let (shutdown, shutdown_receiver) = watch::channel(());
+
+        Self {
+            state: MetricsServerState {
+                metrics,
+                health,
+                functions_store,
+                events,
+                shutdown: shutdown_receiver,
+            },
+            shutdown,
+        }
+    }
+
+    fn router(state: MetricsServerState) -> Router {
+        Router::new()
+            .route("/metrics", get(metrics))
+            .route("/healthz", get(healthz))
+            .route("/readyz", get(readyz))
+            .route("/functions", get(functions))
+            .route("/events", get(events))
+            .with_state(state)
+    }
+
+    /// Serves until `shutdown` completes
+    pub async fn serve(
+        self,
+        addr: SocketAddr,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<(), hyper::Error> {
+        tracing::info!(%addr, "Serving metrics.");
+
+        let Self {
+            state,
+            shutdown: events_shutdown,
+        } = self;
+
+        axum::Server::try_bind(&addr)?
+            .serve(Self::router(state).into_make_service())
+            .with_graceful_shutdown(async move {
+                shutdown.await;
+                drop(events_shutdown);
+            })
+            .await
+    }
+}
+
+/// Renders all metrics in the Prometheus text format
+async fn metrics(State(state): State<MetricsServerState>) -> impl IntoResponse {
+    let encoder = TextEncoder::new();
+
+    match encoder.encode_to_string(&state.metrics.registry().gather()) {
+        Ok(body) => (
+            StatusCode::OK,
+            [(CONTENT_TYPE, encoder.format_type().to_string())],
+            body,
+        ),
+        Err(error) => {
+            tracing::error!(%error, "Failed to encode metrics.");
+
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                [(CONTENT_TYPE, String::from("text/plain"))],
+                error.to_string(),
+            )
+        }
+    }
+}
+
+/// Liveness probe, fails once the controller has terminated
+async fn healthz(State(state): State<MetricsServerState>) -> StatusCode {
+    probe_status(state.health.is_alive())
+}
+
+/// Readiness probe, succeeds once the controller has started streaming reconciliations
+async fn readyz(State(state): State<MetricsServerState>) -> StatusCode {
+    probe_status(state.health.is_ready())
+}
+
+fn probe_status(healthy: bool) -> StatusCode {
+    if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+/// Lists all functions known to the operator, ordered by namespace and name
+async fn functions(State(state): State<MetricsServerState>) -> Json<Vec<FunctionSummary>> {
+    let mut functions: Vec<FunctionSummary> = state
+        .functions_store
+        .state()
+        .iter()
+        .map(|crd| FunctionSummary::from(crd.as_ref()))
+        .collect();
+
+    functions.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+
+    Json(functions)
+}
+
+/// Streams every reconcile outcome as a JSON `data:` line.
+///
+/// Clients that can not keep up skip the oldest outcomes,
+/// the stream ends when the client disconnects or the server shuts down.
+async fn events(
+    State(state): State<MetricsServerState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = state.events.subscribe();
+    let functions_store = state.functions_store;
+
+    let stream = stream::unfold(
+        (receiver, state.shutdown),
+        move |(mut receiver, mut shutdown)| {
+            let functions_store = functions_store.clone();
+
+            async move {
+                loop {
+                    let received = tokio::select! {
+                        received = receiver.recv() => received,
+                        // Only fails once the server shuts down
+                        _ = shutdown.changed() => return None,
+                    };
+
+                    match received {
+                        Ok(outcome) => {
+                            let event = FunctionEvent::new(&outcome, &functions_store);
+
+                            match Event::default().json_data(&event) {
+                                Ok(event) => return Some((Ok(event), (receiver, shutdown))),
+                                Err(error) => {
+                                    tracing::warn!(%error, "Failed to serialize event.");
+                                }
+                            }
+                        }
+                        Err(RecvError::Lagged(skipped)) => {
+                            tracing::debug!(%skipped, "Client lagging. Skipping events.");
+                        }
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            }
+        },
+    );
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
@@ -1,4 +1,5 @@
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, fmt, str::FromStr};
+use thiserror::Error as ThisError;
 
 pub fn remove_whitespace(s: &str) -> String {
     s.chars().filter(|c| !c.is_whitespace()).collect()
@@ -38,3 +39,402 @@ pub fn a_key_is_missing_or_diffirent_btree<'a>(
 
     None
 }
+
+/// Merges `patch` into `target` like a strategic merge patch on built-in Kubernetes types.
+///
+/// Objects are merged recursively and `null` removes a key. Lists of named objects,
+/// e.g. containers, volumes or env vars, are merged by their names, any other list is replaced
+pub fn strategic_merge(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    use serde_json::Value;
+
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.remove(key);
+                    continue;
+                }
+
+                strategic_merge(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+        (Value::Array(target), Value::Array(patch))
+            if target
+                .iter()
+                .chain(patch.iter())
+                .all(|item| json_name(item).is_some()) =>
+        {
+            for item in patch {
+                match target
+                    .iter_mut()
+                    .find(|target_item| json_name(target_item) == json_name(item))
+                {
+                    Some(target_item) => strategic_merge(target_item, item),
+                    None => target.push(item.clone()),
+                }
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+fn json_name(value: &serde_json::Value) -> Option<&str> {
+    value.get("name").and_then(serde_json::Value::as_str)
+}
+
+/// Collects the paths of the fields of `value` that are missing in `known`,
+/// e.g. fields dropped by deserializing `value` into a type and serializing it back.
+///
+/// `null` fields are ignored
+pub fn collect_unknown_json_fields(
+    value: &serde_json::Value,
+    known: &serde_json::Value,
+    path: &str,
+) -> Vec<String> {
+    use serde_json::Value;
+
+    match (value, known) {
+        (Value::Object(value), Value::Object(known)) => value
+            .iter()
+            .filter(|(_, field)| !field.is_null())
+            .flat_map(|(key, field)| {
+                let path = match path {
+                    "" => key.clone(),
+                    path => format!("{path}.{key}"),
+                };
+
+                match known.get(key) {
+                    Some(known_field) => collect_unknown_json_fields(field, known_field, &path),
+                    None => vec![path],
+                }
+            })
+            .collect(),
+        (Value::Array(value), Value::Array(known)) => value
+            .iter()
+            .zip(known.iter())
+            .enumerate()
+            .flat_map(|(index, (item, known_item))| {
+                collect_unknown_json_fields(item, known_item, &format!("{path}[{index}]"))
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn is_alphanumeric(c: char) -> bool {
+    c.is_ascii_alphanumeric()
+}
+
+fn starts_and_ends_with(s: &str, predicate: fn(char) -> bool) -> bool {
+    s.chars().next().is_some_and(predicate) && s.chars().last().is_some_and(predicate)
+}
+
+/// Checks if the given string is a valid name part of a Kubernetes qualified name.
+fn is_qualified_name_part(s: &str) -> bool {
+    !s.is_empty()
+        && s.len() <= 63
+        && starts_and_ends_with(s, is_alphanumeric)
+        && s.chars()
+            .all(|c| is_alphanumeric(c) || c == '-' || c == '_' || c == '.')
+}
+
+/// Checks if the given string is a valid DNS (RFC 1123) subdomain.
+pub fn is_dns_subdomain(s: &str) -> bool {
+    let is_lower_alphanumeric = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+
+    !s.is_empty()
+        && s.len() <= 253
+        && s.split('.').all(|label| {
+            starts_and_ends_with(label, is_lower_alphanumeric)
+                && label.chars().all(|c| is_lower_alphanumeric(c) || c == '-')
+        })
+}
+
+/// Checks if the given string is a valid DNS (RFC 1123) label, e.g. a service name.
+pub fn is_dns_label(s: &str) -> bool {
+    s.len() <= 63 && !s.contains('.') && is_dns_subdomain(s)
+}
+
+/// Checks if the given string is a valid Kubernetes qualified name, e.g. a label or annotation key.
+pub fn is_qualified_name(s: &str) -> bool {
+    match s.split_once('/') {
+        Some((prefix, name)) => is_dns_subdomain(prefix) && is_qualified_name_part(name),
+        None => is_qualified_name_part(s),
+    }
+}
+
+/// Checks if the given string is a valid Kubernetes label value.
+pub fn is_label_value(s: &str) -> bool {
+    s.is_empty() || is_qualified_name_part(s)
+}
+
+const DEFAULT_REGISTRY: &str = "docker.io";
+const DEFAULT_REPOSITORY_PREFIX: &str = "library";
+const DEFAULT_TAG: &str = "latest";
+
+/// A parsed container image reference, e.g. ghcr.io/openfaas/nodeinfo:latest
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageReference {
+    /// The registry, e.g. ghcr.io or localhost:5000
+    pub registry: Option<String>,
+    /// The repository path, e.g. openfaas/nodeinfo
+    pub repository: String,
+    pub tag: Option<String>,
+    pub digest: Option<String>,
+}
+
+#[derive(ThisError, Debug, PartialEq)]
+#[error("invalid image reference {0:?}")]
+pub struct InvalidImageReferenceError(pub String);
+
+impl ImageReference {
+    /// The reference as Docker resolves it, with the default registry, the library prefix for official images
+    /// and the latest tag if neither a tag nor a digest is given
+    pub fn normalized(&self) -> String {
+        let registry = self.registry.as_deref().unwrap_or(DEFAULT_REGISTRY);
+
+        let repository = if registry == DEFAULT_REGISTRY && !self.repository.contains('/') {
+            format!("{DEFAULT_REPOSITORY_PREFIX}/{}", self.repository)
+        } else {
+            self.repository.clone()
+        };
+
+        let tag = match (&self.tag, &self.digest) {
+            (None, None) => Some(DEFAULT_TAG),
+            (tag, _) => tag.as_deref(),
+        };
+
+        let mut reference = format!("{registry}/{repository}");
+
+        if let Some(tag) = tag {
+            reference.push(':');
+            reference.push_str(tag);
+        }
+
+        if let Some(ref digest) = self.digest {
+            reference.push('@');
+            reference.push_str(digest);
+        }
+
+        reference
+    }
+}
+
+impl fmt::Display for ImageReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ref registry) = self.registry {
+            write!(f, "{registry}/")?;
+        }
+
+        write!(f, "{}", self.repository)?;
+
+        if let Some(ref tag) = self.tag {
+            write!(f, ":{tag}")?;
+        }
+
+        if let Some(ref digest) = self.digest {
+            write!(f, "@{digest}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for ImageReference {
+    type Err = InvalidImageReferenceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || InvalidImageReferenceError(s.to_string());
+
+        let (name, digest) = match s.split_once('@') {
+            Some((name, digest)) => (name, Some(digest)),
+            None => (s, None),
+        };
+
+        // A colon after the last slash separates the tag, a colon before it belongs to the registry's port
+        let (name, tag) = match name.rsplit_once(':') {
+            Some((repository, tag)) if !tag.contains('/') => (repository, Some(tag)),
+            _ => (name, None),
+        };
+
+        // The first component is a registry if it looks like a host
+        let (registry, repository) = match name.split_once('/') {
+            Some((registry, repository))
+                if registry.contains('.') || registry.contains(':') || registry == "localhost" =>
+            {
+                (Some(registry), repository)
+            }
+            _ => (None, name),
+        };
+
+        if !registry.map_or(true, is_image_registry)
+            || !repository.split('/').all(is_image_path_component)
+            || !tag.map_or(true, is_image_tag)
+            || !digest.map_or(true, is_image_digest)
+        {
+            return Err(error());
+        }
+
+        Ok(Self {
+            registry: registry.map(String::from),
+            repository: repository.to_string(),
+            tag: tag.map(String::from),
+            digest: digest.map(String::from),
+        })
+    }
+}
+
+/// host[:port], where the host consists of dot separated alphanumeric labels
+fn is_image_registry(s: &str) -> bool {
+    let (host, port) = match s.split_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (s, None),
+    };
+
+    host.split('.').all(|label| {
+        starts_and_ends_with(label, is_alphanumeric)
+            && label.chars().all(|c| is_alphanumeric(c) || c == '-')
+    }) && port.map_or(true, |port| {
+        !port.is_empty() && port.chars().all(|c| c.is_ascii_digit())
+    })
+}
+
+/// Lowercase alphanumerics separated by a single `.` or `_`, `__`, or any number of `-`
+fn is_image_path_component(s: &str) -> bool {
+    let is_lower_alphanumeric = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+
+    if !starts_and_ends_with(s, is_lower_alphanumeric) {
+        return false;
+    }
+
+    let mut separator = String::new();
+    for c in s.chars() {
+        if is_lower_alphanumeric(c) {
+            let valid_separator = separator.is_empty()
+                || separator == "."
+                || separator == "_"
+                || separator == "__"
+                || separator.chars().all(|c| c == '-');
+
+            if !valid_separator {
+                return false;
+            }
+
+            separator.clear();
+        } else if c == '.' || c == '_' || c == '-' {
+            separator.push(c);
+        } else {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Up to 128 word characters, dots and dashes, not starting with a dot or a dash
+fn is_image_tag(s: &str) -> bool {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    !s.is_empty()
+        && s.len() <= 128
+        && s.chars().next().is_some_and(is_word)
+        && s.chars().all(|c| is_word(c) || c == '.' || c == '-')
+}
+
+/// algorithm:hex, e.g. sha256:...
+fn is_image_digest(s: &str) -> bool {
+    let Some((algorithm, encoded)) = s.split_once(':') else {
+        return false;
+    };
+
+    !algorithm.is_empty()
+        && algorithm
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic())
+        && algorithm
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_+.".contains(c))
+        && encoded.len() >= 32
+        && encoded.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn qualified_names_are_validated() {
+        assert!(is_qualified_name("app"));
+        assert!(is_qualified_name("com.openfaas.scale.min"));
+        assert!(is_qualified_name("operato.rs/last-applied-spec"));
+        assert!(is_qualified_name("My_Label-1"));
+
+        assert!(!is_qualified_name(""));
+        assert!(!is_qualified_name("-app"));
+        assert!(!is_qualified_name("app name"));
+        assert!(!is_qualified_name("Operato.rs/key"));
+        assert!(!is_qualified_name("a/b/c"));
+        assert!(!is_qualified_name("operato.rs/"));
+        assert!(!is_qualified_name(&"a".repeat(64)));
+    }
+
+    #[test]
+    fn label_values_are_validated() {
+        assert!(is_label_value(""));
+        assert!(is_label_value("value"));
+        assert!(is_label_value("v1.2_3-4"));
+
+        assert!(!is_label_value("value/with/slash"));
+        assert!(!is_label_value("value-"));
+        assert!(!is_label_value(&"a".repeat(64)));
+    }
+
+    #[test]
+    fn image_references_are_normalized() {
+        let normalize = |image: &str| {
+            image
+                .parse::<ImageReference>()
+                .expect("Invalid image reference")
+                .normalized()
+        };
+
+        assert_eq!(normalize("nginx"), "docker.io/library/nginx:latest");
+        assert_eq!(
+            normalize("openfaas/nodeinfo:1.0"),
+            "docker.io/openfaas/nodeinfo:1.0"
+        );
+        assert_eq!(
+            normalize("ghcr.io/openfaas/nodeinfo"),
+            "ghcr.io/openfaas/nodeinfo:latest"
+        );
+        assert_eq!(
+            normalize("localhost:5000/nodeinfo:dev"),
+            "localhost:5000/nodeinfo:dev"
+        );
+
+        let digest = "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+        assert_eq!(
+            normalize(&format!("nginx@{digest}")),
+            format!("docker.io/library/nginx@{digest}")
+        );
+    }
+
+    #[test]
+    fn invalid_image_references_are_rejected() {
+        for image in [
+            "",
+            "myimage::latest",
+            "myimage:",
+            "MyImage",
+            "ghcr.io/openfaas/",
+            "nginx@sha256:abc",
+            "nginx:-latest",
+        ] {
+            assert!(
+                image.parse::<ImageReference>().is_err(),
+                "{image:?} was accepted"
+            );
+        }
+    }
+}